@@ -4,11 +4,11 @@
 use aya_bpf::{
     helpers::{
         bpf_get_current_cgroup_id, bpf_get_current_comm, bpf_get_current_pid_tgid,
-        bpf_get_current_uid_gid, bpf_ktime_get_ns, bpf_probe_read_user,
+        bpf_get_current_uid_gid, bpf_get_prandom_u32, bpf_ktime_get_ns, bpf_probe_read_user,
         bpf_probe_read_user_buf,
     },
     macros::{map, tracepoint},
-    maps::{HashMap, PerCpuArray, RingBuf},
+    maps::{Array, HashMap, LruHashMap, PerCpuArray, RingBuf},
     programs::TracePointContext,
 };
 use core::{mem, ptr};
@@ -17,13 +17,60 @@ const TASK_COMM_LEN: usize = 16;
 const DNS_PAYLOAD_MAX: usize = 512;
 const UNIX_PATH_MAX: usize = 108;
 
+// Indexes into CONFIG.
+const CONFIG_DNS_CAPTURE_BYTES: u32 = 0;
+// Keep 1 in N net_send/net_recv events; 0 or 1 keeps everything.
+const CONFIG_SAMPLING_RATE: u32 = 1;
+// Low/high halves of the active run's 64-bit tag, stamped on every event.
+const CONFIG_RUN_TAG_LO: u32 = 2;
+const CONFIG_RUN_TAG_HI: u32 = 3;
+// Per-pid limit on socket I/O events per window; 0 disables the limiter.
+const CONFIG_PID_RATE_MAX_EVENTS: u32 = 4;
+const CONFIG_PID_RATE_WINDOW_MS: u32 = 5;
+// Non-zero drops net_connect/net_send/net_recv events whose peer is a loopback address.
+const CONFIG_IGNORE_LOOPBACK: u32 = 6;
+// Non-zero stages events per CPU and submits them to EVENTS a batch at a time.
+const CONFIG_BATCH_EVENTS: u32 = 7;
+// uid the agent runs as; AGENT_UID_UNSET when the loader was not given one.
+const CONFIG_AGENT_UID: u32 = 8;
+// Offset of next_pid in this kernel's sched_switch record, read by the loader from
+// tracefs; 0 when batching is off.
+const CONFIG_SCHED_SWITCH_NEXT_PID_OFFSET: u32 = 9;
+const CONFIG_ENTRIES: u32 = 10;
+
+const AGENT_UID_UNSET: u32 = u32::MAX;
+
+// Events staged per CPU before a batched flush. Each Event is ~800 bytes, so this keeps
+// the EventBatch record small enough to reserve in one go.
+const BATCH_CAPACITY: usize = 4;
+// A partial batch is flushed by the next sched_switch on its CPU once it is this old, and
+// always when the CPU switches to idle.
+const BATCH_MAX_AGE_NS: u64 = 10_000_000;
+
+// Upper bound on collector.exclude_comm entries; the loader rejects longer lists.
+const EXCLUDE_COMM_MAX: u32 = 64;
+
+// Indexes into STATS: slot 0 counts ring-buffer reservation failures, and slot N counts
+// events of type N that made it into the ring buffer.
+const STATS_DROPS: u32 = 0;
+const STATS_ENTRIES: u32 = 16;
+
 const AF_UNIX: u16 = 1;
 const AF_INET: u16 = 2;
 const AF_INET6: u16 = 10;
 
+// `Event::addr_unknown` values for a send whose peer was never captured.
+const ADDR_UNKNOWN_INET: u8 = 1;
+const ADDR_UNKNOWN_UNTRACKED: u8 = 2;
+
 const IPPROTO_TCP: u8 = 6;
 const IPPROTO_UDP: u8 = 17;
 
+const SOCK_STREAM: u32 = 1;
+const SOCK_DGRAM: u32 = 2;
+// socket(2) ORs SOCK_NONBLOCK/SOCK_CLOEXEC into the type argument.
+const SOCK_TYPE_MASK: u32 = 0xf;
+
 const EINPROGRESS: i64 = -115;
 
 const EVENT_NET_CONNECT: u8 = 1;
@@ -31,6 +78,11 @@ const EVENT_NET_SEND: u8 = 2;
 const EVENT_DNS_QUERY: u8 = 3;
 const EVENT_DNS_RESPONSE: u8 = 4;
 const EVENT_UNIX_CONNECT: u8 = 5;
+const EVENT_UNIX_SEND: u8 = 6;
+const EVENT_UNIX_RECV: u8 = 7;
+const EVENT_NET_BIND: u8 = 8;
+const EVENT_NET_LISTEN: u8 = 9;
+const EVENT_NET_RECV: u8 = 10;
 
 #[repr(C)]
 #[derive(Copy, Clone)]
@@ -38,7 +90,10 @@ pub struct Event {
     pub event_type: u8,
     pub family: u8,
     pub protocol: u8,
-    pub _pad: u8,
+    // Set on a net_send whose peer was never captured (connect() predates the collector):
+    // ADDR_UNKNOWN_INET when the fd is a tracked inet socket, ADDR_UNKNOWN_UNTRACKED when
+    // its socket() predates the collector too and the loader must confirm it is inet.
+    pub addr_unknown: u8,
     pub pid: u32,
     pub fd: i32,
     pub uid: u32,
@@ -56,6 +111,21 @@ pub struct Event {
     pub unix_path: [u8; UNIX_PATH_MAX],
     pub dns_payload_len: u16,
     pub dns_payload: [u8; DNS_PAYLOAD_MAX],
+    pub flowinfo: u32,
+    pub scope_id: u32,
+    pub run_tag: u64,
+    pub latency_ns: u64,
+}
+
+// One ring-buffer record carrying up to BATCH_CAPACITY events; only the first `count` are
+// meaningful. The loader tells it apart from a single Event by its length.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct EventBatch {
+    pub count: u32,
+    pub _pad: u32,
+    pub staged_at_ns: u64,
+    pub events: [Event; BATCH_CAPACITY],
 }
 
 #[repr(C)]
@@ -67,6 +137,9 @@ struct ConnectArgs {
     addr: [u8; 16],
     unix_path_len: u16,
     unix_path: [u8; UNIX_PATH_MAX],
+    flowinfo: u32,
+    scope_id: u32,
+    enter_ts: u64,
 }
 
 #[repr(C)]
@@ -77,9 +150,16 @@ struct SendArgs {
     port: u16,
     addr: [u8; 16],
     protocol: u8,
-    _pad: [u8; 3],
+    addr_unknown: u8,
+    _pad: [u8; 2],
     buf: u64,
     len: u32,
+    flowinfo: u32,
+    scope_id: u32,
+    // Bound local endpoint, only filled when `addr_unknown` is set.
+    local: ConnectedSock,
+    unix_path_len: u16,
+    unix_path: [u8; UNIX_PATH_MAX],
 }
 
 #[repr(C)]
@@ -126,9 +206,9 @@ struct SockAddrIn {
 struct SockAddrIn6 {
     sin6_family: u16,
     sin6_port: u16,
-    _flowinfo: u32,
+    sin6_flowinfo: u32,
     sin6_addr: [u8; 16],
-    _scope_id: u32,
+    sin6_scope_id: u32,
 }
 
 #[repr(C)]
@@ -144,12 +224,31 @@ struct SocketKey {
     fd: i32,
 }
 
+// Fixed-window counter per pid. `suppressed` only grows, so the loader can tell which
+// pids have been limited since it last looked.
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct RateState {
+    window_start_ns: u64,
+    count: u32,
+    suppressed: u32,
+}
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 struct ConnectedSock {
     family: u16,
     port: u16,
     addr: [u8; 16],
+    flowinfo: u32,
+    scope_id: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct UnixPeer {
+    path_len: u16,
+    path: [u8; UNIX_PATH_MAX],
 }
 
 #[repr(C)]
@@ -180,6 +279,15 @@ struct RecvMsgArgs {
     msg_ptr: u64,
 }
 
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct SockIoArgs {
+    fd: i32,
+    // 1 when `sock` is the connected peer, 0 when it is the bound local endpoint.
+    peer: u32,
+    sock: ConnectedSock,
+}
+
 #[map(name = "CONNECT_ARGS")]
 static mut CONNECT_ARGS: HashMap<u32, ConnectArgs> = HashMap::with_max_entries(1024, 0);
 
@@ -195,16 +303,183 @@ static mut RECV_ARGS: HashMap<u32, RecvArgs> = HashMap::with_max_entries(4096, 0
 #[map(name = "RECVMSG_ARGS")]
 static mut RECVMSG_ARGS: HashMap<u32, RecvMsgArgs> = HashMap::with_max_entries(4096, 0);
 
+// Protocol chosen at socket() for the fd socket() is about to return.
+#[map(name = "SOCKET_ARGS")]
+static mut SOCKET_ARGS: HashMap<u32, u8> = HashMap::with_max_entries(1024, 0);
+
+#[map(name = "BIND_ARGS")]
+static mut BIND_ARGS: HashMap<u32, ConnectArgs> = HashMap::with_max_entries(1024, 0);
+
+#[map(name = "LISTEN_ARGS")]
+static mut LISTEN_ARGS: HashMap<u32, i32> = HashMap::with_max_entries(1024, 0);
+
+// write()/read() on fds already known to be inet sockets (see lookup_socket_io).
+#[map(name = "WRITE_ARGS")]
+static mut WRITE_ARGS: HashMap<u32, SockIoArgs> = HashMap::with_max_entries(4096, 0);
+
+#[map(name = "READ_ARGS")]
+static mut READ_ARGS: HashMap<u32, SockIoArgs> = HashMap::with_max_entries(4096, 0);
+
 #[map(name = "CONNECTED_SOCKS")]
 static mut CONNECTED_SOCKS: HashMap<SocketKey, ConnectedSock> =
     HashMap::with_max_entries(8192, 0);
 
+#[map(name = "UNIX_CONNECTED_SOCKS")]
+static mut UNIX_CONNECTED_SOCKS: HashMap<SocketKey, UnixPeer> =
+    HashMap::with_max_entries(8192, 0);
+
+// Transport protocol per inet socket fd, recorded at socket() so connect() can tell a UDP
+// default-peer connect from a TCP handshake.
+#[map(name = "SOCK_PROTOS")]
+static mut SOCK_PROTOS: HashMap<SocketKey, u8> = HashMap::with_max_entries(8192, 0);
+
+// Local endpoint recorded at bind() so listen(), which only takes an fd, can report it.
+#[map(name = "BOUND_SOCKS")]
+static mut BOUND_SOCKS: HashMap<SocketKey, ConnectedSock> =
+    HashMap::with_max_entries(8192, 0);
+
+// Compiled default only; the loader resizes this map from COLLECTOR_RINGBUF_BYTES.
 #[map(name = "EVENTS")]
 static mut EVENTS: RingBuf = RingBuf::with_byte_size(1 << 24, 0);
 
 #[map(name = "EVENT_BUF")]
 static mut EVENT_BUF: PerCpuArray<Event> = PerCpuArray::with_max_entries(1, 0);
 
+// Events waiting for a batched flush; only used when CONFIG_BATCH_EVENTS is set.
+#[map(name = "EVENT_BATCH")]
+static mut EVENT_BATCH: PerCpuArray<EventBatch> = PerCpuArray::with_max_entries(1, 0);
+
+// Runtime knobs written by the loader before the tracepoints are attached.
+#[map(name = "CONFIG")]
+static mut CONFIG: Array<u32> = Array::with_max_entries(CONFIG_ENTRIES, 0);
+
+// NUL-padded comms whose syscalls are not traced at all, written by the loader from
+// COLLECTOR_EXCLUDE_COMM before the tracepoints are attached.
+#[map(name = "EXCLUDE_COMM")]
+static mut EXCLUDE_COMM: HashMap<[u8; TASK_COMM_LEN], u8> =
+    HashMap::with_max_entries(EXCLUDE_COMM_MAX, 0);
+
+// Cgroups an agent-uid task has run in. Tasks there belong to the agent even when they
+// switched uid, so EXCLUDE_COMM never applies to them.
+#[map(name = "OWNED_CGROUPS")]
+static mut OWNED_CGROUPS: LruHashMap<u64, u8> = LruHashMap::with_max_entries(1024, 0);
+
+// LRU so pids that have exited age out instead of pinning entries.
+#[map(name = "PID_RATE")]
+static mut PID_RATE: LruHashMap<u32, RateState> = LruHashMap::with_max_entries(8192, 0);
+
+// Per-CPU counters summed and published by the loader.
+#[map(name = "STATS")]
+static mut STATS: PerCpuArray<u64> = PerCpuArray::with_max_entries(STATS_ENTRIES, 0);
+
+// Bytes of DNS payload to copy; the loader clamps it, but the verifier still needs
+// the DNS_PAYLOAD_MAX bound here.
+fn dns_capture_limit() -> u32 {
+    let limit = unsafe { CONFIG.get(CONFIG_DNS_CAPTURE_BYTES) }
+        .copied()
+        .unwrap_or(DNS_PAYLOAD_MAX as u32);
+    if limit > DNS_PAYLOAD_MAX as u32 {
+        DNS_PAYLOAD_MAX as u32
+    } else {
+        limit
+    }
+}
+
+fn run_tag() -> u64 {
+    let lo = unsafe { CONFIG.get(CONFIG_RUN_TAG_LO) }.copied().unwrap_or(0);
+    let hi = unsafe { CONFIG.get(CONFIG_RUN_TAG_HI) }.copied().unwrap_or(0);
+    ((hi as u64) << 32) | lo as u64
+}
+
+// Connect/bind/listen, DNS and unix events are always kept; only the high-volume
+// socket I/O events are sampled.
+fn sampled_out(event_type: u8) -> bool {
+    if event_type != EVENT_NET_SEND && event_type != EVENT_NET_RECV {
+        return false;
+    }
+    let rate = unsafe { CONFIG.get(CONFIG_SAMPLING_RATE) }.copied().unwrap_or(1);
+    if rate <= 1 {
+        return false;
+    }
+    (unsafe { bpf_get_prandom_u32() }) % rate != 0
+}
+
+// DNS and unix events have their own event types, so they are kept even on loopback.
+fn loopback_ignored(event: &Event) -> bool {
+    match event.event_type {
+        EVENT_NET_CONNECT | EVENT_NET_SEND | EVENT_NET_RECV => {}
+        _ => return false,
+    }
+    let enabled = unsafe { CONFIG.get(CONFIG_IGNORE_LOOPBACK) }
+        .copied()
+        .unwrap_or(0);
+    enabled != 0 && is_loopback(event.family as u16, &event.dst_addr)
+}
+
+// 127.0.0.0/8, ::1, and the IPv4-mapped ::ffff:127.0.0.0/104. Compares whole words
+// instead of looping over the bytes.
+fn is_loopback(family: u16, addr: &[u8; 16]) -> bool {
+    if family == AF_INET {
+        return addr[0] == 127;
+    }
+    if family != AF_INET6 {
+        return false;
+    }
+    let words: [u32; 4] = unsafe { ptr::read_unaligned(addr.as_ptr() as *const [u32; 4]) };
+    if words[0] != 0 || words[1] != 0 {
+        return false;
+    }
+    let third = u32::from_be(words[2]);
+    (third == 0 && u32::from_be(words[3]) == 1) || (third == 0xffff && addr[12] == 127)
+}
+
+// Only the high-volume socket I/O events count against the limit; connect, bind,
+// listen and DNS events always bypass it. No loops, so the verifier sees a fixed path.
+fn rate_limited(event: &Event) -> bool {
+    match event.event_type {
+        EVENT_NET_SEND | EVENT_NET_RECV | EVENT_UNIX_SEND | EVENT_UNIX_RECV => {}
+        _ => return false,
+    }
+    let max_events = unsafe { CONFIG.get(CONFIG_PID_RATE_MAX_EVENTS) }
+        .copied()
+        .unwrap_or(0);
+    if max_events == 0 {
+        return false;
+    }
+    let window_ms = unsafe { CONFIG.get(CONFIG_PID_RATE_WINDOW_MS) }
+        .copied()
+        .unwrap_or(1000);
+    let window_ns = if window_ms == 0 { 1 } else { window_ms as u64 } * 1_000_000;
+    let now = now_ns();
+    let pid = event.pid;
+    let ptr = match unsafe { PID_RATE.get_ptr_mut(&pid) } {
+        Some(ptr) => ptr,
+        None => {
+            let fresh = RateState {
+                window_start_ns: now,
+                count: 1,
+                suppressed: 0,
+            };
+            unsafe {
+                let _ = PID_RATE.insert(&pid, &fresh, 0);
+            }
+            return false;
+        }
+    };
+    let state = unsafe { &mut *ptr };
+    if now.wrapping_sub(state.window_start_ns) >= window_ns {
+        state.window_start_ns = now;
+        state.count = 1;
+        return false;
+    }
+    if state.count < max_events {
+        state.count += 1;
+        return false;
+    }
+    state.suppressed = state.suppressed.saturating_add(1);
+    true
+}
+
 fn now_ns() -> u64 {
     unsafe { bpf_ktime_get_ns() }
 }
@@ -218,6 +493,37 @@ fn current_uid_gid() -> (u32, u32) {
     (uid_gid as u32, (uid_gid >> 32) as u32)
 }
 
+fn agent_uid() -> u32 {
+    unsafe { CONFIG.get(CONFIG_AGENT_UID) }
+        .copied()
+        .unwrap_or(AGENT_UID_UNSET)
+}
+
+// The agent's own tasks: anything running as the agent uid, plus anything in a cgroup
+// where fill_common has seen an agent-uid task.
+fn agent_owned() -> bool {
+    let agent_uid = agent_uid();
+    if agent_uid == AGENT_UID_UNSET {
+        return false;
+    }
+    if current_uid_gid().0 == agent_uid {
+        return true;
+    }
+    let cgroup_id = unsafe { bpf_get_current_cgroup_id() };
+    unsafe { OWNED_CGROUPS.get(&cgroup_id) }.is_some()
+}
+
+// Checked first in every enter probe: without recorded args the exit probes emit nothing.
+// Only tasks outside the agent's tree are excluded; a matching comm in the agent's cgroup
+// is still traced.
+fn comm_excluded() -> bool {
+    let excluded = match bpf_get_current_comm() {
+        Ok(comm) => unsafe { EXCLUDE_COMM.get(&comm) }.is_some(),
+        Err(_) => false,
+    };
+    excluded && !agent_owned()
+}
+
 fn fill_common(event: &mut Event) {
     event.ts = now_ns();
     event.pid = current_pid();
@@ -225,6 +531,12 @@ fn fill_common(event: &mut Event) {
     event.uid = uid;
     event.gid = gid;
     event.cgroup_id = unsafe { bpf_get_current_cgroup_id() };
+    if uid != AGENT_UID_UNSET
+        && uid == agent_uid()
+        && unsafe { OWNED_CGROUPS.get(&event.cgroup_id) }.is_none()
+    {
+        let _ = unsafe { OWNED_CGROUPS.insert(&event.cgroup_id, &1, 0) };
+    }
     if let Ok(comm) = bpf_get_current_comm() {
         event.comm = comm;
     }
@@ -241,8 +553,78 @@ fn init_event(event: &mut Event) {
 }
 
 fn emit(event: &Event) {
+    if batching_enabled() {
+        stage_event(event);
+        return;
+    }
+    let slot = match unsafe { EVENTS.output(event, 0) } {
+        Ok(()) => event.event_type as u32,
+        Err(_) => STATS_DROPS,
+    };
+    bump_stat(slot);
+}
+
+fn batching_enabled() -> bool {
+    unsafe { CONFIG.get(CONFIG_BATCH_EVENTS) }
+        .copied()
+        .unwrap_or(0)
+        != 0
+}
+
+// Copies the event into this CPU's batch and flushes once it is full. Stats are only
+// bumped at flush time, so a staged event is neither emitted nor dropped yet.
+fn stage_event(event: &Event) {
+    let batch = match unsafe { EVENT_BATCH.get_ptr_mut(0) } {
+        Some(ptr) => unsafe { &mut *ptr },
+        None => return,
+    };
+    let idx = batch.count as usize;
+    if idx < BATCH_CAPACITY {
+        if idx == 0 {
+            batch.staged_at_ns = now_ns();
+        }
+        unsafe {
+            ptr::copy_nonoverlapping(event as *const Event, &mut batch.events[idx], 1);
+        }
+        batch.count += 1;
+    }
+    if batch.count as usize >= BATCH_CAPACITY {
+        flush_batch(batch);
+    }
+}
+
+// One reservation for the whole batch; a failed reservation drops every staged event.
+fn flush_batch(batch: &mut EventBatch) {
+    let count = batch.count;
+    if count == 0 {
+        return;
+    }
+    match unsafe { EVENTS.reserve::<EventBatch>(0) } {
+        Some(mut entry) => {
+            unsafe {
+                ptr::copy_nonoverlapping(batch as *const EventBatch, entry.as_mut_ptr(), 1);
+            }
+            entry.submit(0);
+            for idx in 0..BATCH_CAPACITY {
+                if idx < count as usize {
+                    bump_stat(batch.events[idx].event_type as u32);
+                }
+            }
+        }
+        None => add_stat(STATS_DROPS, count as u64),
+    }
+    batch.count = 0;
+}
+
+fn bump_stat(index: u32) {
+    add_stat(index, 1);
+}
+
+fn add_stat(index: u32, value: u64) {
     unsafe {
-        let _ = EVENTS.output(event, 0);
+        if let Some(ptr) = STATS.get_ptr_mut(index) {
+            *ptr += value;
+        }
     }
 }
 
@@ -260,9 +642,156 @@ fn lookup_connected(pid: u32, fd: i32, out: &mut ConnectArgs) -> bool {
     out.family = stored.family;
     out.port = stored.port;
     out.addr = stored.addr;
+    out.flowinfo = stored.flowinfo;
+    out.scope_id = stored.scope_id;
+    true
+}
+
+fn lookup_unix_connected(pid: u32, fd: i32, out: &mut ConnectArgs) -> bool {
+    let key = socket_key(pid, fd);
+    let stored = unsafe { UNIX_CONNECTED_SOCKS.get(&key) };
+    let stored = match stored {
+        Some(value) => *value,
+        None => return false,
+    };
+    out.family = AF_UNIX;
+    out.unix_path_len = stored.path_len;
+    out.unix_path = stored.path;
     true
 }
 
+/// Fallback for a send with no address on an fd whose connect() was never seen (e.g. the
+/// collector attached mid-session). Takes the family and local endpoint from a tracked
+/// bind(), or the protocol from a tracked inet socket(); the peer stays unknown either way.
+/// Returns the `addr_unknown` marker: untracked fds may be unix or netlink sockets, so the
+/// loader drops them unless /proc shows an inet socket.
+fn lookup_unknown_peer(
+    pid: u32,
+    fd: i32,
+    out: &mut ConnectArgs,
+    local: &mut ConnectedSock,
+    protocol: &mut u8,
+) -> u8 {
+    let key = socket_key(pid, fd);
+    let mut known = false;
+    if let Some(sock) = unsafe { BOUND_SOCKS.get(&key) } {
+        out.family = sock.family;
+        *local = *sock;
+        known = true;
+    }
+    if let Some(tracked) = unsafe { SOCK_PROTOS.get(&key) } {
+        *protocol = *tracked;
+        known = true;
+    }
+    if known {
+        ADDR_UNKNOWN_INET
+    } else {
+        ADDR_UNKNOWN_UNTRACKED
+    }
+}
+
+/// Resolves an fd to a tracked inet socket: the peer stored at connect(), else the local
+/// endpoint stored at bind(). Anything else (files, pipes, untracked sockets) is `None`.
+fn lookup_socket_io(pid: u32, fd: i32) -> Option<SockIoArgs> {
+    let key = socket_key(pid, fd);
+    if let Some(sock) = unsafe { CONNECTED_SOCKS.get(&key) } {
+        return Some(SockIoArgs {
+            fd,
+            peer: 1,
+            sock: *sock,
+        });
+    }
+    if let Some(sock) = unsafe { BOUND_SOCKS.get(&key) } {
+        return Some(SockIoArgs {
+            fd,
+            peer: 0,
+            sock: *sock,
+        });
+    }
+    None
+}
+
+fn emit_socket_io(event_type: u8, stored: &SockIoArgs, ret: i64) {
+    with_event(|event| {
+        fill_common(event);
+        event.event_type = event_type;
+        event.family = stored.sock.family as u8;
+        // 0 for sockets created before tracing started; the loader then asks /proc.
+        event.protocol = unsafe { SOCK_PROTOS.get(&socket_key(current_pid(), stored.fd)) }
+            .copied()
+            .unwrap_or(0);
+        event.fd = stored.fd;
+        if stored.peer != 0 {
+            event.dst_addr = stored.sock.addr;
+            event.dst_port = stored.sock.port;
+        } else {
+            event.src_addr = stored.sock.addr;
+            event.src_port = stored.sock.port;
+        }
+        event.flowinfo = stored.sock.flowinfo;
+        event.scope_id = stored.sock.scope_id;
+        event.bytes = if ret > 0 { ret as u32 } else { 0 };
+        event.syscall_result = ret;
+        true
+    });
+}
+
+// Non-DNS inet recvfrom/recvmsg: the sender is the remote end, so it goes in dst_* like
+// the connected peer on the read() path.
+fn emit_net_recv(fd: i32, protocol: u8, peer: &ConnectArgs, ret: i64) {
+    with_event(|event| {
+        fill_common(event);
+        event.event_type = EVENT_NET_RECV;
+        event.family = peer.family as u8;
+        event.protocol = protocol;
+        event.fd = fd;
+        event.dst_addr = peer.addr;
+        event.dst_port = peer.port;
+        event.flowinfo = peer.flowinfo;
+        event.scope_id = peer.scope_id;
+        event.bytes = ret as u32;
+        event.syscall_result = ret;
+        true
+    });
+}
+
+fn emit_unix_payload(
+    event_type: u8,
+    fd: i32,
+    ret: i64,
+    buf: u64,
+    len: u32,
+    path_len: u16,
+    path: &[u8; UNIX_PATH_MAX],
+) {
+    with_event(|event| {
+        fill_common(event);
+        event.event_type = event_type;
+        event.family = AF_UNIX as u8;
+        event.fd = fd;
+        event.syscall_result = ret;
+        event.bytes = if ret > 0 { ret as u32 } else { 0 };
+        event.unix_path_len = path_len;
+        event.unix_path = *path;
+
+        let mut payload_len = if ret > 0 { ret as u32 } else { 0 };
+        if payload_len > len {
+            payload_len = len;
+        }
+        if payload_len > DNS_PAYLOAD_MAX as u32 {
+            payload_len = DNS_PAYLOAD_MAX as u32;
+        }
+        event.dns_payload_len = payload_len as u16;
+        if payload_len > 0 && buf != 0 {
+            let dst = &mut event.dns_payload[..payload_len as usize];
+            unsafe {
+                let _ = bpf_probe_read_user_buf(buf as *const u8, dst);
+            }
+        }
+        true
+    });
+}
+
 fn with_event<F>(f: F)
 where
     F: FnOnce(&mut Event) -> bool,
@@ -271,7 +800,12 @@ where
         if let Some(ptr) = EVENT_BUF.get_ptr_mut(0) {
             let event = &mut *ptr;
             init_event(event);
-            if f(event) {
+            event.run_tag = run_tag();
+            if f(event)
+                && !loopback_ignored(event)
+                && !sampled_out(event.event_type)
+                && !rate_limited(event)
+            {
                 emit(event);
             }
         }
@@ -315,6 +849,8 @@ fn parse_sockaddr(uservaddr: u64, addrlen: u32, out: &mut ConnectArgs) -> bool {
         out.family = AF_INET6;
         out.port = u16::from_be(addr.sin6_port);
         out.addr = addr.sin6_addr;
+        out.flowinfo = u32::from_be(addr.sin6_flowinfo);
+        out.scope_id = addr.sin6_scope_id;
         return true;
     }
 
@@ -370,6 +906,107 @@ fn unix_path_len(path: &[u8; UNIX_PATH_MAX]) -> u16 {
     len
 }
 
+// IPPROTO_IP (0) picks the family default for the socket type.
+fn socket_protocol(family: u16, sock_type: u32, protocol: u32) -> u8 {
+    if family != AF_INET && family != AF_INET6 {
+        return 0;
+    }
+    match protocol {
+        6 => IPPROTO_TCP,
+        17 => IPPROTO_UDP,
+        0 => match sock_type & SOCK_TYPE_MASK {
+            SOCK_STREAM => IPPROTO_TCP,
+            SOCK_DGRAM => IPPROTO_UDP,
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
+// Periodic flush for batched emission: a CPU that stops producing events would otherwise
+// hold its partial batch indefinitely.
+#[tracepoint(category = "sched", name = "sched_switch")]
+pub fn sched_switch(ctx: TracePointContext) -> u32 {
+    match try_sched_switch(ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_sched_switch(ctx: TracePointContext) -> Result<(), i64> {
+    if !batching_enabled() {
+        return Ok(());
+    }
+    let batch = match unsafe { EVENT_BATCH.get_ptr_mut(0) } {
+        Some(ptr) => unsafe { &mut *ptr },
+        None => return Ok(()),
+    };
+    if batch.count == 0 {
+        return Ok(());
+    }
+    let offset = unsafe { CONFIG.get(CONFIG_SCHED_SWITCH_NEXT_PID_OFFSET) }
+        .copied()
+        .unwrap_or(0);
+    // Without a known offset the idle check is skipped and only the age bound applies.
+    let next_pid: i32 = if offset == 0 {
+        -1
+    } else {
+        unsafe { ctx.read_at(offset as usize)? }
+    };
+    if next_pid == 0 || now_ns().saturating_sub(batch.staged_at_ns) >= BATCH_MAX_AGE_NS {
+        flush_batch(batch);
+    }
+    Ok(())
+}
+
+#[tracepoint(category = "syscalls", name = "sys_enter_socket")]
+pub fn sys_enter_socket(ctx: TracePointContext) -> u32 {
+    match try_sys_enter_socket(ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_sys_enter_socket(ctx: TracePointContext) -> Result<(), i64> {
+    if comm_excluded() {
+        return Ok(());
+    }
+    let args: SysEnterArgs = unsafe { ctx.read_at(0)? };
+    let protocol = socket_protocol(args.args[0] as u16, args.args[1] as u32, args.args[2] as u32);
+    if protocol == 0 {
+        return Ok(());
+    }
+    let pid = current_pid();
+    unsafe {
+        SOCKET_ARGS.insert(&pid, &protocol, 0)?;
+    }
+    Ok(())
+}
+
+#[tracepoint(category = "syscalls", name = "sys_exit_socket")]
+pub fn sys_exit_socket(ctx: TracePointContext) -> u32 {
+    match try_sys_exit_socket(ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_sys_exit_socket(ctx: TracePointContext) -> Result<(), i64> {
+    let args: SysExitArgs = unsafe { ctx.read_at(0)? };
+    let pid = current_pid();
+    let protocol = match unsafe { SOCKET_ARGS.get(&pid) } {
+        Some(value) => *value,
+        None => return Ok(()),
+    };
+    let _ = unsafe { SOCKET_ARGS.remove(&pid) };
+    if args.ret < 0 {
+        return Ok(());
+    }
+    let key = socket_key(pid, args.ret as i32);
+    let _ = unsafe { SOCK_PROTOS.insert(&key, &protocol, 0) };
+    Ok(())
+}
+
 #[tracepoint(category = "syscalls", name = "sys_enter_connect")]
 pub fn sys_enter_connect(ctx: TracePointContext) -> u32 {
     match try_sys_enter_connect(ctx) {
@@ -379,6 +1016,9 @@ pub fn sys_enter_connect(ctx: TracePointContext) -> u32 {
 }
 
 fn try_sys_enter_connect(ctx: TracePointContext) -> Result<(), i64> {
+    if comm_excluded() {
+        return Ok(());
+    }
     let args: SysEnterArgs = unsafe { ctx.read_at(0)? };
     let fd = args.args[0] as i32;
     let uservaddr = args.args[1];
@@ -389,6 +1029,7 @@ fn try_sys_enter_connect(ctx: TracePointContext) -> Result<(), i64> {
     if !parse_sockaddr(uservaddr, addrlen, &mut parsed) {
         return Ok(());
     }
+    parsed.enter_ts = now_ns();
 
     let pid = current_pid();
     unsafe {
@@ -416,8 +1057,22 @@ fn try_sys_exit_connect(ctx: TracePointContext) -> Result<(), i64> {
         None => return Ok(()),
     };
     let _ = unsafe { CONNECT_ARGS.remove(&pid) };
+    // Zero when the enter probe did not record a timestamp.
+    let latency_ns = if parsed.enter_ts != 0 {
+        now_ns().saturating_sub(parsed.enter_ts)
+    } else {
+        0
+    };
 
     if parsed.family == AF_UNIX {
+        if ret == 0 || ret == EINPROGRESS {
+            let key = socket_key(pid, parsed.fd);
+            let peer = UnixPeer {
+                path_len: parsed.unix_path_len,
+                path: parsed.unix_path,
+            };
+            let _ = unsafe { UNIX_CONNECTED_SOCKS.insert(&key, &peer, 0) };
+        }
         with_event(|event| {
             fill_common(event);
             event.syscall_result = ret;
@@ -426,17 +1081,26 @@ fn try_sys_exit_connect(ctx: TracePointContext) -> Result<(), i64> {
             event.fd = parsed.fd;
             event.unix_path_len = parsed.unix_path_len;
             event.unix_path = parsed.unix_path;
+            event.latency_ns = latency_ns;
             true
         });
         return Ok(());
     }
 
+    // Unknown for sockets created before tracing started; the loader then asks /proc and
+    // falls back to TCP.
+    let protocol = unsafe { SOCK_PROTOS.get(&socket_key(pid, parsed.fd)) }
+        .copied()
+        .unwrap_or(0);
+
     if ret == 0 || ret == EINPROGRESS {
         let key = socket_key(pid, parsed.fd);
         let connected = ConnectedSock {
             family: parsed.family,
             port: parsed.port,
             addr: parsed.addr,
+            flowinfo: parsed.flowinfo,
+            scope_id: parsed.scope_id,
         };
         let _ = unsafe { CONNECTED_SOCKS.insert(&key, &connected, 0) };
     }
@@ -446,10 +1110,13 @@ fn try_sys_exit_connect(ctx: TracePointContext) -> Result<(), i64> {
         event.syscall_result = ret;
         event.event_type = EVENT_NET_CONNECT;
         event.family = parsed.family as u8;
-        event.protocol = 0;
+        event.protocol = protocol;
         event.fd = parsed.fd;
         event.dst_addr = parsed.addr;
         event.dst_port = parsed.port;
+        event.flowinfo = parsed.flowinfo;
+        event.scope_id = parsed.scope_id;
+        event.latency_ns = latency_ns;
         true
     });
 
@@ -465,6 +1132,9 @@ pub fn sys_enter_sendto(ctx: TracePointContext) -> u32 {
 }
 
 fn try_sys_enter_sendto(ctx: TracePointContext) -> Result<(), i64> {
+    if comm_excluded() {
+        return Ok(());
+    }
     let args: SysEnterArgs = unsafe { ctx.read_at(0)? };
     let fd = args.args[0] as i32;
     let buf = args.args[1];
@@ -472,15 +1142,29 @@ fn try_sys_enter_sendto(ctx: TracePointContext) -> Result<(), i64> {
     let dest_addr = args.args[4];
     let addrlen = args.args[5] as u32;
 
+    let pid = current_pid();
     let mut parsed: ConnectArgs = unsafe { mem::zeroed() };
+    let mut local: ConnectedSock = unsafe { mem::zeroed() };
     let mut protocol = 0u8;
+    let mut addr_unknown = 0u8;
     if dest_addr != 0 && parse_sockaddr(dest_addr, addrlen, &mut parsed) {
-        protocol = IPPROTO_UDP;
-    } else if !lookup_connected(current_pid(), fd, &mut parsed) {
-        return Ok(());
+        if parsed.family != AF_UNIX {
+            protocol = IPPROTO_UDP;
+        }
+    } else if !lookup_connected(pid, fd, &mut parsed)
+        && !lookup_unix_connected(pid, fd, &mut parsed)
+    {
+        if dest_addr != 0 {
+            return Ok(());
+        }
+        addr_unknown = lookup_unknown_peer(pid, fd, &mut parsed, &mut local, &mut protocol);
     }
 
-    if parsed.family != AF_INET && parsed.family != AF_INET6 {
+    if addr_unknown == 0
+        && parsed.family != AF_INET
+        && parsed.family != AF_INET6
+        && parsed.family != AF_UNIX
+    {
         return Ok(());
     }
 
@@ -490,12 +1174,17 @@ fn try_sys_enter_sendto(ctx: TracePointContext) -> Result<(), i64> {
         port: parsed.port,
         addr: parsed.addr,
         protocol,
-        _pad: [0u8; 3],
+        addr_unknown,
+        _pad: [0u8; 2],
         buf,
         len,
+        flowinfo: parsed.flowinfo,
+        scope_id: parsed.scope_id,
+        local,
+        unix_path_len: parsed.unix_path_len,
+        unix_path: parsed.unix_path,
     };
 
-    let pid = current_pid();
     unsafe {
         SEND_ARGS.insert(&pid, &send_args, 0)?;
     }
@@ -523,14 +1212,32 @@ fn try_sys_exit_sendto(ctx: TracePointContext) -> Result<(), i64> {
     };
     let _ = unsafe { SEND_ARGS.remove(&pid) };
 
+    if stored.family == AF_UNIX {
+        emit_unix_payload(
+            EVENT_UNIX_SEND,
+            stored.fd,
+            ret,
+            stored.buf,
+            stored.len,
+            stored.unix_path_len,
+            &stored.unix_path,
+        );
+        return Ok(());
+    }
+
     with_event(|event| {
         fill_common(event);
         event.event_type = EVENT_NET_SEND;
         event.family = stored.family as u8;
         event.protocol = stored.protocol;
+        event.addr_unknown = stored.addr_unknown;
         event.fd = stored.fd;
+        event.src_addr = stored.local.addr;
+        event.src_port = stored.local.port;
         event.dst_addr = stored.addr;
         event.dst_port = stored.port;
+        event.flowinfo = stored.flowinfo;
+        event.scope_id = stored.scope_id;
         event.bytes = if ret > 0 { ret as u32 } else { 0 };
         event.syscall_result = ret;
         true
@@ -545,11 +1252,14 @@ fn try_sys_exit_sendto(ctx: TracePointContext) -> Result<(), i64> {
             event.fd = stored.fd;
             event.dst_addr = stored.addr;
             event.dst_port = stored.port;
+            event.flowinfo = stored.flowinfo;
+            event.scope_id = stored.scope_id;
             event.syscall_result = if ret >= 0 { 0 } else { ret };
 
             let mut payload_len = if ret > 0 { ret as u32 } else { stored.len };
-            if payload_len > DNS_PAYLOAD_MAX as u32 {
-                payload_len = DNS_PAYLOAD_MAX as u32;
+            let limit = dns_capture_limit();
+            if payload_len > limit {
+                payload_len = limit;
             }
             event.dns_payload_len = payload_len as u16;
             if payload_len > 0 {
@@ -574,6 +1284,9 @@ pub fn sys_enter_sendmsg(ctx: TracePointContext) -> u32 {
 }
 
 fn try_sys_enter_sendmsg(ctx: TracePointContext) -> Result<(), i64> {
+    if comm_excluded() {
+        return Ok(());
+    }
     let args: SysEnterArgs = unsafe { ctx.read_at(0)? };
     let fd = args.args[0] as i32;
     let msg_ptr = args.args[1];
@@ -598,16 +1311,26 @@ fn try_sys_enter_sendmsg(ctx: TracePointContext) -> Result<(), i64> {
     };
 
     let mut parsed: ConnectArgs = unsafe { mem::zeroed() };
+    let mut local: ConnectedSock = unsafe { mem::zeroed() };
     let mut protocol = 0u8;
+    let mut addr_unknown = 0u8;
     if msg.msg_name != 0 && msg.msg_namelen > 0 {
-        if parse_sockaddr(msg.msg_name, msg.msg_namelen, &mut parsed) {
+        if parse_sockaddr(msg.msg_name, msg.msg_namelen, &mut parsed)
+            && parsed.family != AF_UNIX
+        {
             protocol = IPPROTO_UDP;
         }
-    } else if !lookup_connected(pid, fd, &mut parsed) {
-        return Ok(());
+    } else if !lookup_connected(pid, fd, &mut parsed)
+        && !lookup_unix_connected(pid, fd, &mut parsed)
+    {
+        addr_unknown = lookup_unknown_peer(pid, fd, &mut parsed, &mut local, &mut protocol);
     }
 
-    if parsed.family != AF_INET && parsed.family != AF_INET6 {
+    if addr_unknown == 0
+        && parsed.family != AF_INET
+        && parsed.family != AF_INET6
+        && parsed.family != AF_UNIX
+    {
         return Ok(());
     }
 
@@ -617,9 +1340,15 @@ fn try_sys_enter_sendmsg(ctx: TracePointContext) -> Result<(), i64> {
         port: parsed.port,
         addr: parsed.addr,
         protocol,
-        _pad: [0u8; 3],
+        addr_unknown,
+        _pad: [0u8; 2],
         buf,
         len,
+        flowinfo: parsed.flowinfo,
+        scope_id: parsed.scope_id,
+        local,
+        unix_path_len: parsed.unix_path_len,
+        unix_path: parsed.unix_path,
     };
 
     unsafe {
@@ -648,14 +1377,32 @@ fn try_sys_exit_sendmsg(ctx: TracePointContext) -> Result<(), i64> {
     };
     let _ = unsafe { SENDMSG_ARGS.remove(&pid) };
 
+    if stored.family == AF_UNIX {
+        emit_unix_payload(
+            EVENT_UNIX_SEND,
+            stored.fd,
+            ret,
+            stored.buf,
+            stored.len,
+            stored.unix_path_len,
+            &stored.unix_path,
+        );
+        return Ok(());
+    }
+
     with_event(|event| {
         fill_common(event);
         event.event_type = EVENT_NET_SEND;
         event.family = stored.family as u8;
         event.protocol = stored.protocol;
+        event.addr_unknown = stored.addr_unknown;
         event.fd = stored.fd;
+        event.src_addr = stored.local.addr;
+        event.src_port = stored.local.port;
         event.dst_addr = stored.addr;
         event.dst_port = stored.port;
+        event.flowinfo = stored.flowinfo;
+        event.scope_id = stored.scope_id;
         event.bytes = if ret > 0 { ret as u32 } else { 0 };
         event.syscall_result = ret;
         true
@@ -670,11 +1417,14 @@ fn try_sys_exit_sendmsg(ctx: TracePointContext) -> Result<(), i64> {
             event.fd = stored.fd;
             event.dst_addr = stored.addr;
             event.dst_port = stored.port;
+            event.flowinfo = stored.flowinfo;
+            event.scope_id = stored.scope_id;
             event.syscall_result = if ret >= 0 { 0 } else { ret };
 
             let mut payload_len = if ret > 0 { ret as u32 } else { stored.len };
-            if payload_len > DNS_PAYLOAD_MAX as u32 {
-                payload_len = DNS_PAYLOAD_MAX as u32;
+            let limit = dns_capture_limit();
+            if payload_len > limit {
+                payload_len = limit;
             }
             event.dns_payload_len = payload_len as u16;
             if payload_len > 0 {
@@ -699,6 +1449,9 @@ pub fn sys_enter_recvfrom(ctx: TracePointContext) -> u32 {
 }
 
 fn try_sys_enter_recvfrom(ctx: TracePointContext) -> Result<(), i64> {
+    if comm_excluded() {
+        return Ok(());
+    }
     let args: SysEnterArgs = unsafe { ctx.read_at(0)? };
     let fd = args.args[0] as i32;
     let buf = args.args[1];
@@ -745,15 +1498,30 @@ fn try_sys_exit_recvfrom(ctx: TracePointContext) -> Result<(), i64> {
     let mut parsed: ConnectArgs = unsafe { mem::zeroed() };
     let mut protocol = IPPROTO_UDP;
     if !parse_sockaddr(stored.addr_ptr, stored.addrlen, &mut parsed) {
-        if !lookup_connected(pid, stored.fd, &mut parsed) {
+        if !lookup_connected(pid, stored.fd, &mut parsed)
+            && !lookup_unix_connected(pid, stored.fd, &mut parsed)
+        {
             return Ok(());
         }
         protocol = 0;
     }
+    if parsed.family == AF_UNIX {
+        emit_unix_payload(
+            EVENT_UNIX_RECV,
+            stored.fd,
+            ret,
+            stored.buf,
+            stored.len,
+            parsed.unix_path_len,
+            &parsed.unix_path,
+        );
+        return Ok(());
+    }
     if parsed.family != AF_INET && parsed.family != AF_INET6 {
         return Ok(());
     }
     if parsed.port != 53 {
+        emit_net_recv(stored.fd, protocol, &parsed, ret);
         return Ok(());
     }
 
@@ -765,14 +1533,17 @@ fn try_sys_exit_recvfrom(ctx: TracePointContext) -> Result<(), i64> {
         event.fd = stored.fd;
         event.src_addr = parsed.addr;
         event.src_port = parsed.port;
+        event.flowinfo = parsed.flowinfo;
+        event.scope_id = parsed.scope_id;
         event.syscall_result = if ret >= 0 { 0 } else { ret };
 
         let mut payload_len = ret as u32;
         if payload_len > stored.len {
             payload_len = stored.len;
         }
-        if payload_len > DNS_PAYLOAD_MAX as u32 {
-            payload_len = DNS_PAYLOAD_MAX as u32;
+        let limit = dns_capture_limit();
+        if payload_len > limit {
+            payload_len = limit;
         }
         event.dns_payload_len = payload_len as u16;
         if payload_len > 0 {
@@ -796,6 +1567,9 @@ pub fn sys_enter_recvmsg(ctx: TracePointContext) -> u32 {
 }
 
 fn try_sys_enter_recvmsg(ctx: TracePointContext) -> Result<(), i64> {
+    if comm_excluded() {
+        return Ok(());
+    }
     let args: SysEnterArgs = unsafe { ctx.read_at(0)? };
     let fd = args.args[0] as i32;
     let msg_ptr = args.args[1];
@@ -836,13 +1610,6 @@ fn try_sys_exit_recvmsg(ctx: TracePointContext) -> Result<(), i64> {
     if !read_msghdr(stored.msg_ptr, &mut msg) {
         return Ok(());
     }
-    if msg.msg_iov == 0 || msg.msg_iovlen == 0 {
-        return Ok(());
-    }
-    let mut iov: Iovec = unsafe { mem::zeroed() };
-    if !read_iovec(msg.msg_iov, &mut iov) {
-        return Ok(());
-    }
 
     let mut parsed: ConnectArgs = unsafe { mem::zeroed() };
     let mut protocol = IPPROTO_UDP;
@@ -860,6 +1627,16 @@ fn try_sys_exit_recvmsg(ctx: TracePointContext) -> Result<(), i64> {
         return Ok(());
     }
     if parsed.port != 53 {
+        emit_net_recv(stored.fd, protocol, &parsed, ret);
+        return Ok(());
+    }
+
+    // Only the DNS path copies payload, so the iovec is read after classification.
+    if msg.msg_iov == 0 || msg.msg_iovlen == 0 {
+        return Ok(());
+    }
+    let mut iov: Iovec = unsafe { mem::zeroed() };
+    if !read_iovec(msg.msg_iov, &mut iov) {
         return Ok(());
     }
 
@@ -871,14 +1648,17 @@ fn try_sys_exit_recvmsg(ctx: TracePointContext) -> Result<(), i64> {
         event.fd = stored.fd;
         event.src_addr = parsed.addr;
         event.src_port = parsed.port;
+        event.flowinfo = parsed.flowinfo;
+        event.scope_id = parsed.scope_id;
         event.syscall_result = if ret >= 0 { 0 } else { ret };
 
         let mut payload_len = if ret > 0 { ret as u32 } else { iov.iov_len as u32 };
         if payload_len > iov.iov_len as u32 {
             payload_len = iov.iov_len as u32;
         }
-        if payload_len > DNS_PAYLOAD_MAX as u32 {
-            payload_len = DNS_PAYLOAD_MAX as u32;
+        let limit = dns_capture_limit();
+        if payload_len > limit {
+            payload_len = limit;
         }
         event.dns_payload_len = payload_len as u16;
         if payload_len > 0 {
@@ -894,6 +1674,282 @@ fn try_sys_exit_recvmsg(ctx: TracePointContext) -> Result<(), i64> {
     Ok(())
 }
 
+#[tracepoint(category = "syscalls", name = "sys_enter_bind")]
+pub fn sys_enter_bind(ctx: TracePointContext) -> u32 {
+    match try_sys_enter_bind(ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_sys_enter_bind(ctx: TracePointContext) -> Result<(), i64> {
+    if comm_excluded() {
+        return Ok(());
+    }
+    let args: SysEnterArgs = unsafe { ctx.read_at(0)? };
+    let fd = args.args[0] as i32;
+    let umyaddr = args.args[1];
+    let addrlen = args.args[2] as u32;
+
+    let mut parsed: ConnectArgs = unsafe { mem::zeroed() };
+    parsed.fd = fd;
+    if !parse_sockaddr(umyaddr, addrlen, &mut parsed) {
+        return Ok(());
+    }
+    if parsed.family != AF_INET && parsed.family != AF_INET6 {
+        return Ok(());
+    }
+
+    let pid = current_pid();
+    unsafe {
+        BIND_ARGS.insert(&pid, &parsed, 0)?;
+    }
+    Ok(())
+}
+
+#[tracepoint(category = "syscalls", name = "sys_exit_bind")]
+pub fn sys_exit_bind(ctx: TracePointContext) -> u32 {
+    match try_sys_exit_bind(ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_sys_exit_bind(ctx: TracePointContext) -> Result<(), i64> {
+    let args: SysExitArgs = unsafe { ctx.read_at(0)? };
+    let ret = args.ret;
+    let pid = current_pid();
+
+    let parsed = unsafe { BIND_ARGS.get(&pid) };
+    let parsed = match parsed {
+        Some(value) => *value,
+        None => return Ok(()),
+    };
+    let _ = unsafe { BIND_ARGS.remove(&pid) };
+
+    if ret == 0 {
+        let key = socket_key(pid, parsed.fd);
+        let bound = ConnectedSock {
+            family: parsed.family,
+            port: parsed.port,
+            addr: parsed.addr,
+            flowinfo: parsed.flowinfo,
+            scope_id: parsed.scope_id,
+        };
+        let _ = unsafe { BOUND_SOCKS.insert(&key, &bound, 0) };
+    }
+
+    with_event(|event| {
+        fill_common(event);
+        event.syscall_result = ret;
+        event.event_type = EVENT_NET_BIND;
+        event.family = parsed.family as u8;
+        event.protocol = 0;
+        event.fd = parsed.fd;
+        event.src_addr = parsed.addr;
+        event.src_port = parsed.port;
+        event.flowinfo = parsed.flowinfo;
+        event.scope_id = parsed.scope_id;
+        true
+    });
+
+    Ok(())
+}
+
+#[tracepoint(category = "syscalls", name = "sys_enter_listen")]
+pub fn sys_enter_listen(ctx: TracePointContext) -> u32 {
+    match try_sys_enter_listen(ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_sys_enter_listen(ctx: TracePointContext) -> Result<(), i64> {
+    if comm_excluded() {
+        return Ok(());
+    }
+    let args: SysEnterArgs = unsafe { ctx.read_at(0)? };
+    let fd = args.args[0] as i32;
+    let pid = current_pid();
+    unsafe {
+        LISTEN_ARGS.insert(&pid, &fd, 0)?;
+    }
+    Ok(())
+}
+
+#[tracepoint(category = "syscalls", name = "sys_exit_listen")]
+pub fn sys_exit_listen(ctx: TracePointContext) -> u32 {
+    match try_sys_exit_listen(ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_sys_exit_listen(ctx: TracePointContext) -> Result<(), i64> {
+    let args: SysExitArgs = unsafe { ctx.read_at(0)? };
+    let ret = args.ret;
+    let pid = current_pid();
+
+    let fd = unsafe { LISTEN_ARGS.get(&pid) };
+    let fd = match fd {
+        Some(value) => *value,
+        None => return Ok(()),
+    };
+    let _ = unsafe { LISTEN_ARGS.remove(&pid) };
+
+    // Sockets bound before tracing started (or implicitly bound by listen) have no entry; the
+    // loader falls back to /proc for the local endpoint.
+    let key = socket_key(pid, fd);
+    let bound = unsafe { BOUND_SOCKS.get(&key) }.copied();
+
+    with_event(|event| {
+        fill_common(event);
+        event.syscall_result = ret;
+        event.event_type = EVENT_NET_LISTEN;
+        event.protocol = IPPROTO_TCP;
+        event.fd = fd;
+        if let Some(bound) = bound {
+            event.family = bound.family as u8;
+            event.src_addr = bound.addr;
+            event.src_port = bound.port;
+            event.flowinfo = bound.flowinfo;
+            event.scope_id = bound.scope_id;
+        }
+        true
+    });
+
+    Ok(())
+}
+
+#[tracepoint(category = "syscalls", name = "sys_enter_write")]
+pub fn sys_enter_write(ctx: TracePointContext) -> u32 {
+    match try_sys_enter_write(ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_sys_enter_write(ctx: TracePointContext) -> Result<(), i64> {
+    let args: SysEnterArgs = unsafe { ctx.read_at(0)? };
+    let fd = args.args[0] as i32;
+    let pid = current_pid();
+    // Plain file I/O is by far the common case; bail on the fd lookup before the comm
+    // filter, which has to read the task's comm on every call.
+    let stored = match lookup_socket_io(pid, fd) {
+        Some(value) => value,
+        None => return Ok(()),
+    };
+    if comm_excluded() {
+        return Ok(());
+    }
+    unsafe {
+        WRITE_ARGS.insert(&pid, &stored, 0)?;
+    }
+    Ok(())
+}
+
+#[tracepoint(category = "syscalls", name = "sys_exit_write")]
+pub fn sys_exit_write(ctx: TracePointContext) -> u32 {
+    match try_sys_exit_write(ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_sys_exit_write(ctx: TracePointContext) -> Result<(), i64> {
+    let args: SysExitArgs = unsafe { ctx.read_at(0)? };
+    let ret = args.ret;
+    let pid = current_pid();
+
+    let stored = unsafe { WRITE_ARGS.get(&pid) };
+    let stored = match stored {
+        Some(value) => *value,
+        None => return Ok(()),
+    };
+    let _ = unsafe { WRITE_ARGS.remove(&pid) };
+
+    // No DNS classification here: write() carries no destination of its own, so the stored
+    // address is the only port context and DNS is already covered by the send/recv paths.
+    emit_socket_io(EVENT_NET_SEND, &stored, ret);
+    Ok(())
+}
+
+#[tracepoint(category = "syscalls", name = "sys_enter_read")]
+pub fn sys_enter_read(ctx: TracePointContext) -> u32 {
+    match try_sys_enter_read(ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_sys_enter_read(ctx: TracePointContext) -> Result<(), i64> {
+    let args: SysEnterArgs = unsafe { ctx.read_at(0)? };
+    let fd = args.args[0] as i32;
+    let pid = current_pid();
+    let stored = match lookup_socket_io(pid, fd) {
+        Some(value) => value,
+        None => return Ok(()),
+    };
+    if comm_excluded() {
+        return Ok(());
+    }
+    unsafe {
+        READ_ARGS.insert(&pid, &stored, 0)?;
+    }
+    Ok(())
+}
+
+#[tracepoint(category = "syscalls", name = "sys_exit_read")]
+pub fn sys_exit_read(ctx: TracePointContext) -> u32 {
+    match try_sys_exit_read(ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+fn try_sys_exit_read(ctx: TracePointContext) -> Result<(), i64> {
+    let args: SysExitArgs = unsafe { ctx.read_at(0)? };
+    let ret = args.ret;
+    let pid = current_pid();
+
+    let stored = unsafe { READ_ARGS.get(&pid) };
+    let stored = match stored {
+        Some(value) => *value,
+        None => return Ok(()),
+    };
+    let _ = unsafe { READ_ARGS.remove(&pid) };
+
+    // EOF and EAGAIN on non-blocking sockets would otherwise dominate the stream.
+    if ret <= 0 {
+        return Ok(());
+    }
+    emit_socket_io(EVENT_NET_RECV, &stored, ret);
+    Ok(())
+}
+
+#[tracepoint(category = "syscalls", name = "sys_enter_close")]
+pub fn sys_enter_close(ctx: TracePointContext) -> u32 {
+    match try_sys_enter_close(ctx) {
+        Ok(_) => 0,
+        Err(_) => 0,
+    }
+}
+
+// Forget per-fd socket state so a reused fd number (e.g. a regular file) is not mistaken for
+// the socket that previously held it by the write()/read() probes.
+fn try_sys_enter_close(ctx: TracePointContext) -> Result<(), i64> {
+    let args: SysEnterArgs = unsafe { ctx.read_at(0)? };
+    let fd = args.args[0] as i32;
+    let key = socket_key(current_pid(), fd);
+    unsafe {
+        let _ = CONNECTED_SOCKS.remove(&key);
+        let _ = UNIX_CONNECTED_SOCKS.remove(&key);
+        let _ = BOUND_SOCKS.remove(&key);
+        let _ = SOCK_PROTOS.remove(&key);
+    }
+    Ok(())
+}
+
 #[panic_handler]
 fn panic(_info: &core::panic::PanicInfo) -> ! {
     unsafe { core::hint::unreachable_unchecked() }
@@ -1,20 +1,57 @@
 use anyhow::{Context, Result};
-use aya::{maps::RingBuf, programs::TracePoint, Bpf};
+use aya::{
+    maps::{Array, HashMap, MapData, PerCpuArray, RingBuf},
+    programs::TracePoint,
+    Bpf, BpfLoader,
+};
 use bytemuck::{Pod, Zeroable};
 use serde_json::json;
 use signal_hook::consts::signal::{SIGINT, SIGTERM};
 use signal_hook::flag;
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
 use std::fs::{self, OpenOptions};
 use std::io::{BufWriter, Write};
 use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
 const TASK_COMM_LEN: usize = 16;
 const DNS_PAYLOAD_MAX: usize = 512;
 const UNIX_PATH_MAX: usize = 108;
+const EXCLUDE_COMM_MAX: usize = 64;
+
+const CONFIG_DNS_CAPTURE_BYTES: u32 = 0;
+const CONFIG_SAMPLING_RATE: u32 = 1;
+const CONFIG_RUN_TAG_LO: u32 = 2;
+const CONFIG_RUN_TAG_HI: u32 = 3;
+const CONFIG_PID_RATE_MAX_EVENTS: u32 = 4;
+const CONFIG_PID_RATE_WINDOW_MS: u32 = 5;
+const CONFIG_IGNORE_LOOPBACK: u32 = 6;
+const CONFIG_BATCH_EVENTS: u32 = 7;
+const CONFIG_AGENT_UID: u32 = 8;
+const CONFIG_SCHED_SWITCH_NEXT_PID_OFFSET: u32 = 9;
+
+// Must match AGENT_UID_UNSET in the eBPF program.
+const AGENT_UID_UNSET: u32 = u32::MAX;
+
+// Must match BATCH_CAPACITY in the eBPF program.
+const BATCH_CAPACITY: usize = 4;
+
+// Must match the STATS layout in the eBPF program.
+const STATS_DROPS: u32 = 0;
+const STATS_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+// Compiled-in EVENTS size; COLLECTOR_RINGBUF_BYTES overrides it at load time.
+const DEFAULT_RING_BUFFER_BYTES: u32 = 1 << 24;
+const MIN_RING_BUFFER_BYTES: u32 = 4096;
+const DEFAULT_SLOW_CONNECT_MS: u64 = 1000;
+// tracefs is mounted at either location depending on the kernel and distro.
+const SCHED_SWITCH_FORMAT_PATHS: [&str; 2] = [
+    "/sys/kernel/tracing/events/sched/sched_switch/format",
+    "/sys/kernel/debug/tracing/events/sched/sched_switch/format",
+];
 
 const AF_UNIX: u16 = 1;
 const AF_INET: u16 = 2;
@@ -28,6 +65,14 @@ const EVENT_NET_SEND: u8 = 2;
 const EVENT_DNS_QUERY: u8 = 3;
 const EVENT_DNS_RESPONSE: u8 = 4;
 const EVENT_UNIX_CONNECT: u8 = 5;
+const EVENT_UNIX_SEND: u8 = 6;
+const EVENT_UNIX_RECV: u8 = 7;
+const EVENT_NET_BIND: u8 = 8;
+const EVENT_NET_LISTEN: u8 = 9;
+const EVENT_NET_RECV: u8 = 10;
+
+// `Event::addr_unknown` markers set by the eBPF program; see `lookup_unknown_peer` there.
+const ADDR_UNKNOWN_UNTRACKED: u8 = 2;
 
 #[repr(C)]
 #[derive(Copy, Clone)]
@@ -35,7 +80,7 @@ struct Event {
     event_type: u8,
     family: u8,
     protocol: u8,
-    _pad: u8,
+    addr_unknown: u8,
     pid: u32,
     fd: i32,
     uid: u32,
@@ -53,11 +98,39 @@ struct Event {
     unix_path: [u8; UNIX_PATH_MAX],
     dns_payload_len: u16,
     dns_payload: [u8; DNS_PAYLOAD_MAX],
+    flowinfo: u32,
+    scope_id: u32,
+    run_tag: u64,
+    latency_ns: u64,
 }
 
 unsafe impl Zeroable for Event {}
 unsafe impl Pod for Event {}
 
+// Mirrors the kernel's batched ring-buffer record.
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct EventBatch {
+    count: u32,
+    _pad: u32,
+    staged_at_ns: u64,
+    events: [Event; BATCH_CAPACITY],
+}
+
+unsafe impl Zeroable for EventBatch {}
+unsafe impl Pod for EventBatch {}
+
+// Mirrors the kernel's PID_RATE value.
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct RateState {
+    window_start_ns: u64,
+    count: u32,
+    suppressed: u32,
+}
+
+unsafe impl aya::Pod for RateState {}
+
 fn main() -> Result<()> {
     set_memlock_rlimit().context("set memlock rlimit")?;
 
@@ -66,14 +139,119 @@ fn main() -> Result<()> {
     let output_path = env::var("COLLECTOR_EBPF_OUTPUT")
         .unwrap_or_else(|_| "/logs/ebpf.jsonl".to_string());
 
-    let mut bpf = Bpf::load_file(&bpf_path).context("load ebpf object")?;
+    let dns_capture_bytes = dns_capture_bytes_from_env()?;
+    let sampling_rate = sampling_rate_from_env()?;
+    let (pid_rate_max_events, pid_rate_window_ms) = pid_rate_from_env()?;
+    let run_tag = run_tag_from_env();
+    let ring_buffer_bytes = ring_buffer_bytes_from_env()?;
+    let slow_connect_ns = slow_connect_ms_from_env()?.saturating_mul(1_000_000);
+    let exclude_comm = exclude_comm_from_env()?;
+    let agent_uid = agent_uid_from_env()?;
+    let ignore_loopback = ignore_loopback_from_env()?;
+    let batch_events = batch_events_from_env()?;
+
+    // Ring buffer map sizes are fixed once the map is created, so the override has to be
+    // applied while loading the object rather than through the CONFIG map.
+    let mut bpf: Bpf = BpfLoader::new()
+        .set_max_entries("EVENTS", ring_buffer_bytes)
+        .load_file(&bpf_path)
+        .context("load ebpf object")?;
+
+    let mut config: Array<_, u32> =
+        Array::try_from(bpf.map_mut("CONFIG").context("missing CONFIG map")?)
+            .context("open config map")?;
+    config
+        .set(CONFIG_DNS_CAPTURE_BYTES, dns_capture_bytes, 0)
+        .context("set dns capture bytes")?;
+    config
+        .set(CONFIG_SAMPLING_RATE, sampling_rate, 0)
+        .context("set sampling rate")?;
+    config
+        .set(CONFIG_PID_RATE_MAX_EVENTS, pid_rate_max_events, 0)
+        .context("set pid rate limit")?;
+    config
+        .set(CONFIG_PID_RATE_WINDOW_MS, pid_rate_window_ms, 0)
+        .context("set pid rate window")?;
+    config
+        .set(CONFIG_IGNORE_LOOPBACK, u32::from(ignore_loopback), 0)
+        .context("set ignore loopback")?;
+    config
+        .set(CONFIG_BATCH_EVENTS, u32::from(batch_events), 0)
+        .context("set batch events")?;
+    // The sched_switch record layout differs across kernels, so the flush path reads
+    // next_pid at the offset this kernel reports instead of a compiled-in one.
+    if batch_events {
+        config
+            .set(
+                CONFIG_SCHED_SWITCH_NEXT_PID_OFFSET,
+                sched_switch_next_pid_offset()?,
+                0,
+            )
+            .context("set sched_switch next_pid offset")?;
+    }
+    config
+        .set(CONFIG_AGENT_UID, agent_uid, 0)
+        .context("set agent uid")?;
+    // The collector container is restarted on every run start and rotation cutover, so
+    // the tag written here is the one every event of this run carries.
+    config
+        .set(CONFIG_RUN_TAG_LO, run_tag as u32, 0)
+        .context("set run tag")?;
+    config
+        .set(CONFIG_RUN_TAG_HI, (run_tag >> 32) as u32, 0)
+        .context("set run tag")?;
+
+    // Excluded comms must be in place before the tracepoints attach, otherwise their
+    // first syscalls would still be captured.
+    let mut excluded: HashMap<_, [u8; TASK_COMM_LEN], u8> = HashMap::try_from(
+        bpf.map_mut("EXCLUDE_COMM")
+            .context("missing EXCLUDE_COMM map")?,
+    )
+    .context("open exclude comm map")?;
+    for comm in &exclude_comm {
+        excluded.insert(comm, 1, 0).context("set excluded comm")?;
+    }
+    if !exclude_comm.is_empty() && agent_uid == AGENT_UID_UNSET {
+        eprintln!(
+            "WARNING: COLLECTOR_AGENT_UID is unset, so COLLECTOR_EXCLUDE_COMM also applies to the agent's own processes"
+        );
+    }
 
+    let stats: PerCpuArray<MapData, u64> =
+        PerCpuArray::try_from(bpf.take_map("STATS").context("missing STATS map")?)
+            .context("open stats map")?;
+    let stats_path = stats_path_from_env(&output_path);
+    let pid_rate: HashMap<MapData, u32, RateState> =
+        HashMap::try_from(bpf.take_map("PID_RATE").context("missing PID_RATE map")?)
+            .context("open pid rate map")?;
+    let mut warned_pids = BTreeSet::new();
+
+    attach_tracepoint(&mut bpf, "sys_enter_socket")?;
+    attach_tracepoint(&mut bpf, "sys_exit_socket")?;
     attach_tracepoint(&mut bpf, "sys_enter_connect")?;
     attach_tracepoint(&mut bpf, "sys_exit_connect")?;
     attach_tracepoint(&mut bpf, "sys_enter_sendto")?;
     attach_tracepoint(&mut bpf, "sys_exit_sendto")?;
+    attach_tracepoint(&mut bpf, "sys_enter_sendmsg")?;
+    attach_tracepoint(&mut bpf, "sys_exit_sendmsg")?;
     attach_tracepoint(&mut bpf, "sys_enter_recvfrom")?;
     attach_tracepoint(&mut bpf, "sys_exit_recvfrom")?;
+    attach_tracepoint(&mut bpf, "sys_enter_recvmsg")?;
+    attach_tracepoint(&mut bpf, "sys_exit_recvmsg")?;
+    attach_tracepoint(&mut bpf, "sys_enter_bind")?;
+    attach_tracepoint(&mut bpf, "sys_exit_bind")?;
+    attach_tracepoint(&mut bpf, "sys_enter_listen")?;
+    attach_tracepoint(&mut bpf, "sys_exit_listen")?;
+    attach_tracepoint(&mut bpf, "sys_enter_write")?;
+    attach_tracepoint(&mut bpf, "sys_exit_write")?;
+    attach_tracepoint(&mut bpf, "sys_enter_read")?;
+    attach_tracepoint(&mut bpf, "sys_exit_read")?;
+    attach_tracepoint(&mut bpf, "sys_enter_close")?;
+    // Only batched emission needs the periodic flush, so the default path pays nothing
+    // per context switch.
+    if batch_events {
+        attach_tracepoint_in(&mut bpf, "sched", "sched_switch")?;
+    }
 
     let mut ring = RingBuf::try_from(
         bpf.map_mut("EVENTS").context("missing EVENTS map")?,
@@ -91,16 +269,38 @@ fn main() -> Result<()> {
     flag::register(SIGINT, Arc::clone(&running)).context("register SIGINT")?;
     flag::register(SIGTERM, Arc::clone(&running)).context("register SIGTERM")?;
 
+    let mut events_written: u64 = 0;
+    let mut last_stats_flush = Instant::now();
     while running.load(Ordering::Relaxed) {
+        if last_stats_flush.elapsed() >= STATS_FLUSH_INTERVAL {
+            // Stats are best-effort; a failed write must not stop event capture.
+            let rate_limited = rate_limited_pids(&pid_rate);
+            warn_rate_limited_once(&rate_limited, &mut warned_pids);
+            let _ = write_stats(
+                &stats,
+                &stats_path,
+                run_tag,
+                ring_buffer_bytes,
+                batch_events,
+                events_written,
+                &rate_limited,
+            );
+            last_stats_flush = Instant::now();
+        }
         if let Some(item) = ring.next() {
-            let data = &*item;
-            if data.len() >= std::mem::size_of::<Event>() {
-                let event = *bytemuck::from_bytes::<Event>(
-                    &data[..std::mem::size_of::<Event>()],
-                );
-                if let Some(line) = render_event(&event) {
+            for event in decode_record(&item) {
+                if is_slow_connect(&event, slow_connect_ns) {
+                    eprintln!(
+                        "WARNING: slow connect by pid {} ({}): {} ms",
+                        event.pid,
+                        bytes_to_string(&event.comm),
+                        event.latency_ns / 1_000_000
+                    );
+                }
+                if let Some(line) = render_event(&event, slow_connect_ns) {
                     writer.write_all(line.as_bytes())?;
                     writer.write_all(b"\n")?;
+                    events_written += 1;
                 }
             }
         } else {
@@ -109,19 +309,173 @@ fn main() -> Result<()> {
     }
 
     writer.flush()?;
+    let rate_limited = rate_limited_pids(&pid_rate);
+    let _ = write_stats(
+        &stats,
+        &stats_path,
+        run_tag,
+        ring_buffer_bytes,
+        batch_events,
+        events_written,
+        &rate_limited,
+    );
     Ok(())
 }
 
+// COLLECTOR_EBPF_STATS_OUTPUT overrides where counters are published; by default they
+// sit next to the raw log as ebpf.stats.json.
+fn stats_path_from_env(output_path: &str) -> PathBuf {
+    match env::var("COLLECTOR_EBPF_STATS_OUTPUT") {
+        Ok(value) if !value.trim().is_empty() => PathBuf::from(value.trim()),
+        _ => Path::new(output_path)
+            .parent()
+            .unwrap_or_else(|| Path::new("/logs"))
+            .join("ebpf.stats.json"),
+    }
+}
+
+// Suppressed-event counts for every pid the kernel limiter has dropped events for.
+fn rate_limited_pids(pid_rate: &HashMap<MapData, u32, RateState>) -> BTreeMap<u32, u32> {
+    pid_rate
+        .iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|(_, state)| state.suppressed > 0)
+        .map(|(pid, state)| (pid, state.suppressed))
+        .collect()
+}
+
+fn warn_rate_limited_once(rate_limited: &BTreeMap<u32, u32>, warned: &mut BTreeSet<u32>) {
+    for pid in rate_limited.keys() {
+        if warned.insert(*pid) {
+            eprintln!(
+                "WARNING: pid {pid} exceeded the per-pid event rate limit; suppressing its socket I/O events"
+            );
+        }
+    }
+}
+
+fn stat_total(stats: &PerCpuArray<MapData, u64>, index: u32) -> u64 {
+    stats
+        .get(&index, 0)
+        .map(|values| values.iter().sum())
+        .unwrap_or(0)
+}
+
+// Sums the per-CPU counters and replaces the stats file atomically so readers never
+// see a partial document.
+fn write_stats(
+    stats: &PerCpuArray<MapData, u64>,
+    path: &Path,
+    run_tag: u64,
+    ring_buffer_bytes: u32,
+    batch_events: bool,
+    events_written: u64,
+    rate_limited: &BTreeMap<u32, u32>,
+) -> Result<()> {
+    let mut emitted = serde_json::Map::new();
+    let mut emitted_total: u64 = 0;
+    for event_type in EVENT_NET_CONNECT..=EVENT_NET_RECV {
+        let count = stat_total(stats, event_type as u32);
+        emitted_total += count;
+        emitted.insert(event_type_name(event_type).to_string(), json!(count));
+    }
+    let document = json!({
+        "schema_version": "ebpf.stats.v1",
+        "updated_at": format_ts(monotonic_now_ns()),
+        "run_tag": format!("0x{run_tag:016x}"),
+        "drops": stat_total(stats, STATS_DROPS),
+        "events_emitted": emitted,
+        "events_emitted_total": emitted_total,
+        "events_written": events_written,
+        "ring_buffer_bytes": ring_buffer_bytes,
+        "batch_events": batch_events,
+        "rate_limited": rate_limited
+            .iter()
+            .map(|(pid, suppressed)| (pid.to_string(), json!(suppressed)))
+            .collect::<serde_json::Map<_, _>>(),
+        "rate_limited_total": rate_limited.values().map(|count| *count as u64).sum::<u64>(),
+    });
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, format!("{document}\n"))
+        .with_context(|| format!("write {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("publish {}", path.display()))?;
+    Ok(())
+}
+
+fn event_type_name(event_type: u8) -> &'static str {
+    match event_type {
+        EVENT_NET_CONNECT => "net_connect",
+        EVENT_NET_SEND => "net_send",
+        EVENT_DNS_QUERY => "dns_query",
+        EVENT_DNS_RESPONSE => "dns_response",
+        EVENT_UNIX_CONNECT => "unix_connect",
+        EVENT_UNIX_SEND => "unix_send",
+        EVENT_UNIX_RECV => "unix_recv",
+        EVENT_NET_BIND => "net_bind",
+        EVENT_NET_LISTEN => "net_listen",
+        EVENT_NET_RECV => "net_recv",
+        _ => "unknown",
+    }
+}
+
 fn attach_tracepoint(bpf: &mut Bpf, name: &str) -> Result<()> {
+    attach_tracepoint_in(bpf, "syscalls", name)
+}
+
+fn attach_tracepoint_in(bpf: &mut Bpf, category: &str, name: &str) -> Result<()> {
     let program: &mut TracePoint = bpf
         .program_mut(name)
         .context(format!("missing program {name}"))?
         .try_into()?;
     program.load()?;
-    program.attach("syscalls", name)?;
+    program.attach(category, name)?;
     Ok(())
 }
 
+fn sched_switch_next_pid_offset() -> Result<u32> {
+    for path in SCHED_SWITCH_FORMAT_PATHS {
+        if let Ok(content) = fs::read_to_string(path) {
+            return tracepoint_field_offset(&content, "next_pid")
+                .with_context(|| format!("no next_pid field in {path}"));
+        }
+    }
+    anyhow::bail!(
+        "COLLECTOR_BATCH_EVENTS needs the sched_switch format from tracefs; none of {} is readable",
+        SCHED_SWITCH_FORMAT_PATHS.join(", ")
+    )
+}
+
+// Offset of `field` in a tracefs `format` file, from lines such as
+// `field:pid_t next_pid;	offset:56;	size:4;	signed:1;`.
+fn tracepoint_field_offset(format: &str, field: &str) -> Option<u32> {
+    format.lines().find_map(|line| {
+        let mut parts = line.trim().split(';').map(str::trim);
+        let decl = parts.next()?.strip_prefix("field:")?;
+        let name = decl.split_whitespace().last()?;
+        let name = name.split('[').next().unwrap_or(name);
+        if name != field {
+            return None;
+        }
+        parts.find_map(|part| part.strip_prefix("offset:")?.parse().ok())
+    })
+}
+
+// A ring-buffer record is either a single Event or, with COLLECTOR_BATCH_EVENTS, an
+// EventBatch; the two have different sizes. Anything shorter than an Event is ignored.
+fn decode_record(data: &[u8]) -> Vec<Event> {
+    let event_size = std::mem::size_of::<Event>();
+    let batch_size = std::mem::size_of::<EventBatch>();
+    if data.len() >= batch_size {
+        let batch = bytemuck::pod_read_unaligned::<EventBatch>(&data[..batch_size]);
+        let count = (batch.count as usize).min(BATCH_CAPACITY);
+        return batch.events[..count].to_vec();
+    }
+    if data.len() >= event_size {
+        return vec![bytemuck::pod_read_unaligned::<Event>(&data[..event_size])];
+    }
+    Vec::new()
+}
+
 struct NetFields {
     protocol: String,
     family: String,
@@ -129,13 +483,24 @@ struct NetFields {
     src_port: u16,
     dst_ip: String,
     dst_port: u16,
+    mapped_v4: bool,
 }
 
 fn merge_net_fields(event: &Event, socket: Option<SocketInfo>) -> NetFields {
     let mut protocol = protocol_to_string(event.protocol).to_string();
     let mut family = family_to_string(event.family as u16).to_string();
-    let mut src_ip = addr_to_string(event.family as u16, &event.src_addr);
-    let mut dst_ip = addr_to_string(event.family as u16, &event.dst_addr);
+    let mut src_ip = scoped_addr_to_string(
+        event.pid,
+        event.family as u16,
+        &event.src_addr,
+        event.scope_id,
+    );
+    let mut dst_ip = scoped_addr_to_string(
+        event.pid,
+        event.family as u16,
+        &event.dst_addr,
+        event.scope_id,
+    );
     let mut src_port = event.src_port;
     let mut dst_port = event.dst_port;
     let mut src_missing = src_ip.is_empty() || is_zero_ip(&src_ip);
@@ -162,6 +527,13 @@ fn merge_net_fields(event: &Event, socket: Option<SocketInfo>) -> NetFields {
         }
     }
 
+    // An ipv6 socket whose endpoint renders as dotted-quad was talking to an IPv4-mapped
+    // address (from the event or /proc); the family stays ipv6 so the socket type is kept.
+    let mapped_v4 = family == "ipv6"
+        && [&src_ip, &dst_ip]
+            .iter()
+            .any(|ip| ip.parse::<Ipv4Addr>().is_ok());
+
     NetFields {
         protocol,
         family,
@@ -169,10 +541,18 @@ fn merge_net_fields(event: &Event, socket: Option<SocketInfo>) -> NetFields {
         src_port,
         dst_ip,
         dst_port,
+        mapped_v4,
     }
 }
 
-fn render_event(event: &Event) -> Option<String> {
+// Connects whose enter->exit latency reaches the threshold; 0 disables the check.
+fn is_slow_connect(event: &Event, slow_connect_ns: u64) -> bool {
+    slow_connect_ns > 0
+        && matches!(event.event_type, EVENT_NET_CONNECT | EVENT_UNIX_CONNECT)
+        && event.latency_ns >= slow_connect_ns
+}
+
+fn render_event(event: &Event, slow_connect_ns: u64) -> Option<String> {
     let ts = format_ts(event.ts);
     let comm = bytes_to_string(&event.comm);
     let pid = event.pid;
@@ -180,12 +560,17 @@ fn render_event(event: &Event) -> Option<String> {
     let uid = event.uid;
     let gid = event.gid;
     let cgroup_id = format!("0x{0:016x}", event.cgroup_id);
+    let run_tag = format!("0x{0:016x}", event.run_tag);
     let syscall_result = event.syscall_result;
 
     match event.event_type {
         EVENT_NET_CONNECT => {
             let socket = socket_info(pid, event.fd);
-            let net = merge_net_fields(event, socket);
+            let mut net = merge_net_fields(event, socket);
+            // connect() without a socket() record or a /proc entry is most likely TCP.
+            if net.protocol == "unknown" {
+                net.protocol = "tcp".to_string();
+            }
             Some(
                 json!({
                     "schema_version": "ebpf.v1",
@@ -197,6 +582,7 @@ fn render_event(event: &Event) -> Option<String> {
                     "gid": gid,
                     "comm": comm,
                     "cgroup_id": cgroup_id,
+                    "run_tag": run_tag,
                     "syscall_result": syscall_result,
                     "net": {
                         "protocol": net.protocol,
@@ -204,26 +590,46 @@ fn render_event(event: &Event) -> Option<String> {
                         "src_ip": net.src_ip,
                         "src_port": net.src_port,
                         "dst_ip": net.dst_ip,
-                        "dst_port": net.dst_port
+                        "dst_port": net.dst_port,
+                        "flowinfo": event.flowinfo,
+                        "scope_id": event.scope_id,
+                        "mapped_v4": net.mapped_v4,
+                        "latency_ns": event.latency_ns,
+                        "slow_connect": is_slow_connect(event, slow_connect_ns)
                     }
                 })
                 .to_string(),
             )
         }
-        EVENT_NET_SEND => {
+        EVENT_NET_SEND | EVENT_NET_RECV => {
             let socket = socket_info(pid, event.fd);
+            // The kernel never saw this fd's socket() either, so it may be a unix or netlink
+            // socket; only an inet socket in /proc makes it a network send.
+            if event.addr_unknown == ADDR_UNKNOWN_UNTRACKED && socket.is_none() {
+                return None;
+            }
             let net = merge_net_fields(event, socket);
+            let event_type = if event.event_type == EVENT_NET_SEND {
+                "net_send"
+            } else {
+                "net_recv"
+            };
+            // The kernel never saw this socket's connect(); only report the peer as
+            // unknown if /proc could not fill it in either.
+            let addr_unknown = event.addr_unknown != 0
+                && (net.dst_ip.is_empty() || is_zero_ip(&net.dst_ip));
             Some(
                 json!({
                     "schema_version": "ebpf.v1",
                     "ts": ts,
-                    "event_type": "net_send",
+                    "event_type": event_type,
                     "pid": pid,
                     "ppid": ppid,
                     "uid": uid,
                     "gid": gid,
                     "comm": comm,
                     "cgroup_id": cgroup_id,
+                    "run_tag": run_tag,
                     "syscall_result": syscall_result,
                     "net": {
                         "protocol": net.protocol,
@@ -232,7 +638,47 @@ fn render_event(event: &Event) -> Option<String> {
                         "src_port": net.src_port,
                         "dst_ip": net.dst_ip,
                         "dst_port": net.dst_port,
-                        "bytes": event.bytes
+                        "flowinfo": event.flowinfo,
+                        "scope_id": event.scope_id,
+                        "mapped_v4": net.mapped_v4,
+                        "bytes": event.bytes,
+                        "addr_unknown": addr_unknown
+                    }
+                })
+                .to_string(),
+            )
+        }
+        EVENT_NET_BIND | EVENT_NET_LISTEN => {
+            let socket = socket_info(pid, event.fd);
+            let net = merge_net_fields(event, socket);
+            let event_type = if event.event_type == EVENT_NET_BIND {
+                "net_bind"
+            } else {
+                "net_listen"
+            };
+            let wildcard = is_zero_ip(&net.src_ip);
+            Some(
+                json!({
+                    "schema_version": "ebpf.v1",
+                    "ts": ts,
+                    "event_type": event_type,
+                    "pid": pid,
+                    "ppid": ppid,
+                    "uid": uid,
+                    "gid": gid,
+                    "comm": comm,
+                    "cgroup_id": cgroup_id,
+                    "run_tag": run_tag,
+                    "syscall_result": syscall_result,
+                    "net": {
+                        "protocol": net.protocol,
+                        "family": net.family,
+                        "src_ip": net.src_ip,
+                        "src_port": net.src_port,
+                        "wildcard": wildcard,
+                        "mapped_v4": net.mapped_v4,
+                        "flowinfo": event.flowinfo,
+                        "scope_id": event.scope_id
                     }
                 })
                 .to_string(),
@@ -242,6 +688,7 @@ fn render_event(event: &Event) -> Option<String> {
             let payload = dns_payload(event);
             let (dns_bytes, mut transport) = dns_payload_view(&payload);
             let parsed = parse_dns(dns_bytes);
+            let questions = dns_questions_json(&parsed);
             let socket = socket_info(pid, event.fd);
             if transport == "udp" {
                 if let Some(info) = socket.as_ref() {
@@ -262,13 +709,18 @@ fn render_event(event: &Event) -> Option<String> {
                     "gid": gid,
                     "comm": comm,
                     "cgroup_id": cgroup_id,
+                    "run_tag": run_tag,
                     "syscall_result": syscall_result,
                     "dns": {
                         "transport": transport,
                         "query_name": parsed.query_name.unwrap_or_else(|| "".to_string()),
                         "query_type": parsed.query_type.unwrap_or_else(|| "".to_string()),
+                        "questions": questions,
                         "server_ip": server_ip,
-                        "server_port": server_port
+                        "server_port": server_port,
+                        "truncated": parsed.truncated,
+                        "capture_truncated": parsed.capture_truncated,
+                        "payload_hex": hex_encode(dns_bytes)
                     }
                 })
                 .to_string(),
@@ -278,6 +730,8 @@ fn render_event(event: &Event) -> Option<String> {
             let payload = dns_payload(event);
             let (dns_bytes, mut transport) = dns_payload_view(&payload);
             let parsed = parse_dns(dns_bytes);
+            let questions = dns_questions_json(&parsed);
+            let records = dns_records_json(&parsed);
             let socket = socket_info(pid, event.fd);
             if transport == "udp" {
                 if let Some(info) = socket.as_ref() {
@@ -297,13 +751,19 @@ fn render_event(event: &Event) -> Option<String> {
                     "gid": gid,
                     "comm": comm,
                     "cgroup_id": cgroup_id,
+                    "run_tag": run_tag,
                     "syscall_result": syscall_result,
                     "dns": {
                         "transport": transport,
                         "query_name": parsed.query_name.unwrap_or_else(|| "".to_string()),
                         "query_type": parsed.query_type.unwrap_or_else(|| "".to_string()),
                         "rcode": parsed.rcode.unwrap_or_else(|| "".to_string()),
-                        "answers": parsed.answers
+                        "questions": questions,
+                        "answers": parsed.answers,
+                        "records": records,
+                        "truncated": parsed.truncated,
+                        "capture_truncated": parsed.capture_truncated,
+                        "payload_hex": hex_encode(dns_bytes)
                     }
                 })
                 .to_string(),
@@ -323,11 +783,49 @@ fn render_event(event: &Event) -> Option<String> {
                     "gid": gid,
                     "comm": comm,
                     "cgroup_id": cgroup_id,
+                    "run_tag": run_tag,
+                    "syscall_result": syscall_result,
+                    "unix": {
+                        "path": path,
+                        "abstract": abstract_flag,
+                        "sock_type": sock_type,
+                        "latency_ns": event.latency_ns,
+                        "slow_connect": is_slow_connect(event, slow_connect_ns)
+                    }
+                })
+                .to_string(),
+            )
+        }
+        EVENT_UNIX_SEND | EVENT_UNIX_RECV => {
+            let (path, abstract_flag) = unix_path(event);
+            let sock_type = unix_socket_type(pid, event.fd).unwrap_or("unknown");
+            let payload = dns_payload(event);
+            let event_type = if event.event_type == EVENT_UNIX_SEND {
+                "unix_send"
+            } else {
+                "unix_recv"
+            };
+            Some(
+                json!({
+                    "schema_version": "ebpf.v1",
+                    "ts": ts,
+                    "event_type": event_type,
+                    "pid": pid,
+                    "ppid": ppid,
+                    "uid": uid,
+                    "gid": gid,
+                    "comm": comm,
+                    "cgroup_id": cgroup_id,
+                    "run_tag": run_tag,
                     "syscall_result": syscall_result,
                     "unix": {
                         "path": path,
                         "abstract": abstract_flag,
-                        "sock_type": sock_type
+                        "sock_type": sock_type,
+                        "bytes": event.bytes,
+                        "payload_len": payload.len(),
+                        "payload_truncated": (event.bytes as usize) > payload.len(),
+                        "payload_hex": hex_encode(&payload)
                     }
                 })
                 .to_string(),
@@ -338,7 +836,7 @@ fn render_event(event: &Event) -> Option<String> {
 }
 
 fn dns_payload(event: &Event) -> Vec<u8> {
-    let len = event.dns_payload_len as usize;
+    let len = (event.dns_payload_len as usize).min(DNS_PAYLOAD_MAX);
     event.dns_payload[..len].to_vec()
 }
 
@@ -350,11 +848,52 @@ fn bytes_to_string(bytes: &[u8]) -> String {
 fn addr_to_string(family: u16, addr: &[u8; 16]) -> String {
     match family {
         AF_INET => Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]).to_string(),
-        AF_INET6 => Ipv6Addr::from(*addr).to_string(),
+        AF_INET6 => ipv6_to_string(Ipv6Addr::from(*addr)),
         _ => "".to_string(),
     }
 }
 
+// `::ffff:a.b.c.d` is the same endpoint as `a.b.c.d`; rendering it in IPv4 form keeps
+// consumers from counting one peer under two addresses.
+fn ipv6_to_string(addr: Ipv6Addr) -> String {
+    match addr.to_ipv4_mapped() {
+        Some(v4) => v4.to_string(),
+        None => addr.to_string(),
+    }
+}
+
+// Link-local IPv6 addresses are only meaningful with their interface, so render
+// them as `fe80::1%eth0`. The index belongs to the traced process's network
+// namespace, so the name comes from its /proc view, not the loader's; `%<index>`
+// when the interface cannot be found there.
+fn scoped_addr_to_string(pid: u32, family: u16, addr: &[u8; 16], scope_id: u32) -> String {
+    let ip = addr_to_string(family, addr);
+    if family != AF_INET6 || scope_id == 0 {
+        return ip;
+    }
+    let is_link_local = addr[0] == 0xfe && (addr[1] & 0xc0) == 0x80;
+    if !is_link_local {
+        return ip;
+    }
+    let name = fs::read_to_string(format!("/proc/{pid}/net/if_inet6"))
+        .ok()
+        .and_then(|content| if_inet6_name(&content, scope_id))
+        .unwrap_or_else(|| scope_id.to_string());
+    format!("{ip}%{name}")
+}
+
+// Rows are `<addr> <ifindex hex> <prefix> <scope> <flags> <name>`; every
+// IPv6-enabled interface has at least its own link-local row.
+fn if_inet6_name(content: &str, index: u32) -> Option<String> {
+    content.lines().find_map(|line| {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 6 {
+            return None;
+        }
+        (u32::from_str_radix(parts[1], 16).ok()? == index).then(|| parts[5].to_string())
+    })
+}
+
 fn is_zero_ip(ip: &str) -> bool {
     ip == "0.0.0.0" || ip == "::"
 }
@@ -425,6 +964,181 @@ fn monotonic_now_ns() -> u64 {
     (ts.tv_sec as u64) * 1_000_000_000 + (ts.tv_nsec as u64)
 }
 
+// COLLECTOR_DNS_CAPTURE_BYTES caps how much of each DNS payload the kernel copies.
+// Values outside [0, DNS_PAYLOAD_MAX] are clamped rather than rejected.
+fn dns_capture_bytes_from_env() -> Result<u32> {
+    let raw = match env::var("COLLECTOR_DNS_CAPTURE_BYTES") {
+        Ok(value) if !value.trim().is_empty() => value,
+        _ => return Ok(DNS_PAYLOAD_MAX as u32),
+    };
+    let value: i64 = raw
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid COLLECTOR_DNS_CAPTURE_BYTES: {raw}"))?;
+    Ok(value.clamp(0, DNS_PAYLOAD_MAX as i64) as u32)
+}
+
+// COLLECTOR_SAMPLING_RATE keeps 1 in N net_send/net_recv events in-kernel; 1 (the
+// default) keeps everything.
+fn sampling_rate_from_env() -> Result<u32> {
+    let raw = match env::var("COLLECTOR_SAMPLING_RATE") {
+        Ok(value) if !value.trim().is_empty() => value,
+        _ => return Ok(1),
+    };
+    let value: u32 = raw
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid COLLECTOR_SAMPLING_RATE: {raw}"))?;
+    if value == 0 {
+        anyhow::bail!("COLLECTOR_SAMPLING_RATE must be at least 1");
+    }
+    Ok(value)
+}
+
+// COLLECTOR_PID_RATE_MAX_EVENTS caps socket I/O events per pid per
+// COLLECTOR_PID_RATE_WINDOW_MS window in-kernel; 0 (the default) disables the limiter.
+fn pid_rate_from_env() -> Result<(u32, u32)> {
+    let max_events = match env::var("COLLECTOR_PID_RATE_MAX_EVENTS") {
+        Ok(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid COLLECTOR_PID_RATE_MAX_EVENTS: {value}"))?,
+        _ => 0,
+    };
+    let window_ms: u32 = match env::var("COLLECTOR_PID_RATE_WINDOW_MS") {
+        Ok(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid COLLECTOR_PID_RATE_WINDOW_MS: {value}"))?,
+        _ => 1000,
+    };
+    if window_ms == 0 {
+        anyhow::bail!("COLLECTOR_PID_RATE_WINDOW_MS must be at least 1");
+    }
+    Ok((max_events, window_ms))
+}
+
+// COLLECTOR_SLOW_CONNECT_MS is the connect() latency at which events are flagged
+// `slow_connect` and a warning is logged; 0 disables the check.
+fn slow_connect_ms_from_env() -> Result<u64> {
+    let raw = match env::var("COLLECTOR_SLOW_CONNECT_MS") {
+        Ok(value) if !value.trim().is_empty() => value,
+        _ => return Ok(DEFAULT_SLOW_CONNECT_MS),
+    };
+    raw.trim()
+        .parse()
+        .with_context(|| format!("invalid COLLECTOR_SLOW_CONNECT_MS: {raw}"))
+}
+
+// COLLECTOR_RINGBUF_BYTES sizes the EVENTS ring buffer. The kernel requires a
+// page-aligned power of two, so values are rounded up and floored at 4 KiB.
+fn ring_buffer_bytes_from_env() -> Result<u32> {
+    let raw = match env::var("COLLECTOR_RINGBUF_BYTES") {
+        Ok(value) if !value.trim().is_empty() => value,
+        _ => return Ok(DEFAULT_RING_BUFFER_BYTES),
+    };
+    let value: u32 = raw
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid COLLECTOR_RINGBUF_BYTES: {raw}"))?;
+    value
+        .max(MIN_RING_BUFFER_BYTES)
+        .checked_next_power_of_two()
+        .with_context(|| format!("COLLECTOR_RINGBUF_BYTES too large: {raw}"))
+}
+
+// COLLECTOR_IGNORE_LOOPBACK drops net_connect/net_send/net_recv events to loopback peers
+// in-kernel; DNS and unix socket events are kept. Off unless set to a true value.
+fn ignore_loopback_from_env() -> Result<bool> {
+    let raw = match env::var("COLLECTOR_IGNORE_LOOPBACK") {
+        Ok(value) if !value.trim().is_empty() => value,
+        _ => return Ok(false),
+    };
+    parse_env_bool(&raw).with_context(|| format!("invalid COLLECTOR_IGNORE_LOOPBACK: {raw}"))
+}
+
+// COLLECTOR_BATCH_EVENTS stages events per CPU and submits them to the ring buffer
+// BATCH_CAPACITY at a time, flushing partial batches from sched_switch. Off by default;
+// compare ebpf.stats.json `drops` with it on and off before relying on it.
+fn batch_events_from_env() -> Result<bool> {
+    let raw = match env::var("COLLECTOR_BATCH_EVENTS") {
+        Ok(value) if !value.trim().is_empty() => value,
+        _ => return Ok(false),
+    };
+    parse_env_bool(&raw).with_context(|| format!("invalid COLLECTOR_BATCH_EVENTS: {raw}"))
+}
+
+fn parse_env_bool(raw: &str) -> Option<bool> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+// COLLECTOR_EXCLUDE_COMM is a comma-separated list of process names whose syscalls the
+// kernel drops before emitting anything. Names are matched the way the kernel stores
+// them: truncated to TASK_COMM_LEN - 1 bytes and NUL-padded.
+fn exclude_comm_from_env() -> Result<Vec<[u8; TASK_COMM_LEN]>> {
+    let raw = match env::var("COLLECTOR_EXCLUDE_COMM") {
+        Ok(value) if !value.trim().is_empty() => value,
+        _ => return Ok(Vec::new()),
+    };
+    let mut names = Vec::new();
+    for name in raw.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        let mut comm = [0u8; TASK_COMM_LEN];
+        let bytes = name.as_bytes();
+        let len = bytes.len().min(TASK_COMM_LEN - 1);
+        comm[..len].copy_from_slice(&bytes[..len]);
+        if !names.contains(&comm) {
+            names.push(comm);
+        }
+    }
+    if names.len() > EXCLUDE_COMM_MAX {
+        anyhow::bail!(
+            "COLLECTOR_EXCLUDE_COMM lists {} names; at most {EXCLUDE_COMM_MAX} are supported",
+            names.len()
+        );
+    }
+    Ok(names)
+}
+
+// COLLECTOR_AGENT_UID is the uid the agent runs as. The kernel never applies
+// COLLECTOR_EXCLUDE_COMM to the agent's own tasks: those running as this uid, or sharing
+// a cgroup with one that does. Unset means no task is treated as the agent's.
+fn agent_uid_from_env() -> Result<u32> {
+    let raw = match env::var("COLLECTOR_AGENT_UID") {
+        Ok(value) if !value.trim().is_empty() => value,
+        _ => return Ok(AGENT_UID_UNSET),
+    };
+    let value: u32 = raw
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid COLLECTOR_AGENT_UID: {raw}"))?;
+    if value == AGENT_UID_UNSET {
+        anyhow::bail!("COLLECTOR_AGENT_UID {value} is reserved");
+    }
+    Ok(value)
+}
+
+// run_tag is the 64-bit FNV-1a hash of LUX_RUN_ID (0 when unset), so consumers can map
+// a tag back to its run directory without a lookup table.
+fn run_tag_from_env() -> u64 {
+    match env::var("LUX_RUN_ID") {
+        Ok(run_id) if !run_id.trim().is_empty() => fnv1a64(run_id.trim().as_bytes()),
+        _ => 0,
+    }
+}
+
+fn fnv1a64(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
 fn set_memlock_rlimit() -> Result<()> {
     let rlim = libc::rlimit {
         rlim_cur: libc::RLIM_INFINITY,
@@ -533,7 +1247,7 @@ fn split_addr_port(addr: &str) -> Option<(&str, &str)> {
 fn parse_addr(family: u16, addr: &str) -> Option<String> {
     match family {
         AF_INET => Some(parse_ipv4_hex(addr)?.to_string()),
-        AF_INET6 => Some(parse_ipv6_hex(addr)?.to_string()),
+        AF_INET6 => Some(ipv6_to_string(parse_ipv6_hex(addr)?)),
         _ => None,
     }
 }
@@ -596,80 +1310,160 @@ fn unix_type_to_string(socket_type: u32) -> &'static str {
     }
 }
 
+const DNS_FLAG_TC: u16 = 0x0200;
+const DNS_MAX_ANSWER_IPS: usize = 4;
+const DNS_MAX_RECORDS: usize = 16;
+const DNS_MAX_POINTER_JUMPS: usize = 16;
+
+struct DnsQuestion {
+    name: String,
+    qtype: String,
+}
+
+struct DnsRecord {
+    name: String,
+    rtype: String,
+    ttl: u32,
+    data: String,
+}
+
 #[derive(Default)]
 struct DnsParsed {
     query_name: Option<String>,
     query_type: Option<String>,
     rcode: Option<String>,
     answers: Vec<String>,
+    questions: Vec<DnsQuestion>,
+    records: Vec<DnsRecord>,
+    // The message's TC bit: the sender cut the message down to fit the transport.
+    truncated: bool,
+    // The captured bytes end before the declared sections do.
+    capture_truncated: bool,
 }
 
+// Payloads are capped at DNS_PAYLOAD_MAX in-kernel, so running off the end of
+// the buffer marks the capture as truncated instead of discarding the message.
 fn parse_dns(payload: &[u8]) -> DnsParsed {
     let mut parsed = DnsParsed::default();
     if payload.len() < 12 {
+        parsed.capture_truncated = !payload.is_empty();
         return parsed;
     }
 
     let flags = u16::from_be_bytes([payload[2], payload[3]]);
     parsed.rcode = Some(rcode_to_string((flags & 0x0f) as u8));
+    parsed.truncated = flags & DNS_FLAG_TC != 0;
 
     let qdcount = u16::from_be_bytes([payload[4], payload[5]]) as usize;
     let ancount = u16::from_be_bytes([payload[6], payload[7]]) as usize;
 
     let mut offset = 12usize;
-    if qdcount > 0 {
-        if let Some((name, new_offset)) = read_dns_name(payload, offset, 0) {
-            parsed.query_name = Some(name);
-            offset = new_offset;
-            if payload.len() >= offset + 4 {
-                let qtype = u16::from_be_bytes([payload[offset], payload[offset + 1]]);
-                parsed.query_type = Some(qtype_to_string(qtype));
-                offset += 4;
-            }
+    for _ in 0..qdcount {
+        let Some((name, new_offset)) = read_dns_name(payload, offset) else {
+            parsed.capture_truncated = true;
+            break;
+        };
+        if payload.len() < new_offset + 4 {
+            parsed.capture_truncated = true;
+            break;
+        }
+        let qtype = u16::from_be_bytes([payload[new_offset], payload[new_offset + 1]]);
+        offset = new_offset + 4;
+        if parsed.query_name.is_none() {
+            parsed.query_name = Some(name.clone());
+            parsed.query_type = Some(qtype_to_string(qtype));
         }
+        parsed.questions.push(DnsQuestion {
+            name,
+            qtype: qtype_to_string(qtype),
+        });
     }
 
-    let mut answers = Vec::new();
-    let mut i = 0usize;
-    while i < ancount {
-        if let Some((_, new_offset)) = read_dns_name(payload, offset, 0) {
-            offset = new_offset;
-        } else {
+    if parsed.capture_truncated && parsed.questions.len() < qdcount {
+        return parsed;
+    }
+
+    for _ in 0..ancount {
+        let Some((name, new_offset)) = read_dns_name(payload, offset) else {
+            parsed.capture_truncated = true;
             break;
-        }
+        };
+        offset = new_offset;
         if payload.len() < offset + 10 {
+            parsed.capture_truncated = true;
             break;
         }
         let atype = u16::from_be_bytes([payload[offset], payload[offset + 1]]);
+        let ttl = u32::from_be_bytes([
+            payload[offset + 4],
+            payload[offset + 5],
+            payload[offset + 6],
+            payload[offset + 7],
+        ]);
         let rdlen = u16::from_be_bytes([payload[offset + 8], payload[offset + 9]]) as usize;
         offset += 10;
         if payload.len() < offset + rdlen {
+            parsed.capture_truncated = true;
             break;
         }
-        if atype == 1 && rdlen == 4 {
-            let ip = Ipv4Addr::new(
-                payload[offset],
-                payload[offset + 1],
-                payload[offset + 2],
-                payload[offset + 3],
-            );
-            answers.push(ip.to_string());
-        } else if atype == 28 && rdlen == 16 {
-            let mut addr = [0u8; 16];
-            addr.copy_from_slice(&payload[offset..offset + 16]);
-            answers.push(Ipv6Addr::from(addr).to_string());
+        let rdata = &payload[offset..offset + rdlen];
+        let data = match (atype, rdlen) {
+            (1, 4) => Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3]).to_string(),
+            (28, 16) => {
+                let mut addr = [0u8; 16];
+                addr.copy_from_slice(rdata);
+                Ipv6Addr::from(addr).to_string()
+            }
+            // CNAME, NS, and PTR rdata is a (possibly compressed) name.
+            (2 | 5 | 12, _) => read_dns_name(payload, offset)
+                .map(|(target, _)| target)
+                .unwrap_or_default(),
+            _ => String::new(),
+        };
+        if (atype == 1 || atype == 28)
+            && !data.is_empty()
+            && parsed.answers.len() < DNS_MAX_ANSWER_IPS
+        {
+            parsed.answers.push(data.clone());
         }
-        offset += rdlen;
-        i += 1;
-        if answers.len() >= 4 {
-            break;
+        if parsed.records.len() < DNS_MAX_RECORDS {
+            parsed.records.push(DnsRecord {
+                name,
+                rtype: qtype_to_string(atype),
+                ttl,
+                data,
+            });
         }
+        offset += rdlen;
     }
 
-    parsed.answers = answers;
     parsed
 }
 
+fn dns_questions_json(parsed: &DnsParsed) -> Vec<serde_json::Value> {
+    parsed
+        .questions
+        .iter()
+        .map(|q| json!({ "name": q.name, "type": q.qtype }))
+        .collect()
+}
+
+fn dns_records_json(parsed: &DnsParsed) -> Vec<serde_json::Value> {
+    parsed
+        .records
+        .iter()
+        .map(|r| json!({ "name": r.name, "type": r.rtype, "ttl": r.ttl, "data": r.data }))
+        .collect()
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        out.push_str(&format!("{byte:02x}"));
+    }
+    out
+}
+
 fn dns_payload_view(payload: &[u8]) -> (&[u8], &'static str) {
     if payload.len() >= 2 {
         let tcp_len = u16::from_be_bytes([payload[0], payload[1]]) as usize;
@@ -687,12 +1481,22 @@ fn dns_server_info(
 ) -> (String, u16) {
     let (mut ip, mut port) = if is_query {
         (
-            addr_to_string(event.family as u16, &event.dst_addr),
+            scoped_addr_to_string(
+                event.pid,
+                event.family as u16,
+                &event.dst_addr,
+                event.scope_id,
+            ),
             event.dst_port,
         )
     } else {
         (
-            addr_to_string(event.family as u16, &event.src_addr),
+            scoped_addr_to_string(
+                event.pid,
+                event.family as u16,
+                &event.src_addr,
+                event.scope_id,
+            ),
             event.src_port,
         )
     };
@@ -710,14 +1514,10 @@ fn dns_server_info(
     (ip, port)
 }
 
-fn read_dns_name(payload: &[u8], mut offset: usize, depth: u8) -> Option<(String, usize)> {
-    if depth > 5 {
-        return None;
-    }
-
+fn read_dns_name(payload: &[u8], mut offset: usize) -> Option<(String, usize)> {
     let mut labels: Vec<String> = Vec::new();
-    let mut jumped = false;
-    let mut jump_offset = 0usize;
+    let mut jumps = 0usize;
+    let mut jump_offset: Option<usize> = None;
 
     loop {
         if offset >= payload.len() {
@@ -732,12 +1532,16 @@ fn read_dns_name(payload: &[u8], mut offset: usize, depth: u8) -> Option<(String
             if offset + 1 >= payload.len() {
                 return None;
             }
+            // Bound pointer chasing so a malicious loop cannot spin forever.
+            jumps += 1;
+            if jumps > DNS_MAX_POINTER_JUMPS {
+                return None;
+            }
             let ptr = (((len & 0x3f) as usize) << 8) | payload[offset + 1] as usize;
-            if !jumped {
-                jump_offset = offset + 2;
+            if jump_offset.is_none() {
+                jump_offset = Some(offset + 2);
             }
             offset = ptr;
-            jumped = true;
             continue;
         }
         let label_len = len as usize;
@@ -750,12 +1554,7 @@ fn read_dns_name(payload: &[u8], mut offset: usize, depth: u8) -> Option<(String
         offset += label_len;
     }
 
-    let name = labels.join(".");
-    if jumped {
-        Some((name, jump_offset))
-    } else {
-        Some((name, offset))
-    }
+    Some((labels.join("."), jump_offset.unwrap_or(offset)))
 }
 
 fn qtype_to_string(qtype: u16) -> String {
@@ -763,8 +1562,13 @@ fn qtype_to_string(qtype: u16) -> String {
         1 => "A".to_string(),
         28 => "AAAA".to_string(),
         5 => "CNAME".to_string(),
+        2 => "NS".to_string(),
+        6 => "SOA".to_string(),
+        12 => "PTR".to_string(),
         15 => "MX".to_string(),
         16 => "TXT".to_string(),
+        33 => "SRV".to_string(),
+        65 => "HTTPS".to_string(),
         _ => format!("TYPE{qtype}"),
     }
 }
@@ -780,3 +1584,305 @@ fn rcode_to_string(rcode: u8) -> String {
         _ => format!("RCODE{rcode}"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn net_event(family: u16, src: [u8; 16], dst: [u8; 16]) -> Event {
+        let mut event = Event::zeroed();
+        event.event_type = EVENT_NET_CONNECT;
+        event.family = family as u8;
+        event.protocol = IPPROTO_TCP;
+        event.src_addr = src;
+        event.dst_addr = dst;
+        event.src_port = 40000;
+        event.dst_port = 443;
+        event
+    }
+
+    fn v4_bytes(a: u8, b: u8, c: u8, d: u8) -> [u8; 16] {
+        let mut bytes = [0u8; 16];
+        bytes[..4].copy_from_slice(&[a, b, c, d]);
+        bytes
+    }
+
+    #[test]
+    fn untracked_unknown_peer_send_needs_an_inet_socket() {
+        use std::os::unix::io::AsRawFd;
+
+        let udp = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let (unix, _peer) = std::os::unix::net::UnixDatagram::pair().unwrap();
+        let mut event = net_event(0, [0u8; 16], [0u8; 16]);
+        event.event_type = EVENT_NET_SEND;
+        event.protocol = 0;
+        event.pid = std::process::id();
+        event.addr_unknown = ADDR_UNKNOWN_UNTRACKED;
+
+        event.fd = unix.as_raw_fd();
+        assert!(render_event(&event, 0).is_none());
+
+        event.fd = udp.as_raw_fd();
+        let line: serde_json::Value =
+            serde_json::from_str(&render_event(&event, 0).unwrap()).unwrap();
+        assert_eq!(line["net"]["protocol"], "udp");
+        assert_eq!(line["net"]["addr_unknown"], true);
+
+        // A socket the kernel tracked is reported even when /proc cannot resolve it.
+        event.fd = unix.as_raw_fd();
+        event.addr_unknown = 1;
+        assert!(render_event(&event, 0).is_some());
+    }
+
+    #[test]
+    fn mapped_v6_renders_as_v4_and_keeps_family() {
+        let src = Ipv4Addr::new(10, 0, 0, 2).to_ipv6_mapped().octets();
+        let dst = Ipv4Addr::new(1, 2, 3, 4).to_ipv6_mapped().octets();
+        let net = merge_net_fields(&net_event(AF_INET6, src, dst), None);
+        assert_eq!(net.family, "ipv6");
+        assert_eq!(net.src_ip, "10.0.0.2");
+        assert_eq!(net.dst_ip, "1.2.3.4");
+        assert!(net.mapped_v4);
+    }
+
+    #[test]
+    fn pure_v6_is_not_mapped() {
+        let src = "2001:db8::2".parse::<Ipv6Addr>().unwrap().octets();
+        let dst = "2001:db8::1".parse::<Ipv6Addr>().unwrap().octets();
+        let net = merge_net_fields(&net_event(AF_INET6, src, dst), None);
+        assert_eq!(net.family, "ipv6");
+        assert_eq!(net.dst_ip, "2001:db8::1");
+        assert!(!net.mapped_v4);
+    }
+
+    #[test]
+    fn pure_v4_is_not_mapped() {
+        let net = merge_net_fields(
+            &net_event(AF_INET, v4_bytes(10, 0, 0, 2), v4_bytes(1, 2, 3, 4)),
+            None,
+        );
+        assert_eq!(net.family, "ipv4");
+        assert_eq!(net.dst_ip, "1.2.3.4");
+        assert!(!net.mapped_v4);
+    }
+
+    #[test]
+    fn decode_record_unpacks_single_events_and_batches() {
+        let mut event = net_event(AF_INET as u16, [0; 16], [0; 16]);
+        event.pid = 7;
+        let single = decode_record(bytemuck::bytes_of(&event));
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].pid, 7);
+
+        let mut batch = EventBatch::zeroed();
+        batch.count = 2;
+        batch.events[0] = event;
+        batch.events[1] = event;
+        batch.events[1].pid = 8;
+        let events = decode_record(bytemuck::bytes_of(&batch));
+        assert_eq!(
+            events.iter().map(|event| event.pid).collect::<Vec<_>>(),
+            vec![7, 8]
+        );
+
+        // A corrupt count never reads past the batch.
+        batch.count = 99;
+        assert_eq!(decode_record(bytemuck::bytes_of(&batch)).len(), BATCH_CAPACITY);
+        assert!(decode_record(&[0u8; 8]).is_empty());
+    }
+
+    fn dns_header(flags: u16, qdcount: u16, ancount: u16) -> Vec<u8> {
+        let mut msg = vec![0x12, 0x34];
+        msg.extend_from_slice(&flags.to_be_bytes());
+        msg.extend_from_slice(&qdcount.to_be_bytes());
+        msg.extend_from_slice(&ancount.to_be_bytes());
+        msg.extend_from_slice(&[0, 0, 0, 0]);
+        msg
+    }
+
+    fn dns_name(name: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        for label in name.split('.') {
+            out.push(label.len() as u8);
+            out.extend_from_slice(label.as_bytes());
+        }
+        out.push(0);
+        out
+    }
+
+    fn dns_question(name: &str, qtype: u16) -> Vec<u8> {
+        let mut out = dns_name(name);
+        out.extend_from_slice(&qtype.to_be_bytes());
+        out.extend_from_slice(&1u16.to_be_bytes());
+        out
+    }
+
+    #[test]
+    fn parse_dns_decodes_every_question() {
+        let mut msg = dns_header(0x0100, 2, 0);
+        msg.extend(dns_question("example.com", 1));
+        msg.extend(dns_question("example.org", 28));
+        let parsed = parse_dns(&msg);
+        assert!(!parsed.truncated);
+        assert!(!parsed.capture_truncated);
+        assert_eq!(parsed.query_name.as_deref(), Some("example.com"));
+        assert_eq!(parsed.query_type.as_deref(), Some("A"));
+        let questions: Vec<_> = parsed
+            .questions
+            .iter()
+            .map(|q| (q.name.as_str(), q.qtype.as_str()))
+            .collect();
+        assert_eq!(
+            questions,
+            vec![("example.com", "A"), ("example.org", "AAAA")]
+        );
+    }
+
+    #[test]
+    fn parse_dns_follows_compression_pointers_in_records() {
+        let mut msg = dns_header(0x8180, 1, 2);
+        msg.extend(dns_question("www.example.com", 1));
+        // CNAME www.example.com -> cdn.example.com, with both names compressed.
+        msg.extend_from_slice(&[0xc0, 12]);
+        msg.extend_from_slice(&5u16.to_be_bytes());
+        msg.extend_from_slice(&1u16.to_be_bytes());
+        msg.extend_from_slice(&60u32.to_be_bytes());
+        msg.extend_from_slice(&6u16.to_be_bytes());
+        let cname_at = msg.len();
+        msg.extend_from_slice(&[3, b'c', b'd', b'n', 0xc0, 16]);
+        msg.extend_from_slice(&[0xc0, cname_at as u8]);
+        msg.extend_from_slice(&1u16.to_be_bytes());
+        msg.extend_from_slice(&1u16.to_be_bytes());
+        msg.extend_from_slice(&300u32.to_be_bytes());
+        msg.extend_from_slice(&4u16.to_be_bytes());
+        msg.extend_from_slice(&[93, 184, 216, 34]);
+
+        let parsed = parse_dns(&msg);
+        assert!(!parsed.truncated);
+        assert_eq!(parsed.rcode.as_deref(), Some("NOERROR"));
+        assert_eq!(parsed.records.len(), 2);
+        assert_eq!(parsed.records[0].name, "www.example.com");
+        assert_eq!(parsed.records[0].rtype, "CNAME");
+        assert_eq!(parsed.records[0].data, "cdn.example.com");
+        assert_eq!(parsed.records[1].name, "cdn.example.com");
+        assert_eq!(parsed.records[1].ttl, 300);
+        assert_eq!(parsed.answers, vec!["93.184.216.34".to_string()]);
+    }
+
+    #[test]
+    fn parse_dns_keeps_what_parsed_before_truncation() {
+        let mut msg = dns_header(0x8180, 1, 2);
+        msg.extend(dns_question("example.com", 1));
+        msg.extend_from_slice(&[0xc0, 12]);
+        msg.extend_from_slice(&1u16.to_be_bytes());
+        msg.extend_from_slice(&1u16.to_be_bytes());
+        msg.extend_from_slice(&300u32.to_be_bytes());
+        msg.extend_from_slice(&4u16.to_be_bytes());
+        msg.extend_from_slice(&[1, 2, 3, 4]);
+        // Second answer cut off mid-header, as a capped in-kernel copy would be.
+        msg.extend_from_slice(&[0xc0, 12, 0, 1]);
+
+        let parsed = parse_dns(&msg);
+        assert!(parsed.capture_truncated);
+        assert!(!parsed.truncated);
+        assert_eq!(parsed.query_name.as_deref(), Some("example.com"));
+        assert_eq!(parsed.records.len(), 1);
+        assert_eq!(parsed.answers, vec!["1.2.3.4".to_string()]);
+
+        // A question cut off inside its name yields no question at all.
+        let mut msg = dns_header(0x0100, 1, 0);
+        msg.extend_from_slice(&dns_name("example.com")[..5]);
+        let parsed = parse_dns(&msg);
+        assert!(parsed.capture_truncated);
+        assert!(parsed.questions.is_empty());
+        assert!(parse_dns(&msg[..6]).capture_truncated);
+    }
+
+    #[test]
+    fn parse_dns_reports_the_tc_bit_apart_from_capture_truncation() {
+        let mut msg = dns_header(0x8380, 1, 0);
+        msg.extend(dns_question("example.com", 1));
+        let parsed = parse_dns(&msg);
+        assert!(parsed.truncated);
+        assert!(!parsed.capture_truncated);
+        assert_eq!(parsed.query_name.as_deref(), Some("example.com"));
+    }
+
+    #[test]
+    fn read_dns_name_rejects_pointer_loops() {
+        let mut msg = dns_header(0x0100, 1, 0);
+        msg.extend_from_slice(&[0xc0, 12]);
+        assert!(read_dns_name(&msg, 12).is_none());
+    }
+
+    #[test]
+    fn if_inet6_name_matches_hex_index() {
+        let content = "\
+00000000000000000000000000000001 01 80 10 80       lo
+fe800000000000000242ac11000a0002 0a 40 20 80     eth0
+";
+        assert_eq!(if_inet6_name(content, 1).as_deref(), Some("lo"));
+        assert_eq!(if_inet6_name(content, 10).as_deref(), Some("eth0"));
+        assert_eq!(if_inet6_name(content, 2), None);
+    }
+
+    #[test]
+    fn tracepoint_field_offset_reads_the_format_file() {
+        let format = "\
+name: sched_switch
+ID: 316
+format:
+\tfield:unsigned short common_type;\toffset:0;\tsize:2;\tsigned:0;
+
+\tfield:char prev_comm[16];\toffset:8;\tsize:16;\tsigned:0;
+\tfield:pid_t prev_pid;\toffset:24;\tsize:4;\tsigned:1;
+\tfield:char next_comm[16];\toffset:40;\tsize:16;\tsigned:0;
+\tfield:pid_t next_pid;\toffset:56;\tsize:4;\tsigned:1;
+
+print fmt: \"prev_comm=%s next_pid=%d\", REC->prev_comm, REC->next_pid
+";
+        assert_eq!(tracepoint_field_offset(format, "next_pid"), Some(56));
+        assert_eq!(tracepoint_field_offset(format, "next_comm"), Some(40));
+        assert_eq!(tracepoint_field_offset(format, "next_prio"), None);
+    }
+
+    #[test]
+    fn link_local_addresses_are_scoped_on_both_sides() {
+        let src = "fe80::2".parse::<Ipv6Addr>().unwrap().octets();
+        let dst = "fe80::1".parse::<Ipv6Addr>().unwrap().octets();
+        let mut event = net_event(AF_INET6, src, dst);
+        // No such pid, so the index cannot be named and renders numerically.
+        event.pid = u32::MAX;
+        event.scope_id = 7;
+        let net = merge_net_fields(&event, None);
+        assert_eq!(net.src_ip, "fe80::2%7");
+        assert_eq!(net.dst_ip, "fe80::1%7");
+
+        let global = "2001:db8::1".parse::<Ipv6Addr>().unwrap().octets();
+        assert_eq!(
+            scoped_addr_to_string(u32::MAX, AF_INET6, &global, 7),
+            "2001:db8::1"
+        );
+        assert_eq!(
+            scoped_addr_to_string(u32::MAX, AF_INET6, &dst, 0),
+            "fe80::1"
+        );
+    }
+
+    #[test]
+    fn env_bool_accepts_common_spellings() {
+        assert_eq!(parse_env_bool("true"), Some(true));
+        assert_eq!(parse_env_bool(" 1 "), Some(true));
+        assert_eq!(parse_env_bool("No"), Some(false));
+        assert_eq!(parse_env_bool("maybe"), None);
+    }
+
+    #[test]
+    fn proc_net_mapped_address_renders_as_v4() {
+        // /proc/net/tcp6 spelling of ::ffff:1.2.3.4.
+        assert_eq!(
+            parse_addr(AF_INET6, "0000000000000000FFFF000004030201").as_deref(),
+            Some("1.2.3.4")
+        );
+    }
+}
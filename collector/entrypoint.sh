@@ -20,9 +20,24 @@ MERGE_FILTER_CONFIG=${COLLECTOR_MERGE_FILTER_CONFIG:-/etc/collector/merge_filter
 MERGE_FILTER_LOG=${COLLECTOR_MERGE_FILTER_OUTPUT:-/logs/filtered_timeline.jsonl}
 MERGE_FILTER_BIN=${COLLECTOR_MERGE_FILTER_BIN:-/usr/local/bin/collector-merge-filtered}
 MERGE_FILTER_INTERVAL=${COLLECTOR_MERGE_FILTER_INTERVAL:-2}
+MANIFEST_DIR=${COLLECTOR_MANIFEST_DIR:-}
+MANIFEST_INTERVAL=${COLLECTOR_MANIFEST_INTERVAL:-30}
 
 mkdir -p /sys/kernel/tracing /sys/kernel/debug /sys/fs/bpf
 
+# Hash every evidence file under MANIFEST_DIR into manifest.sha256 (read by `lux verify`).
+write_manifest() {
+  [ -n "${MANIFEST_DIR}" ] && [ -d "${MANIFEST_DIR}" ] || return 0
+  tmp="${MANIFEST_DIR}/.manifest.sha256.tmp"
+  if (cd "${MANIFEST_DIR}" && find . -type f ! -name manifest.sha256 ! -name .manifest.sha256.tmp -print \
+    | LC_ALL=C sort | sed 's#^\./##' | while IFS= read -r file; do sha256sum "${file}"; done) >"${tmp}"; then
+    mv "${tmp}" "${MANIFEST_DIR}/manifest.sha256"
+  else
+    rm -f "${tmp}"
+    echo "collector: warning: failed to write evidence manifest in ${MANIFEST_DIR}" >&2
+  fi
+}
+
 ensure_log_file() {
   target="$1"
   mkdir -p "$(dirname "${target}")"
@@ -51,6 +66,19 @@ ensure_log_file "${EBPF_FILTER_LOG}"
 ensure_log_file "${EBPF_SUMMARY_LOG}"
 ensure_log_file "${MERGE_FILTER_LOG}"
 
+# Written at startup and refreshed while running, so an active run (or one whose collector
+# was SIGKILLed) still has a manifest to verify against.
+write_manifest
+if [ -n "${MANIFEST_DIR}" ]; then
+  (
+    while true; do
+      sleep "${MANIFEST_INTERVAL}"
+      write_manifest
+    done
+  ) &
+  MANIFEST_PID=$!
+fi
+
 auditd
 AUDITD_PID=$(pidof auditd 2>/dev/null || cat /var/run/auditd.pid 2>/dev/null || true)
 if ! /usr/sbin/auditctl -D; then
@@ -74,7 +102,14 @@ FILTER_PID=$!
   --poll-interval "${EBPF_FILTER_POLL}" &
 EBPF_FILTER_PID=$!
 
-/usr/bin/env COLLECTOR_EBPF_OUTPUT="${EBPF_LOG}" COLLECTOR_EBPF_BPF="${EBPF_OBJ}" "${EBPF_BIN}" &
+# The loader keeps the agent's own processes out of COLLECTOR_EXCLUDE_COMM; default to the
+# ownership uid the eBPF filter attributes events with.
+AGENT_UID=${COLLECTOR_AGENT_UID:-$(awk '/^ownership:/ { in_block = 1; next }
+  in_block && /^[^ #]/ { in_block = 0 }
+  in_block && $1 == "uid:" { print $2; exit }' "${EBPF_FILTER_CONFIG}" 2>/dev/null || true)}
+
+/usr/bin/env COLLECTOR_EBPF_OUTPUT="${EBPF_LOG}" COLLECTOR_EBPF_BPF="${EBPF_OBJ}" \
+  COLLECTOR_AGENT_UID="${AGENT_UID}" "${EBPF_BIN}" &
 EBPF_PID=$!
 
 if [ -f "${EBPF_SUMMARY_CONFIG}" ]; then
@@ -106,5 +141,18 @@ fi
 tail -F "${AUDIT_LOG}" &
 TAIL_PID=$!
 
-trap 'kill ${TAIL_PID} 2>/dev/null || true; kill ${FILTER_PID} 2>/dev/null || true; kill ${EBPF_FILTER_PID} 2>/dev/null || true; kill ${EBPF_SUMMARY_PID} 2>/dev/null || true; kill ${MERGE_PID} 2>/dev/null || true; kill ${EBPF_PID} 2>/dev/null || true; kill ${AUDITD_PID} 2>/dev/null || true' TERM INT
+# Runs on every exit path (signal, loader exit or a failing command under `set -e`) so the
+# final manifest covers everything the collector wrote.
+shutdown() {
+  trap - EXIT
+  for pid in ${MANIFEST_PID} ${TAIL_PID} ${FILTER_PID} ${EBPF_FILTER_PID} ${EBPF_SUMMARY_PID} \
+    ${MERGE_PID} ${EBPF_PID} ${AUDITD_PID}; do
+    kill "${pid}" 2>/dev/null || true
+  done
+  wait 2>/dev/null || true
+  write_manifest
+}
+trap shutdown EXIT
+trap 'exit 143' TERM
+trap 'exit 130' INT
 wait "${EBPF_PID}"
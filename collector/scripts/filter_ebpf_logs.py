@@ -804,16 +804,18 @@ def build_output(
         "syscall_result": event.get("syscall_result"),
         "agent_owned": True,
     }
+    if event.get("run_tag"):
+        output["run_tag"] = event.get("run_tag")
     if job_id:
         output["job_id"] = job_id
     if cmd:
         output["cmd"] = cmd
     event_type = event.get("event_type")
-    if event_type in ("net_connect", "net_send") and event.get("net") is not None:
+    if event_type in ("net_connect", "net_send", "net_recv", "net_bind", "net_listen") and event.get("net") is not None:
         output["net"] = event.get("net")
     if event_type in ("dns_query", "dns_response") and event.get("dns") is not None:
         output["dns"] = event.get("dns")
-    if event_type == "unix_connect" and event.get("unix") is not None:
+    if event_type in ("unix_connect", "unix_send", "unix_recv") and event.get("unix") is not None:
         output["unix"] = event.get("unix")
     return output
 
@@ -909,11 +911,11 @@ def main() -> int:
             comm = event.get("comm") or ""
             if comm in exclude_comm:
                 continue
-            if event_type == "unix_connect":
+            if event_type in ("unix_connect", "unix_send", "unix_recv"):
                 unix = event.get("unix") or {}
                 if unix.get("path") in exclude_unix_paths:
                     continue
-            if event_type in ("net_connect", "net_send"):
+            if event_type in ("net_connect", "net_send", "net_recv"):
                 net = event.get("net") or {}
                 dst_ip = net.get("dst_ip")
                 dst_port = net.get("dst_port")
@@ -13,6 +13,11 @@ try:
 except ImportError:
     yaml = None
 
+# Defaults for the DNS row options. ebpf_summary.yaml leaves them unset, so they are
+# only defined here.
+DEFAULT_INCLUDE_DNS_ROWS = True
+DEFAULT_INCLUDE_DNS_ANSWERS = True
+
 
 class SendEvent:
     __slots__ = ("ts", "bytes", "protocol", "comm", "ppid", "uid", "gid")
@@ -89,6 +94,56 @@ def protocol_candidate(value: str | None) -> str | None:
     return value
 
 
+DNS_ANSWER_TYPES = ("A", "AAAA", "CNAME")
+
+
+def dns_answer_row(event: dict, dns: dict, schema_version: str) -> dict | None:
+    """Build a dns_answer row linking the query name to its resolved addresses."""
+    query_name = dns.get("query_name")
+    if not query_name:
+        return None
+    answers = []
+    for record in dns.get("records") or []:
+        if not isinstance(record, dict) or record.get("type") not in DNS_ANSWER_TYPES:
+            continue
+        if not record.get("data"):
+            continue
+        answers.append({
+            "name": record.get("name"),
+            "type": record.get("type"),
+            "ttl": parse_int(record.get("ttl")),
+            "data": record.get("data"),
+        })
+    addresses = [a["data"] for a in answers if a["type"] in ("A", "AAAA")]
+    if not addresses:
+        # Older loaders only emitted the flat address list.
+        addresses = [ip for ip in dns.get("answers") or [] if ip]
+    if not answers and not addresses:
+        return None
+
+    row = {
+        "schema_version": schema_version,
+        "session_id": event.get("session_id", "unknown"),
+        "ts": event.get("ts"),
+        "source": "ebpf",
+        "event_type": "dns_answer",
+        "pid": parse_int(event.get("pid")),
+        "ppid": parse_int(event.get("ppid")),
+        "uid": parse_int(event.get("uid")),
+        "gid": parse_int(event.get("gid")),
+        "comm": event.get("comm") or "",
+        "query_name": query_name,
+        "query_type": dns.get("query_type"),
+        "addresses": addresses,
+        "answers": answers,
+        "truncated": bool(dns.get("truncated", False)),
+        "capture_truncated": bool(dns.get("capture_truncated", False)),
+    }
+    if event.get("job_id"):
+        row["job_id"] = event["job_id"]
+    return row
+
+
 def main() -> int:
     args = parse_args()
     cfg = load_config(args.config)
@@ -106,6 +161,8 @@ def main() -> int:
     dns_lookback_sec = float(cfg.get("dns_lookback_sec", 2))
     min_send_count = int(cfg.get("min_send_count", 0))
     min_bytes_sent_total = int(cfg.get("min_bytes_sent_total", 0))
+    include_dns_rows = bool(cfg.get("include_dns_rows", DEFAULT_INCLUDE_DNS_ROWS))
+    include_dns_answers = bool(cfg.get("include_dns_answers", DEFAULT_INCLUDE_DNS_ANSWERS))
     if dns_lookback_sec < 0:
         dns_lookback_sec = 0
 
@@ -140,11 +197,20 @@ def main() -> int:
                 if session_id == "unknown" and not job_id:
                     continue
 
+                if include_dns_rows and event_type in ("dns_query", "dns_response"):
+                    passthrough = dict(event)
+                    passthrough["schema_version"] = schema_version
+                    passthrough_rows.append((ts_dt, passthrough))
+
                 if event_type == "dns_response":
                     pid = parse_int(event.get("pid"))
                     dns = event.get("dns") or event.get("details", {}).get("dns")
                     if pid is None or not isinstance(dns, dict):
                         continue
+                    if include_dns_answers:
+                        answer_row = dns_answer_row(event, dns, schema_version)
+                        if answer_row is not None:
+                            passthrough_rows.append((ts_dt, answer_row))
                     query_name = dns.get("query_name")
                     answers = dns.get("answers") or []
                     if query_name:
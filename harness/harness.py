@@ -4,6 +4,7 @@ import datetime as dt
 import fcntl
 import json
 import os
+import re
 import selectors
 import shlex
 import signal
@@ -44,6 +45,12 @@ RUN_CMD_TEMPLATE = os.getenv("HARNESS_RUN_CMD_TEMPLATE", "").strip() or "bash -l
 DEFAULT_CWD = os.getenv("HARNESS_AGENT_WORKDIR", "/work")
 ROOT_PID_TIMEOUT_SEC = float(os.getenv("HARNESS_ROOT_PID_TIMEOUT_SEC", "15"))
 ROOT_PID_POLL_SEC = float(os.getenv("HARNESS_ROOT_PID_POLL_SEC", "0.2"))
+RUN_TIMEOUT_SEC = int(os.getenv("HARNESS_RUN_TIMEOUT_SEC", "0") or "0") or None
+RUN_ID = os.getenv("LUX_RUN_ID", "")
+
+# `{name}` or `{env:KEY}`; `${...}` shell expansions and brace expansions such as
+# `{a,b}` are left alone.
+RUN_TEMPLATE_PLACEHOLDER_RE = re.compile(r"(?<!\$)\{(env:[^{}]*|[A-Za-z_][A-Za-z0-9_]*)\}")
 
 JOBS = {}
 JOBS_LOCK = threading.Lock()
@@ -261,6 +268,33 @@ def update_json(path: str, updates: dict) -> None:
     write_json(path, payload)
 
 
+def render_run_command(template: str, prompt: str, cwd: str, env: dict) -> str:
+    """Expand run template placeholders, shell-quoting every substituted value.
+
+    Raises ValueError for unknown placeholders and for `{env:KEY}` lookups the job env
+    does not define.
+    """
+    values = {
+        "prompt": prompt,
+        "start_dir": cwd,
+        "workspace": DEFAULT_CWD,
+        "run_id": RUN_ID,
+    }
+
+    def substitute(match: re.Match) -> str:
+        name = match.group(1)
+        if name.startswith("env:"):
+            key = name[len("env:"):]
+            if key not in env:
+                raise ValueError(f"run template references {{{name}}} but env {key} is not set")
+            return shlex.quote(env[key])
+        if name not in values:
+            raise ValueError(f"run template has unknown placeholder {{{name}}}")
+        return shlex.quote(values[name])
+
+    return RUN_TEMPLATE_PLACEHOLDER_RE.sub(substitute, template)
+
+
 def build_remote_command(
     prompt: str,
     cwd: str,
@@ -282,10 +316,7 @@ def build_remote_command(
     if prefix:
         cmd += f"{prefix} "
     timeout_prefix = f"timeout {int(timeout)} " if timeout else ""
-    if "{prompt}" in RUN_CMD_TEMPLATE:
-        run_cmd = RUN_CMD_TEMPLATE.replace("{prompt}", shlex.quote(prompt))
-    else:
-        run_cmd = RUN_CMD_TEMPLATE
+    run_cmd = render_run_command(RUN_CMD_TEMPLATE, prompt, cwd, env)
     cmd += f"exec {timeout_prefix}{run_cmd}"
     return wrap_with_setsid(cmd.strip(), with_ctty=False)
 
@@ -475,7 +506,11 @@ def handle_run(payload: dict) -> tuple[dict, int]:
         return {"error": cwd_err}, 400
     env = sanitize_env(payload.get("env", {}))
     timeout = payload.get("timeout_sec")
-    timeout = int(timeout) if isinstance(timeout, (int, float)) and timeout > 0 else None
+    timeout = int(timeout) if isinstance(timeout, (int, float)) and timeout > 0 else RUN_TIMEOUT_SEC
+    try:
+        render_run_command(RUN_CMD_TEMPLATE, prompt, cwd, env)
+    except ValueError as exc:
+        return {"error": str(exc)}, 400
 
     job_id = f"job_{dt.datetime.utcnow().strftime('%Y%m%d_%H%M%S')}_{uuid.uuid4().hex[:4]}"
 
@@ -551,6 +586,9 @@ class HarnessHandler(BaseHTTPRequestHandler):
     def do_GET(self) -> None:
         if not self._authorized():
             return
+        if self.path == "/healthz":
+            self._json_response({"ok": True}, 200)
+            return
         if not self.path.startswith("/jobs/"):
             self._json_response({"error": "not found"}, 404)
             return
@@ -595,6 +633,17 @@ def resolve_tui_name(cli_name: str | None) -> tuple[str | None, str | None]:
     return trimmed, None
 
 
+def tui_session_env() -> dict:
+    # `lux tui --env` sets each variable on this container and lists the names here.
+    raw = os.getenv("HARNESS_TUI_ENV_KEYS", "")
+    env = {}
+    for key in raw.split(","):
+        key = key.strip()
+        if key and key in os.environ:
+            env[key] = os.environ[key]
+    return sanitize_env(env)
+
+
 def run_tui(tui_name: str | None) -> int:
     ensure_dir(LOG_DIR)
     ensure_dir(SESSION_DIR)
@@ -630,7 +679,8 @@ def run_tui(tui_name: str | None) -> int:
     pid_path = root_pid_path(session_id)
     sid_path = root_sid_path(session_id)
     # Keep TUI on the native SSH PTY path; capture SID from that session without forcing a new session.
-    remote_cmd = f"{root_marker_prefix(pid_path, sid_path)}cd {shlex.quote(DEFAULT_CWD)} && exec {TUI_CMD}"
+    env_prefix = "".join(f"{key}={shlex.quote(value)} " for key, value in tui_session_env().items())
+    remote_cmd = f"{root_marker_prefix(pid_path, sid_path)}cd {shlex.quote(DEFAULT_CWD)} && {env_prefix}exec {TUI_CMD}"
     cmd = ssh_base_args() + ["-tt", ssh_target(), "bash", "-lc", shlex.quote(remote_cmd)] # Build the ssh command with -tt (force PTY allocation)
 
     ''' 
@@ -0,0 +1,30 @@
+use std::env;
+use std::process::Command;
+
+// Compiles the commit and target triple into `lux version`. Builds outside a git checkout
+// (or without git) report `unknown`; `LUX_GIT_COMMIT` overrides the lookup.
+fn main() {
+    println!("cargo:rerun-if-env-changed=LUX_GIT_COMMIT");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+    let commit = env::var("LUX_GIT_COMMIT")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .or_else(git_commit)
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=LUX_GIT_COMMIT={commit}");
+    let target = env::var("TARGET").unwrap_or_else(|_| "unknown".to_string());
+    println!("cargo:rustc-env=LUX_BUILD_TARGET={target}");
+}
+
+fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let commit = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!commit.is_empty()).then_some(commit)
+}
@@ -1,18 +1,21 @@
 use chrono::{DateTime, Utc};
-use clap::{Parser, Subcommand};
-use dialoguer::console::style;
+use clap::{ArgGroup, Parser, Subcommand};
+use dialoguer::console::StyledObject;
 use dialoguer::console::Term;
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::{Confirm, Input, MultiSelect, Password, Select};
 use dirs::home_dir;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::collections::{BTreeMap, VecDeque};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::env;
 use std::fs;
 use std::io;
 use std::io::IsTerminal;
-use std::io::{Read, Write};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+#[cfg(unix)]
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
 #[cfg(unix)]
 use std::os::unix::ffi::OsStrExt;
 #[cfg(unix)]
@@ -21,31 +24,93 @@ use std::os::unix::fs::symlink;
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use std::sync::{Arc, Condvar, Mutex};
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
 use std::thread;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
 const DEFAULT_CONFIG_YAML: &str = include_str!("../config/default.yaml");
 const RUNTIME_BYPASS_ENV: &str = "LUX_RUNTIME_BYPASS";
+const RUNTIME_TCP_ENV: &str = "LUX_RUNTIME_TCP";
+const RUNTIME_TOKEN_ENV: &str = "LUX_RUNTIME_TOKEN";
+const DEFAULT_RUNTIME_MAX_CONNECTIONS: u32 = 64;
+const DEFAULT_RUNTIME_EVENTS_MAX_BYTES: u64 = 32 * 1024 * 1024;
+const DEFAULT_RUNTIME_START_TIMEOUT_SEC: u64 = 30;
+// `runtime up` readiness polling starts fast and backs off to this ceiling.
+const RUNTIME_READY_INITIAL_BACKOFF: Duration = Duration::from_millis(50);
+const RUNTIME_READY_MAX_BACKOFF: Duration = Duration::from_secs(1);
+const RUNTIME_EVENTS_KEEP_GENERATIONS: u32 = 3;
+// Must match DNS_PAYLOAD_MAX in the collector's eBPF program.
+const COLLECTOR_DNS_PAYLOAD_MAX: u32 = 512;
+// Compiled-in EVENTS ring buffer size; the loader resizes the map to collector.ringbuf_bytes.
+const COLLECTOR_DEFAULT_RINGBUF_BYTES: u32 = 1 << 24;
+const COLLECTOR_MIN_RINGBUF_BYTES: u32 = 4096;
+// Must match EXCLUDE_COMM_MAX in the collector's eBPF program.
+const COLLECTOR_EXCLUDE_COMM_MAX: usize = 64;
+// Must match the `agent` image in the bundled compose.yml; `providers.<name>.image` replaces it.
+const AGENT_IMAGE_REPO: &str = "ghcr.io/scottmaran/lux-agent";
+const RUNTIME_BUSY_RETRY_AFTER_SEC: u64 = 1;
 const UI_LOCAL_HOST: &str = "127.0.0.1";
 const UI_LOCAL_PORT: u16 = 8090;
+const RUN_JOB_POLL_INTERVAL_MS: u64 = 1000;
+const JOBS_LOGS_FOLLOW_INTERVAL_MS: u64 = 500;
+const RUNTIME_LOGS_FOLLOW_INTERVAL_MS: u64 = 500;
+const RUN_TIMEOUT_GRACE_SEC: u64 = 10;
+// How long a `run` forwarded through the runtime daemon waits on a job that has no timeout;
+// each forwarded command holds a daemon slot until it returns.
+const RUN_PROXIED_WAIT_SEC: u64 = 3600;
+const DOWNLOAD_MAX_ATTEMPTS: u32 = 3;
+// Wait before the first download retry; doubled before each later one.
+const DOWNLOAD_RETRY_BACKOFF_MS: u64 = 500;
+// Written into versions/<tag>/ once its bundle passed the checksum; `update apply` reuses such dirs.
+const UPDATE_VERIFIED_MARKER: &str = ".lux-verified";
+const HARNESS_READY_DEFAULT_TIMEOUT_SEC: u64 = 60;
+const HARNESS_READY_POLL_INTERVAL_MS: u64 = 500;
+const RUNTIME_EVENTS_DRAIN_IDLE_MS: u64 = 1000;
+const RUNTIME_DOWN_POLL_INTERVAL_MS: u64 = 100;
+const RUNTIME_SIGTERM_GRACE_MS: u64 = 3000;
 #[cfg(unix)]
 const UNIX_SOCKET_PATH_LIMIT_BYTES: usize = 100;
 
+const LUX_GIT_COMMIT: &str = env!("LUX_GIT_COMMIT");
+const LUX_BUILD_TARGET: &str = env!("LUX_BUILD_TARGET");
+const LUX_LONG_VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    " (",
+    env!("LUX_GIT_COMMIT"),
+    ", ",
+    env!("LUX_BUILD_TARGET"),
+    ")"
+);
+
 #[derive(Parser, Debug)]
 #[command(
     name = "lux",
     version,
+    long_version = LUX_LONG_VERSION,
     about = "Agent observability CLI for setup, lifecycle control, and log review"
 )]
 struct Cli {
     #[arg(long, global = true)]
     config: Option<PathBuf>,
     #[arg(long, global = true)]
+    config_dir: Option<PathBuf>,
+    #[arg(long, global = true)]
     json: bool,
+    #[arg(long, global = true, conflicts_with = "json_compact")]
+    json_pretty: bool,
+    #[arg(long, global = true)]
+    json_compact: bool,
+    #[arg(long, global = true)]
+    quiet: bool,
+    #[arg(long, global = true)]
+    no_color: bool,
     #[arg(long = "compose-file", global = true)]
     compose_file: Vec<PathBuf>,
+    #[arg(long, global = true)]
+    project_name: Option<String>,
     #[arg(long, global = true, hide = true)]
     bundle_dir: Option<PathBuf>,
     #[arg(long, global = true, hide = true)]
@@ -71,6 +136,8 @@ enum Commands {
         no_apply: bool,
         #[arg(long, default_value_t = false)]
         dry_run: bool,
+        #[arg(long, requires = "defaults")]
+        import_env: Option<String>,
     },
     #[command(about = "Start collector-only or provider plane services")]
     Up {
@@ -83,9 +150,25 @@ enum Commands {
         #[arg(long, value_parser = ["always", "never", "missing"]) ]
         pull: Option<String>,
         #[arg(long)]
+        pull_timeout_sec: Option<u64>,
+        #[arg(long)]
+        build: bool,
+        #[arg(long)]
+        no_cache: bool,
+        #[arg(long)]
         wait: bool,
         #[arg(long)]
         timeout_sec: Option<u64>,
+        #[arg(long)]
+        idle_timeout_min: Option<u64>,
+        #[arg(long = "mount", value_name = "HOST:CONTAINER[:ro|:rw]")]
+        mounts: Vec<String>,
+        #[arg(long, conflicts_with_all = ["wait", "detach"])]
+        foreground: bool,
+        #[arg(long)]
+        detach: bool,
+        #[arg(long)]
+        dry_run: bool,
     },
     #[command(about = "Stop collector-only or provider plane services")]
     Down {
@@ -93,6 +176,8 @@ enum Commands {
         provider: Option<String>,
         #[arg(long, default_value_t = false, conflicts_with = "provider")]
         collector_only: bool,
+        #[arg(long, conflicts_with_all = ["provider", "collector_only"])]
+        all: bool,
     },
     #[command(about = "Show collector-only or provider plane status")]
     Status {
@@ -100,6 +185,8 @@ enum Commands {
         provider: Option<String>,
         #[arg(long, default_value_t = false, conflicts_with = "provider")]
         collector_only: bool,
+        #[arg(long)]
+        raw: bool,
     },
     #[command(about = "Manage UI service lifecycle")]
     Ui {
@@ -116,11 +203,21 @@ enum Commands {
         #[command(subcommand)]
         command: ShimCommand,
     },
-    #[command(about = "Run a non-interactive provider prompt through harness API")]
+    #[command(about = "Rotate provider API-key secrets files")]
+    Secrets {
+        #[command(subcommand)]
+        command: SecretsCommand,
+    },
+    #[command(
+        about = "Run a non-interactive provider prompt through harness API",
+        group(ArgGroup::new("prompt_source").required(true).args(["prompt", "input_file"]))
+    )]
     Run {
         #[arg(long)]
         provider: String,
-        prompt: String,
+        prompt: Option<String>,
+        #[arg(long)]
+        input_file: Option<String>,
         #[arg(long)]
         capture_input: Option<bool>,
         #[arg(long)]
@@ -129,6 +226,10 @@ enum Commands {
         timeout_sec: Option<u64>,
         #[arg(long)]
         env: Vec<String>,
+        #[arg(long)]
+        run_env_file: Option<String>,
+        #[arg(long)]
+        output_file: Option<String>,
     },
     #[command(about = "Launch an interactive provider TUI session")]
     Tui {
@@ -136,6 +237,13 @@ enum Commands {
         provider: String,
         #[arg(long)]
         start_dir: Option<String>,
+        #[arg(long)]
+        env: Vec<String>,
+    },
+    #[command(about = "Open an interactive shell in the running agent container")]
+    Attach {
+        #[arg(long)]
+        provider: String,
     },
     #[command(about = "List or fetch run-scoped harness jobs")]
     Jobs {
@@ -146,11 +254,19 @@ enum Commands {
     Doctor {
         #[arg(long, default_value_t = false)]
         strict: bool,
+        #[arg(long, conflicts_with = "list")]
+        check: Option<String>,
+        #[arg(long)]
+        list: bool,
+        #[arg(long)]
+        network: bool,
     },
     #[command(about = "Explain Lux concepts and first-run quickstart tracks")]
     Info,
     #[command(about = "Print resolved config, runtime, install, and compose paths")]
     Paths,
+    #[command(about = "Print version, build, bundle, and installed-version details")]
+    Version,
     #[command(about = "Check, apply, or rollback Lux versions")]
     Update {
         #[command(subcommand)]
@@ -168,18 +284,78 @@ enum Commands {
         dry_run: bool,
         #[arg(long)]
         force: bool,
+        #[arg(
+            long,
+            default_value_t = true,
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true",
+            action = clap::ArgAction::Set
+        )]
+        keep_logs: bool,
+        #[arg(
+            long,
+            default_value_t = true,
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true",
+            action = clap::ArgAction::Set
+        )]
+        keep_secrets: bool,
     },
     #[command(about = "Inspect run-scoped logs and summary stats")]
     Logs {
         #[command(subcommand)]
         command: LogsCommand,
     },
+    #[command(about = "Inspect collector health counters")]
+    Collector {
+        #[command(subcommand)]
+        command: CollectorCommand,
+    },
+    #[command(about = "Check a run's collector evidence against its sha256 manifest")]
+    Verify {
+        #[arg(long, conflicts_with = "latest")]
+        run_id: Option<String>,
+        #[arg(long)]
+        latest: bool,
+    },
+    #[command(about = "Manage run directories under the log root")]
+    Runs {
+        #[command(subcommand)]
+        command: RunsCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum RunsCommand {
+    #[command(about = "Delete the oldest runs beyond the retention policy")]
+    Prune {
+        #[arg(long, conflicts_with = "keep_days")]
+        max_runs: Option<u64>,
+        #[arg(long)]
+        keep_days: Option<u64>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    #[command(about = "Show a run's metadata, pipeline files, and session/job counts")]
+    Show {
+        #[arg(long, conflicts_with = "latest")]
+        run_id: Option<String>,
+        #[arg(long)]
+        latest: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
 enum UpdateCommand {
     #[command(about = "Check for newer available Lux versions")]
     Check,
+    #[command(about = "List installed Lux versions and the latest remote release")]
+    List {
+        #[arg(long)]
+        offline: bool,
+    },
     #[command(about = "Apply an update to a target or latest version")]
     Apply {
         #[arg(long, conflicts_with = "latest")]
@@ -190,6 +366,8 @@ enum UpdateCommand {
         yes: bool,
         #[arg(long)]
         dry_run: bool,
+        #[arg(long, conflicts_with = "dry_run")]
+        verify_only: bool,
     },
     #[command(about = "Rollback to a target or previous version")]
     Rollback {
@@ -207,13 +385,44 @@ enum UpdateCommand {
 #[derive(Subcommand, Debug)]
 enum ConfigCommand {
     #[command(about = "Create config file if missing")]
-    Init,
+    Init {
+        #[arg(long, conflicts_with = "print")]
+        force: bool,
+        #[arg(long)]
+        print: bool,
+    },
     #[command(about = "Open config in your editor")]
     Edit,
     #[command(about = "Validate config contract and policy rules")]
-    Validate,
+    Validate {
+        #[arg(long)]
+        strict: bool,
+    },
+    #[command(about = "Show where config diverges from the shipped defaults")]
+    Diff,
     #[command(about = "Apply config and write compose env/state directories")]
-    Apply,
+    Apply {
+        #[arg(long)]
+        diff: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SecretsCommand {
+    #[command(about = "Atomically replace a provider API key and optionally restart its plane")]
+    Rotate {
+        #[arg(long)]
+        provider: String,
+        // Only `-` (read stdin): a key given inline would be visible in the process list.
+        #[arg(long, conflicts_with = "from_env", value_parser = ["-"])]
+        value: Option<String>,
+        #[arg(long)]
+        from_env: bool,
+        #[arg(long, conflicts_with = "no_restart")]
+        restart: bool,
+        #[arg(long)]
+        no_restart: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -233,6 +442,16 @@ enum JobsCommand {
         #[arg(long)]
         latest: bool,
     },
+    #[command(about = "Print a job's captured stdout/stderr")]
+    Logs {
+        id: String,
+        #[arg(long, conflicts_with = "latest")]
+        run_id: Option<String>,
+        #[arg(long)]
+        latest: bool,
+        #[arg(long)]
+        follow: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -245,11 +464,20 @@ enum UiCommand {
         timeout_sec: Option<u64>,
         #[arg(long, value_parser = ["always", "never", "missing"]) ]
         pull: Option<String>,
+        #[arg(long)]
+        pull_timeout_sec: Option<u64>,
+        #[arg(long)]
+        build: bool,
+        #[arg(long)]
+        no_cache: bool,
     },
     #[command(about = "Stop UI service")]
     Down,
     #[command(about = "Show UI service status")]
-    Status,
+    Status {
+        #[arg(long)]
+        raw: bool,
+    },
     #[command(about = "Print local UI URL")]
     Url,
 }
@@ -257,11 +485,33 @@ enum UiCommand {
 #[derive(Subcommand, Debug)]
 enum RuntimeCommand {
     #[command(about = "Start runtime control-plane daemon")]
-    Up,
+    Up {
+        #[arg(long)]
+        wait: bool,
+    },
     #[command(about = "Stop runtime control-plane daemon")]
-    Down,
+    Down {
+        #[arg(long, default_value_t = 3)]
+        timeout_sec: u64,
+    },
     #[command(about = "Show runtime control-plane status")]
     Status,
+    #[command(about = "Print runtime events from the control-plane event stream")]
+    Events {
+        #[arg(long)]
+        follow: bool,
+        #[arg(long)]
+        since_id: Option<u64>,
+        #[arg(long = "event-type")]
+        event_type: Vec<String>,
+    },
+    #[command(about = "Print the runtime daemon's diagnostic log")]
+    Logs {
+        #[arg(long, default_value_t = 50)]
+        lines: usize,
+        #[arg(long)]
+        follow: bool,
+    },
     #[command(hide = true)]
     Serve,
 }
@@ -277,11 +527,24 @@ enum ShimCommand {
     #[command(about = "Execute provider command through Lux shim path")]
     Exec {
         provider: String,
+        #[arg(long)]
+        timeout_sec: Option<u64>,
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         argv: Vec<String>,
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum CollectorCommand {
+    #[command(about = "Show in-kernel event and drop counters published by the eBPF loader")]
+    Stats {
+        #[arg(long, conflicts_with = "latest")]
+        run_id: Option<String>,
+        #[arg(long)]
+        latest: bool,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 enum LogsCommand {
     #[command(about = "Show run-scoped log summary statistics")]
@@ -290,6 +553,10 @@ enum LogsCommand {
         run_id: Option<String>,
         #[arg(long)]
         latest: bool,
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long)]
+        until: Option<String>,
     },
     #[command(about = "Tail run-scoped log files")]
     Tail {
@@ -301,6 +568,26 @@ enum LogsCommand {
         run_id: Option<String>,
         #[arg(long)]
         latest: bool,
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long)]
+        until: Option<String>,
+        #[arg(long, value_parser = ["raw", "ndjson", "pretty"])]
+        format: Option<String>,
+    },
+    #[command(about = "Search a run's timeline (and optionally raw logs) for a pattern")]
+    Grep {
+        pattern: String,
+        #[arg(long, conflicts_with = "latest")]
+        run_id: Option<String>,
+        #[arg(long)]
+        latest: bool,
+        #[arg(short = 'i', long)]
+        ignore_case: bool,
+        #[arg(long)]
+        regex: bool,
+        #[arg(long)]
+        raw: bool,
     },
 }
 
@@ -321,7 +608,7 @@ enum LuxError {
     #[error("process error: {message}")]
     ProcessDetailed {
         message: String,
-        details: ProcessErrorDetails,
+        details: Box<ProcessErrorDetails>,
     },
     #[error("http error: {0}")]
     Http(#[from] reqwest::Error),
@@ -373,6 +660,11 @@ struct Harness {
     api_host: String,
     api_port: u16,
     api_token: String,
+    /// Route polled by the `--wait` readiness check.
+    health_path: String,
+    /// Compose healthcheck for the `harness` service in provider overrides.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    healthcheck: Option<ServiceHealthcheck>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -381,6 +673,32 @@ struct CollectorConfig {
     auto_start: bool,
     idle_timeout_min: u64,
     rotate_every_min: u64,
+    dns_capture_bytes: u32,
+    sampling_rate: u32,
+    per_pid_rate: CollectorPerPidRate,
+    ringbuf_bytes: u32,
+    slow_connect_ms: u64,
+    max_runs: u64,
+    exclude_comm: Vec<String>,
+    ignore_loopback: bool,
+    batch_events: bool,
+}
+
+/// In-kernel cap on socket I/O events per pid; `max_events: 0` disables it.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+struct CollectorPerPidRate {
+    max_events: u32,
+    window_ms: u32,
+}
+
+impl Default for CollectorPerPidRate {
+    fn default() -> Self {
+        Self {
+            max_events: 0,
+            window_ms: 1000,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -388,6 +706,12 @@ struct CollectorConfig {
 struct RuntimeControlPlaneConfig {
     socket_path: String,
     socket_gid: Option<u32>,
+    max_connections: u32,
+    tcp_listen: String,
+    tcp_token: String,
+    events_max_bytes: u64,
+    log_file: String,
+    start_timeout_sec: u64,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
@@ -414,6 +738,49 @@ struct Provider {
     commands: ProviderCommands,
     auth: ProviderAuth,
     ownership: ProviderOwnership,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resources: Option<ProviderResources>,
+    /// Overrides `paths.workspace_root` for runs started for this provider.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    workspace_root: Option<String>,
+    /// Compose profiles enabled with `--profile` when this provider is the lifecycle target.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    compose_profiles: Vec<String>,
+    /// Short names accepted wherever a provider is selected; state keeps the canonical name.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    /// Compose healthcheck for the provider's `agent` service.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    healthcheck: Option<ServiceHealthcheck>,
+    /// Replaces the bundled `agent` image, e.g. a team's fork of the agent container.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+}
+
+/// Rendered as a compose `healthcheck` with `CMD-SHELL`; durations use compose syntax
+/// (`10s`, `1m30s`) and unset fields keep the Docker defaults.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+struct ServiceHealthcheck {
+    command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interval: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_period: Option<String>,
+}
+
+/// Container limits for the provider's `agent` service; unset fields stay unbounded.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+struct ProviderResources {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpus: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -421,6 +788,8 @@ struct Provider {
 struct ProviderCommands {
     tui: String,
     run_template: String,
+    default_timeout_sec: Option<u64>,
+    api_endpoint: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -441,6 +810,16 @@ struct ProviderApiKeyAuth {
 #[serde(default, deny_unknown_fields)]
 struct ProviderHostStateAuth {
     paths: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    keychain_items: Vec<ProviderKeychainItem>,
+}
+
+/// A macOS Keychain generic password copied into the agent container at `path`.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+struct ProviderKeychainItem {
+    service: String,
+    path: String,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -531,6 +910,8 @@ impl Default for Harness {
             api_host: "127.0.0.1".to_string(),
             api_port: 8081,
             api_token: "TEMP_STR_TO_CHANGE".to_string(),
+            health_path: "/healthz".to_string(),
+            healthcheck: None,
         }
     }
 }
@@ -541,6 +922,15 @@ impl Default for CollectorConfig {
             auto_start: true,
             idle_timeout_min: 10_080,
             rotate_every_min: 1_440,
+            dns_capture_bytes: COLLECTOR_DNS_PAYLOAD_MAX,
+            sampling_rate: 1,
+            per_pid_rate: CollectorPerPidRate::default(),
+            ringbuf_bytes: COLLECTOR_DEFAULT_RINGBUF_BYTES,
+            slow_connect_ms: 1_000,
+            max_runs: 0,
+            exclude_comm: Vec::new(),
+            ignore_loopback: false,
+            batch_events: false,
         }
     }
 }
@@ -550,6 +940,12 @@ impl Default for RuntimeControlPlaneConfig {
         Self {
             socket_path: String::new(),
             socket_gid: None,
+            max_connections: DEFAULT_RUNTIME_MAX_CONNECTIONS,
+            tcp_listen: String::new(),
+            tcp_token: String::new(),
+            events_max_bytes: DEFAULT_RUNTIME_EVENTS_MAX_BYTES,
+            log_file: String::new(),
+            start_timeout_sec: DEFAULT_RUNTIME_START_TIMEOUT_SEC,
         }
     }
 }
@@ -568,6 +964,12 @@ impl Default for Provider {
             commands: ProviderCommands::default(),
             auth: ProviderAuth::default(),
             ownership: ProviderOwnership::default(),
+            resources: None,
+            healthcheck: None,
+            image: None,
+            workspace_root: None,
+            compose_profiles: Vec::new(),
+            aliases: Vec::new(),
         }
     }
 }
@@ -577,6 +979,8 @@ impl Default for ProviderCommands {
         Self {
             tui: "bash -l".to_string(),
             run_template: "bash -lc {prompt}".to_string(),
+            default_timeout_sec: None,
+            api_endpoint: None,
         }
     }
 }
@@ -601,7 +1005,10 @@ impl Default for ProviderApiKeyAuth {
 
 impl Default for ProviderHostStateAuth {
     fn default() -> Self {
-        Self { paths: Vec::new() }
+        Self {
+            paths: Vec::new(),
+            keychain_items: Vec::new(),
+        }
     }
 }
 
@@ -636,6 +1043,8 @@ fn default_providers() -> BTreeMap<String, Provider> {
                 tui: "codex -s danger-full-access".to_string(),
                 run_template: "codex -s danger-full-access exec --skip-git-repo-check {prompt}"
                     .to_string(),
+                default_timeout_sec: None,
+                api_endpoint: Some("api.openai.com:443".to_string()),
             },
             auth: ProviderAuth {
                 api_key: ProviderApiKeyAuth {
@@ -650,11 +1059,18 @@ fn default_providers() -> BTreeMap<String, Provider> {
                         "~/.codex/auth.json".to_string(),
                         "~/.codex/skills".to_string(),
                     ],
+                    keychain_items: Vec::new(),
                 },
             },
             ownership: ProviderOwnership {
                 root_comm: vec!["codex".to_string()],
             },
+            resources: None,
+            healthcheck: None,
+            image: None,
+            workspace_root: None,
+            compose_profiles: Vec::new(),
+            aliases: Vec::new(),
         },
     );
     providers.insert(
@@ -665,6 +1081,8 @@ fn default_providers() -> BTreeMap<String, Provider> {
             commands: ProviderCommands {
                 tui: "claude".to_string(),
                 run_template: "claude -p {prompt}".to_string(),
+                default_timeout_sec: None,
+                api_endpoint: Some("api.anthropic.com:443".to_string()),
             },
             auth: ProviderAuth {
                 api_key: ProviderApiKeyAuth {
@@ -680,11 +1098,18 @@ fn default_providers() -> BTreeMap<String, Provider> {
                         "~/.claude".to_string(),
                         "~/.config/claude-code/auth.json".to_string(),
                     ],
+                    keychain_items: Vec::new(),
                 },
             },
             ownership: ProviderOwnership {
                 root_comm: vec!["claude".to_string()],
             },
+            resources: None,
+            healthcheck: None,
+            image: None,
+            workspace_root: None,
+            compose_profiles: Vec::new(),
+            aliases: Vec::new(),
         },
     );
     providers
@@ -718,7 +1143,10 @@ struct Context {
     env_file: PathBuf,
     bundle_dir: PathBuf,
     compose_file_overrides: Vec<PathBuf>,
+    project_name: Option<String>,
     json: bool,
+    json_pretty: bool,
+    quiet: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -727,6 +1155,31 @@ struct ActiveRunState {
     started_at: String,
     #[serde(default)]
     workspace_root: Option<String>,
+    /// Set by `lux up --idle-timeout-min`; overrides `collector.idle_timeout_min`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    idle_timeout_min: Option<u64>,
+    /// Extra agent mounts from `lux up --mount`; reapplied whenever the provider override
+    /// is regenerated and carried across rotation cutover.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    mounts: Vec<RunMount>,
+    /// Compose project the run's collector was started under (`--project-name` or
+    /// `docker.project_name`); absent in state written before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct RunMount {
+    host: String,
+    container: String,
+    read_only: bool,
+}
+
+impl RunMount {
+    fn spec(&self) -> String {
+        let mode = if self.read_only { "ro" } else { "rw" };
+        format!("{}:{}:{mode}", self.host, self.container)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -758,13 +1211,28 @@ struct RuntimeSharedState {
     events: VecDeque<RuntimeEvent>,
     warnings: VecDeque<RuntimeWarning>,
     shutdown: bool,
+    active_connections: usize,
     rotation_pending: bool,
+    // Set for the duration of a rotation cutover so a slow stop/start cannot overlap another.
+    rotation_in_progress: bool,
     last_provider_activity_at: Option<String>,
+    // Size cap for events.jsonl; 0 leaves the file unbounded.
+    events_max_bytes: u64,
+    // `runtime_control_plane.log_file`; None keeps the daemon silent.
+    log_file: Option<PathBuf>,
+    // Serialized events waiting to be appended to events.jsonl, in id order.
+    pending_event_lines: VecDeque<String>,
+    // Held while appending/rotating events.jsonl; never taken while holding the state lock.
+    events_file_lock: Arc<Mutex<()>>,
 }
 
 #[derive(Debug, Deserialize)]
 struct RuntimeExecuteRequest {
     argv: Vec<String>,
+    // The caller's working directory, so relative path arguments resolve as they would
+    // have without the proxy.
+    #[serde(default)]
+    cwd: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
@@ -788,6 +1256,19 @@ trait DockerRunner {
         env_overrides: &BTreeMap<String, String>,
         capture_output: bool,
     ) -> Result<CommandOutput, io::Error>;
+
+    /// Like `run`, but kills the command once `timeout` elapses. Returns `Ok(None)` on
+    /// timeout.
+    fn run_with_timeout(
+        &self,
+        args: &[String],
+        cwd: &Path,
+        env_overrides: &BTreeMap<String, String>,
+        capture_output: bool,
+        _timeout: Duration,
+    ) -> Result<Option<CommandOutput>, io::Error> {
+        self.run(args, cwd, env_overrides, capture_output).map(Some)
+    }
 }
 
 struct RealDockerRunner;
@@ -829,16 +1310,94 @@ impl DockerRunner for RealDockerRunner {
             })
         }
     }
+
+    fn run_with_timeout(
+        &self,
+        args: &[String],
+        cwd: &Path,
+        env_overrides: &BTreeMap<String, String>,
+        capture_output: bool,
+        timeout: Duration,
+    ) -> Result<Option<CommandOutput>, io::Error> {
+        let mut cmd = Command::new("docker");
+        cmd.args(args).current_dir(cwd);
+        for (key, value) in env_overrides {
+            cmd.env(key, value);
+        }
+        if capture_output {
+            cmd.stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped());
+            // Compose runs as a CLI plugin child of `docker`; a process group lets the
+            // timeout kill both instead of orphaning the plugin mid-pull. Interactive
+            // commands stay in the caller's group so they keep the terminal foreground.
+            #[cfg(unix)]
+            {
+                use std::os::unix::process::CommandExt;
+                cmd.process_group(0);
+            }
+        }
+        let mut child = cmd.spawn()?;
+        // Drain on threads so a chatty pull cannot stall on a full pipe.
+        let stdout = child.stdout.take().map(drain_pipe);
+        let stderr = child.stderr.take().map(drain_pipe);
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                #[cfg(unix)]
+                if capture_output {
+                    let _ = Command::new("kill")
+                        .args(["-KILL", &format!("-{}", child.id())])
+                        .stderr(Stdio::null())
+                        .status();
+                }
+                let _ = child.kill();
+                let _ = child.wait();
+                return Ok(None);
+            }
+            thread::sleep(Duration::from_millis(100));
+        };
+        let status_code = status
+            .code()
+            .unwrap_or(if status.success() { 0 } else { 1 });
+        Ok(Some(CommandOutput {
+            status_code,
+            stdout: stdout.and_then(|h| h.join().ok()).unwrap_or_default(),
+            stderr: stderr.and_then(|h| h.join().ok()).unwrap_or_default(),
+        }))
+    }
+}
+
+fn drain_pipe<T: Read + Send + 'static>(mut pipe: T) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
 }
 
 fn main() -> Result<(), LuxError> {
     let raw_args: Vec<String> = env::args().skip(1).collect();
     let cli = Cli::parse();
+    init_color_output(cli.no_color);
     let ctx = build_context(&cli)?;
     let runner = RealDockerRunner;
 
-    let result = if should_route_through_runtime(&cli.command) && !runtime_bypass_enabled() {
-        handle_runtime_execute_proxy(&ctx, &raw_args)
+    let stdin_config = config_is_stdin(&ctx.config_path);
+    let result = if stdin_config && !command_accepts_stdin_config(&cli.command) {
+        Err(LuxError::Config(
+            "--config - (stdin) is read-only and only supported by `config validate`, `config diff`, `status`, `paths` and `version`; write the config to a file for commands that persist state".to_string(),
+        ))
+    } else if should_route_through_runtime(&cli.command)
+        && !runtime_bypass_enabled()
+        && !stdin_config
+    {
+        runtime_proxy_args(&cli.command, &raw_args)
+            .map(|raw_args| runtime_proxy_pin_json_format(&ctx, raw_args))
+            .and_then(|raw_args| handle_runtime_execute_proxy(&ctx, &raw_args))
     } else {
         match cli.command {
             Commands::Config { command } => handle_config(&ctx, command),
@@ -847,59 +1406,102 @@ fn main() -> Result<(), LuxError> {
                 yes,
                 no_apply,
                 dry_run,
-            } => handle_setup(&ctx, defaults, yes, no_apply, dry_run),
+                import_env,
+            } => handle_setup(&ctx, defaults, yes, no_apply, dry_run, import_env),
             Commands::Up {
                 provider,
                 collector_only,
                 workspace,
                 pull,
+                pull_timeout_sec,
+                build,
+                no_cache,
                 wait,
                 timeout_sec,
-            } => handle_up(
-                &ctx,
-                provider,
-                collector_only,
-                workspace,
-                pull,
-                wait,
-                timeout_sec,
-                &runner,
-            ),
+                idle_timeout_min,
+                mounts,
+                foreground,
+                detach: _,
+                dry_run,
+            } => {
+                let options = UpOptions {
+                    provider,
+                    collector_only,
+                    workspace,
+                    pull,
+                    pull_timeout_sec,
+                    build,
+                    no_cache,
+                    wait,
+                    timeout_sec,
+                    idle_timeout_min,
+                    mounts,
+                    foreground,
+                };
+                if let Ok(policy) =
+                    read_config(&ctx.config_path).and_then(|cfg| resolve_config_policy_paths(&cfg))
+                {
+                    warn_if_trusted_root_volatile(&policy.trusted_root);
+                }
+                if dry_run {
+                    handle_up_dry_run(&ctx, options)
+                } else {
+                    handle_up(&ctx, options, &runner)
+                }
+            }
             Commands::Down {
                 provider,
                 collector_only,
-            } => handle_down(&ctx, provider, collector_only, &runner),
+                all,
+            } => handle_down(&ctx, provider, collector_only, all, &runner),
             Commands::Status {
                 provider,
                 collector_only,
-            } => handle_status(&ctx, provider, collector_only, &runner),
+                raw,
+            } => handle_status(&ctx, provider, collector_only, raw, &runner),
             Commands::Ui { command } => handle_ui(&ctx, command, &runner),
             Commands::Runtime { command } => handle_runtime(&ctx, command),
             Commands::Shim { command } => handle_shim(&ctx, command, &runner),
+            Commands::Secrets { command } => handle_secrets(&ctx, command, &runner),
             Commands::Run {
                 provider,
                 prompt,
+                input_file,
                 capture_input,
                 start_dir,
                 timeout_sec,
                 env,
+                run_env_file,
+                output_file,
             } => handle_run(
                 &ctx,
-                provider,
-                prompt,
-                capture_input,
-                start_dir,
-                timeout_sec,
-                env,
+                RunOptions {
+                    provider,
+                    prompt: resolve_run_prompt(prompt, input_file.as_deref())?,
+                    capture_input,
+                    start_dir,
+                    timeout_sec,
+                    env,
+                    run_env_file,
+                    output_file,
+                },
             ),
             Commands::Tui {
                 provider,
                 start_dir,
-            } => handle_tui(&ctx, provider, start_dir, &runner),
+                env,
+            } => handle_tui(&ctx, provider, start_dir, &env, &runner),
+            Commands::Attach { provider } => handle_attach(&ctx, provider, &runner),
             Commands::Jobs { command } => handle_jobs(&ctx, command),
-            Commands::Doctor { strict } => handle_doctor(&ctx, strict),
+            Commands::Doctor {
+                strict,
+                check,
+                list,
+                network,
+            } => handle_doctor(&ctx, strict, check, list, network),
             Commands::Info => handle_info(&ctx),
             Commands::Paths => handle_paths(&ctx),
+            Commands::Version => handle_version(&ctx),
             Commands::Update { command } => handle_update(&ctx, command),
             Commands::Uninstall {
                 remove_config,
@@ -907,16 +1509,25 @@ fn main() -> Result<(), LuxError> {
                 yes,
                 dry_run,
                 force,
+                keep_logs,
+                keep_secrets,
             } => handle_uninstall(
                 &ctx,
-                remove_config,
-                all_versions,
-                yes,
-                dry_run,
-                force,
+                UninstallOptions {
+                    remove_config,
+                    all_versions,
+                    yes,
+                    dry_run,
+                    force,
+                    keep_logs,
+                    keep_secrets,
+                },
                 &runner,
             ),
             Commands::Logs { command } => handle_logs(&ctx, command),
+            Commands::Collector { command } => handle_collector(&ctx, command),
+            Commands::Verify { run_id, latest } => handle_verify(&ctx, run_id, latest),
+            Commands::Runs { command } => handle_runs(&ctx, command),
         }
     };
 
@@ -928,7 +1539,7 @@ fn main() -> Result<(), LuxError> {
                 error: Some(err.to_string()),
                 error_details: extract_process_error_details(&err),
             };
-            print_json(&payload)?;
+            print_json(&ctx, &payload)?;
         } else {
             eprintln!("{err}");
         }
@@ -938,8 +1549,45 @@ fn main() -> Result<(), LuxError> {
     Ok(())
 }
 
+fn resolve_json_pretty(pretty: bool, compact: bool) -> bool {
+    if pretty || compact {
+        return pretty;
+    }
+    io::stdout().is_terminal()
+}
+
+static COLOR_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// `--no-color` or a non-empty `NO_COLOR` disables styling; otherwise colors follow
+/// whether stdout is a terminal.
+fn resolve_color_enabled(no_color: bool, no_color_env: Option<&str>, stdout_is_tty: bool) -> bool {
+    let requested_off = no_color || no_color_env.is_some_and(|value| !value.is_empty());
+    !requested_off && stdout_is_tty
+}
+
+/// Applies the color decision to our own `style()` output and to dialoguer prompts.
+fn init_color_output(no_color: bool) {
+    let no_color_env = env::var("NO_COLOR").ok();
+    let enabled = resolve_color_enabled(
+        no_color,
+        no_color_env.as_deref(),
+        io::stdout().is_terminal(),
+    );
+    COLOR_ENABLED.store(enabled, Ordering::Relaxed);
+    dialoguer::console::set_colors_enabled(enabled);
+    // Prompts render on stderr, so only an explicit opt-out turns their colors off.
+    if !resolve_color_enabled(no_color, no_color_env.as_deref(), true) {
+        dialoguer::console::set_colors_enabled_stderr(false);
+    }
+}
+
+/// All colored text goes through here so `--no-color`/`NO_COLOR` apply everywhere.
+fn style<D>(value: D) -> StyledObject<D> {
+    dialoguer::console::style(value).force_styling(COLOR_ENABLED.load(Ordering::Relaxed))
+}
+
 fn build_context(cli: &Cli) -> Result<Context, LuxError> {
-    let config_path = resolve_config_path(cli.config.as_ref());
+    let config_path = resolve_config_path(cli.config.as_ref(), cli.config_dir.as_ref());
     let env_file = resolve_env_file(cli.env_file.as_ref(), &config_path);
     let bundle_dir = resolve_bundle_dir(cli.bundle_dir.as_ref());
     let compose_file_overrides = resolve_compose_overrides(&cli.compose_file);
@@ -948,18 +1596,54 @@ fn build_context(cli: &Cli) -> Result<Context, LuxError> {
         env_file,
         bundle_dir,
         compose_file_overrides,
+        project_name: cli.project_name.clone(),
         json: cli.json,
+        json_pretty: resolve_json_pretty(cli.json_pretty, cli.json_compact),
+        quiet: cli.quiet,
     })
 }
 
-fn resolve_config_path(override_path: Option<&PathBuf>) -> PathBuf {
+/// Compose only accepts lowercase alphanumerics, `_` and `-`, starting with a letter or digit.
+fn validate_compose_project_name(name: &str) -> Result<(), LuxError> {
+    validate_compose_project_name_field("--project-name", name)
+}
+
+fn validate_compose_project_name_field(field: &str, name: &str) -> Result<(), LuxError> {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_lowercase() || first.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if !valid {
+        return Err(LuxError::Config(format!(
+            "invalid {field} {name:?}: must start with a lowercase letter or digit and contain only lowercase letters, digits, '_' and '-'"
+        )));
+    }
+    Ok(())
+}
+
+/// `--project-name` wins over `docker.project_name`, which `validate_config` keeps non-empty.
+fn compose_project_name(ctx: &Context, cfg: &Config) -> Result<String, LuxError> {
+    match ctx.project_name.as_deref() {
+        Some(name) => {
+            validate_compose_project_name(name)?;
+            Ok(name.to_string())
+        }
+        None => Ok(cfg.docker.project_name.trim().to_string()),
+    }
+}
+
+/// `--config` > `LUX_CONFIG` > `--config-dir` > `LUX_CONFIG_DIR` > `~/.config/lux`.
+fn resolve_config_path(override_path: Option<&PathBuf>, config_dir: Option<&PathBuf>) -> PathBuf {
     if let Some(path) = override_path {
         return path.clone();
     }
     if let Ok(path) = env::var("LUX_CONFIG") {
         return PathBuf::from(path);
     }
-    let mut base = default_config_dir();
+    let mut base = config_dir.cloned().unwrap_or_else(default_config_dir);
     base.push("config.yaml");
     base
 }
@@ -971,7 +1655,7 @@ fn resolve_env_file(override_path: Option<&PathBuf>, config_path: &Path) -> Path
     if let Ok(path) = env::var("LUX_ENV_FILE") {
         return PathBuf::from(path);
     }
-    if config_path.exists() {
+    if config_present(config_path) {
         if let Ok(cfg) = read_config(config_path) {
             return PathBuf::from(expand_path(&cfg.paths.trusted_root))
                 .join("state")
@@ -1312,6 +1996,15 @@ fn resolve_config_policy_paths(cfg: &Config) -> Result<PolicyPaths, LuxError> {
             display_path_with_home(&shims_bin_dir, Some(&home))
         )));
     }
+    // Only one provider plane runs at a time and each mounts its workspace at /work, so
+    // provider workspaces may overlap each other freely.
+    for (name, provider) in &cfg.providers {
+        if let Some(raw) = provider.workspace_root.as_deref() {
+            let field = format!("providers.{name}.workspace_root");
+            let provider_workspace = resolve_policy_path(raw, &field, &home)?;
+            validate_workspace_policy(&provider_workspace, &home, &log_root, &field)?;
+        }
+    }
 
     Ok(PolicyPaths {
         home,
@@ -1358,31 +2051,358 @@ fn runtime_bypass_enabled() -> bool {
 fn should_route_through_runtime(command: &Commands) -> bool {
     matches!(
         command,
-        Commands::Up { .. }
-            | Commands::Down { .. }
+        Commands::Up {
+            dry_run: false,
+            foreground: false,
+            ..
+        } | Commands::Down { .. }
             | Commands::Status { .. }
             | Commands::Ui { .. }
             | Commands::Run { .. }
     )
 }
 
-#[cfg(unix)]
-fn runtime_control_plane_request(
+/// The runtime daemon re-executes proxied argv without our stdin, cwd or environment, so
+/// `run --input-file` is read here and forwarded as the positional prompt, and `--env KEY`
+/// pass-throughs are resolved to `--env KEY=VALUE`.
+fn runtime_proxy_args(command: &Commands, raw_args: &[String]) -> Result<Vec<String>, LuxError> {
+    let Commands::Run { input_file, .. } = command else {
+        return Ok(raw_args.to_vec());
+    };
+    let mut args = Vec::with_capacity(raw_args.len() + 2);
+    let mut iter = raw_args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            if input_file.is_none() {
+                args.push(arg.clone());
+                args.extend(iter.cloned());
+            }
+            break;
+        }
+        if input_file.is_some() {
+            if arg == "--input-file" {
+                iter.next();
+                continue;
+            }
+            if arg.starts_with("--input-file=") {
+                continue;
+            }
+        }
+        if arg == "--env" {
+            args.push(arg.clone());
+            if let Some(entry) = iter.next() {
+                args.push(resolve_env_passthrough(entry)?);
+            }
+            continue;
+        }
+        if let Some(entry) = arg.strip_prefix("--env=") {
+            args.push(format!("--env={}", resolve_env_passthrough(entry)?));
+            continue;
+        }
+        args.push(arg.clone());
+    }
+    if let Some(input_file) = input_file {
+        args.push("--".to_string());
+        args.push(resolve_run_prompt(None, Some(input_file))?);
+    }
+    Ok(args)
+}
+
+/// The daemon's captured stdout is never a terminal, so the JSON layout this process chose
+/// is passed along explicitly.
+fn runtime_proxy_pin_json_format(ctx: &Context, raw_args: Vec<String>) -> Vec<String> {
+    if raw_args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--json-pretty" || arg == "--json-compact")
+    {
+        return raw_args;
+    }
+    let flag = if ctx.json_pretty {
+        "--json-pretty"
+    } else {
+        "--json-compact"
+    };
+    std::iter::once(flag.to_string()).chain(raw_args).collect()
+}
+
+#[cfg(unix)]
+fn runtime_control_plane_request(
     ctx: &Context,
     method: &str,
     path: &str,
     headers: &[(String, String)],
     body: Option<&[u8]>,
 ) -> Result<RuntimeHttpResponse, LuxError> {
+    let (stream, headers) = runtime_control_plane_connect(ctx, headers)?;
+    runtime_http_exchange(stream, method, path, &headers, body)
+}
+
+#[cfg(unix)]
+type RuntimeConnection = (Box<dyn RuntimeStream>, Vec<(String, String)>);
+
+/// Opens a connection to the runtime (TCP when `LUX_RUNTIME_TCP` is set, otherwise the
+/// unix socket) and returns the request headers to send on it.
+#[cfg(unix)]
+fn runtime_control_plane_connect(
+    ctx: &Context,
+    headers: &[(String, String)],
+) -> Result<RuntimeConnection, LuxError> {
+    let mut headers = headers.to_vec();
+    if let Some(addr) = runtime_tcp_target() {
+        let cfg = read_config(&ctx.config_path)?;
+        headers.push((
+            "Authorization".to_string(),
+            format!("Bearer {}", resolve_runtime_tcp_token(&cfg)?),
+        ));
+        let stream = TcpStream::connect(&addr).map_err(|err| {
+            LuxError::Process(format!(
+                "failed to connect runtime control plane at {}: {}",
+                addr, err
+            ))
+        })?;
+        return Ok((Box::new(stream), headers));
+    }
     let (paths, _) = resolve_runtime_paths(ctx)?;
     let socket_path = &paths.runtime_socket_path;
-    let mut stream = UnixStream::connect(socket_path).map_err(|err| {
+    let stream = UnixStream::connect(socket_path).map_err(|err| {
         LuxError::Process(format!(
             "failed to connect runtime control plane socket {}: {}",
             socket_path.display(),
             err
         ))
     })?;
+    Ok((Box::new(stream), headers))
+}
+
+/// `LUX_RUNTIME_TCP=host:port` points the CLI at a runtime's TCP listener instead of the
+/// local unix socket.
+fn runtime_tcp_target() -> Option<String> {
+    env::var(RUNTIME_TCP_ENV)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(unix)]
+fn runtime_http_exchange<S: Read + Write>(
+    mut stream: S,
+    method: &str,
+    path: &str,
+    headers: &[(String, String)],
+    body: Option<&[u8]>,
+) -> Result<RuntimeHttpResponse, LuxError> {
+    runtime_write_http_request(&mut stream, method, path, headers, body)?;
+
+    let mut raw = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let split = loop {
+        let scan_from = raw.len().saturating_sub(chunk.len() + 3);
+        if let Some(pos) = raw[scan_from..].windows(4).position(|w| w == b"\r\n\r\n") {
+            break scan_from + pos;
+        }
+        let read = stream.read(&mut chunk)?;
+        if read == 0 {
+            return Err(LuxError::Process(
+                "runtime response missing header delimiter".to_string(),
+            ));
+        }
+        raw.extend_from_slice(&chunk[..read]);
+    };
+    let header_text = String::from_utf8_lossy(&raw[..split]).to_string();
+    let mut body_bytes = raw.split_off(split + 4);
+    let mut lines = header_text.lines();
+    let status_line = lines
+        .next()
+        .ok_or_else(|| LuxError::Process("runtime response missing status line".to_string()))?;
+    let status = runtime_parse_status_line(status_line)?;
+    let parsed_headers: BTreeMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    runtime_read_response_body(&mut stream, &parsed_headers, &mut body_bytes)?;
+    Ok(RuntimeHttpResponse {
+        status,
+        body: body_bytes,
+    })
+}
+
+/// Completes `body` (which holds whatever arrived with the headers) using `Content-Length`,
+/// so the client stops at the advertised length instead of buffering until EOF. Without a
+/// length, only a `Connection: close` response may be read to the end.
+#[cfg(unix)]
+fn runtime_read_response_body<S: Read>(
+    stream: &mut S,
+    headers: &BTreeMap<String, String>,
+    body: &mut Vec<u8>,
+) -> Result<(), LuxError> {
+    let Some(raw_length) = headers.get("content-length") else {
+        let closes = headers
+            .get("connection")
+            .is_some_and(|value| value.eq_ignore_ascii_case("close"));
+        if !closes {
+            return Err(LuxError::Process(
+                "runtime response has neither Content-Length nor Connection: close".to_string(),
+            ));
+        }
+        stream.read_to_end(body)?;
+        return Ok(());
+    };
+    let length = raw_length.parse::<usize>().map_err(|_| {
+        LuxError::Process(format!(
+            "runtime response has invalid Content-Length '{raw_length}'"
+        ))
+    })?;
+    if body.len() >= length {
+        body.truncate(length);
+        return Ok(());
+    }
+    let remaining = (length - body.len()) as u64;
+    stream.take(remaining).read_to_end(body)?;
+    if body.len() < length {
+        return Err(LuxError::Process(format!(
+            "runtime response body truncated: expected {length} bytes, got {}",
+            body.len()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn runtime_parse_status_line(line: &str) -> Result<u16, LuxError> {
+    line.split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse::<u16>().ok())
+        .ok_or_else(|| LuxError::Process("runtime response has invalid status".to_string()))
+}
+
+/// Reads SSE frames from a `/v1/events` response body, calling `on_event` for each
+/// decoded event. Stops at end of stream, or, unless `follow` is set, at the first
+/// keepalive or idle read timeout (i.e. once the buffered backlog has been drained).
+#[cfg(unix)]
+fn runtime_read_sse_events<R: BufRead>(
+    reader: &mut R,
+    follow: bool,
+    mut on_event: impl FnMut(RuntimeEvent) -> Result<(), LuxError>,
+) -> Result<(), LuxError> {
+    let mut data = String::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) => return Ok(()),
+            Ok(_) => {}
+            Err(err)
+                if !follow
+                    && matches!(
+                        err.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+            {
+                return Ok(());
+            }
+            Err(err) => return Err(err.into()),
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            if !data.is_empty() {
+                let event: RuntimeEvent = serde_json::from_str(&data).map_err(|err| {
+                    LuxError::Process(format!("invalid runtime event frame: {err}"))
+                })?;
+                on_event(event)?;
+                data.clear();
+            }
+            continue;
+        }
+        if line.starts_with(':') {
+            if !follow {
+                return Ok(());
+            }
+            continue;
+        }
+        if let Some(value) = line.strip_prefix("data:") {
+            if !data.is_empty() {
+                data.push('\n');
+            }
+            data.push_str(value.strip_prefix(' ').unwrap_or(value));
+        }
+    }
+}
+
+#[cfg(unix)]
+fn runtime_events(
+    ctx: &Context,
+    follow: bool,
+    since_id: Option<u64>,
+    event_types: &[String],
+) -> Result<(), LuxError> {
+    let mut query = vec![format!("last_event_id={}", since_id.unwrap_or(0))];
+    query.extend(
+        event_types
+            .iter()
+            .map(|value| format!("event_type={value}")),
+    );
+    let path = format!("/v1/events?{}", query.join("&"));
+    let (mut stream, headers) = runtime_control_plane_connect(ctx, &[])?;
+    if !follow {
+        stream.set_io_read_timeout(Some(Duration::from_millis(RUNTIME_EVENTS_DRAIN_IDLE_MS)))?;
+    }
+    runtime_write_http_request(&mut stream, "GET", &path, &headers, None)?;
+
+    let mut reader = BufReader::new(stream);
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line)?;
+    let status = runtime_parse_status_line(&status_line)?;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+    }
+    if status >= 400 {
+        let mut body = String::new();
+        let _ = reader.read_to_string(&mut body);
+        return Err(LuxError::Process(format!(
+            "runtime events request failed with status {}: {}",
+            status,
+            body.trim()
+        )));
+    }
+    runtime_read_sse_events(&mut reader, follow, |event| {
+        if ctx.json {
+            print_json(ctx, &event)?;
+        } else {
+            println!(
+                "{} {} {} {} {}",
+                event.id, event.ts, event.severity, event.event_type, event.payload
+            );
+        }
+        io::stdout().flush()?;
+        Ok(())
+    })
+}
+
+#[cfg(not(unix))]
+fn runtime_events(
+    _ctx: &Context,
+    _follow: bool,
+    _since_id: Option<u64>,
+    _event_types: &[String],
+) -> Result<(), LuxError> {
+    Err(LuxError::Config(
+        "runtime control plane is only supported on unix hosts".to_string(),
+    ))
+}
+
+#[cfg(unix)]
+fn runtime_write_http_request<S: Write + ?Sized>(
+    stream: &mut S,
+    method: &str,
+    path: &str,
+    headers: &[(String, String)],
+    body: Option<&[u8]>,
+) -> Result<(), LuxError> {
     let mut request = format!(
         "{} {} HTTP/1.1\r\nHost: lux-runtime\r\nConnection: close\r\n",
         method, path
@@ -1403,35 +2423,7 @@ fn runtime_control_plane_request(
     if let Some(body) = body {
         stream.write_all(body)?;
     }
-
-    let mut raw = Vec::new();
-    stream.read_to_end(&mut raw)?;
-    let split = raw
-        .windows(4)
-        .position(|w| w == b"\r\n\r\n")
-        .ok_or_else(|| {
-            LuxError::Process("runtime response missing header delimiter".to_string())
-        })?;
-    let header_bytes = &raw[..split];
-    let body_bytes = raw[split + 4..].to_vec();
-    let header_text = String::from_utf8_lossy(header_bytes);
-    let mut lines = header_text.lines();
-    let status_line = lines
-        .next()
-        .ok_or_else(|| LuxError::Process("runtime response missing status line".to_string()))?;
-    let status = status_line
-        .split_whitespace()
-        .nth(1)
-        .and_then(|s| s.parse::<u16>().ok())
-        .ok_or_else(|| LuxError::Process("runtime response has invalid status".to_string()))?;
-    let _parsed_headers: BTreeMap<String, String> = lines
-        .filter_map(|line| line.split_once(':'))
-        .map(|(key, value)| (key.trim().to_ascii_lowercase(), value.trim().to_string()))
-        .collect();
-    Ok(RuntimeHttpResponse {
-        status,
-        body: body_bytes,
-    })
+    Ok(())
 }
 
 #[cfg(not(unix))]
@@ -1459,16 +2451,26 @@ fn runtime_ping(ctx: &Context) -> Result<(), LuxError> {
 }
 
 fn ensure_runtime_running(ctx: &Context) -> Result<(), LuxError> {
-    if runtime_ping(ctx).is_ok() {
+    let ping = runtime_ping(ctx);
+    if ping.is_ok() {
         return Ok(());
     }
-    runtime_up_internal(ctx, false)?;
+    if let Some(addr) = runtime_tcp_target() {
+        // A remote runtime cannot be auto-started from here.
+        return ping.map_err(|err| {
+            LuxError::Process(format!(
+                "runtime control plane at {addr} ({RUNTIME_TCP_ENV}) is unreachable: {err}"
+            ))
+        });
+    }
+    runtime_up_internal(ctx, false, false)?;
     runtime_ping(ctx)
 }
 
 fn handle_runtime_execute_proxy(ctx: &Context, raw_args: &[String]) -> Result<(), LuxError> {
     ensure_runtime_running(ctx)?;
-    let body = serde_json::to_vec(&json!({ "argv": raw_args }))?;
+    let cwd = env::current_dir()?;
+    let body = serde_json::to_vec(&json!({ "argv": raw_args, "cwd": cwd }))?;
     let response = runtime_control_plane_request(
         ctx,
         "POST",
@@ -1510,8 +2512,124 @@ fn handle_runtime_execute_proxy(ctx: &Context, raw_args: &[String]) -> Result<()
     Ok(())
 }
 
-fn read_config_from_str(content: &str) -> Result<Config, LuxError> {
+const CONFIG_INCLUDE_KEY: &str = "include";
+
+fn merge_config_values(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    match (base, overlay) {
+        (serde_yaml::Value::Mapping(base_map), serde_yaml::Value::Mapping(overlay_map)) => {
+            for (key, value) in overlay_map {
+                match base_map.get_mut(&key) {
+                    Some(existing) => merge_config_values(existing, value),
+                    None => {
+                        base_map.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn take_config_includes(raw: &mut serde_yaml::Value) -> Result<Vec<String>, LuxError> {
+    let Some(root) = raw.as_mapping_mut() else {
+        return Ok(Vec::new());
+    };
+    let Some(value) = root.remove(serde_yaml::Value::String(CONFIG_INCLUDE_KEY.to_string())) else {
+        return Ok(Vec::new());
+    };
+    let entries = match value {
+        serde_yaml::Value::Null => return Ok(Vec::new()),
+        serde_yaml::Value::Sequence(entries) => entries,
+        _ => {
+            return Err(LuxError::Config(
+                "include must be a list of config file paths".to_string(),
+            ))
+        }
+    };
+    entries
+        .into_iter()
+        .map(|entry| match entry {
+            serde_yaml::Value::String(path) if !path.trim().is_empty() => Ok(path),
+            _ => Err(LuxError::Config(
+                "include entries must be non-empty path strings".to_string(),
+            )),
+        })
+        .collect()
+}
+
+/// Expands `include:` entries into a single composed YAML value. Included files are merged in
+/// order and the including file is applied last, so later layers win.
+fn resolve_config_includes(
+    mut raw: serde_yaml::Value,
+    base_dir: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> Result<serde_yaml::Value, LuxError> {
+    let includes = take_config_includes(&mut raw)?;
+    if includes.is_empty() {
+        return Ok(raw);
+    }
+    let mut composed = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+    for include in includes {
+        let include_path = PathBuf::from(expand_path(&include));
+        let include_path = if include_path.is_absolute() {
+            include_path
+        } else {
+            base_dir.join(include_path)
+        };
+        let layer = load_config_layer(&include_path, stack)?;
+        merge_config_values(&mut composed, layer);
+    }
+    merge_config_values(&mut composed, raw);
+    Ok(composed)
+}
+
+fn load_config_layer(path: &Path, stack: &mut Vec<PathBuf>) -> Result<serde_yaml::Value, LuxError> {
+    let canonical = fs::canonicalize(path).map_err(|err| {
+        LuxError::Config(format!(
+            "unable to read included config {}: {}",
+            path.display(),
+            err
+        ))
+    })?;
+    if stack.contains(&canonical) {
+        let chain = stack
+            .iter()
+            .chain(std::iter::once(&canonical))
+            .map(|p| p.display().to_string())
+            .collect::<Vec<String>>()
+            .join(" -> ");
+        return Err(LuxError::Config(format!(
+            "config include cycle detected: {chain}"
+        )));
+    }
+    let content = fs::read_to_string(&canonical)?;
+    let raw: serde_yaml::Value = serde_yaml::from_str(&content)?;
+    let base_dir = canonical
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    stack.push(canonical);
+    let composed = resolve_config_includes(raw, &base_dir, stack);
+    stack.pop();
+    composed
+}
+
+/// Parses config text that was read from (or will be written to) `origin`, resolving relative
+/// includes against its directory.
+fn read_config_from_str(content: &str, origin: &Path) -> Result<Config, LuxError> {
     let raw: serde_yaml::Value = serde_yaml::from_str(content)?;
+    let mut stack = Vec::new();
+    if let Ok(canonical) = fs::canonicalize(origin) {
+        stack.push(canonical);
+    }
+    let base_dir = origin
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    read_config_from_value(resolve_config_includes(raw, &base_dir, &mut stack)?)
+}
+
+fn read_config_from_value(raw: serde_yaml::Value) -> Result<Config, LuxError> {
     let has_explicit_trusted_root = raw
         .as_mapping()
         .and_then(|root| root.get(&serde_yaml::Value::String("paths".to_string())))
@@ -1524,7 +2642,7 @@ fn read_config_from_str(content: &str) -> Result<Config, LuxError> {
         ));
     }
 
-    let cfg: Config = serde_yaml::from_str(content)?;
+    let cfg: Config = serde_yaml::from_value(raw)?;
     if cfg.version != 2 {
         return Err(LuxError::Config(format!(
             "unsupported config version {}",
@@ -1535,41 +2653,282 @@ fn read_config_from_str(content: &str) -> Result<Config, LuxError> {
     Ok(cfg)
 }
 
+/// `--config -` reads the config from stdin. There is nowhere to write it back, so only
+/// commands in `command_accepts_stdin_config` run with it.
+fn config_is_stdin(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+fn config_present(path: &Path) -> bool {
+    config_is_stdin(path) || path.exists()
+}
+
+/// Stdin can only be consumed once, but commands read the config several times.
+fn stdin_config_content() -> Result<&'static str, LuxError> {
+    static CONTENT: OnceLock<Result<String, String>> = OnceLock::new();
+    CONTENT
+        .get_or_init(|| {
+            let mut content = String::new();
+            io::stdin()
+                .read_to_string(&mut content)
+                .map(|_| content)
+                .map_err(|err| err.to_string())
+        })
+        .as_deref()
+        .map_err(|err| LuxError::Config(format!("failed to read config from stdin: {err}")))
+}
+
 fn read_config(path: &Path) -> Result<Config, LuxError> {
+    if config_is_stdin(path) {
+        return read_config_from_str(stdin_config_content()?, path);
+    }
     let content = fs::read_to_string(path)?;
-    read_config_from_str(&content)
+    read_config_from_str(&content, path)
 }
 
-fn validate_config(cfg: &Config) -> Result<(), LuxError> {
-    if env::consts::OS != "macos" && env::consts::OS != "linux" {
-        return Err(LuxError::Config(format!(
-            "unsupported host operating system '{}'; supported: macos, linux",
-            env::consts::OS
-        )));
+fn command_accepts_stdin_config(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::Config {
+            command: ConfigCommand::Validate { .. } | ConfigCommand::Diff
+        } | Commands::Status { .. }
+            | Commands::Paths
+            | Commands::Version
+    )
+}
+
+fn valid_cpus_limit(value: &str) -> bool {
+    value
+        .trim()
+        .parse::<f64>()
+        .is_ok_and(|cpus| cpus.is_finite() && cpus > 0.0)
+}
+
+/// Accepts compose byte sizes: digits with an optional `b`, `k`, `m` or `g` unit, optionally
+/// followed by `i` and/or `b` (`512m`, `2gb`, `1GiB`), case-insensitive.
+fn valid_memory_limit(value: &str) -> bool {
+    let value = value.trim().to_ascii_lowercase();
+    let digits_end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    if !matches!(value[..digits_end].parse::<u64>(), Ok(bytes) if bytes > 0) {
+        return false;
     }
-    let _ = resolve_config_policy_paths(cfg)?;
-    if cfg.collector.idle_timeout_min == 0 {
-        return Err(LuxError::Config(
-            "collector.idle_timeout_min must be greater than 0".to_string(),
-        ));
+    matches!(
+        &value[digits_end..],
+        "" | "b" | "k" | "kb" | "kib" | "m" | "mb" | "mib" | "g" | "gb" | "gib"
+    )
+}
+
+/// Accepts `[registry[:port]/]repo[:tag][@algo:digest]`. Repo path components are lowercase
+/// alphanumerics joined by `.`, `_`, `__` or `-`; tags are at most 128 `[A-Za-z0-9_.-]` chars
+/// not starting with `.` or `-`.
+fn valid_image_reference(value: &str) -> bool {
+    let (name_tag, digest) = match value.split_once('@') {
+        Some((name_tag, digest)) => (name_tag, Some(digest)),
+        None => (value, None),
+    };
+    if let Some(digest) = digest {
+        let Some((algorithm, hex)) = digest.split_once(':') else {
+            return false;
+        };
+        if algorithm.is_empty()
+            || !algorithm
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '+' || c == '_')
+            || hex.is_empty()
+            || !hex.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return false;
+        }
     }
-    if cfg.collector.rotate_every_min == 0 {
-        return Err(LuxError::Config(
-            "collector.rotate_every_min must be greater than 0".to_string(),
-        ));
+    let (name, tag) = match name_tag.rsplit_once(':') {
+        Some((name, tag)) if !tag.contains('/') => (name, Some(tag)),
+        _ => (name_tag, None),
+    };
+    if let Some(tag) = tag {
+        let valid_tag = tag.len() <= 128
+            && tag
+                .chars()
+                .next()
+                .is_some_and(|first| first.is_ascii_alphanumeric() || first == '_')
+            && tag
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+        if !valid_tag {
+            return false;
+        }
     }
-    if cfg.harness.api_port == 0 {
+    let mut components = name.split('/').collect::<Vec<_>>();
+    if components.len() > 1 {
+        let first = components[0];
+        if first.contains(['.', ':']) || first == "localhost" {
+            let (host, port) = match first.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (first, None),
+            };
+            let valid_registry = !host.is_empty()
+                && host
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+                && port.is_none_or(|port| {
+                    !port.is_empty() && port.chars().all(|c| c.is_ascii_digit())
+                });
+            if !valid_registry {
+                return false;
+            }
+            components.remove(0);
+        }
+    }
+    components.iter().all(|component| {
+        let bytes = component.as_bytes();
+        !bytes.is_empty()
+            && bytes
+                .first()
+                .is_some_and(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+            && bytes
+                .last()
+                .is_some_and(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+            && bytes.iter().all(|b| {
+                b.is_ascii_lowercase() || b.is_ascii_digit() || matches!(b, b'.' | b'_' | b'-')
+            })
+    })
+}
+
+/// Accepts Go-style durations as compose parses them: one or more `<digits><unit>` pairs with
+/// units `ns`, `us`, `ms`, `s`, `m` or `h` (`500ms`, `10s`, `1m30s`).
+fn valid_compose_duration(value: &str) -> bool {
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return false;
+    }
+    while !rest.is_empty() {
+        let digits_end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits_end == 0 {
+            return false;
+        }
+        rest = &rest[digits_end..];
+        let Some(unit) = ["ns", "us", "ms", "s", "m", "h"]
+            .into_iter()
+            .find(|unit| rest.starts_with(unit))
+        else {
+            return false;
+        };
+        rest = &rest[unit.len()..];
+    }
+    true
+}
+
+fn validate_service_healthcheck(field: &str, check: &ServiceHealthcheck) -> Result<(), LuxError> {
+    if check.command.trim().is_empty() {
+        return Err(LuxError::Config(format!(
+            "{field}.command must be non-empty"
+        )));
+    }
+    for (name, value) in [
+        ("interval", &check.interval),
+        ("timeout", &check.timeout),
+        ("start_period", &check.start_period),
+    ] {
+        if let Some(value) = value.as_deref() {
+            if !valid_compose_duration(value) {
+                return Err(LuxError::Config(format!(
+                    "{field}.{name} must be a duration like `10s`, `1m30s` or `500ms` (got '{value}')"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_config(cfg: &Config) -> Result<(), LuxError> {
+    if env::consts::OS != "macos" && env::consts::OS != "linux" {
+        return Err(LuxError::Config(format!(
+            "unsupported host operating system '{}'; supported: macos, linux",
+            env::consts::OS
+        )));
+    }
+    let _ = resolve_config_policy_paths(cfg)?;
+    // Container names such as `<project>-shim-<provider>-<pid>` are built from this.
+    validate_compose_project_name_field("docker.project_name", cfg.docker.project_name.trim())?;
+    if cfg.collector.idle_timeout_min == 0 {
         return Err(LuxError::Config(
-            "harness.api_port must be greater than 0".to_string(),
+            "collector.idle_timeout_min must be greater than 0".to_string(),
+        ));
+    }
+    if cfg.collector.rotate_every_min == 0 {
+        return Err(LuxError::Config(
+            "collector.rotate_every_min must be greater than 0".to_string(),
+        ));
+    }
+    if cfg.collector.dns_capture_bytes > COLLECTOR_DNS_PAYLOAD_MAX {
+        return Err(LuxError::Config(format!(
+            "collector.dns_capture_bytes must be at most {COLLECTOR_DNS_PAYLOAD_MAX}"
+        )));
+    }
+    if cfg.collector.sampling_rate == 0 {
+        return Err(LuxError::Config(
+            "collector.sampling_rate must be at least 1 (1 disables sampling)".to_string(),
         ));
     }
-    if cfg.runtime_control_plane.socket_path.contains('\n')
-        || cfg.runtime_control_plane.socket_path.contains('\r')
+    if !cfg.collector.ringbuf_bytes.is_power_of_two()
+        || cfg.collector.ringbuf_bytes < COLLECTOR_MIN_RINGBUF_BYTES
     {
+        return Err(LuxError::Config(format!(
+            "collector.ringbuf_bytes must be a power of two and at least {COLLECTOR_MIN_RINGBUF_BYTES} (got {})",
+            cfg.collector.ringbuf_bytes
+        )));
+    }
+    if cfg.collector.per_pid_rate.window_ms == 0 {
+        return Err(LuxError::Config(
+            "collector.per_pid_rate.window_ms must be greater than 0".to_string(),
+        ));
+    }
+    if cfg.collector.exclude_comm.len() > COLLECTOR_EXCLUDE_COMM_MAX {
+        return Err(LuxError::Config(format!(
+            "collector.exclude_comm supports at most {COLLECTOR_EXCLUDE_COMM_MAX} names (got {})",
+            cfg.collector.exclude_comm.len()
+        )));
+    }
+    for name in &cfg.collector.exclude_comm {
+        if name.trim().is_empty() || name.contains(',') {
+            return Err(LuxError::Config(format!(
+                "collector.exclude_comm entries must be non-empty process names without commas (got {name:?})"
+            )));
+        }
+    }
+    if cfg.harness.api_port == 0 {
         return Err(LuxError::Config(
-            "runtime_control_plane.socket_path contains an invalid newline".to_string(),
+            "harness.api_port must be greater than 0".to_string(),
         ));
     }
+    if let Some(check) = &cfg.harness.healthcheck {
+        validate_service_healthcheck("harness.healthcheck", check)?;
+    }
+    if !cfg.harness.health_path.starts_with('/') {
+        return Err(LuxError::Config(format!(
+            "harness.health_path must start with '/' (got {:?})",
+            cfg.harness.health_path
+        )));
+    }
+    // These reach compose.env or the generated compose override, where a newline would
+    // start a new entry; `write_env_file` and the override generation re-check the result.
+    for (field, value) in [
+        ("paths.trusted_root", cfg.paths.trusted_root.as_str()),
+        ("paths.log_root", cfg.paths.log_root.as_str()),
+        ("paths.workspace_root", cfg.paths.workspace_root.as_str()),
+        ("shims.bin_dir", cfg.shims.bin_dir.as_str()),
+        ("release.tag", cfg.release.tag.as_str()),
+        ("harness.api_token", cfg.harness.api_token.as_str()),
+        (
+            "runtime_control_plane.socket_path",
+            cfg.runtime_control_plane.socket_path.as_str(),
+        ),
+    ] {
+        reject_control_characters(field, value)?;
+    }
     #[cfg(unix)]
     {
         let configured = cfg.runtime_control_plane.socket_path.trim();
@@ -1583,6 +2942,38 @@ fn validate_config(cfg: &Config) -> Result<(), LuxError> {
             }
         }
     }
+    if cfg.runtime_control_plane.max_connections == 0 {
+        return Err(LuxError::Config(
+            "runtime_control_plane.max_connections must be greater than 0".to_string(),
+        ));
+    }
+    if cfg.runtime_control_plane.events_max_bytes == 0 {
+        return Err(LuxError::Config(
+            "runtime_control_plane.events_max_bytes must be greater than 0".to_string(),
+        ));
+    }
+    if cfg.runtime_control_plane.start_timeout_sec == 0 {
+        return Err(LuxError::Config(
+            "runtime_control_plane.start_timeout_sec must be greater than 0".to_string(),
+        ));
+    }
+    let tcp_listen = cfg.runtime_control_plane.tcp_listen.trim();
+    if !tcp_listen.is_empty() {
+        let valid = tcp_listen
+            .rsplit_once(':')
+            .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+        if !valid {
+            return Err(LuxError::Config(format!(
+                "runtime_control_plane.tcp_listen must be host:port (got {tcp_listen})"
+            )));
+        }
+    }
+    let tcp_token = cfg.runtime_control_plane.tcp_token.trim();
+    if !tcp_token.is_empty() && tcp_token == cfg.harness.api_token.trim() {
+        return Err(LuxError::Config(
+            "runtime_control_plane.tcp_token must differ from harness.api_token".to_string(),
+        ));
+    }
     if cfg.providers.is_empty() {
         return Err(LuxError::Config(
             "config.providers must contain at least one provider".to_string(),
@@ -1599,6 +2990,56 @@ fn validate_config(cfg: &Config) -> Result<(), LuxError> {
                 "providers.{name}.commands.run_template must be non-empty"
             )));
         }
+        if let Some(err) = run_template_placeholder_error(&provider.commands.run_template) {
+            return Err(LuxError::Config(format!(
+                "providers.{name}.commands.run_template {err}"
+            )));
+        }
+        if provider.commands.default_timeout_sec == Some(0) {
+            return Err(LuxError::Config(format!(
+                "providers.{name}.commands.default_timeout_sec must be greater than 0"
+            )));
+        }
+        if let Some(endpoint) = provider.commands.api_endpoint.as_deref() {
+            parse_api_endpoint(endpoint).map_err(|err| {
+                LuxError::Config(format!("providers.{name}.commands.api_endpoint {err}"))
+            })?;
+        }
+        if let Some(resources) = &provider.resources {
+            if let Some(cpus) = resources.cpus.as_deref() {
+                if !valid_cpus_limit(cpus) {
+                    return Err(LuxError::Config(format!(
+                        "providers.{name}.resources.cpus must be a positive number like `1.5` (got '{cpus}')"
+                    )));
+                }
+            }
+            if let Some(memory) = resources.memory.as_deref() {
+                if !valid_memory_limit(memory) {
+                    return Err(LuxError::Config(format!(
+                        "providers.{name}.resources.memory must be a size like `512m`, `2g` or `1GiB` (got '{memory}')"
+                    )));
+                }
+            }
+        }
+        if let Some(check) = &provider.healthcheck {
+            validate_service_healthcheck(&format!("providers.{name}.healthcheck"), check)?;
+        }
+        for (field, value) in [
+            ("commands.tui", provider.commands.tui.as_str()),
+            (
+                "commands.run_template",
+                provider.commands.run_template.as_str(),
+            ),
+        ] {
+            reject_control_characters(&format!("providers.{name}.{field}"), value)?;
+        }
+        if let Some(image) = provider.image.as_deref() {
+            if !valid_image_reference(image) {
+                return Err(LuxError::Config(format!(
+                    "providers.{name}.image must be an image reference like `repo[:tag]` (got '{image}')"
+                )));
+            }
+        }
         if provider.auth.api_key.secrets_file.trim().is_empty() {
             return Err(LuxError::Config(format!(
                 "providers.{name}.auth.api_key.secrets_file must be non-empty"
@@ -1614,11 +3055,47 @@ fn validate_config(cfg: &Config) -> Result<(), LuxError> {
                 "providers.{name}.auth.host_state.paths must contain at least one path"
             )));
         }
+        for item in &provider.auth.host_state.keychain_items {
+            if item.service.trim().is_empty() || item.path.trim().is_empty() {
+                return Err(LuxError::Config(format!(
+                    "providers.{name}.auth.host_state.keychain_items entries need a non-empty service and path"
+                )));
+            }
+        }
         if provider.ownership.root_comm.is_empty() {
             return Err(LuxError::Config(format!(
                 "providers.{name}.ownership.root_comm must contain at least one process name"
             )));
         }
+        if provider
+            .compose_profiles
+            .iter()
+            .any(|profile| profile.trim().is_empty())
+        {
+            return Err(LuxError::Config(format!(
+                "providers.{name}.compose_profiles entries must be non-empty"
+            )));
+        }
+    }
+    let mut alias_owners: BTreeMap<&str, &str> = BTreeMap::new();
+    for (name, provider) in &cfg.providers {
+        for alias in &provider.aliases {
+            if alias.trim().is_empty() {
+                return Err(LuxError::Config(format!(
+                    "providers.{name}.aliases entries must be non-empty"
+                )));
+            }
+            if cfg.providers.contains_key(alias) {
+                return Err(LuxError::Config(format!(
+                    "providers.{name}.aliases entry '{alias}' collides with provider '{alias}'"
+                )));
+            }
+            if let Some(owner) = alias_owners.insert(alias, name) {
+                return Err(LuxError::Config(format!(
+                    "providers.{name}.aliases entry '{alias}' is already used by provider '{owner}'"
+                )));
+            }
+        }
     }
     Ok(())
 }
@@ -1664,6 +3141,22 @@ fn current_uid() -> u32 {
     0
 }
 
+fn current_group_ids() -> Vec<u32> {
+    #[cfg(unix)]
+    {
+        let output = Command::new("id").arg("-G").output();
+        if let Ok(output) = output {
+            if output.status.success() {
+                return String::from_utf8_lossy(&output.stdout)
+                    .split_whitespace()
+                    .filter_map(|value| value.parse::<u32>().ok())
+                    .collect();
+            }
+        }
+    }
+    vec![current_primary_gid()]
+}
+
 #[cfg(unix)]
 fn unix_socket_path_too_long(path: &Path) -> bool {
     path.as_os_str().as_bytes().len() >= UNIX_SOCKET_PATH_LIMIT_BYTES
@@ -1721,6 +3214,11 @@ fn effective_runtime_socket_path(cfg: &Config) -> PathBuf {
     preferred
 }
 
+fn effective_runtime_log_file(cfg: &Config) -> Option<PathBuf> {
+    let configured = cfg.runtime_control_plane.log_file.trim();
+    (!configured.is_empty()).then(|| PathBuf::from(expand_path(configured)))
+}
+
 fn effective_runtime_socket_gid(cfg: &Config) -> u32 {
     cfg.runtime_control_plane
         .socket_gid
@@ -1773,6 +3271,47 @@ fn config_to_env(cfg: &Config) -> BTreeMap<String, String> {
     if !root_comm.is_empty() {
         envs.insert("COLLECTOR_ROOT_COMM".to_string(), root_comm.join(","));
     }
+    envs.insert(
+        "COLLECTOR_DNS_CAPTURE_BYTES".to_string(),
+        cfg.collector.dns_capture_bytes.to_string(),
+    );
+    envs.insert(
+        "COLLECTOR_SAMPLING_RATE".to_string(),
+        cfg.collector.sampling_rate.to_string(),
+    );
+    envs.insert(
+        "COLLECTOR_PID_RATE_MAX_EVENTS".to_string(),
+        cfg.collector.per_pid_rate.max_events.to_string(),
+    );
+    envs.insert(
+        "COLLECTOR_PID_RATE_WINDOW_MS".to_string(),
+        cfg.collector.per_pid_rate.window_ms.to_string(),
+    );
+    envs.insert(
+        "COLLECTOR_RINGBUF_BYTES".to_string(),
+        cfg.collector.ringbuf_bytes.to_string(),
+    );
+    envs.insert(
+        "COLLECTOR_SLOW_CONNECT_MS".to_string(),
+        cfg.collector.slow_connect_ms.to_string(),
+    );
+    envs.insert(
+        "COLLECTOR_EXCLUDE_COMM".to_string(),
+        cfg.collector
+            .exclude_comm
+            .iter()
+            .map(|name| name.trim())
+            .collect::<Vec<_>>()
+            .join(","),
+    );
+    envs.insert(
+        "COLLECTOR_IGNORE_LOOPBACK".to_string(),
+        cfg.collector.ignore_loopback.to_string(),
+    );
+    envs.insert(
+        "COLLECTOR_BATCH_EVENTS".to_string(),
+        cfg.collector.batch_events.to_string(),
+    );
     let runtime_socket = effective_runtime_socket_path(cfg);
     if let Some(runtime_dir) = runtime_socket.parent() {
         envs.insert(
@@ -1800,16 +3339,68 @@ fn merged_root_comm(cfg: &Config) -> Vec<String> {
     merged.into_iter().collect()
 }
 
+/// Tabs are allowed; any other control character (newlines included) is rejected.
+fn reject_control_characters(field: &str, value: &str) -> Result<(), LuxError> {
+    if value.chars().any(|c| c.is_control() && c != '\t') {
+        return Err(LuxError::Config(format!(
+            "{field} contains a newline or control character: {value:?}"
+        )));
+    }
+    Ok(())
+}
+
+fn valid_env_key(key: &str) -> bool {
+    key.chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// compose.env is line-oriented `KEY=value`, so a newline in a value would smuggle in extra
+/// variables; such maps are rejected before anything is written. Everything else is written,
+/// quoted by `env_file_value` where compose would otherwise mangle it.
 fn write_env_file(path: &Path, envs: &BTreeMap<String, String>) -> Result<(), LuxError> {
+    for (key, value) in envs {
+        if !valid_env_key(key) {
+            return Err(LuxError::Config(format!(
+                "refusing to write invalid env key {key:?} to {}",
+                path.display()
+            )));
+        }
+        reject_control_characters(key, value)?;
+    }
     ensure_parent(path)?;
     let mut content = String::new();
     for (key, value) in envs {
-        content.push_str(&format!("{}={}\n", key, value));
+        content.push_str(&format!("{}={}\n", key, env_file_value(value)));
     }
     fs::write(path, content)?;
     Ok(())
 }
 
+/// Unquoted env-file values have `$` interpolated, quotes stripped, a trailing ` #` comment
+/// dropped and surrounding blanks trimmed by compose. Such values are double-quoted instead,
+/// with `\`, `"` and `$` backslash-escaped so compose reads them back verbatim.
+fn env_file_value(value: &str) -> String {
+    let needs_quotes = value.contains(['$', '\'', '"'])
+        || value.contains(" #")
+        || value.contains("\t#")
+        || value.trim() != value;
+    if !needs_quotes {
+        return value.to_string();
+    }
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if matches!(c, '\\' | '"' | '$') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
 fn host_dir_writable(path: &Path) -> bool {
     fs::create_dir_all(path)
         .and_then(|_| {
@@ -2089,6 +3680,10 @@ fn setup_delegated_command_args(
         args.push("--compose-file".to_string());
         args.push(compose_file.to_string_lossy().to_string());
     }
+    if let Some(project_name) = &ctx.project_name {
+        args.push("--project-name".to_string());
+        args.push(project_name.clone());
+    }
     args.extend(command.iter().cloned());
     args
 }
@@ -2105,7 +3700,7 @@ fn setup_wrap_post_action_error(err: LuxError, outcomes: Vec<SetupPostActionOutc
         }
         other => LuxError::ProcessDetailed {
             message: format!("setup post-actions failed: {}", other),
-            details: ProcessErrorDetails {
+            details: Box::new(ProcessErrorDetails {
                 error_code: "setup_post_actions_failed".to_string(),
                 hint: Some(
                     "Fix the failing post-setup action and retry the suggested command."
@@ -2114,7 +3709,7 @@ fn setup_wrap_post_action_error(err: LuxError, outcomes: Vec<SetupPostActionOutc
                 command: None,
                 raw_stderr: None,
                 partial_outcome: Some(partial_outcome),
-            },
+            }),
         },
     }
 }
@@ -2150,7 +3745,7 @@ impl<'a> DelegatedSetupPostActionRunner<'a> {
 
     fn run_json_command(&self, command: &[String]) -> Result<JsonResponseEnvelope, LuxError> {
         let argv = setup_delegated_command_args(self.ctx, command, true);
-        let output = runtime_run_cli_subprocess(self.ctx, &argv)?;
+        let output = runtime_run_cli_subprocess(self.ctx, &argv, None)?;
         let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
         let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
         let manual_command = render_shell_command_for_display(&setup_delegated_command_args(
@@ -2161,7 +3756,7 @@ impl<'a> DelegatedSetupPostActionRunner<'a> {
                 message: format!(
                     "setup delegated command returned invalid JSON (`{manual_command}`): {err}"
                 ),
-                details: ProcessErrorDetails {
+                details: Box::new(ProcessErrorDetails {
                     error_code: "setup_delegated_invalid_json".to_string(),
                     hint: Some(format!("Retry `{manual_command}` manually.")),
                     command: Some(manual_command.clone()),
@@ -2173,7 +3768,7 @@ impl<'a> DelegatedSetupPostActionRunner<'a> {
                         None
                     },
                     partial_outcome: None,
-                },
+                }),
             })?;
 
         if output.status_code != 0 || !envelope.ok {
@@ -2202,7 +3797,10 @@ impl<'a> DelegatedSetupPostActionRunner<'a> {
             let message = envelope
                 .error
                 .unwrap_or_else(|| format!("setup delegated command failed: {}", manual_command));
-            return Err(LuxError::ProcessDetailed { message, details });
+            return Err(LuxError::ProcessDetailed {
+                message,
+                details: Box::new(details),
+            });
         }
 
         Ok(envelope)
@@ -2273,6 +3871,7 @@ fn handle_setup(
     yes: bool,
     no_apply: bool,
     dry_run: bool,
+    import_env: Option<String>,
 ) -> Result<(), LuxError> {
     let apply = !no_apply && !dry_run;
     if ctx.json && !defaults {
@@ -2298,7 +3897,7 @@ fn handle_setup(
     if !base_yaml.ends_with('\n') {
         base_yaml.push('\n');
     }
-    let base_cfg = match read_config_from_str(&base_yaml) {
+    let base_cfg = match read_config_from_str(&base_yaml, config_path) {
         Ok(cfg) => cfg,
         Err(err) => {
             return Err(LuxError::Config(format!(
@@ -2314,6 +3913,20 @@ fn handle_setup(
     let mut wrote_secrets: Vec<SetupSecretPlan> = Vec::new();
 
     if defaults {
+        let imported_env = match import_env.as_deref() {
+            Some(path) => {
+                let path = PathBuf::from(expand_path(path));
+                parse_dotenv_file(&path, "--import-env")?
+                    .into_iter()
+                    .filter_map(|entry| {
+                        entry
+                            .split_once('=')
+                            .map(|(key, value)| (key.to_string(), value.to_string()))
+                    })
+                    .collect::<BTreeMap<String, String>>()
+            }
+            None => BTreeMap::new(),
+        };
         // Non-interactive: keep values as-is, but ensure API-key providers have secrets.
         for (provider_name, provider) in &base_cfg.providers {
             if provider.auth_mode != AuthMode::ApiKey {
@@ -2324,10 +3937,22 @@ fn handle_setup(
                 continue;
             }
             let env_key = provider.auth.api_key.env_key.trim();
-            let value = env::var(env_key).ok().unwrap_or_default();
+            // Explicit process env wins over the imported dotenv file.
+            let (value, action) = match env::var(env_key) {
+                Ok(value) if !value.trim().is_empty() => (value, "create_from_env"),
+                _ => (
+                    imported_env.get(env_key).cloned().unwrap_or_default(),
+                    "create_from_env_file",
+                ),
+            };
             if value.trim().is_empty() {
+                let source = if import_env.is_some() {
+                    "your environment or the --import-env file"
+                } else {
+                    "your environment"
+                };
                 return Err(LuxError::Process(format!(
-                    "provider '{provider_name}' uses auth_mode=api_key but secrets file is missing at {}; set {} in your environment or create the secrets file manually",
+                    "provider '{provider_name}' uses auth_mode=api_key but secrets file is missing at {}; set {} in {source} or create the secrets file manually",
                     display_path_with_home(&secrets_file, home_for_display.as_deref()),
                     env_key
                 )));
@@ -2339,7 +3964,7 @@ fn handle_setup(
                 provider: provider_name.clone(),
                 env_key: env_key.to_string(),
                 path: secrets_file.to_string_lossy().to_string(),
-                action: "create_from_env".to_string(),
+                action: action.to_string(),
             });
         }
 
@@ -2350,7 +3975,7 @@ fn handle_setup(
         if apply && !dry_run {
             let _ = apply_config(ctx, &base_cfg)?;
         }
-        if let Ok(doctor_checks) = collect_doctor_checks(ctx, &base_cfg) {
+        if let Ok(doctor_checks) = collect_doctor_checks(ctx, &base_cfg, false) {
             for check in doctor_checks.into_iter().filter(|check| !check.ok) {
                 warnings.push(format!("doctor:{}: {}", check.id, check.message));
             }
@@ -2626,9 +4251,13 @@ For safety and policy compliance, workspace must be under $HOME."
             let chosen = values[selection].to_string();
             provider_auth_state.insert(provider_name.clone(), chosen.clone());
 
-            if provider_name == "claude" && chosen == "host_state" && env::consts::OS == "macos" {
+            if provider_name == "claude"
+                && chosen == "host_state"
+                && env::consts::OS == "macos"
+                && provider.auth.host_state.keychain_items.is_empty()
+            {
                 warnings.push("provider 'claude': host_state mode on macOS can fail when auth depends on Keychain; 
-may need to login the first time".to_string());
+list the Keychain services under providers.claude.auth.host_state.keychain_items to import them, or login the first time".to_string());
             }
 
             let should_mount_host_state =
@@ -2918,7 +4547,7 @@ continue using your normal codex/claude command
         }
 
         let (candidate_yaml, yaml_changed) = patch_setup_config_yaml(&base_yaml, &yaml_edits)?;
-        let candidate_cfg = read_config_from_str(&candidate_yaml)?;
+        let candidate_cfg = read_config_from_str(&candidate_yaml, config_path)?;
         let should_write_config = created_config || yaml_changed;
 
         print_step(6, total_steps, "Review");
@@ -3336,7 +4965,7 @@ continue using your normal codex/claude command
             }
         }
     }
-    if let Ok(doctor_checks) = collect_doctor_checks(ctx, &cfg_after_yaml) {
+    if let Ok(doctor_checks) = collect_doctor_checks(ctx, &cfg_after_yaml, false) {
         let failed: Vec<DoctorCheck> = doctor_checks
             .into_iter()
             .filter(|check| !check.ok)
@@ -3360,6 +4989,9 @@ continue using your normal codex/claude command
             .iter()
             .any(|row| row.action == "ui_up" && row.status == "ok");
 
+    if ctx.quiet {
+        return Ok(());
+    }
     println!();
     println!("{}", style("That's it!"));
     if services_auto_started {
@@ -3460,16 +5092,56 @@ continue using your normal codex/claude command
     Ok(())
 }
 
+fn config_init(ctx: &Context, force: bool, print: bool) -> Result<(), LuxError> {
+    let content = build_default_config_yaml()?;
+    if print {
+        print!("{content}");
+        return Ok(());
+    }
+    let exists = ctx.config_path.exists();
+    if exists && !force {
+        return output(
+            ctx,
+            json!({"path": ctx.config_path, "created": false, "backup_path": null}),
+        );
+    }
+    ensure_parent(&ctx.config_path)?;
+    // The existing file is copied aside rather than renamed so a failed rewrite still leaves
+    // the original in place.
+    let backup_path = if exists {
+        let backup = config_backup_path(&ctx.config_path);
+        fs::copy(&ctx.config_path, &backup)?;
+        Some(backup)
+    } else {
+        None
+    };
+    fs::write(&ctx.config_path, content)?;
+    output(
+        ctx,
+        json!({"path": ctx.config_path, "created": true, "backup_path": backup_path}),
+    )
+}
+
+/// `config.yaml.bak.<timestamp>`, with a numeric suffix when two backups land in the same second.
+fn config_backup_path(config_path: &Path) -> PathBuf {
+    let file_name = config_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "config.yaml".to_string());
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    let base = config_path.with_file_name(format!("{file_name}.bak.{stamp}"));
+    let mut candidate = base.clone();
+    let mut suffix = 1;
+    while candidate.exists() {
+        candidate = PathBuf::from(format!("{}.{suffix}", base.to_string_lossy()));
+        suffix += 1;
+    }
+    candidate
+}
+
 fn handle_config(ctx: &Context, command: ConfigCommand) -> Result<(), LuxError> {
     match command {
-        ConfigCommand::Init => {
-            if ctx.config_path.exists() {
-                return output(ctx, json!({"path": ctx.config_path, "created": false}));
-            }
-            ensure_parent(&ctx.config_path)?;
-            fs::write(&ctx.config_path, build_default_config_yaml()?)?;
-            output(ctx, json!({"path": ctx.config_path, "created": true}))
-        }
+        ConfigCommand::Init { force, print } => config_init(ctx, force, print),
         ConfigCommand::Edit => {
             if !ctx.config_path.exists() {
                 ensure_parent(&ctx.config_path)?;
@@ -3491,11 +5163,15 @@ fn handle_config(ctx: &Context, command: ConfigCommand) -> Result<(), LuxError>
                 ))
             }
         }
-        ConfigCommand::Validate => {
-            let _cfg = read_config(&ctx.config_path)?;
-            output(ctx, json!({"path": ctx.config_path, "valid": true}))
+        ConfigCommand::Validate { strict } => {
+            let cfg = read_config(&ctx.config_path)?;
+            if !strict {
+                return output(ctx, json!({"path": ctx.config_path, "valid": true}));
+            }
+            config_validate_strict(ctx, &cfg)
         }
-        ConfigCommand::Apply => {
+        ConfigCommand::Diff => config_diff(ctx),
+        ConfigCommand::Apply { diff } => {
             let cfg = match read_config(&ctx.config_path) {
                 Ok(cfg) => cfg,
                 Err(err) => {
@@ -3506,6 +5182,9 @@ fn handle_config(ctx: &Context, command: ConfigCommand) -> Result<(), LuxError>
                     )));
                 }
             };
+            if diff {
+                return config_apply_diff(ctx, &cfg);
+            }
             let (log_root, workspace_root) = apply_config(ctx, &cfg)?;
             output(
                 ctx,
@@ -3515,33 +5194,252 @@ fn handle_config(ctx: &Context, command: ConfigCommand) -> Result<(), LuxError>
     }
 }
 
-fn apply_config(ctx: &Context, cfg: &Config) -> Result<(PathBuf, PathBuf), LuxError> {
-    fn create_log_root_with_guidance(log_root: &Path) -> Result<(), LuxError> {
-        fs::create_dir_all(log_root).map_err(|err| {
-            if err.kind() == io::ErrorKind::PermissionDenied {
-                if env::consts::OS == "linux" && log_root.starts_with(Path::new("/var/lib/lux")) {
-                    return LuxError::Config(format!(
-                        "failed to create paths.log_root at {}: permission denied.\n\
-Linux default log root often needs a one-time setup:\n  sudo mkdir -p /var/lib/lux/logs\n  sudo chown -R $USER /var/lib/lux/logs\n\
-Then re-run `lux config apply`.",
-                        log_root.display()
-                    ));
-                }
-                return LuxError::Config(format!(
-                    "failed to create paths.log_root at {}: permission denied.\n\
-Choose a writable log root outside $HOME or create this directory with sufficient permissions.",
-                    log_root.display()
-                ));
-            }
-            LuxError::Io(err)
-        })
-    }
+#[derive(Debug, Clone, Serialize)]
+struct ConfigFinding {
+    check: &'static str,
+    severity: &'static str,
+    ok: bool,
+    path: String,
+    message: String,
+}
 
-    fn create_dir_with_guidance(field: &str, path: &Path) -> Result<(), LuxError> {
-        fs::create_dir_all(path).map_err(|err| {
-            if err.kind() == io::ErrorKind::PermissionDenied {
-                if env::consts::OS == "linux" && path.starts_with(Path::new("/var/lib/lux")) {
-                    return LuxError::Config(format!(
+impl ConfigFinding {
+    fn new(
+        check: &'static str,
+        severity: &'static str,
+        ok: bool,
+        path: &Path,
+        message: String,
+    ) -> Self {
+        Self {
+            check,
+            severity,
+            ok,
+            path: path.to_string_lossy().to_string(),
+            message,
+        }
+    }
+}
+
+/// Filesystem reachability checks behind `config validate --strict`. Every check runs so
+/// all problems are reported together; only `error` findings fail validation.
+fn config_strict_findings(cfg: &Config) -> Result<Vec<ConfigFinding>, LuxError> {
+    let policy = resolve_config_policy_paths(cfg)?;
+    let mut findings = Vec::new();
+    for (check, root) in [
+        ("trusted_root_writable", &policy.trusted_root),
+        ("log_root_writable", &policy.log_root),
+    ] {
+        let ok = host_dir_writable(root);
+        let message = if ok {
+            "directory is writable".to_string()
+        } else {
+            "directory cannot be created or written".to_string()
+        };
+        findings.push(ConfigFinding::new(check, "error", ok, root, message));
+    }
+    for (name, provider) in &cfg.providers {
+        if provider.auth_mode == AuthMode::ApiKey {
+            let secrets_file = PathBuf::from(expand_path(&provider.auth.api_key.secrets_file));
+            let (ok, message) = if secrets_file.is_file() {
+                (true, format!("provider '{name}': secrets file found"))
+            } else if secrets_file.exists() {
+                (
+                    false,
+                    format!("provider '{name}': secrets path is not a regular file"),
+                )
+            } else {
+                (false, format!("provider '{name}': secrets file not found"))
+            };
+            findings.push(ConfigFinding::new(
+                "secrets_file",
+                "error",
+                ok,
+                &secrets_file,
+                message,
+            ));
+        }
+        if provider.auth_mode == AuthMode::HostState || provider.mount_host_state_in_api_mode {
+            for configured in &provider.auth.host_state.paths {
+                let host_path = PathBuf::from(expand_path(configured));
+                let ok = host_path.exists();
+                let message = if ok {
+                    format!("provider '{name}': host-state path found")
+                } else {
+                    format!("provider '{name}': host-state path missing; it will not be mounted")
+                };
+                findings.push(ConfigFinding::new(
+                    "host_state_path",
+                    "warn",
+                    ok,
+                    &host_path,
+                    message,
+                ));
+            }
+        }
+    }
+    Ok(findings)
+}
+
+fn config_validate_strict(ctx: &Context, cfg: &Config) -> Result<(), LuxError> {
+    let findings = config_strict_findings(cfg)?;
+    let failures = findings
+        .iter()
+        .filter(|finding| !finding.ok && finding.severity == "error")
+        .count();
+    let valid = failures == 0;
+    if ctx.json {
+        let payload = JsonResult {
+            ok: valid,
+            result: Some(json!({
+                "path": ctx.config_path,
+                "valid": valid,
+                "strict": true,
+                "findings": findings,
+            })),
+            error: if valid {
+                None
+            } else {
+                Some(format!(
+                    "config validate --strict found {failures} failing check(s)"
+                ))
+            },
+            error_details: None,
+        };
+        print_json(ctx, &payload)?;
+        if !valid {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    for finding in &findings {
+        let state = if finding.ok { "ok" } else { "fail" };
+        println!(
+            "[{}] {} ({}) - {}: {}",
+            state, finding.check, finding.severity, finding.message, finding.path
+        );
+    }
+    if valid {
+        return Ok(());
+    }
+    Err(LuxError::Config(format!(
+        "config validate --strict found {failures} failing check(s)"
+    )))
+}
+
+fn flatten_config_diff_value(
+    prefix: &str,
+    value: serde_json::Value,
+    out: &mut BTreeMap<String, serde_json::Value>,
+) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                flatten_config_diff_value(&format!("{prefix}.{key}"), value, out);
+            }
+        }
+        other => {
+            out.insert(prefix.to_string(), other);
+        }
+    }
+}
+
+/// Flattens the config sections `config diff` reports on into dotted keys.
+fn config_diff_fields(cfg: &Config) -> Result<BTreeMap<String, serde_json::Value>, LuxError> {
+    let mut fields = BTreeMap::new();
+    flatten_config_diff_value("paths", serde_json::to_value(&cfg.paths)?, &mut fields);
+    flatten_config_diff_value("shims", serde_json::to_value(&cfg.shims)?, &mut fields);
+    flatten_config_diff_value(
+        "collector",
+        serde_json::to_value(&cfg.collector)?,
+        &mut fields,
+    );
+    for (name, provider) in &cfg.providers {
+        fields.insert(
+            format!("providers.{name}.auth_mode"),
+            json!(provider.auth_mode),
+        );
+    }
+    Ok(fields)
+}
+
+fn config_diff(ctx: &Context) -> Result<(), LuxError> {
+    let current = read_config(&ctx.config_path)?;
+    // Parse without policy validation: the computed defaults may point at paths that do not
+    // exist on this host yet.
+    let defaults: Config = serde_yaml::from_str(&build_default_config_yaml()?)?;
+    let current_fields = config_diff_fields(&current)?;
+    let default_fields = config_diff_fields(&defaults)?;
+    let keys: BTreeSet<&String> = current_fields.keys().chain(default_fields.keys()).collect();
+    let mut changed = serde_json::Map::new();
+    for key in keys {
+        let default = default_fields
+            .get(key)
+            .cloned()
+            .unwrap_or(serde_json::Value::Null);
+        let current = current_fields
+            .get(key)
+            .cloned()
+            .unwrap_or(serde_json::Value::Null);
+        if default != current {
+            changed.insert(key.clone(), json!({"default": default, "current": current}));
+        }
+    }
+    if ctx.json {
+        return output(ctx, json!({"path": ctx.config_path, "changed": changed}));
+    }
+    let display = |value: &serde_json::Value| match value {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Null => "(unset)".to_string(),
+        other => other.to_string(),
+    };
+    println!(
+        "{} {}",
+        style("Config diff vs defaults:").bold(),
+        style(ctx.config_path.display()).dim()
+    );
+    if changed.is_empty() {
+        println!("  {}", style("no changes").dim());
+    }
+    for (key, pair) in &changed {
+        println!(
+            "  {} {} {} {}",
+            style(format!("{key}:")).dim(),
+            style(display(&pair["default"])).dim(),
+            style("->").dim(),
+            style(display(&pair["current"])).green()
+        );
+    }
+    Ok(())
+}
+
+fn apply_config(ctx: &Context, cfg: &Config) -> Result<(PathBuf, PathBuf), LuxError> {
+    fn create_log_root_with_guidance(log_root: &Path) -> Result<(), LuxError> {
+        fs::create_dir_all(log_root).map_err(|err| {
+            if err.kind() == io::ErrorKind::PermissionDenied {
+                if env::consts::OS == "linux" && log_root.starts_with(Path::new("/var/lib/lux")) {
+                    return LuxError::Config(format!(
+                        "failed to create paths.log_root at {}: permission denied.\n\
+Linux default log root often needs a one-time setup:\n  sudo mkdir -p /var/lib/lux/logs\n  sudo chown -R $USER /var/lib/lux/logs\n\
+Then re-run `lux config apply`.",
+                        log_root.display()
+                    ));
+                }
+                return LuxError::Config(format!(
+                    "failed to create paths.log_root at {}: permission denied.\n\
+Choose a writable log root outside $HOME or create this directory with sufficient permissions.",
+                    log_root.display()
+                ));
+            }
+            LuxError::Io(err)
+        })
+    }
+
+    fn create_dir_with_guidance(field: &str, path: &Path) -> Result<(), LuxError> {
+        fs::create_dir_all(path).map_err(|err| {
+            if err.kind() == io::ErrorKind::PermissionDenied {
+                if env::consts::OS == "linux" && path.starts_with(Path::new("/var/lib/lux")) {
+                    return LuxError::Config(format!(
                         "failed to create {field} at {}: permission denied.\n\
 Linux default trusted root often needs a one-time setup:\n  sudo mkdir -p /var/lib/lux\n  sudo chown -R $USER /var/lib/lux\n\
 Then re-run `lux config apply`.",
@@ -3559,6 +5457,30 @@ Choose a writable trusted root outside $HOME or create this directory with suffi
     }
 
     let policy_paths = resolve_config_policy_paths(cfg)?;
+    write_env_file(&ctx.env_file, &applied_env(cfg, &policy_paths))?;
+    let log_root = policy_paths.log_root;
+    create_log_root_with_guidance(&log_root)?;
+    create_dir_with_guidance("paths.trusted_root", &policy_paths.trusted_root)?;
+    create_dir_with_guidance("state root", &policy_paths.state_root)?;
+    create_dir_with_guidance("runtime root", &policy_paths.runtime_root)?;
+    create_dir_with_guidance("secrets root", &policy_paths.secrets_root)?;
+    create_dir_with_guidance("shims.bin_dir", &policy_paths.shims_bin_dir)?;
+    let workspace_root = policy_paths.workspace_root;
+    fs::create_dir_all(&workspace_root).map_err(|err| {
+        if err.kind() == io::ErrorKind::PermissionDenied {
+            return LuxError::Config(format!(
+                "failed to create paths.workspace_root at {}: permission denied.\n\
+Choose a writable workspace under $HOME.",
+                workspace_root.display()
+            ));
+        }
+        LuxError::Io(err)
+    })?;
+    Ok((log_root, workspace_root))
+}
+
+/// Env map written to the compose env file by `lux config apply`.
+fn applied_env(cfg: &Config, policy_paths: &PolicyPaths) -> BTreeMap<String, String> {
     let mut envs = config_to_env(cfg);
     envs.insert(
         "LUX_LOG_ROOT".to_string(),
@@ -3588,26 +5510,154 @@ Choose a writable trusted root outside $HOME or create this directory with suffi
         "LUX_SHIMS_BIN_DIR".to_string(),
         policy_paths.shims_bin_dir.to_string_lossy().to_string(),
     );
-    write_env_file(&ctx.env_file, &envs)?;
-    let log_root = policy_paths.log_root;
-    create_log_root_with_guidance(&log_root)?;
-    create_dir_with_guidance("paths.trusted_root", &policy_paths.trusted_root)?;
-    create_dir_with_guidance("state root", &policy_paths.state_root)?;
-    create_dir_with_guidance("runtime root", &policy_paths.runtime_root)?;
-    create_dir_with_guidance("secrets root", &policy_paths.secrets_root)?;
-    create_dir_with_guidance("shims.bin_dir", &policy_paths.shims_bin_dir)?;
-    let workspace_root = policy_paths.workspace_root;
-    fs::create_dir_all(&workspace_root).map_err(|err| {
-        if err.kind() == io::ErrorKind::PermissionDenied {
-            return LuxError::Config(format!(
-                "failed to create paths.workspace_root at {}: permission denied.\n\
-Choose a writable workspace under $HOME.",
-                workspace_root.display()
-            ));
+    envs
+}
+
+/// Directories `lux config apply` creates, labelled the way its errors name them.
+fn applied_dirs(policy_paths: &PolicyPaths) -> Vec<(&'static str, &Path)> {
+    vec![
+        ("paths.log_root", policy_paths.log_root.as_path()),
+        ("paths.trusted_root", policy_paths.trusted_root.as_path()),
+        ("state root", policy_paths.state_root.as_path()),
+        ("runtime root", policy_paths.runtime_root.as_path()),
+        ("secrets root", policy_paths.secrets_root.as_path()),
+        ("shims.bin_dir", policy_paths.shims_bin_dir.as_path()),
+        (
+            "paths.workspace_root",
+            policy_paths.workspace_root.as_path(),
+        ),
+    ]
+}
+
+// Env keys whose values `config apply --diff` never prints.
+const SECRET_ENV_KEY_SUFFIXES: &[&str] = &["_TOKEN", "_SECRET", "_PASSWORD", "_API_KEY"];
+const REDACTED_ENV_VALUE: &str = "(redacted)";
+
+fn is_secret_env_key(key: &str) -> bool {
+    SECRET_ENV_KEY_SUFFIXES
+        .iter()
+        .any(|suffix| key.ends_with(suffix))
+}
+
+/// `lux config apply --diff`: compares the env map apply would write against the current
+/// compose env file and lists missing directories, without touching disk.
+fn config_apply_diff(ctx: &Context, cfg: &Config) -> Result<(), LuxError> {
+    let policy_paths = resolve_config_policy_paths(cfg)?;
+    let next = applied_env(cfg, &policy_paths);
+    let env_file_exists = ctx.env_file.exists();
+    let mut current = BTreeMap::new();
+    if env_file_exists {
+        for line in fs::read_to_string(&ctx.env_file)?.lines() {
+            if let Some((key, value)) = line.split_once('=') {
+                let value = match value
+                    .strip_prefix('"')
+                    .and_then(|inner| inner.strip_suffix('"'))
+                {
+                    Some(inner) => unescape_double_quoted(inner),
+                    None => value.to_string(),
+                };
+                current.insert(key.trim().to_string(), value);
+            }
         }
-        LuxError::Io(err)
-    })?;
-    Ok((log_root, workspace_root))
+    }
+    let shown = |key: &str, value: &str| -> String {
+        if is_secret_env_key(key) {
+            REDACTED_ENV_VALUE.to_string()
+        } else {
+            value.to_string()
+        }
+    };
+    let added: BTreeMap<&String, String> = next
+        .iter()
+        .filter(|(key, _)| !current.contains_key(*key))
+        .map(|(key, value)| (key, shown(key, value)))
+        .collect();
+    let removed: BTreeMap<&String, String> = current
+        .iter()
+        .filter(|(key, _)| !next.contains_key(*key))
+        .map(|(key, value)| (key, shown(key, value)))
+        .collect();
+    let changed: Vec<serde_json::Value> = next
+        .iter()
+        .filter_map(|(key, value)| {
+            let old = current.get(key)?;
+            (old != value).then(|| {
+                json!({
+                    "key": key,
+                    "old": shown(key, old),
+                    "new": shown(key, value),
+                    "redacted": is_secret_env_key(key),
+                })
+            })
+        })
+        .collect();
+    let create_dirs: Vec<serde_json::Value> = applied_dirs(&policy_paths)
+        .into_iter()
+        .filter(|(_, path)| !path.exists())
+        .map(|(field, path)| json!({"field": field, "path": path}))
+        .collect();
+
+    if ctx.json {
+        return output(
+            ctx,
+            json!({
+                "dry_run": true,
+                "env_file": ctx.env_file,
+                "env_file_exists": env_file_exists,
+                "added": added,
+                "removed": removed,
+                "changed": changed,
+                "create_dirs": create_dirs,
+            }),
+        );
+    }
+    println!(
+        "{} {}",
+        style("Config apply diff:").bold(),
+        style(ctx.env_file.display()).dim()
+    );
+    if !env_file_exists {
+        println!("  {}", style("(env file does not exist yet)").dim());
+    }
+    if added.is_empty() && removed.is_empty() && changed.is_empty() {
+        println!("  {}", style("no env changes").dim());
+    }
+    for (key, value) in &added {
+        println!("  {}", style(format!("+ {key}={value}")).green());
+    }
+    for (key, value) in &removed {
+        println!("  {}", style(format!("- {key}={value}")).red());
+    }
+    for change in &changed {
+        let key = change["key"].as_str().unwrap_or_default();
+        if change["redacted"] == true {
+            println!(
+                "  {} {}",
+                style(format!("~ {key}:")).yellow(),
+                style("(changed)").dim()
+            );
+            continue;
+        }
+        println!(
+            "  {} {} {} {}",
+            style(format!("~ {key}:")).yellow(),
+            style(change["old"].as_str().unwrap_or_default()).dim(),
+            style("->").dim(),
+            style(change["new"].as_str().unwrap_or_default()).green()
+        );
+    }
+    if !create_dirs.is_empty() {
+        println!("{}", style("Directories to create:").bold());
+        for dir in &create_dirs {
+            println!(
+                "  {} {}",
+                style(format!("{}:", dir["field"].as_str().unwrap_or_default())).dim(),
+                dir["path"].as_str().unwrap_or_default()
+            );
+        }
+    }
+    println!("{}", style("dry run: nothing written").dim());
+    Ok(())
 }
 
 fn shell_single_quote(value: &str) -> String {
@@ -4175,6 +6225,7 @@ struct RuntimePaths {
     trusted_root: PathBuf,
     runtime_dir: PathBuf,
     runtime_socket_path: PathBuf,
+    runtime_socket_gid: u32,
     runtime_pid_path: PathBuf,
     runtime_events_path: PathBuf,
     state_dir: PathBuf,
@@ -4192,7 +6243,7 @@ struct RuntimePaths {
 }
 
 fn resolve_runtime_paths(ctx: &Context) -> Result<(RuntimePaths, bool), LuxError> {
-    let config_exists = ctx.config_path.exists();
+    let config_exists = config_present(&ctx.config_path);
     let cfg = if config_exists {
         read_config(&ctx.config_path)?
     } else {
@@ -4224,6 +6275,7 @@ fn resolve_runtime_paths(ctx: &Context) -> Result<(RuntimePaths, bool), LuxError
             trusted_root: policy_paths.trusted_root.clone(),
             runtime_dir: runtime_dir.clone(),
             runtime_socket_path: runtime_socket_path.clone(),
+            runtime_socket_gid: effective_runtime_socket_gid(&cfg),
             runtime_pid_path: runtime_dir.join("control_plane.pid"),
             runtime_events_path: runtime_dir.join("events.jsonl"),
             state_dir: policy_paths.state_root.clone(),
@@ -4387,21 +6439,116 @@ fn fetch_latest_release_tag() -> Result<String, LuxError> {
     Ok(normalize_version_tag(&payload.tag_name))
 }
 
+enum DownloadAttempt {
+    Complete,
+    /// The resume range was refused and the partial file dropped; start over right away.
+    Restart(LuxError),
+    Retry(LuxError),
+}
+
+/// Streams `url` into `<path>.part` and renames it into place. Interrupted transfers are
+/// retried with exponential backoff; once the server has advertised `Accept-Ranges: bytes`, a
+/// retry resumes from the bytes already on disk. The caller still verifies the checksum, which
+/// catches a bad resume.
 fn download_file(url: &str, path: &Path) -> Result<(), LuxError> {
+    ensure_parent(path)?;
+    let mut part_name = path.file_name().unwrap_or_default().to_os_string();
+    part_name.push(".part");
+    let part_path = path.with_file_name(part_name);
     let client = reqwest::blocking::Client::new();
-    let response = client.get(url).header("User-Agent", "lux-cli").send()?;
+    let mut accepts_ranges = false;
+    let mut last_error = None;
+    let mut backoff = Duration::from_millis(DOWNLOAD_RETRY_BACKOFF_MS);
+    for attempt in 1..=DOWNLOAD_MAX_ATTEMPTS {
+        match download_attempt(&client, url, &part_path, &mut accepts_ranges)? {
+            DownloadAttempt::Complete => {
+                fs::rename(&part_path, path)?;
+                return Ok(());
+            }
+            DownloadAttempt::Restart(err) => last_error = Some(err),
+            DownloadAttempt::Retry(err) => {
+                last_error = Some(err);
+                if attempt < DOWNLOAD_MAX_ATTEMPTS {
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                }
+            }
+        }
+    }
+    let _ = fs::remove_file(&part_path);
+    Err(LuxError::Process(format!(
+        "download failed after {DOWNLOAD_MAX_ATTEMPTS} attempts: {url} ({})",
+        last_error.map(|err| err.to_string()).unwrap_or_default()
+    )))
+}
+
+fn download_attempt(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    part_path: &Path,
+    accepts_ranges: &mut bool,
+) -> Result<DownloadAttempt, LuxError> {
+    let offset = if *accepts_ranges {
+        fs::metadata(part_path).map(|meta| meta.len()).unwrap_or(0)
+    } else {
+        0
+    };
+    let mut request = client.get(url).header("User-Agent", "lux-cli");
+    if offset > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={offset}-"));
+    }
+    let mut response = match request.send() {
+        Ok(response) => response,
+        Err(err) => return Ok(DownloadAttempt::Retry(err.into())),
+    };
     let status = response.status();
+    if offset > 0 && status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        // The partial file no longer lines up with the remote (e.g. it was replaced or is
+        // already at least as long), so drop it and fetch the whole file again.
+        fs::remove_file(part_path)?;
+        *accepts_ranges = false;
+        return Ok(DownloadAttempt::Restart(LuxError::Process(format!(
+            "download failed: {url} (resume from byte {offset} not satisfiable)"
+        ))));
+    }
     if !status.is_success() {
         let body = response.text().unwrap_or_default();
-        return Err(LuxError::Process(format!(
+        let err = LuxError::Process(format!(
             "download failed: {} (HTTP {} {})",
             url, status, body
-        )));
+        ));
+        if status.is_server_error() {
+            return Ok(DownloadAttempt::Retry(err));
+        }
+        return Err(err);
+    }
+    // Resume only when the server honoured the range from the expected offset; a plain 200
+    // (or a mismatched Content-Range) means starting over.
+    let resumed = offset > 0
+        && status == reqwest::StatusCode::PARTIAL_CONTENT
+        && response
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with(&format!("bytes {offset}-")));
+    *accepts_ranges = resumed
+        || response
+            .headers()
+            .get(reqwest::header::ACCEPT_RANGES)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.trim().eq_ignore_ascii_case("bytes"));
+    let mut file = if resumed {
+        fs::OpenOptions::new().append(true).open(part_path)?
+    } else {
+        fs::File::create(part_path)?
+    };
+    match io::copy(&mut response, &mut file) {
+        Ok(_) => {
+            file.flush()?;
+            Ok(DownloadAttempt::Complete)
+        }
+        Err(err) => Ok(DownloadAttempt::Retry(err.into())),
     }
-    let bytes = response.bytes()?;
-    ensure_parent(path)?;
-    fs::write(path, &bytes)?;
-    Ok(())
 }
 
 fn parse_checksum(content: &str) -> Option<String> {
@@ -4440,9 +6587,80 @@ fn sha256_file(path: &Path) -> Result<String, LuxError> {
             return Ok(token);
         }
     }
-    Err(LuxError::Process(
-        "no SHA256 tool found (expected shasum, sha256sum, or openssl)".to_string(),
-    ))
+    // None of the external tools are available (minimal systems); hash in-process.
+    sha256_file_native(path)
+}
+
+fn sha256_file_native(path: &Path) -> Result<String, LuxError> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// Sidecar recording the hash of `{bundle}` as of a given size and mtime, so a retried
+/// verification does not re-hash an unchanged file.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct ComputedSha256Cache {
+    size_bytes: u64,
+    mtime_ns: u128,
+    sha256: String,
+}
+
+fn computed_sha256_cache_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".computed-sha256");
+    path.with_file_name(name)
+}
+
+fn sha256_file_fingerprint(path: &Path) -> Option<(u64, u128)> {
+    let metadata = fs::metadata(path).ok()?;
+    let mtime_ns = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_nanos();
+    Some((metadata.len(), mtime_ns))
+}
+
+fn cached_sha256_file(path: &Path) -> Result<String, LuxError> {
+    let cache_path = computed_sha256_cache_path(path);
+    let fingerprint = sha256_file_fingerprint(path);
+    if let Some((size_bytes, mtime_ns)) = fingerprint {
+        let cached = fs::read_to_string(&cache_path)
+            .ok()
+            .and_then(|raw| serde_json::from_str::<ComputedSha256Cache>(&raw).ok());
+        if let Some(cached) = cached {
+            if cached.size_bytes == size_bytes && cached.mtime_ns == mtime_ns {
+                return Ok(cached.sha256);
+            }
+        }
+    }
+    let sha256 = sha256_file(path)?;
+    if let Some((size_bytes, mtime_ns)) = fingerprint {
+        let cache = ComputedSha256Cache {
+            size_bytes,
+            mtime_ns,
+            sha256: sha256.clone(),
+        };
+        // Best effort: a missing cache only costs a re-hash next time.
+        if let Ok(body) = serde_json::to_string(&cache) {
+            let _ = fs::write(&cache_path, body);
+        }
+    }
+    Ok(sha256)
 }
 
 fn verify_bundle_checksum(bundle_path: &Path, checksum_path: &Path) -> Result<(), LuxError> {
@@ -4453,7 +6671,7 @@ fn verify_bundle_checksum(bundle_path: &Path, checksum_path: &Path) -> Result<()
             checksum_path.display()
         )));
     };
-    let actual = sha256_file(bundle_path)?;
+    let actual = cached_sha256_file(bundle_path)?;
     if expected != actual {
         return Err(LuxError::Process(format!(
             "checksum mismatch for {}: expected {}, got {}",
@@ -4604,11 +6822,52 @@ enum LifecycleTarget {
 
 #[derive(Debug, Serialize, Default)]
 struct ComposeServiceOverride {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     volumes: Vec<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     environment: Vec<String>,
-}
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deploy: Option<ComposeDeploy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    healthcheck: Option<ComposeHealthcheck>,
+}
+
+#[derive(Debug, Serialize)]
+struct ComposeHealthcheck {
+    test: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interval: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_period: Option<String>,
+}
+
+impl From<&ServiceHealthcheck> for ComposeHealthcheck {
+    fn from(check: &ServiceHealthcheck) -> Self {
+        Self {
+            test: vec!["CMD-SHELL".to_string(), check.command.clone()],
+            interval: check.interval.clone(),
+            timeout: check.timeout.clone(),
+            retries: check.retries,
+            start_period: check.start_period.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ComposeDeploy {
+    resources: ComposeDeployResources,
+}
+
+#[derive(Debug, Serialize)]
+struct ComposeDeployResources {
+    limits: ProviderResources,
+}
 
 #[derive(Debug, Serialize, Default)]
 struct ComposeRuntimeOverride {
@@ -4619,6 +6878,9 @@ struct ComposeRuntimeOverride {
 struct ProviderRuntimeCompose {
     override_file: PathBuf,
     warnings: Vec<String>,
+    resource_limits: Option<ProviderResources>,
+    /// Image the `agent` service runs: the provider override, else the bundled image.
+    image: String,
 }
 
 fn configured_compose_files(
@@ -4656,43 +6918,80 @@ fn compose_files(
     Ok(files)
 }
 
+/// The env a missing compose.env is generated with.
+fn compose_config_env(cfg: &Config) -> Result<BTreeMap<String, String>, LuxError> {
+    let policy_paths = resolve_config_policy_paths(cfg)?;
+    let mut envs = config_to_env(cfg);
+    envs.insert(
+        "LUX_LOG_ROOT".to_string(),
+        policy_paths.log_root.to_string_lossy().to_string(),
+    );
+    envs.insert(
+        "LUX_WORKSPACE_ROOT".to_string(),
+        policy_paths.workspace_root.to_string_lossy().to_string(),
+    );
+    Ok(envs)
+}
+
+/// Env for a docker invocation: `env_overrides`, plus, for a stdin config without a
+/// compose.env to point at, the config's compose env. Variables already set in lux's own
+/// environment keep precedence there, as they would over an env file.
+fn docker_env(
+    ctx: &Context,
+    env_overrides: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, LuxError> {
+    let mut envs = BTreeMap::new();
+    if config_is_stdin(&ctx.config_path) && !ctx.env_file.exists() {
+        let cfg = read_config(&ctx.config_path)?;
+        envs.extend(
+            compose_config_env(&cfg)?
+                .into_iter()
+                .filter(|(key, _)| env::var_os(key).is_none()),
+        );
+    }
+    envs.extend(
+        env_overrides
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone())),
+    );
+    Ok(envs)
+}
+
 fn compose_base_args(
     ctx: &Context,
     cfg: &Config,
     ui: bool,
     runtime_overrides: &[PathBuf],
+    profiles: &[String],
 ) -> Result<Vec<String>, LuxError> {
     let files = compose_files(ctx, ui, runtime_overrides)?;
-    if !ctx.env_file.exists() {
-        let policy_paths = resolve_config_policy_paths(cfg)?;
-        let mut envs = config_to_env(cfg);
-        envs.insert(
-            "LUX_LOG_ROOT".to_string(),
-            policy_paths.log_root.to_string_lossy().to_string(),
-        );
-        envs.insert(
-            "LUX_WORKSPACE_ROOT".to_string(),
-            policy_paths.workspace_root.to_string_lossy().to_string(),
-        );
-        write_env_file(&ctx.env_file, &envs)?;
+    let mut args = vec!["compose".to_string()];
+    // A stdin config is read-only, so compose gets its env through `docker_env` instead.
+    if !ctx.env_file.exists() && !config_is_stdin(&ctx.config_path) {
+        write_env_file(&ctx.env_file, &compose_config_env(cfg)?)?;
     }
-    let mut args = vec![
-        "compose".to_string(),
-        "--env-file".to_string(),
-        ctx.env_file.to_string_lossy().to_string(),
-    ];
-    if !cfg.docker.project_name.trim().is_empty() {
+    if ctx.env_file.exists() {
+        args.push("--env-file".to_string());
+        args.push(ctx.env_file.to_string_lossy().to_string());
+    }
+    let project_name = compose_project_name(ctx, cfg)?;
+    if !project_name.is_empty() {
         args.push("-p".to_string());
-        args.push(cfg.docker.project_name.clone());
+        args.push(project_name);
     }
     for file in files {
         args.push("-f".to_string());
         args.push(file.to_string_lossy().to_string());
     }
+    for profile in profiles {
+        args.push("--profile".to_string());
+        args.push(profile.clone());
+    }
     Ok(args)
 }
 
 fn resolve_lifecycle_target(
+    cfg: &Config,
     provider: Option<String>,
     collector_only: bool,
 ) -> Result<LifecycleTarget, LuxError> {
@@ -4710,15 +7009,36 @@ fn resolve_lifecycle_target(
     if provider.trim().is_empty() {
         return Err(LuxError::Config("--provider must be non-empty".to_string()));
     }
+    let provider = resolve_provider_name(cfg, &provider)?.to_string();
     Ok(LifecycleTarget::Provider(provider))
 }
 
-fn provider_from_config<'a>(cfg: &'a Config, provider: &str) -> Result<&'a Provider, LuxError> {
-    cfg.providers.get(provider).ok_or_else(|| {
-        LuxError::Config(format!(
+/// Maps a provider name or one of its `aliases` to the canonical `config.providers` key.
+fn resolve_provider_name<'a>(cfg: &'a Config, provider: &str) -> Result<&'a str, LuxError> {
+    if let Some((name, _)) = cfg.providers.get_key_value(provider) {
+        return Ok(name.as_str());
+    }
+    let matches: Vec<&str> = cfg
+        .providers
+        .iter()
+        .filter(|(_, candidate)| candidate.aliases.iter().any(|alias| alias == provider))
+        .map(|(name, _)| name.as_str())
+        .collect();
+    match matches.as_slice() {
+        [name] => Ok(name),
+        [] => Err(LuxError::Config(format!(
             "provider '{provider}' is not defined in config.providers"
-        ))
-    })
+        ))),
+        names => Err(LuxError::Config(format!(
+            "provider alias '{provider}' is ambiguous (matches {})",
+            names.join(", ")
+        ))),
+    }
+}
+
+fn provider_from_config<'a>(cfg: &'a Config, provider: &str) -> Result<&'a Provider, LuxError> {
+    let name = resolve_provider_name(cfg, provider)?;
+    Ok(&cfg.providers[name])
 }
 
 fn active_provider_state_path(state_root: &Path) -> PathBuf {
@@ -4780,6 +7100,102 @@ fn resolve_host_state_destination(host_path: &Path) -> String {
         .to_string()
 }
 
+fn provider_keychain_dir(runtime_dir: &Path, provider_name: &str) -> PathBuf {
+    runtime_dir.join("provider_keychain").join(provider_name)
+}
+
+/// Writes each Keychain item to a 0600 file under `dir` and returns `(host_file,
+/// container_destination)` pairs. Unreadable items become warnings; values are never
+/// included in messages.
+fn materialize_keychain_items<F>(
+    dir: &Path,
+    provider_name: &str,
+    items: &[ProviderKeychainItem],
+    read_item: F,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<(PathBuf, String)>, LuxError>
+where
+    F: Fn(&str) -> Result<String, LuxError>,
+{
+    clear_provider_keychain_material(dir)?;
+    let mut imported = Vec::new();
+    for (idx, item) in items.iter().enumerate() {
+        let value = match read_item(item.service.trim()) {
+            Ok(value) => value,
+            Err(err) => {
+                warnings.push(format!(
+                    "provider '{provider_name}': could not read Keychain item '{}', skipping: {err}",
+                    item.service
+                ));
+                continue;
+            }
+        };
+        let host_path = dir.join(idx.to_string());
+        write_atomic_text_file(&host_path, &value, Some(0o600))?;
+        let destination =
+            resolve_host_state_destination(&PathBuf::from(expand_path(item.path.trim())));
+        imported.push((host_path, destination));
+    }
+    Ok(imported)
+}
+
+fn read_macos_keychain_item(service: &str) -> Result<String, LuxError> {
+    let output = Command::new("security")
+        .args(["find-generic-password", "-s", service, "-w"])
+        .output()?;
+    if !output.status.success() {
+        return Err(LuxError::Process(format!(
+            "security find-generic-password exited with {}",
+            output.status
+        )));
+    }
+    let mut value = String::from_utf8(output.stdout)
+        .map_err(|_| LuxError::Process("Keychain item is not valid UTF-8".to_string()))?;
+    if value.ends_with('\n') {
+        value.pop();
+    }
+    Ok(value)
+}
+
+fn clear_provider_keychain_material(dir: &Path) -> Result<(), LuxError> {
+    match fs::remove_dir_all(dir) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+const RUN_TEMPLATE_PLACEHOLDERS: [&str; 4] = ["prompt", "start_dir", "workspace", "run_id"];
+
+/// Mirrors the harness's template expansion: `{name}` and `{env:KEY}` are placeholders,
+/// while `${...}` shell expansions and brace expansions such as `{a,b}` stay literal.
+fn run_template_placeholder_error(template: &str) -> Option<String> {
+    let placeholder = regex::Regex::new(r"\{(env:[^{}]*|[A-Za-z_][A-Za-z0-9_]*)\}")
+        .expect("static placeholder pattern");
+    for found in placeholder.captures_iter(template) {
+        let whole = found.get(0).expect("capture 0 always matches");
+        if template[..whole.start()].ends_with('$') {
+            continue;
+        }
+        let name = &found[1];
+        if let Some(key) = name.strip_prefix("env:") {
+            let valid_key = key
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid_key {
+                return Some(format!("has invalid env placeholder {{{name}}}"));
+            }
+        } else if !RUN_TEMPLATE_PLACEHOLDERS.contains(&name) {
+            return Some(format!(
+                "has unknown placeholder {{{name}}} (expected one of {{prompt}}, {{start_dir}}, {{workspace}}, {{run_id}}, {{env:KEY}})"
+            ));
+        }
+    }
+    None
+}
+
 fn generate_provider_runtime_compose(
     ctx: &Context,
     provider_name: &str,
@@ -4787,7 +7203,8 @@ fn generate_provider_runtime_compose(
     tui_cmd_override: Option<&str>,
 ) -> Result<ProviderRuntimeCompose, LuxError> {
     let cfg = read_config(&ctx.config_path)?;
-    let runtime_dir = resolve_config_policy_paths(&cfg)?.runtime_root;
+    let policy = resolve_config_policy_paths(&cfg)?;
+    let runtime_dir = policy.runtime_root;
     fs::create_dir_all(&runtime_dir)?;
 
     let override_file = runtime_dir.join(format!("compose.provider.{provider_name}.yml"));
@@ -4804,6 +7221,11 @@ fn generate_provider_runtime_compose(
         "HARNESS_RUN_CMD_TEMPLATE={}",
         provider.commands.run_template
     ));
+    if let Some(timeout_sec) = provider.commands.default_timeout_sec {
+        harness
+            .environment
+            .push(format!("HARNESS_RUN_TIMEOUT_SEC={timeout_sec}"));
+    }
 
     agent
         .environment
@@ -4850,6 +7272,29 @@ fn generate_provider_runtime_compose(
             ));
             host_state_count += 1;
         }
+        // Keychain items ride the host-state copy path; off macOS there is no Keychain.
+        if env::consts::OS == "macos" && !provider.auth.host_state.keychain_items.is_empty() {
+            let imported = materialize_keychain_items(
+                &provider_keychain_dir(&runtime_dir, provider_name),
+                provider_name,
+                &provider.auth.host_state.keychain_items,
+                read_macos_keychain_item,
+                &mut warnings,
+            )?;
+            for (host_path, destination) in imported {
+                let mount_dst = format!("/run/lux/provider_host_state/{host_state_count}");
+                agent
+                    .volumes
+                    .push(format!("{}:{}:ro", host_path.to_string_lossy(), mount_dst));
+                agent.environment.push(format!(
+                    "LUX_PROVIDER_HOST_STATE_SRC_{host_state_count}={mount_dst}"
+                ));
+                agent.environment.push(format!(
+                    "LUX_PROVIDER_HOST_STATE_DST_{host_state_count}={destination}"
+                ));
+                host_state_count += 1;
+            }
+        }
         if host_state_count == 0 {
             warnings.push(format!(
                 "provider '{provider_name}': all configured host-state paths are missing"
@@ -4889,6 +7334,46 @@ fn generate_provider_runtime_compose(
             .push("LUX_PROVIDER_SECRETS_FILE=".to_string());
     }
 
+    if let Some(active_run) = load_active_run_state(&policy.state_root)? {
+        for mount in &active_run.mounts {
+            if !Path::new(&mount.host).exists() {
+                warnings.push(format!(
+                    "run mount source missing, skipping: {}",
+                    mount.host
+                ));
+                continue;
+            }
+            agent.volumes.push(mount.spec());
+        }
+    }
+
+    // Compose v2 applies `deploy.resources.limits` outside swarm mode too.
+    let resource_limits = provider
+        .resources
+        .clone()
+        .filter(|resources| resources.cpus.is_some() || resources.memory.is_some());
+    agent.deploy = resource_limits.clone().map(|limits| ComposeDeploy {
+        resources: ComposeDeployResources { limits },
+    });
+    agent.healthcheck = provider.healthcheck.as_ref().map(ComposeHealthcheck::from);
+    agent.image = provider.image.clone();
+    let image = provider.image.clone().unwrap_or_else(|| {
+        format!(
+            "{AGENT_IMAGE_REPO}:{}",
+            config_to_env(&cfg)
+                .remove("LUX_VERSION")
+                .unwrap_or_default()
+        )
+    });
+    harness.healthcheck = cfg
+        .harness
+        .healthcheck
+        .as_ref()
+        .map(ComposeHealthcheck::from);
+
+    agent.environment = compose_environment_entries(agent.environment)?;
+    harness.environment = compose_environment_entries(harness.environment)?;
+
     let mut runtime_override = ComposeRuntimeOverride::default();
     runtime_override.services.insert("agent".to_string(), agent);
     runtime_override
@@ -4900,13 +7385,67 @@ fn generate_provider_runtime_compose(
     Ok(ProviderRuntimeCompose {
         override_file,
         warnings,
+        resource_limits,
+        image,
     })
 }
 
+/// Validates `KEY=value` entries for a compose `environment:` list and escapes `$` as `$$`,
+/// so values such as a `run_template` reach the container verbatim instead of being
+/// interpolated by compose. YAML quoting is left to serde_yaml.
+fn compose_environment_entries(entries: Vec<String>) -> Result<Vec<String>, LuxError> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            let Some((key, value)) = entry.split_once('=') else {
+                return Err(LuxError::Config(format!(
+                    "compose environment entry is missing '=': {entry:?}"
+                )));
+            };
+            if !valid_env_key(key) {
+                return Err(LuxError::Config(format!(
+                    "invalid compose environment key {key:?}"
+                )));
+            }
+            reject_control_characters(key, value)?;
+            Ok(format!("{key}={}", value.replace('$', "$$")))
+        })
+        .collect()
+}
+
 fn run_id_from_now() -> String {
     format!("lux__{}", Utc::now().format("%Y_%m_%d_%H_%M_%S"))
 }
 
+/// Run ids have second precision, so a run started in the same second as an existing one
+/// gets `_2`, `_3`, ... appended. Used to preview the id; `claim_run_id` reserves it.
+fn next_free_run_id(log_root: &Path, base: &str) -> String {
+    std::iter::once(base.to_string())
+        .chain((2u32..).map(|suffix| format!("{base}_{suffix}")))
+        .find(|candidate| !run_root(log_root, candidate).exists())
+        .expect("suffix range is unbounded")
+}
+
+/// Creates the run directory for the first free id derived from `base` (see
+/// `next_free_run_id`). `create_dir` fails on an existing directory, so concurrent `up`
+/// calls cannot both claim the same id.
+fn claim_run_id(log_root: &Path, base: &str) -> Result<String, LuxError> {
+    fs::create_dir_all(log_root)?;
+    let mut suffix = 1u32;
+    loop {
+        let candidate = if suffix == 1 {
+            base.to_string()
+        } else {
+            format!("{base}_{suffix}")
+        };
+        match fs::create_dir(run_root(log_root, &candidate)) {
+            Ok(()) => return Ok(candidate),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => suffix += 1,
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
 fn active_run_state_path(state_root: &Path) -> PathBuf {
     state_root.join(".active_run.json")
 }
@@ -4925,21 +7464,155 @@ fn write_active_run_state(
     state_root: &Path,
     run_id: &str,
     workspace_root: &Path,
+    mounts: &[RunMount],
+    project_name: &str,
 ) -> Result<(), LuxError> {
     fs::create_dir_all(state_root)?;
     let state = ActiveRunState {
         run_id: run_id.to_string(),
         started_at: Utc::now().to_rfc3339(),
         workspace_root: Some(workspace_root.to_string_lossy().to_string()),
+        idle_timeout_min: None,
+        mounts: mounts.to_vec(),
+        project_name: Some(project_name.to_string()),
     };
+    store_active_run_state(state_root, &state)
+}
+
+/// The active run and runtime state live under `paths.trusted_root`, not per compose
+/// project, so stacks under different project names cannot run side by side: commands
+/// aimed at one project refuse an active run recorded for another.
+fn ensure_active_run_project(active: &ActiveRunState, project_name: &str) -> Result<(), LuxError> {
+    match active.project_name.as_deref() {
+        Some(recorded) if recorded != project_name => Err(LuxError::Config(format!(
+            "the active run {} belongs to compose project '{recorded}', not '{project_name}'; \
+lux keeps one active run per paths.trusted_root, so stop it with \
+`lux --project-name {recorded} down --all` or use a separate trusted_root",
+            active.run_id
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn store_active_run_state(state_root: &Path, state: &ActiveRunState) -> Result<(), LuxError> {
     let path = active_run_state_path(state_root);
     let tmp_path = path.with_extension("json.tmp");
-    let body = serde_json::to_string_pretty(&state)?;
+    let body = serde_json::to_string_pretty(state)?;
     fs::write(&tmp_path, format!("{body}\n"))?;
     fs::rename(&tmp_path, &path)?;
     Ok(())
 }
 
+// Container paths the base compose file or the provider override already own.
+const RUN_MOUNT_RESERVED_CONTAINER_PATHS: &[&str] = &["/work", "/logs", "/config", "/run/lux"];
+
+/// Parses `--mount HOST:CONTAINER[:ro|:rw]` specs. Host paths must exist under `$HOME` or
+/// the trusted root and must not overlap the workspace. The runtime and secrets roots are never
+/// mountable, and anything else under the trusted root (including the log root) may only be
+/// mounted read-only so the agent can never write evidence or lux state.
+fn parse_run_mounts(specs: &[String], policy: &PolicyPaths) -> Result<Vec<RunMount>, LuxError> {
+    // Host paths are canonicalized below, so the roots must be too or a symlinked trusted
+    // root would never match.
+    let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let home = canonical(&policy.home);
+    let trusted_root = canonical(&policy.trusted_root);
+    let workspace_root = canonical(&policy.workspace_root);
+    let runtime_root = canonical(&policy.runtime_root);
+    let secrets_root = canonical(&policy.secrets_root);
+    let log_root = canonical(&policy.log_root);
+    let mut mounts = Vec::new();
+    for spec in specs {
+        let parts: Vec<&str> = spec.split(':').collect();
+        let (host, container, read_only) = match parts.as_slice() {
+            [host, container] => (*host, *container, false),
+            [host, container, "ro"] => (*host, *container, true),
+            [host, container, "rw"] => (*host, *container, false),
+            _ => {
+                return Err(LuxError::Config(format!(
+                    "--mount must be HOST:CONTAINER[:ro|:rw]: {spec}"
+                )))
+            }
+        };
+        let expanded = expand_home_path(host, &policy.home, "--mount")?;
+        let host_path = fs::canonicalize(&expanded).map_err(|_| {
+            LuxError::Config(format!(
+                "--mount host path does not exist: {}",
+                expanded.display()
+            ))
+        })?;
+        if !path_is_within(&host_path, &home) && !path_is_within(&host_path, &trusted_root) {
+            return Err(LuxError::Config(format!(
+                "--mount host path must be under $HOME or paths.trusted_root: {}",
+                host_path.display()
+            )));
+        }
+        let overlaps =
+            |root: &Path| path_is_within(&host_path, root) || path_is_within(root, &host_path);
+        if overlaps(&workspace_root) {
+            return Err(LuxError::Config(format!(
+                "--mount host path overlaps the workspace root (already mounted at /work): {}",
+                host_path.display()
+            )));
+        }
+        // A read-only bind still lets the agent connect to the control socket or read secrets.
+        if overlaps(&runtime_root) || overlaps(&secrets_root) {
+            return Err(LuxError::Config(format!(
+                "--mount host path overlaps the lux runtime or secrets directory: {}",
+                host_path.display()
+            )));
+        }
+        if !read_only && overlaps(&log_root) {
+            return Err(LuxError::Config(format!(
+                "--mount of the log root must be read-only (append :ro): {}",
+                host_path.display()
+            )));
+        }
+        if !read_only && overlaps(&trusted_root) {
+            return Err(LuxError::Config(format!(
+                "--mount of a path under paths.trusted_root must be read-only (append :ro): {}",
+                host_path.display()
+            )));
+        }
+        let container_path = Path::new(container);
+        if !container_path.is_absolute() {
+            return Err(LuxError::Config(format!(
+                "--mount container path must be absolute: {container}"
+            )));
+        }
+        if RUN_MOUNT_RESERVED_CONTAINER_PATHS
+            .iter()
+            .any(|reserved| path_is_within(container_path, Path::new(reserved)))
+        {
+            return Err(LuxError::Config(format!(
+                "--mount container path is reserved by lux: {container}"
+            )));
+        }
+        mounts.push(RunMount {
+            host: host_path.to_string_lossy().to_string(),
+            container: container.to_string(),
+            read_only,
+        });
+    }
+    Ok(mounts)
+}
+
+fn validate_idle_timeout_override(idle_timeout_min: Option<u64>) -> Result<(), LuxError> {
+    if idle_timeout_min == Some(0) {
+        return Err(LuxError::Config(
+            "--idle-timeout-min must be greater than 0".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Idle timeout the runtime scheduler applies to `active`, with where it came from.
+fn effective_idle_timeout_min(cfg: &Config, active: &ActiveRunState) -> (u64, &'static str) {
+    match active.idle_timeout_min {
+        Some(minutes) => (minutes, "run"),
+        None => (cfg.collector.idle_timeout_min, "config"),
+    }
+}
+
 fn clear_active_run_state(state_root: &Path) -> Result<(), LuxError> {
     let path = active_run_state_path(state_root);
     if path.exists() {
@@ -5054,12 +7727,22 @@ fn validate_workspace_policy(
     Ok(())
 }
 
+/// `--workspace` wins over `providers.<name>.workspace_root`, which wins over
+/// `paths.workspace_root`.
 fn resolve_effective_workspace_root(
     cfg: &Config,
     workspace_override: Option<&str>,
+    provider: Option<&str>,
 ) -> Result<PathBuf, LuxError> {
     let policy = resolve_config_policy_paths(cfg)?;
     let mut workspace_root = policy.workspace_root;
+    if let Some(raw) = provider
+        .and_then(|name| cfg.providers.get(name))
+        .and_then(|provider| provider.workspace_root.as_deref())
+    {
+        // Already validated by resolve_config_policy_paths.
+        workspace_root = resolve_policy_path(raw, "provider workspace_root", &policy.home)?;
+    }
     if let Some(raw_override) = workspace_override {
         workspace_root = resolve_policy_path(raw_override, "--workspace", &policy.home)?;
         validate_workspace_policy(
@@ -5148,7 +7831,7 @@ fn running_services<R: DockerRunner>(
     env_overrides: &BTreeMap<String, String>,
     services: &[&str],
 ) -> Result<Vec<String>, LuxError> {
-    let mut args = compose_base_args(ctx, cfg, ui, runtime_overrides)?;
+    let mut args = compose_base_args(ctx, cfg, ui, runtime_overrides, &[])?;
     args.push("ps".to_string());
     args.push("--status".to_string());
     args.push("running".to_string());
@@ -5166,6 +7849,45 @@ fn running_services<R: DockerRunner>(
         .collect())
 }
 
+fn compose_config_services<R: DockerRunner>(
+    ctx: &Context,
+    runner: &R,
+    cfg: &Config,
+    profiles: &[String],
+    env_overrides: &BTreeMap<String, String>,
+) -> Result<Vec<String>, LuxError> {
+    let mut args = compose_base_args(ctx, cfg, false, &[], profiles)?;
+    args.push("config".to_string());
+    args.push("--services".to_string());
+    let output = execute_docker(ctx, runner, &args, env_overrides, true, false)?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(ToString::to_string)
+        .collect())
+}
+
+/// Services only enabled by `profiles` (e.g. a provider's proxy sidecar), resolved by diffing
+/// `compose config --services` with and without the profiles.
+fn compose_profile_services<R: DockerRunner>(
+    ctx: &Context,
+    runner: &R,
+    cfg: &Config,
+    profiles: &[String],
+    env_overrides: &BTreeMap<String, String>,
+) -> Result<Vec<String>, LuxError> {
+    if profiles.is_empty() {
+        return Ok(Vec::new());
+    }
+    let with_profiles = compose_config_services(ctx, runner, cfg, profiles, env_overrides)?;
+    let without = compose_config_services(ctx, runner, cfg, &[], env_overrides)?;
+    Ok(with_profiles
+        .into_iter()
+        .filter(|service| !without.contains(service))
+        .collect())
+}
+
 fn provider_plane_is_running<R: DockerRunner>(
     ctx: &Context,
     runner: &R,
@@ -5223,32 +7945,299 @@ fn parse_compose_ps_output(text: &str) -> serde_json::Value {
     }
 }
 
-fn provider_mismatch_error(active_provider: &str, requested_provider: &str) -> LuxError {
-    LuxError::Process(format!(
-        "provider mismatch: active provider is '{active_provider}', requested '{requested_provider}'. \
-Run `lux down --provider {active_provider}` first."
-    ))
+/// Compose-version-independent view of one `docker compose ps` row, as emitted by
+/// `lux status`/`lux ui status`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+struct ServiceStatus {
+    name: String,
+    state: String,
+    health: Option<String>,
+    image: Option<String>,
+    ports: Vec<String>,
+    /// Set on `lux status` rows for lux-labelled containers outside the current compose
+    /// project; `name` is then the container name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    orphaned_project: Option<String>,
 }
 
-fn render_docker_command(args: &[String]) -> String {
-    fn shell_quote(part: &str) -> String {
-        if part.is_empty() {
-            return "\"\"".to_string();
+fn compose_ps_string<'a>(row: &'a serde_json::Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .filter_map(|key| row.get(*key).and_then(|v| v.as_str()))
+        .map(str::trim)
+        .find(|value| !value.is_empty())
+}
+
+/// Maps a compose row into a `ServiceStatus`. Newer Compose reports `Service`/`State`/
+/// `Health`/`Publishers`; older releases only carry `Name(s)`, a free-form `Status`
+/// ("Up 3 minutes (healthy)", "Exited (0) ...") and a `Ports` string.
+fn compose_service_status(row: &serde_json::Value) -> Option<ServiceStatus> {
+    let name = compose_ps_string(row, &["Service", "Name", "Names"])?.to_string();
+    let status = compose_ps_string(row, &["Status"]).unwrap_or_default();
+    let state = match compose_ps_string(row, &["State"]) {
+        Some(state) => state.to_lowercase(),
+        None => {
+            let lowered = status.to_lowercase();
+            if lowered.starts_with("up") {
+                "running".to_string()
+            } else if lowered.starts_with("exited") {
+                "exited".to_string()
+            } else if lowered.starts_with("created") {
+                "created".to_string()
+            } else if lowered.starts_with("restarting") {
+                "restarting".to_string()
+            } else {
+                "unknown".to_string()
+            }
         }
-        if part.chars().any(|c| c.is_whitespace()) {
-            return format!("\"{}\"", part.replace('"', "\\\""));
+    };
+    let health = compose_ps_string(row, &["Health"])
+        .map(str::to_lowercase)
+        .or_else(|| {
+            ["unhealthy", "healthy", "health: starting"]
+                .into_iter()
+                .find(|marker| status.contains(&format!("({marker})")))
+                .map(|marker| marker.trim_start_matches("health: ").to_string())
+        });
+    let image = compose_ps_string(row, &["Image"]).map(str::to_string);
+    let ports = match row.get("Publishers").and_then(|v| v.as_array()) {
+        Some(publishers) => {
+            let mut ports = Vec::new();
+            for publisher in publishers {
+                let Some(target) = publisher.get("TargetPort").and_then(|v| v.as_u64()) else {
+                    continue;
+                };
+                let protocol = compose_ps_string(publisher, &["Protocol"]).unwrap_or("tcp");
+                let published = publisher
+                    .get("PublishedPort")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0);
+                let port = if published > 0 {
+                    let url = compose_ps_string(publisher, &["URL"]).unwrap_or("0.0.0.0");
+                    format!("{url}:{published}->{target}/{protocol}")
+                } else {
+                    format!("{target}/{protocol}")
+                };
+                if !ports.contains(&port) {
+                    ports.push(port);
+                }
+            }
+            ports
         }
-        part.to_string()
-    }
-    let mut parts = Vec::with_capacity(args.len() + 1);
-    parts.push("docker".to_string());
-    parts.extend(args.iter().map(|arg| shell_quote(arg)));
-    parts.join(" ")
+        None => compose_ps_string(row, &["Ports"])
+            .map(|raw| {
+                raw.split(',')
+                    .map(str::trim)
+                    .filter(|port| !port.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+    Some(ServiceStatus {
+        name,
+        state,
+        health,
+        image,
+        ports,
+        orphaned_project: None,
+    })
 }
 
-fn docker_spawn_error_details(err: &io::Error, command: &str) -> ProcessErrorDetails {
-    if err.kind() == io::ErrorKind::NotFound {
-        return ProcessErrorDetails {
+const LUX_MANAGED_LABEL: &str = "com.lux.managed";
+const LUX_RUN_ID_LABEL: &str = "com.lux.run_id";
+const COMPOSE_PROJECT_LABEL: &str = "com.docker.compose.project";
+
+/// A container labelled `com.lux.managed=true` by the bundled compose files, found by label
+/// rather than service name so renamed or leftover services are still tracked.
+#[derive(Debug, Clone, PartialEq)]
+struct ManagedContainer {
+    id: String,
+    project: Option<String>,
+    run_id: Option<String>,
+    status: ServiceStatus,
+}
+
+/// `docker ps --format` for `parse_managed_containers`: the row, then the project and run id
+/// labels, each JSON-encoded and tab-separated. Docker's own `Labels` field is a `k=v,k=v`
+/// string that a comma in any label value would break, so the two labels are read one by one.
+fn managed_container_format() -> String {
+    format!(
+        "{{{{json .}}}}\t{{{{json (.Label \"{COMPOSE_PROJECT_LABEL}\")}}}}\t{{{{json (.Label \"{LUX_RUN_ID_LABEL}\")}}}}"
+    )
+}
+
+fn parse_managed_containers(text: &str) -> Vec<ManagedContainer> {
+    let label = |raw: &str| {
+        serde_json::from_str::<String>(raw)
+            .ok()
+            .filter(|value| !value.is_empty())
+    };
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().split('\t');
+            let row = serde_json::from_str::<serde_json::Value>(fields.next()?).ok()?;
+            let project = label(fields.next()?);
+            let run_id = label(fields.next()?);
+            Some(ManagedContainer {
+                id: compose_ps_string(&row, &["ID"])?.to_string(),
+                project,
+                run_id,
+                status: compose_service_status(&row)?,
+            })
+        })
+        .collect()
+}
+
+/// Lists `com.lux.managed=true` containers; `filters` are extra `docker ps --filter` values.
+fn list_managed_containers<R: DockerRunner>(
+    ctx: &Context,
+    runner: &R,
+    filters: &[String],
+) -> Result<Vec<ManagedContainer>, LuxError> {
+    let mut args = vec![
+        "ps".to_string(),
+        "--all".to_string(),
+        "--filter".to_string(),
+        format!("label={LUX_MANAGED_LABEL}=true"),
+    ];
+    for filter in filters {
+        args.push("--filter".to_string());
+        args.push(filter.clone());
+    }
+    args.push("--format".to_string());
+    args.push(managed_container_format());
+    let output = execute_docker(ctx, runner, &args, &BTreeMap::new(), true, false)?;
+    Ok(parse_managed_containers(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+fn parse_compose_service_statuses(text: &str) -> Vec<ServiceStatus> {
+    parse_compose_ps_output(text)
+        .as_array()
+        .map(|rows| rows.iter().filter_map(compose_service_status).collect())
+        .unwrap_or_default()
+}
+
+fn render_service_status_table(services: &[ServiceStatus]) -> String {
+    let headers = ["NAME", "STATE", "HEALTH", "IMAGE", "PORTS"];
+    let rows: Vec<[String; 5]> = services
+        .iter()
+        .map(|service| {
+            [
+                match &service.orphaned_project {
+                    Some(project) => format!("{} (orphaned, project {project})", service.name),
+                    None => service.name.clone(),
+                },
+                service.state.clone(),
+                service.health.clone().unwrap_or_else(|| "-".to_string()),
+                service.image.clone().unwrap_or_else(|| "-".to_string()),
+                if service.ports.is_empty() {
+                    "-".to_string()
+                } else {
+                    service.ports.join(", ")
+                },
+            ]
+        })
+        .collect();
+    let mut widths = headers.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let render_line = |cells: [&str; 5]| {
+        let mut line = String::new();
+        for (idx, cell) in cells.iter().enumerate() {
+            if idx + 1 == cells.len() {
+                line.push_str(cell);
+            } else {
+                line.push_str(&format!("{cell:<width$}  ", width = widths[idx]));
+            }
+        }
+        line.trim_end().to_string()
+    };
+    let mut lines = vec![render_line(headers)];
+    for row in &rows {
+        lines.push(render_line([
+            row[0].as_str(),
+            row[1].as_str(),
+            row[2].as_str(),
+            row[3].as_str(),
+            row[4].as_str(),
+        ]));
+    }
+    lines.join("\n")
+}
+
+fn service_statuses_json(
+    services: &[ServiceStatus],
+    raw: Option<&str>,
+) -> Result<serde_json::Value, LuxError> {
+    Ok(match raw {
+        Some(text) => json!({
+            "normalized": services,
+            "raw": parse_compose_ps_output(text),
+        }),
+        None => serde_json::to_value(services)?,
+    })
+}
+
+/// `raw` carries the compose `ps` output for `--raw`: it is kept next to the normalized rows
+/// in JSON and printed verbatim in text mode.
+fn print_service_statuses(
+    ctx: &Context,
+    services: Vec<ServiceStatus>,
+    raw: Option<&str>,
+) -> Result<(), LuxError> {
+    if ctx.json {
+        let payload = JsonResult {
+            ok: true,
+            result: Some(service_statuses_json(&services, raw)?),
+            error: None,
+            error_details: None,
+        };
+        print_json(ctx, &payload)?;
+        return Ok(());
+    }
+    if let Some(text) = raw {
+        print!("{text}");
+        return Ok(());
+    }
+    if services.is_empty() {
+        println!("No containers running.");
+    } else {
+        println!("{}", render_service_status_table(&services));
+    }
+    Ok(())
+}
+
+fn provider_mismatch_error(active_provider: &str, requested_provider: &str) -> LuxError {
+    LuxError::Process(format!(
+        "provider mismatch: active provider is '{active_provider}', requested '{requested_provider}'. \
+Run `lux down --provider {active_provider}` first."
+    ))
+}
+
+fn render_docker_command(args: &[String]) -> String {
+    fn shell_quote(part: &str) -> String {
+        if part.is_empty() {
+            return "\"\"".to_string();
+        }
+        if part.chars().any(|c| c.is_whitespace()) {
+            return format!("\"{}\"", part.replace('"', "\\\""));
+        }
+        part.to_string()
+    }
+    let mut parts = Vec::with_capacity(args.len() + 1);
+    parts.push("docker".to_string());
+    parts.extend(args.iter().map(|arg| shell_quote(arg)));
+    parts.join(" ")
+}
+
+fn docker_spawn_error_details(err: &io::Error, command: &str) -> ProcessErrorDetails {
+    if err.kind() == io::ErrorKind::NotFound {
+        return ProcessErrorDetails {
             error_code: "docker_not_found".to_string(),
             hint: Some("Install Docker and ensure `docker` is on your PATH.".to_string()),
             command: Some(command.to_string()),
@@ -5348,6 +8337,85 @@ fn classify_docker_command_failure(stderr: &str) -> (String, Option<String>) {
     ("process_command_failed".to_string(), None)
 }
 
+fn docker_command_failure(command: String, cmd_output: &CommandOutput) -> LuxError {
+    let stderr = String::from_utf8_lossy(&cmd_output.stderr)
+        .trim()
+        .to_string();
+    let (error_code, hint) = classify_docker_command_failure(&stderr);
+    let mut message = format!(
+        "command failed with status {} while running `{}`",
+        cmd_output.status_code, command
+    );
+    if !stderr.is_empty() {
+        message = format!("{message}: {stderr}");
+    }
+    if let Some(ref hint_message) = hint {
+        message = format!("{message}\nHint: {hint_message}");
+    }
+    LuxError::ProcessDetailed {
+        message,
+        details: Box::new(ProcessErrorDetails {
+            error_code,
+            hint,
+            command: Some(command),
+            raw_stderr: if stderr.is_empty() {
+                None
+            } else {
+                Some(stderr)
+            },
+            partial_outcome: None,
+        }),
+    }
+}
+
+/// Runs `compose pull` under `pull_timeout_sec`, mapping a timeout to `docker_pull_timeout`.
+fn execute_docker_pull<R: DockerRunner>(
+    ctx: &Context,
+    runner: &R,
+    args: &[String],
+    env_overrides: &BTreeMap<String, String>,
+    pull_timeout_sec: u64,
+) -> Result<(), LuxError> {
+    let command = render_docker_command(args);
+    let envs = docker_env(ctx, env_overrides)?;
+    let cmd_output = runner
+        .run_with_timeout(
+            args,
+            &ctx.bundle_dir,
+            &envs,
+            true,
+            Duration::from_secs(pull_timeout_sec),
+        )
+        .map_err(|err| {
+            let details = docker_spawn_error_details(&err, &command);
+            LuxError::ProcessDetailed {
+                message: format!("failed to run command `{command}`: {err}"),
+                details: Box::new(details),
+            }
+        })?;
+    let Some(cmd_output) = cmd_output else {
+        return Err(LuxError::ProcessDetailed {
+            message: format!(
+                "image pull did not finish within {pull_timeout_sec}s while running `{command}`"
+            ),
+            details: Box::new(ProcessErrorDetails {
+                error_code: "docker_pull_timeout".to_string(),
+                hint: Some(
+                    "Check registry connectivity, or retry with a larger --pull-timeout-sec."
+                        .to_string(),
+                ),
+                command: Some(command),
+                raw_stderr: None,
+                partial_outcome: None,
+            }),
+        });
+    };
+    if !cmd_output.success() {
+        return Err(docker_command_failure(command, &cmd_output));
+    }
+    Ok(())
+}
+
 fn execute_docker<R: DockerRunner>(
     ctx: &Context,
     runner: &R,
@@ -5357,44 +8425,18 @@ fn execute_docker<R: DockerRunner>(
     passthrough_stdout: bool,
 ) -> Result<CommandOutput, LuxError> {
     let command = render_docker_command(args);
+    let envs = docker_env(ctx, env_overrides)?;
     let cmd_output = runner
-        .run(args, &ctx.bundle_dir, env_overrides, capture_output)
+        .run(args, &ctx.bundle_dir, &envs, capture_output)
         .map_err(|err| {
             let details = docker_spawn_error_details(&err, &command);
             LuxError::ProcessDetailed {
                 message: format!("failed to run command `{command}`: {err}"),
-                details,
+                details: Box::new(details),
             }
         })?;
     if !cmd_output.success() {
-        let stderr = String::from_utf8_lossy(&cmd_output.stderr)
-            .trim()
-            .to_string();
-        let (error_code, hint) = classify_docker_command_failure(&stderr);
-        let mut message = format!(
-            "command failed with status {} while running `{}`",
-            cmd_output.status_code, command
-        );
-        if !stderr.is_empty() {
-            message = format!("{message}: {stderr}");
-        }
-        if let Some(ref hint_message) = hint {
-            message = format!("{message}\nHint: {hint_message}");
-        }
-        return Err(LuxError::ProcessDetailed {
-            message,
-            details: ProcessErrorDetails {
-                error_code,
-                hint,
-                command: Some(command),
-                raw_stderr: if stderr.is_empty() {
-                    None
-                } else {
-                    Some(stderr)
-                },
-                partial_outcome: None,
-            },
-        });
+        return Err(docker_command_failure(command, &cmd_output));
     }
     if capture_output && passthrough_stdout && !cmd_output.stdout.is_empty() && !ctx.json {
         let stdout = String::from_utf8_lossy(&cmd_output.stdout);
@@ -5415,16 +8457,197 @@ fn run_docker_command<R: DockerRunner>(
     output(ctx, json_payload)
 }
 
-fn append_harness_tui_run_args(args: &mut Vec<String>, container_workdir: &str) {
+/// `session_env` is set on the harness container and named in `HARNESS_TUI_ENV_KEYS` so the
+/// harness re-exports exactly those variables into the agent SSH session.
+fn append_harness_tui_run_args(
+    args: &mut Vec<String>,
+    container_workdir: &str,
+    session_env: &[(String, String)],
+) {
     args.push("run".to_string());
     args.push("--rm".to_string());
     args.push("-e".to_string());
     args.push("HARNESS_MODE=tui".to_string());
     args.push("-e".to_string());
     args.push(format!("HARNESS_AGENT_WORKDIR={container_workdir}"));
+    if !session_env.is_empty() {
+        for (key, value) in session_env {
+            args.push("-e".to_string());
+            args.push(format!("{key}={value}"));
+        }
+        let keys: Vec<&str> = session_env.iter().map(|(key, _)| key.as_str()).collect();
+        args.push("-e".to_string());
+        args.push(format!("HARNESS_TUI_ENV_KEYS={}", keys.join(",")));
+    }
     args.push("harness".to_string());
 }
 
+/// Prefers bash but falls back to sh, since not every agent image ships bash.
+const AGENT_ATTACH_SHELL: &str =
+    "if command -v bash >/dev/null 2>&1; then exec bash; else exec sh; fi";
+
+fn append_agent_attach_args(args: &mut Vec<String>, container_workdir: &str) {
+    args.push("exec".to_string());
+    args.push("-w".to_string());
+    args.push(container_workdir.to_string());
+    args.push("agent".to_string());
+    args.push("sh".to_string());
+    args.push("-c".to_string());
+    args.push(AGENT_ATTACH_SHELL.to_string());
+}
+
+/// Parses `--env KEY=VALUE` entries for `lux tui` and `lux run`. Keys must be shell
+/// identifiers and may not use the `LUX_`/`HARNESS_` prefixes that carry runtime wiring.
+/// `--env KEY` (no `=`) takes KEY's value from the caller's environment.
+fn env_passthrough_value(key: &str) -> Result<String, LuxError> {
+    env::var(key).map_err(|_| {
+        LuxError::Config(format!(
+            "--env {key} passes {key} through from the current environment, but it is not set; use --env {key}=VALUE to set it explicitly"
+        ))
+    })
+}
+
+/// Rewrites a bare `--env KEY` entry to `KEY=VALUE`; entries with a value are unchanged.
+fn resolve_env_passthrough(entry: &str) -> Result<String, LuxError> {
+    if entry.contains('=') {
+        return Ok(entry.to_string());
+    }
+    let key = entry.trim();
+    Ok(format!("{key}={}", env_passthrough_value(key)?))
+}
+
+/// Accepts `KEY=VALUE` and bare `KEY` entries; a bare key is resolved from the caller's
+/// environment once it passes the key checks.
+fn parse_env_entries(entries: &[String]) -> Result<Vec<(String, String)>, LuxError> {
+    let mut parsed: Vec<(String, String)> = Vec::new();
+    for entry in entries {
+        let (key, value) = match entry.split_once('=') {
+            Some((key, value)) => (key, Some(value)),
+            None => (entry.as_str(), None),
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(LuxError::Config(format!(
+                "--env key must not be empty (got '{entry}')"
+            )));
+        }
+        let valid_identifier = key
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_identifier {
+            return Err(LuxError::Config(format!(
+                "--env key '{key}' must contain only letters, digits, and '_' and not start with a digit"
+            )));
+        }
+        let upper = key.to_ascii_uppercase();
+        if upper.starts_with("LUX_") || upper.starts_with("HARNESS_") {
+            return Err(LuxError::Config(format!(
+                "--env key '{key}' uses a reserved prefix (LUX_*, HARNESS_*)"
+            )));
+        }
+        let value = match value {
+            Some(value) => value.to_string(),
+            None => env_passthrough_value(key)?,
+        };
+        parsed.retain(|(existing, _)| existing != key);
+        parsed.push((key.to_string(), value));
+    }
+    Ok(parsed)
+}
+
+/// Reads a dotenv-style `--run-env-file`: one `KEY=VALUE` per line, blank lines and `#`
+/// comments skipped, an optional `export ` prefix, and single- or double-quoted values.
+/// Returns `KEY=VALUE` entries in file order for `parse_env_entries`.
+fn parse_dotenv_file(path: &Path, flag: &str) -> Result<Vec<String>, LuxError> {
+    let content = fs::read_to_string(path).map_err(|err| {
+        LuxError::Config(format!("failed to read {flag} {}: {err}", path.display()))
+    })?;
+    let mut entries = Vec::new();
+    for (index, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(LuxError::Config(format!(
+                "{flag} {}:{}: expected KEY=VALUE",
+                path.display(),
+                index + 1
+            )));
+        };
+        let value = value.trim();
+        let value = if let Some(inner) = value.strip_prefix('"') {
+            let Some(inner) = inner.strip_suffix('"') else {
+                return Err(LuxError::Config(format!(
+                    "{flag} {}:{}: unterminated double quote",
+                    path.display(),
+                    index + 1
+                )));
+            };
+            unescape_double_quoted(inner)
+        } else if let Some(inner) = value.strip_prefix('\'') {
+            let Some(inner) = inner.strip_suffix('\'') else {
+                return Err(LuxError::Config(format!(
+                    "{flag} {}:{}: unterminated single quote",
+                    path.display(),
+                    index + 1
+                )));
+            };
+            inner.to_string()
+        } else {
+            // Unquoted values may carry a trailing ` # comment`.
+            value
+                .split_once(" #")
+                .map(|(head, _)| head.trim_end())
+                .unwrap_or(value)
+                .to_string()
+        };
+        entries.push(format!("{}={}", key.trim(), value));
+    }
+    Ok(entries)
+}
+
+fn unescape_double_quoted(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Merges `--run-env-file` entries with `--env` entries; later (CLI) entries win.
+fn resolve_run_env(
+    env_list: &[String],
+    run_env_file: Option<&str>,
+) -> Result<BTreeMap<String, String>, LuxError> {
+    let mut entries = Vec::new();
+    if let Some(raw) = run_env_file {
+        let path = PathBuf::from(expand_path(raw));
+        let file_entries = parse_dotenv_file(&path, "--run-env-file")?;
+        parse_env_entries(&file_entries).map_err(|err| match err {
+            LuxError::Config(message) => {
+                LuxError::Config(format!("--run-env-file {}: {message}", path.display()))
+            }
+            other => other,
+        })?;
+        entries.extend(file_entries);
+    }
+    entries.extend(env_list.iter().cloned());
+    Ok(parse_env_entries(&entries)?.into_iter().collect())
+}
+
 fn handle_ui<R: DockerRunner>(
     ctx: &Context,
     command: UiCommand,
@@ -5436,26 +8659,40 @@ fn handle_ui<R: DockerRunner>(
             wait,
             timeout_sec,
             pull,
+            pull_timeout_sec,
+            build,
+            no_cache,
         } => {
             if timeout_sec.is_some() && !wait {
                 return Err(LuxError::Config(
                     "--timeout-sec requires --wait".to_string(),
                 ));
             }
-            let mut args = compose_base_args(ctx, &cfg, true, &[])?;
+            warn_if_no_cache_without_build(build, no_cache);
+            let base_args = compose_base_args(ctx, &cfg, true, &[], &[])?;
+            if build && no_cache {
+                execute_docker(
+                    ctx,
+                    runner,
+                    &compose_no_cache_build_args(&base_args, &["ui"]),
+                    &BTreeMap::new(),
+                    true,
+                    true,
+                )?;
+            }
+            let pull = pull_images_with_timeout(
+                ctx,
+                runner,
+                &base_args,
+                pull,
+                pull_timeout_sec,
+                &["ui"],
+                &BTreeMap::new(),
+            )?;
+            let mut args = base_args;
             args.push("up".to_string());
             args.push("-d".to_string());
-            if let Some(pull) = pull {
-                args.push("--pull".to_string());
-                args.push(pull);
-            }
-            if wait {
-                args.push("--wait".to_string());
-                if let Some(timeout_sec) = timeout_sec {
-                    args.push("--wait-timeout".to_string());
-                    args.push(timeout_sec.to_string());
-                }
-            }
+            push_compose_up_flags(&mut args, pull, build, wait, timeout_sec);
             args.push("ui".to_string());
             run_docker_command(
                 ctx,
@@ -5467,7 +8704,7 @@ fn handle_ui<R: DockerRunner>(
             )
         }
         UiCommand::Down => {
-            let mut args = compose_base_args(ctx, &cfg, true, &[])?;
+            let mut args = compose_base_args(ctx, &cfg, true, &[], &[])?;
             args.push("stop".to_string());
             args.push("ui".to_string());
             run_docker_command(
@@ -5479,31 +8716,20 @@ fn handle_ui<R: DockerRunner>(
                 true,
             )
         }
-        UiCommand::Status => {
-            let mut args = compose_base_args(ctx, &cfg, true, &[])?;
+        UiCommand::Status { raw } => {
+            let mut args = compose_base_args(ctx, &cfg, true, &[], &[])?;
             args.push("ps".to_string());
+            args.push("--all".to_string());
             args.push("--format".to_string());
             args.push("json".to_string());
             args.push("ui".to_string());
             let cmd_output = execute_docker(ctx, runner, &args, &BTreeMap::new(), true, false)?;
             let text = String::from_utf8_lossy(&cmd_output.stdout);
-            let rows = parse_compose_ps_output(&text);
-            if ctx.json {
-                let payload = JsonResult {
-                    ok: true,
-                    result: Some(rows),
-                    error: None,
-                    error_details: None,
-                };
-                print_json(&payload)?;
-                return Ok(());
-            }
-            if rows.as_array().map(|a| a.is_empty()).unwrap_or(true) {
-                println!("No containers running.");
-            } else {
-                println!("{}", text.trim());
-            }
-            Ok(())
+            print_service_statuses(
+                ctx,
+                parse_compose_service_statuses(&text),
+                raw.then_some(text.as_ref()),
+            )
         }
         UiCommand::Url => {
             let payload = json!({"url": ui_local_url()});
@@ -5574,6 +8800,69 @@ fn process_is_alive(pid: u32) -> bool {
         .unwrap_or(false)
 }
 
+fn send_sigterm(pid: u32) -> bool {
+    if pid == 0 {
+        return false;
+    }
+    Command::new("kill")
+        .arg("-TERM")
+        .arg(pid.to_string())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// Whether `argv` is a `lux ... runtime serve` daemon (global flags may sit in between).
+fn cmdline_is_lux_runtime_serve(argv: &[String]) -> bool {
+    let Some(program) = argv.first() else {
+        return false;
+    };
+    Path::new(program)
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with("lux"))
+        && argv
+            .windows(2)
+            .any(|pair| pair[0] == "runtime" && pair[1] == "serve")
+}
+
+/// Guards the runtime pid file against pid reuse: only a live `lux runtime serve` counts.
+fn pid_is_lux_runtime(pid: u32) -> bool {
+    if pid == 0 {
+        return false;
+    }
+    let argv: Vec<String> = match fs::read(format!("/proc/{pid}/cmdline")) {
+        Ok(raw) => raw
+            .split(|byte| *byte == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).to_string())
+            .collect(),
+        // No procfs (macOS): fall back to ps, which splits on whitespace.
+        Err(_) => match Command::new("ps")
+            .args(["-o", "args=", "-p", &pid.to_string()])
+            .output()
+        {
+            Ok(out) if out.status.success() => String::from_utf8_lossy(&out.stdout)
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            _ => return false,
+        },
+    };
+    cmdline_is_lux_runtime_serve(&argv)
+}
+
+/// Polls until `pid` exits or `timeout` passes; returns whether it exited.
+fn wait_for_process_exit(pid: u32, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while process_is_alive(pid) {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(RUNTIME_DOWN_POLL_INTERVAL_MS));
+    }
+    true
+}
+
 fn read_pid_file(path: &Path) -> Option<u32> {
     let text = fs::read_to_string(path).ok()?;
     text.trim().parse::<u32>().ok()
@@ -5607,21 +8896,127 @@ fn runtime_emit_event(
     while state.events.len() > 512 {
         let _ = state.events.pop_front();
     }
+    let mut line = serde_json::to_string(&event)?;
+    line.push('\n');
+    state.pending_event_lines.push_back(line);
+    let file_lock = Arc::clone(&state.events_file_lock);
     condvar.notify_all();
     drop(state);
 
-    ensure_parent(events_path)?;
-    let line = serde_json::to_string(&event)?;
-    let mut content = line;
-    content.push('\n');
-    let mut file = fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(events_path)?;
-    file.write_all(content.as_bytes())?;
+    // Disk I/O happens outside the state lock. Whoever holds the file lock drains every queued
+    // line, so lines land in id order and never interleave with a rotation.
+    let _file_guard = file_lock
+        .lock()
+        .map_err(|_| LuxError::Process("runtime events file lock poisoned".to_string()))?;
+    let (lines, max_bytes) = {
+        let mut state = lock
+            .lock()
+            .map_err(|_| LuxError::Process("runtime state lock poisoned".to_string()))?;
+        (
+            std::mem::take(&mut state.pending_event_lines),
+            state.events_max_bytes,
+        )
+    };
+    if lines.is_empty() {
+        return Ok(event);
+    }
+    ensure_parent(events_path)?;
+    for content in lines {
+        if max_bytes > 0 {
+            rotate_runtime_events_file(
+                events_path,
+                max_bytes,
+                content.len() as u64,
+                RUNTIME_EVENTS_KEEP_GENERATIONS,
+            )?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(events_path)?;
+        file.write_all(content.as_bytes())?;
+    }
     Ok(event)
 }
 
+fn runtime_events_generation_path(events_path: &Path, generation: u32) -> PathBuf {
+    let mut name = events_path.as_os_str().to_os_string();
+    name.push(format!(".{generation}"));
+    PathBuf::from(name)
+}
+
+/// Shifts `events.jsonl` to `events.jsonl.1` (and older generations up by one) when appending
+/// `incoming` bytes would push it past `max_bytes`. Generations beyond `keep` are deleted.
+fn rotate_runtime_events_file(
+    events_path: &Path,
+    max_bytes: u64,
+    incoming: u64,
+    keep: u32,
+) -> Result<bool, LuxError> {
+    let current = match fs::metadata(events_path) {
+        Ok(meta) => meta.len(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err.into()),
+    };
+    if current == 0 || current.saturating_add(incoming) <= max_bytes {
+        return Ok(false);
+    }
+    if keep == 0 {
+        fs::remove_file(events_path)?;
+        return Ok(true);
+    }
+    let _ = fs::remove_file(runtime_events_generation_path(events_path, keep));
+    for generation in (1..keep).rev() {
+        let from = runtime_events_generation_path(events_path, generation);
+        if from.exists() {
+            fs::rename(
+                &from,
+                runtime_events_generation_path(events_path, generation + 1),
+            )?;
+        }
+    }
+    fs::rename(events_path, runtime_events_generation_path(events_path, 1))?;
+    Ok(true)
+}
+
+/// Appends one `{ts, level, message, ...fields}` line to the daemon's diagnostic log. This is
+/// best-effort: a missing or unwritable log never affects the control plane itself.
+fn runtime_log(
+    shared: &Arc<(Mutex<RuntimeSharedState>, Condvar)>,
+    level: &str,
+    message: &str,
+    fields: serde_json::Value,
+) {
+    let log_file = match shared.0.lock() {
+        Ok(state) => state.log_file.clone(),
+        Err(_) => return,
+    };
+    let Some(log_file) = log_file else {
+        return;
+    };
+    let mut line = serde_json::Map::new();
+    line.insert("ts".to_string(), json!(Utc::now().to_rfc3339()));
+    line.insert("level".to_string(), json!(level));
+    line.insert("message".to_string(), json!(message));
+    if let serde_json::Value::Object(fields) = fields {
+        line.extend(fields);
+    }
+    let Ok(mut content) = serde_json::to_string(&line) else {
+        return;
+    };
+    content.push('\n');
+    if ensure_parent(&log_file).is_err() {
+        return;
+    }
+    if let Ok(mut file) = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_file)
+    {
+        let _ = file.write_all(content.as_bytes());
+    }
+}
+
 fn runtime_emit_warning(
     shared: &Arc<(Mutex<RuntimeSharedState>, Condvar)>,
     events_path: &Path,
@@ -5701,9 +9096,25 @@ fn runtime_record_command_events(
     Ok(())
 }
 
-fn runtime_run_cli_subprocess(ctx: &Context, argv: &[String]) -> Result<CommandOutput, LuxError> {
+fn runtime_run_cli_subprocess(
+    ctx: &Context,
+    argv: &[String],
+    cwd: Option<&Path>,
+) -> Result<CommandOutput, LuxError> {
     let exe = env::current_exe()?;
     let mut cmd = Command::new(exe);
+    if let Some(cwd) = cwd {
+        cmd.current_dir(cwd);
+    }
+    // Scheduler cutovers build their own argv; keep them on the daemon's project.
+    if let Some(project_name) = &ctx.project_name {
+        let explicit = argv
+            .iter()
+            .any(|arg| arg == "--project-name" || arg.starts_with("--project-name="));
+        if !explicit {
+            cmd.arg("--project-name").arg(project_name);
+        }
+    }
     cmd.args(argv);
     cmd.env(RUNTIME_BYPASS_ENV, "1");
     cmd.env("LUX_CONFIG", ctx.config_path.to_string_lossy().to_string());
@@ -5726,36 +9137,122 @@ fn runtime_run_cli_subprocess(ctx: &Context, argv: &[String]) -> Result<CommandO
     })
 }
 
+/// Runs a delegated lux subcommand for the runtime daemon (scheduler cutovers and
+/// `/v1/execute`), in `cwd` when given and the daemon's working directory otherwise.
+trait RuntimeCliRunner {
+    fn run(
+        &self,
+        ctx: &Context,
+        argv: &[String],
+        cwd: Option<&Path>,
+    ) -> Result<CommandOutput, LuxError>;
+}
+
+struct RealRuntimeCliRunner;
+
+impl RuntimeCliRunner for RealRuntimeCliRunner {
+    fn run(
+        &self,
+        ctx: &Context,
+        argv: &[String],
+        cwd: Option<&Path>,
+    ) -> Result<CommandOutput, LuxError> {
+        runtime_run_cli_subprocess(ctx, argv, cwd)
+    }
+}
+
 #[derive(Debug)]
 struct RuntimeIncomingRequest {
     method: String,
     path: String,
-    query: BTreeMap<String, String>,
+    query: BTreeMap<String, Vec<String>>,
     headers: BTreeMap<String, String>,
     body: Vec<u8>,
 }
 
-fn parse_query_map(query: &str) -> BTreeMap<String, String> {
-    let mut result = BTreeMap::new();
+fn parse_query_map(query: &str) -> BTreeMap<String, Vec<String>> {
+    let mut result: BTreeMap<String, Vec<String>> = BTreeMap::new();
     for pair in query.split('&') {
         if pair.trim().is_empty() {
             continue;
         }
-        if let Some((key, value)) = pair.split_once('=') {
-            result.insert(key.to_string(), value.to_string());
-        } else {
-            result.insert(pair.to_string(), String::new());
-        }
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        result
+            .entry(key.to_string())
+            .or_default()
+            .push(value.to_string());
     }
     result
 }
 
+/// Server-side filter for `GET /v1/events`. Each param is repeatable and also accepts
+/// comma-separated values; an empty set matches everything.
+#[derive(Debug, Default)]
+struct RuntimeEventFilter {
+    event_types: BTreeSet<String>,
+    severities: BTreeSet<String>,
+}
+
+impl RuntimeEventFilter {
+    fn from_query(query: &BTreeMap<String, Vec<String>>) -> Self {
+        let collect = |key: &str| -> BTreeSet<String> {
+            query
+                .get(key)
+                .into_iter()
+                .flatten()
+                .flat_map(|value| value.split(','))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        Self {
+            event_types: collect("event_type"),
+            severities: collect("severity"),
+        }
+    }
+
+    fn matches(&self, event: &RuntimeEvent) -> bool {
+        (self.event_types.is_empty() || self.event_types.contains(&event.event_type))
+            && (self.severities.is_empty() || self.severities.contains(&event.severity))
+    }
+}
+
+/// Transport-agnostic connection used by the runtime server (unix socket or opt-in TCP).
+#[cfg(unix)]
+trait RuntimeStream: Read + Write {
+    fn set_io_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+    fn set_io_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+}
+
+#[cfg(unix)]
+impl RuntimeStream for UnixStream {
+    fn set_io_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_read_timeout(timeout)
+    }
+
+    fn set_io_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_write_timeout(timeout)
+    }
+}
+
+#[cfg(unix)]
+impl RuntimeStream for TcpStream {
+    fn set_io_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_read_timeout(timeout)
+    }
+
+    fn set_io_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_write_timeout(timeout)
+    }
+}
+
 #[cfg(unix)]
-fn runtime_read_http_request(
-    stream: &mut UnixStream,
+fn runtime_read_http_request<S: RuntimeStream>(
+    stream: &mut S,
 ) -> Result<Option<RuntimeIncomingRequest>, LuxError> {
     stream
-        .set_read_timeout(Some(Duration::from_secs(10)))
+        .set_io_read_timeout(Some(Duration::from_secs(10)))
         .map_err(LuxError::Io)?;
     let mut buf = Vec::new();
     let mut header_end: Option<usize> = None;
@@ -5832,14 +9329,15 @@ fn runtime_read_http_request(
 }
 
 #[cfg(unix)]
-fn runtime_write_json_response(
-    stream: &mut UnixStream,
+fn runtime_write_json_response<S: Write>(
+    stream: &mut S,
     status: u16,
     payload: &serde_json::Value,
 ) -> Result<(), LuxError> {
     let status_text = match status {
         200 => "OK",
         400 => "Bad Request",
+        401 => "Unauthorized",
         404 => "Not Found",
         405 => "Method Not Allowed",
         500 => "Internal Server Error",
@@ -5859,8 +9357,8 @@ fn runtime_write_json_response(
 }
 
 #[cfg(unix)]
-fn runtime_write_text_response(
-    stream: &mut UnixStream,
+fn runtime_write_text_response<S: Write>(
+    stream: &mut S,
     status: u16,
     content_type: &str,
     body: &str,
@@ -5868,6 +9366,7 @@ fn runtime_write_text_response(
     let status_text = match status {
         200 => "OK",
         400 => "Bad Request",
+        401 => "Unauthorized",
         404 => "Not Found",
         405 => "Method Not Allowed",
         500 => "Internal Server Error",
@@ -5888,7 +9387,7 @@ fn runtime_write_text_response(
 }
 
 #[cfg(unix)]
-fn runtime_send_sse_event(stream: &mut UnixStream, event: &RuntimeEvent) -> Result<(), LuxError> {
+fn runtime_send_sse_event<S: Write>(stream: &mut S, event: &RuntimeEvent) -> Result<(), LuxError> {
     let data = serde_json::to_string(event)?;
     let frame = format!(
         "id: {}\nevent: {}\ndata: {}\n\n",
@@ -5957,23 +9456,27 @@ fn runtime_collect_run_status(
         state.rotation_pending
     };
     if let Some(active) = active {
+        let (idle_timeout_min, idle_timeout_source) = effective_idle_timeout_min(&cfg, &active);
         return Ok(json!({
             "active_run": active,
             "pending_rotation": pending_rotation,
-            "rotate_every_min": cfg.collector.rotate_every_min
+            "rotate_every_min": cfg.collector.rotate_every_min,
+            "idle_timeout_min": idle_timeout_min,
+            "idle_timeout_source": idle_timeout_source
         }));
     }
     Ok(json!({
         "active_run": null,
         "pending_rotation": pending_rotation,
-        "rotate_every_min": cfg.collector.rotate_every_min
+        "rotate_every_min": cfg.collector.rotate_every_min,
+        "idle_timeout_min": cfg.collector.idle_timeout_min,
+        "idle_timeout_source": "config"
     }))
 }
 
 fn runtime_collect_session_job_status(ctx: &Context) -> Result<serde_json::Value, LuxError> {
     let cfg = read_config(&ctx.config_path)?;
     let policy = resolve_config_policy_paths(&cfg)?;
-    let log_root = policy.log_root;
     let active = load_active_run_state(&policy.state_root)?;
     let Some(active) = active else {
         return Ok(json!({
@@ -5982,7 +9485,17 @@ fn runtime_collect_session_job_status(ctx: &Context) -> Result<serde_json::Value
             "jobs": {"count": 0, "running": 0, "finished": 0}
         }));
     };
-    let run_root = run_root(&log_root, &active.run_id);
+    let (sessions, jobs) = run_session_job_counts(&policy.log_root, &active.run_id);
+    Ok(json!({
+        "active_run_id": active.run_id,
+        "sessions": sessions,
+        "jobs": jobs
+    }))
+}
+
+/// `({count}, {count, running, finished})` for the run's harness sessions and jobs.
+fn run_session_job_counts(log_root: &Path, run_id: &str) -> (serde_json::Value, serde_json::Value) {
+    let run_root = run_root(log_root, run_id);
     let sessions_dir = run_root.join("harness").join("sessions");
     let jobs_dir = run_root.join("harness").join("jobs");
     let session_count = fs::read_dir(&sessions_dir)
@@ -6018,22 +9531,28 @@ fn runtime_collect_session_job_status(ctx: &Context) -> Result<serde_json::Value
             }
         }
     }
-    Ok(json!({
-        "active_run_id": active.run_id,
-        "sessions": {"count": session_count},
-        "jobs": {"count": job_count, "running": running, "finished": finished}
-    }))
+    (
+        json!({"count": session_count}),
+        json!({"count": job_count, "running": running, "finished": finished}),
+    )
 }
 
 fn runtime_collect_collector_pipeline(ctx: &Context) -> Result<serde_json::Value, LuxError> {
     let cfg = read_config(&ctx.config_path)?;
     let policy = resolve_config_policy_paths(&cfg)?;
-    let log_root = policy.log_root;
     let active = load_active_run_state(&policy.state_root)?;
     let Some(active) = active else {
         return Ok(json!({"active_run_id": null, "pipeline": []}));
     };
-    let run_root = run_root(&log_root, &active.run_id);
+    Ok(json!({
+        "active_run_id": active.run_id,
+        "pipeline": run_pipeline_files(&policy.log_root, &active.run_id)
+    }))
+}
+
+/// Presence, size, and mtime of the run's raw and filtered collector outputs.
+fn run_pipeline_files(log_root: &Path, run_id: &str) -> Vec<serde_json::Value> {
+    let run_root = run_root(log_root, run_id);
     let pipeline_files = vec![
         (
             "raw.audit",
@@ -6067,10 +9586,7 @@ fn runtime_collect_collector_pipeline(ctx: &Context) -> Result<serde_json::Value
             "modified_at": modified
         }));
     }
-    Ok(json!({
-        "active_run_id": active.run_id,
-        "pipeline": rows
-    }))
+    rows
 }
 
 fn runtime_collect_warnings(
@@ -6097,13 +9613,14 @@ fn parse_rfc3339_utc(value: &str) -> Option<DateTime<Utc>> {
         .map(|dt| dt.with_timezone(&Utc))
 }
 
-fn runtime_scheduler_tick(
+fn runtime_scheduler_tick<R: DockerRunner, C: RuntimeCliRunner>(
     ctx: &Context,
+    runner: &R,
+    cli: &C,
     shared: &Arc<(Mutex<RuntimeSharedState>, Condvar)>,
     events_path: &Path,
 ) -> Result<(), LuxError> {
     let cfg = read_config(&ctx.config_path)?;
-    let runner = RealDockerRunner;
     let active = load_active_run_state(&resolve_config_policy_paths(&cfg)?.state_root)?;
     let Some(active) = active else {
         return Ok(());
@@ -6111,9 +9628,9 @@ fn runtime_scheduler_tick(
     let active_workspace = resolve_active_run_workspace_root(&cfg, &active)?;
     let run_env = compose_env_for_run(Some(&active.run_id), Some(&active_workspace));
     let provider_running =
-        provider_plane_is_running(ctx, &runner, &cfg, false, &run_env).unwrap_or(false);
+        provider_plane_is_running(ctx, runner, &cfg, false, &run_env).unwrap_or(false);
     let collector_running =
-        collector_is_running(ctx, &runner, &cfg, false, &run_env).unwrap_or(false);
+        collector_is_running(ctx, runner, &cfg, false, &run_env).unwrap_or(false);
 
     {
         let (lock, _) = &**shared;
@@ -6137,12 +9654,14 @@ fn runtime_scheduler_tick(
                 .and_then(|value| parse_rfc3339_utc(value))
         }
         .or_else(|| parse_rfc3339_utc(&active.started_at));
+        let (idle_timeout_min, _) = effective_idle_timeout_min(&cfg, &active);
         if let Some(idle_since) = idle_ref {
             let idle_age = Utc::now() - idle_since;
-            if idle_age.num_minutes() >= cfg.collector.idle_timeout_min as i64 {
-                let output = runtime_run_cli_subprocess(
+            if idle_age.num_minutes() >= idle_timeout_min as i64 {
+                let output = cli.run(
                     ctx,
                     &["down".to_string(), "--collector-only".to_string()],
+                    None,
                 )?;
                 if output.status_code == 0 {
                     let _ = runtime_emit_event(
@@ -6150,7 +9669,7 @@ fn runtime_scheduler_tick(
                         events_path,
                         "run.stopped",
                         "info",
-                        json!({"reason":"idle_timeout", "idle_timeout_min": cfg.collector.idle_timeout_min}),
+                        json!({"reason":"idle_timeout", "idle_timeout_min": idle_timeout_min}),
                     );
                 } else {
                     let _ = runtime_emit_warning(
@@ -6187,55 +9706,7 @@ fn runtime_scheduler_tick(
                     );
                 }
             } else if collector_running {
-                let _ = runtime_emit_event(
-                    shared,
-                    events_path,
-                    "run.stopped",
-                    "info",
-                    json!({"reason":"rotation_cutover_start", "run_id": active.run_id}),
-                );
-                let stop_out = runtime_run_cli_subprocess(
-                    ctx,
-                    &["down".to_string(), "--collector-only".to_string()],
-                )?;
-                thread::sleep(Duration::from_secs(2));
-                let start_out = runtime_run_cli_subprocess(
-                    ctx,
-                    &[
-                        "up".to_string(),
-                        "--collector-only".to_string(),
-                        "--wait".to_string(),
-                    ],
-                )?;
-                if stop_out.status_code == 0 && start_out.status_code == 0 {
-                    {
-                        let (lock, _) = &**shared;
-                        let mut state = lock.lock().map_err(|_| {
-                            LuxError::Process("runtime state lock poisoned".to_string())
-                        })?;
-                        state.rotation_pending = false;
-                    }
-                    let _ = runtime_emit_event(
-                        shared,
-                        events_path,
-                        "run.started",
-                        "info",
-                        json!({"reason":"rotation_cutover_complete"}),
-                    );
-                } else {
-                    let _ = runtime_emit_event(
-                        shared,
-                        events_path,
-                        "attribution.uncertainty.warning",
-                        "error",
-                        json!({"reason":"rotation_cutover_failed"}),
-                    );
-                    let _ = runtime_emit_warning(
-                        shared,
-                        events_path,
-                        "rotation cutover failed; active run may require manual recovery",
-                    );
-                }
+                let _ = runtime_rotation_cutover(ctx, shared, events_path, &active, cli)?;
             }
         }
     }
@@ -6243,8 +9714,205 @@ fn runtime_scheduler_tick(
     Ok(())
 }
 
-fn runtime_scheduler_loop(
+/// Clears `rotation_in_progress` when a cutover finishes, including on early error returns.
+struct RuntimeRotationGuard<'a> {
+    shared: &'a Arc<(Mutex<RuntimeSharedState>, Condvar)>,
+}
+
+impl Drop for RuntimeRotationGuard<'_> {
+    fn drop(&mut self) {
+        let (lock, _) = &**self.shared;
+        if let Ok(mut state) = lock.lock() {
+            state.rotation_in_progress = false;
+        }
+    }
+}
+
+/// Stops and restarts the collector for a rotation cutover. Returns `Ok(false)` without running
+/// anything when another cutover is still in flight.
+fn runtime_rotation_cutover<C: RuntimeCliRunner>(
+    ctx: &Context,
+    shared: &Arc<(Mutex<RuntimeSharedState>, Condvar)>,
+    events_path: &Path,
+    active: &ActiveRunState,
+    cli: &C,
+) -> Result<bool, LuxError> {
+    let already_in_progress = {
+        let (lock, _) = &**shared;
+        let mut state = lock
+            .lock()
+            .map_err(|_| LuxError::Process("runtime state lock poisoned".to_string()))?;
+        let already = state.rotation_in_progress;
+        state.rotation_in_progress = true;
+        already
+    };
+    if already_in_progress {
+        let _ = runtime_emit_event(
+            shared,
+            events_path,
+            "collector.rotation.skipped",
+            "debug",
+            json!({"reason":"rotation_cutover_in_progress", "run_id": active.run_id}),
+        );
+        return Ok(false);
+    }
+    let _guard = RuntimeRotationGuard { shared };
+
+    let _ = runtime_emit_event(
+        shared,
+        events_path,
+        "run.stopped",
+        "info",
+        json!({"reason":"rotation_cutover_start", "run_id": active.run_id}),
+    );
+    let stop_out = cli.run(
+        ctx,
+        &["down".to_string(), "--collector-only".to_string()],
+        None,
+    )?;
+    thread::sleep(Duration::from_secs(2));
+    let mut start_args = vec![
+        "up".to_string(),
+        "--collector-only".to_string(),
+        "--wait".to_string(),
+    ];
+    for mount in &active.mounts {
+        start_args.push("--mount".to_string());
+        start_args.push(mount.spec());
+    }
+    if let Some(minutes) = active.idle_timeout_min {
+        start_args.push("--idle-timeout-min".to_string());
+        start_args.push(minutes.to_string());
+    }
+    let start_out = cli.run(ctx, &start_args, None)?;
+    if stop_out.status_code == 0 && start_out.status_code == 0 {
+        {
+            let (lock, _) = &**shared;
+            let mut state = lock
+                .lock()
+                .map_err(|_| LuxError::Process("runtime state lock poisoned".to_string()))?;
+            state.rotation_pending = false;
+        }
+        let _ = runtime_emit_event(
+            shared,
+            events_path,
+            "run.started",
+            "info",
+            json!({"reason":"rotation_cutover_complete"}),
+        );
+    } else {
+        let _ = runtime_emit_event(
+            shared,
+            events_path,
+            "attribution.uncertainty.warning",
+            "error",
+            json!({"reason":"rotation_cutover_failed"}),
+        );
+        let _ = runtime_emit_warning(
+            shared,
+            events_path,
+            "rotation cutover failed; active run may require manual recovery",
+        );
+    }
+
+    Ok(true)
+}
+
+/// Holds one slot of the runtime connection limit; released when the handler thread finishes
+/// (including long-lived SSE streams).
+#[cfg(unix)]
+struct RuntimeConnectionSlot {
+    shared: Arc<(Mutex<RuntimeSharedState>, Condvar)>,
+}
+
+#[cfg(unix)]
+impl Drop for RuntimeConnectionSlot {
+    fn drop(&mut self) {
+        let (lock, _) = &*self.shared;
+        if let Ok(mut state) = lock.lock() {
+            state.active_connections = state.active_connections.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn runtime_acquire_connection_slot(
+    shared: &Arc<(Mutex<RuntimeSharedState>, Condvar)>,
+    max_connections: usize,
+) -> Result<Option<RuntimeConnectionSlot>, LuxError> {
+    let (lock, _) = &**shared;
+    let mut state = lock
+        .lock()
+        .map_err(|_| LuxError::Process("runtime state lock poisoned".to_string()))?;
+    if state.active_connections >= max_connections {
+        return Ok(None);
+    }
+    state.active_connections += 1;
+    Ok(Some(RuntimeConnectionSlot {
+        shared: Arc::clone(shared),
+    }))
+}
+
+/// Admits a connection against the concurrency limit and serves it on its own thread.
+/// `auth_token` is set for TCP connections, which must present it as a bearer token.
+#[cfg(unix)]
+fn runtime_dispatch_connection<S, C>(
+    mut stream: S,
+    ctx: &Context,
+    cli: &Arc<C>,
+    shared: &Arc<(Mutex<RuntimeSharedState>, Condvar)>,
+    events_path: &Path,
+    max_connections: usize,
+    auth_token: Option<String>,
+) -> Result<(), LuxError>
+where
+    S: RuntimeStream + Send + 'static,
+    C: RuntimeCliRunner + Send + Sync + 'static,
+{
+    let Some(slot) = runtime_acquire_connection_slot(shared, max_connections)? else {
+        let _ = runtime_write_busy_response(&mut stream);
+        return Ok(());
+    };
+    let ctx_clone = ctx.clone();
+    let cli_clone = Arc::clone(cli);
+    let shared_clone = Arc::clone(shared);
+    let events_clone = events_path.to_path_buf();
+    thread::spawn(move || {
+        let _slot = slot;
+        let _ = runtime_handle_connection(
+            stream,
+            ctx_clone,
+            &*cli_clone,
+            shared_clone,
+            events_clone,
+            auth_token,
+        );
+    });
+    Ok(())
+}
+
+#[cfg(unix)]
+fn runtime_write_busy_response<S: RuntimeStream>(stream: &mut S) -> Result<(), LuxError> {
+    // Drain whatever request bytes already arrived; closing with unread input resets the
+    // connection and the client would never see the 503.
+    let _ = stream.set_io_write_timeout(Some(Duration::from_secs(1)));
+    let _ = stream.set_io_read_timeout(Some(Duration::from_millis(100)));
+    let mut discard = [0u8; 4096];
+    let _ = stream.read(&mut discard);
+    let body = "{\"error\":\"runtime connection limit reached\"}";
+    let header = format!(
+        "HTTP/1.1 503 Service Unavailable\r\nContent-Type: application/json\r\nContent-Length: {}\r\nRetry-After: {}\r\nConnection: close\r\n\r\n",
+        body.len(),
+        RUNTIME_BUSY_RETRY_AFTER_SEC
+    );
+    stream.write_all(header.as_bytes())?;
+    stream.write_all(body.as_bytes())?;
+    Ok(())
+}
+
+fn runtime_scheduler_loop<C: RuntimeCliRunner>(
     ctx: Context,
+    cli: Arc<C>,
     shared: Arc<(Mutex<RuntimeSharedState>, Condvar)>,
     events_path: PathBuf,
 ) {
@@ -6259,28 +9927,72 @@ fn runtime_scheduler_loop(
                 return;
             }
         }
-        if let Err(err) = runtime_scheduler_tick(&ctx, &shared, &events_path) {
+        let tick_started = Instant::now();
+        let tick = runtime_scheduler_tick(&ctx, &RealDockerRunner, &*cli, &shared, &events_path);
+        let elapsed_ms = tick_started.elapsed().as_millis() as u64;
+        match &tick {
+            Ok(()) => runtime_log(
+                &shared,
+                "debug",
+                "scheduler tick",
+                json!({"elapsed_ms": elapsed_ms}),
+            ),
+            Err(err) => runtime_log(
+                &shared,
+                "error",
+                "scheduler tick failed",
+                json!({"elapsed_ms": elapsed_ms, "error": err.to_string()}),
+            ),
+        }
+        if let Err(err) = tick {
             let _ = runtime_emit_warning(
                 &shared,
                 &events_path,
                 &format!("runtime scheduler tick failed: {err}"),
             );
         }
-        thread::sleep(Duration::from_secs(30));
+        // Wait on the condvar rather than sleeping so a shutdown wakes the scheduler immediately.
+        let (lock, condvar) = &*shared;
+        let state = match lock.lock() {
+            Ok(state) => state,
+            Err(_) => return,
+        };
+        if condvar
+            .wait_timeout_while(state, Duration::from_secs(30), |state| !state.shutdown)
+            .is_err()
+        {
+            return;
+        }
     }
 }
 
 #[cfg(unix)]
-fn runtime_handle_connection(
-    mut stream: UnixStream,
+fn runtime_handle_connection<S: RuntimeStream, C: RuntimeCliRunner>(
+    mut stream: S,
     ctx: Context,
+    cli: &C,
     shared: Arc<(Mutex<RuntimeSharedState>, Condvar)>,
     events_path: PathBuf,
+    auth_token: Option<String>,
 ) -> Result<(), LuxError> {
     let request = runtime_read_http_request(&mut stream)?;
     let Some(request) = request else {
         return Ok(());
     };
+    if let Some(token) = auth_token.as_deref() {
+        let authorized = request
+            .headers
+            .get("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|value| constant_time_eq(value.trim().as_bytes(), token.as_bytes()));
+        if !authorized {
+            return runtime_write_json_response(
+                &mut stream,
+                401,
+                &json!({"error":"missing or invalid bearer token"}),
+            );
+        }
+    }
     match (request.method.as_str(), request.path.as_str()) {
         ("GET", "/v1/healthz") => {
             runtime_write_json_response(
@@ -6318,11 +10030,16 @@ fn runtime_handle_connection(
                 .get("last-event-id")
                 .and_then(|value| value.parse::<u64>().ok())
                 .unwrap_or(0);
-            if let Some(value) = request.query.get("last_event_id") {
+            if let Some(value) = request
+                .query
+                .get("last_event_id")
+                .and_then(|values| values.last())
+            {
                 if let Ok(parsed) = value.parse::<u64>() {
                     last_event_id = parsed;
                 }
             }
+            let filter = RuntimeEventFilter::from_query(&request.query);
             let header = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n";
             stream.write_all(header.as_bytes())?;
             loop {
@@ -6353,6 +10070,15 @@ fn runtime_handle_connection(
                         .collect();
                     (events, state.shutdown)
                 };
+                // Advance past every scanned event, matching or not, so filtered-out events are
+                // not rescanned on the next wake.
+                if let Some(last) = pending.last() {
+                    last_event_id = last.id;
+                }
+                let pending: Vec<RuntimeEvent> = pending
+                    .into_iter()
+                    .filter(|event| filter.matches(event))
+                    .collect();
                 if pending.is_empty() {
                     if shutdown {
                         break;
@@ -6362,7 +10088,6 @@ fn runtime_handle_connection(
                     continue;
                 }
                 for event in pending {
-                    last_event_id = event.id;
                     runtime_send_sse_event(&mut stream, &event)?;
                 }
                 if shutdown {
@@ -6382,7 +10107,21 @@ fn runtime_handle_connection(
                     &json!({"error":"argv must not be empty"}),
                 );
             }
-            let output = runtime_run_cli_subprocess(&ctx, &request_body.argv)?;
+            if let Some(cwd) = request_body.cwd.as_deref() {
+                if !cwd.is_absolute() || !cwd.is_dir() {
+                    return runtime_write_json_response(
+                        &mut stream,
+                        400,
+                        &json!({
+                            "error": format!(
+                                "working directory {} is not a directory on the runtime host",
+                                cwd.display()
+                            )
+                        }),
+                    );
+                }
+            }
+            let output = cli.run(&ctx, &request_body.argv, request_body.cwd.as_deref())?;
             let _ = runtime_record_command_events(
                 &shared,
                 &events_path,
@@ -6450,10 +10189,10 @@ fn runtime_status_payload(ctx: &Context) -> Result<serde_json::Value, LuxError>
     }))
 }
 
-fn runtime_up_internal(ctx: &Context, emit_output: bool) -> Result<(), LuxError> {
+fn runtime_up_internal(ctx: &Context, emit_output: bool, wait: bool) -> Result<(), LuxError> {
     #[cfg(not(unix))]
     {
-        let _ = (ctx, emit_output);
+        let _ = (ctx, emit_output, wait);
         return Err(LuxError::Config(
             "runtime control plane is only supported on unix hosts".to_string(),
         ));
@@ -6467,16 +10206,17 @@ fn runtime_up_internal(ctx: &Context, emit_output: bool) -> Result<(), LuxError>
         };
         let (paths, _) = resolve_runtime_paths(ctx)?;
         if runtime_ping(ctx).is_ok() {
+            let mut payload = json!({"running": true, "already_running": true, "socket_path": paths.runtime_socket_path});
+            if wait {
+                payload["stack"] = runtime_wait_stack_status(ctx)?;
+            }
             if emit_output {
-                return output(
-                    ctx,
-                    json!({"running": true, "already_running": true, "socket_path": paths.runtime_socket_path}),
-                );
+                return output(ctx, payload);
             }
             return Ok(());
         }
         if let Some(pid) = read_pid_file(&paths.runtime_pid_path) {
-            if process_is_alive(pid) {
+            if process_is_alive(pid) && pid_is_lux_runtime(pid) {
                 return Err(LuxError::Process(format!(
                     "runtime pid {} is alive but socket {} is unavailable; run `lux runtime down` and retry",
                     pid,
@@ -6494,18 +10234,43 @@ fn runtime_up_internal(ctx: &Context, emit_output: bool) -> Result<(), LuxError>
         for compose_override in &ctx.compose_file_overrides {
             cmd.arg("--compose-file").arg(compose_override);
         }
+        if let Some(project_name) = &ctx.project_name {
+            cmd.arg("--project-name").arg(project_name);
+        }
         cmd.arg("runtime").arg("serve");
         cmd.env(RUNTIME_BYPASS_ENV, "1");
         cmd.stdin(Stdio::null());
         cmd.stdout(Stdio::null());
-        cmd.stderr(Stdio::null());
+        // With a log file configured, anything the daemon prints before (or instead of)
+        // structured logging still lands somewhere readable.
+        match effective_runtime_log_file(&cfg) {
+            Some(log_file) => {
+                ensure_parent(&log_file)?;
+                let file = fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&log_file)?;
+                cmd.stderr(file);
+            }
+            None => {
+                cmd.stderr(Stdio::null());
+            }
+        }
         let mut child = cmd.spawn().map_err(|err| {
             LuxError::Process(format!("failed to start runtime control plane: {err}"))
         })?;
 
+        let start_timeout = Duration::from_secs(cfg.runtime_control_plane.start_timeout_sec);
+        let deadline = Instant::now() + start_timeout;
+        let mut delay = RUNTIME_READY_INITIAL_BACKOFF;
         let mut started = false;
-        for _ in 0..300 {
-            thread::sleep(Duration::from_millis(100));
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            thread::sleep(delay.min(deadline - now));
+            delay = runtime_ready_next_backoff(delay);
             if runtime_ping(ctx).is_ok() {
                 started = true;
                 break;
@@ -6519,26 +10284,59 @@ fn runtime_up_internal(ctx: &Context, emit_output: bool) -> Result<(), LuxError>
         }
         if !started {
             return Err(LuxError::Process(format!(
-                "runtime control plane did not become ready at {}",
-                paths.runtime_socket_path.display()
+                "runtime control plane did not become ready at {} within {}s (runtime_control_plane.start_timeout_sec)",
+                paths.runtime_socket_path.display(),
+                start_timeout.as_secs()
             )));
         }
+        let mut payload = json!({
+            "running": true,
+            "already_running": false,
+            "socket_path": paths.runtime_socket_path
+        });
+        if wait {
+            payload["stack"] = runtime_wait_stack_status(ctx)?;
+        }
         if emit_output {
-            output(
-                ctx,
-                json!({
-                    "running": true,
-                    "already_running": false,
-                    "socket_path": paths.runtime_socket_path
-                }),
-            )?;
+            output(ctx, payload)?;
         }
         Ok(())
     }
 }
 
-fn runtime_down_internal(ctx: &Context) -> Result<(), LuxError> {
+/// `/v1/healthz` only proves the listener is up; a stack status round-trip also exercises
+/// config loading and docker queries inside the daemon.
+fn runtime_wait_stack_status(ctx: &Context) -> Result<serde_json::Value, LuxError> {
+    let response = runtime_control_plane_request(ctx, "GET", "/v1/stack/status", &[], None)
+        .map_err(|err| {
+            LuxError::Process(format!(
+                "runtime control plane answered healthz but stack status failed: {err}"
+            ))
+        })?;
+    if response.status >= 400 {
+        return Err(LuxError::Process(format!(
+            "runtime control plane answered healthz but stack status failed (HTTP {}): {}",
+            response.status,
+            String::from_utf8_lossy(&response.body)
+        )));
+    }
+    let payload: serde_json::Value = serde_json::from_slice(&response.body).map_err(|err| {
+        LuxError::Process(format!("runtime stack status returned invalid JSON: {err}"))
+    })?;
+    Ok(payload.get("stack").cloned().unwrap_or(payload))
+}
+
+fn runtime_ready_next_backoff(delay: Duration) -> Duration {
+    delay.saturating_mul(2).min(RUNTIME_READY_MAX_BACKOFF)
+}
+
+/// Asks the daemon to stop and polls for up to `timeout_sec` until it stops answering. A
+/// daemon still answering after that gets SIGTERM via its pid file; `shutdown` in the result
+/// is `graceful`, `forced`, or `not_running`.
+fn runtime_down_internal(ctx: &Context, timeout_sec: u64) -> Result<(), LuxError> {
     let (paths, _) = resolve_runtime_paths(ctx)?;
+    let mut shutdown = "not_running";
+    let mut signaled_pid = None;
     if runtime_ping(ctx).is_ok() {
         let response = runtime_control_plane_request(
             ctx,
@@ -6553,17 +10351,38 @@ fn runtime_down_internal(ctx: &Context) -> Result<(), LuxError> {
                 response.status
             )));
         }
-        for _ in 0..30 {
-            thread::sleep(Duration::from_millis(100));
+        let deadline = Instant::now() + Duration::from_secs(timeout_sec);
+        shutdown = "graceful";
+        loop {
+            thread::sleep(Duration::from_millis(RUNTIME_DOWN_POLL_INTERVAL_MS));
             if runtime_ping(ctx).is_err() {
                 break;
             }
+            if Instant::now() >= deadline {
+                shutdown = "forced";
+                break;
+            }
+        }
+    }
+    if shutdown == "forced" {
+        if let Some(pid) = read_pid_file(&paths.runtime_pid_path) {
+            // A stale pid file may name an unrelated process that reused the pid.
+            if process_is_alive(pid) && pid_is_lux_runtime(pid) && send_sigterm(pid) {
+                signaled_pid = Some(pid);
+                // Let the daemon release its socket before the artifacts are removed.
+                let _ = wait_for_process_exit(pid, Duration::from_millis(RUNTIME_SIGTERM_GRACE_MS));
+            }
         }
     }
     runtime_cleanup_artifacts(&paths);
     output(
         ctx,
-        json!({"running": false, "socket_path": paths.runtime_socket_path}),
+        json!({
+            "running": false,
+            "socket_path": paths.runtime_socket_path,
+            "shutdown": shutdown,
+            "signaled_pid": signaled_pid,
+        }),
     )
 }
 
@@ -6583,9 +10402,71 @@ fn runtime_serve(ctx: &Context) -> Result<(), LuxError> {
             Config::default()
         };
         let (paths, _) = resolve_runtime_paths(ctx)?;
+        let shared: Arc<(Mutex<RuntimeSharedState>, Condvar)> = Arc::new((
+            Mutex::new(RuntimeSharedState {
+                events_max_bytes: cfg.runtime_control_plane.events_max_bytes,
+                log_file: effective_runtime_log_file(&cfg),
+                ..RuntimeSharedState::default()
+            }),
+            Condvar::new(),
+        ));
+        runtime_log(
+            &shared,
+            "info",
+            "runtime starting",
+            json!({
+                "pid": std::process::id(),
+                "config_path": ctx.config_path,
+                "socket_path": paths.runtime_socket_path,
+                "tcp_listen": cfg.runtime_control_plane.tcp_listen.trim(),
+            }),
+        );
+        // TCP is opt-in and always token-gated; the unix socket stays the primary transport.
+        let tcp_listen = cfg.runtime_control_plane.tcp_listen.trim().to_string();
+        let tcp = if tcp_listen.is_empty() {
+            None
+        } else {
+            let token = resolve_runtime_tcp_token(&cfg);
+            let tcp_listener = token.and_then(|token| {
+                let listener = TcpListener::bind(&tcp_listen).map_err(|err| {
+                    LuxError::Process(format!(
+                        "failed to bind runtime tcp listener {}: {}",
+                        tcp_listen, err
+                    ))
+                })?;
+                listener.set_nonblocking(true)?;
+                Ok((listener, token))
+            });
+            match tcp_listener {
+                Ok(tcp) => Some(tcp),
+                Err(err) => {
+                    runtime_log(
+                        &shared,
+                        "error",
+                        "runtime failed to start",
+                        json!({"error": err.to_string()}),
+                    );
+                    return Err(err);
+                }
+            }
+        };
         ensure_runtime_permissions(&cfg, &paths.runtime_dir, None)?;
         let _ = fs::remove_file(&paths.runtime_socket_path);
-        let listener = UnixListener::bind(&paths.runtime_socket_path)?;
+        let listener = match UnixListener::bind(&paths.runtime_socket_path) {
+            Ok(listener) => listener,
+            Err(err) => {
+                runtime_log(
+                    &shared,
+                    "error",
+                    "runtime failed to start",
+                    json!({
+                        "error": err.to_string(),
+                        "socket_path": paths.runtime_socket_path,
+                    }),
+                );
+                return Err(err.into());
+            }
+        };
         listener.set_nonblocking(true)?;
         ensure_runtime_permissions(&cfg, &paths.runtime_dir, Some(&paths.runtime_socket_path))?;
         write_atomic_text_file(
@@ -6594,8 +10475,7 @@ fn runtime_serve(ctx: &Context) -> Result<(), LuxError> {
             Some(0o660),
         )?;
 
-        let shared: Arc<(Mutex<RuntimeSharedState>, Condvar)> =
-            Arc::new((Mutex::new(RuntimeSharedState::default()), Condvar::new()));
+        let max_connections = cfg.runtime_control_plane.max_connections as usize;
         let _ = runtime_emit_event(
             &shared,
             &paths.runtime_events_path,
@@ -6603,41 +10483,94 @@ fn runtime_serve(ctx: &Context) -> Result<(), LuxError> {
             "info",
             json!({"socket_path": paths.runtime_socket_path}),
         );
+        runtime_log(
+            &shared,
+            "info",
+            "runtime listening",
+            json!({"socket_path": paths.runtime_socket_path, "tcp": tcp.is_some()}),
+        );
+        // The handler only records the signal number; the accept loop below turns it into a
+        // normal shutdown so the scheduler is joined and socket/pid files are cleaned up.
+        let received_signal = Arc::new(AtomicUsize::new(0));
+        for signal in [signal_hook::consts::SIGTERM, signal_hook::consts::SIGINT] {
+            signal_hook::flag::register_usize(
+                signal,
+                Arc::clone(&received_signal),
+                signal as usize,
+            )?;
+        }
+        let cli = Arc::new(RealRuntimeCliRunner);
         let scheduler_shared = Arc::clone(&shared);
         let scheduler_ctx = ctx.clone();
+        let scheduler_cli = Arc::clone(&cli);
         let scheduler_events = paths.runtime_events_path.clone();
         let scheduler_handle = thread::spawn(move || {
-            runtime_scheduler_loop(scheduler_ctx, scheduler_shared, scheduler_events)
+            runtime_scheduler_loop(
+                scheduler_ctx,
+                scheduler_cli,
+                scheduler_shared,
+                scheduler_events,
+            )
         });
 
         loop {
+            let signal = received_signal.load(Ordering::Relaxed);
             {
-                let (lock, _) = &*shared;
-                let state = lock
+                let (lock, condvar) = &*shared;
+                let mut state = lock
                     .lock()
                     .map_err(|_| LuxError::Process("runtime state lock poisoned".to_string()))?;
                 if state.shutdown {
                     break;
                 }
+                if signal != 0 {
+                    state.shutdown = true;
+                    condvar.notify_all();
+                }
+            }
+            if signal != 0 {
+                let signal_name = if signal == signal_hook::consts::SIGTERM as usize {
+                    "SIGTERM"
+                } else {
+                    "SIGINT"
+                };
+                let _ = runtime_emit_event(
+                    &shared,
+                    &paths.runtime_events_path,
+                    "runtime.stopped",
+                    "info",
+                    json!({"reason": "signal", "signal": signal_name}),
+                );
+                runtime_log(
+                    &shared,
+                    "info",
+                    "runtime shutting down",
+                    json!({"reason": "signal", "signal": signal_name}),
+                );
+                break;
             }
+            let mut accepted = false;
             match listener.accept() {
                 Ok((stream, _addr)) => {
-                    let ctx_clone = ctx.clone();
-                    let shared_clone = Arc::clone(&shared);
-                    let events_clone = paths.runtime_events_path.clone();
-                    thread::spawn(move || {
-                        let _ = runtime_handle_connection(
-                            stream,
-                            ctx_clone,
-                            shared_clone,
-                            events_clone,
-                        );
-                    });
-                }
-                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
-                    thread::sleep(Duration::from_millis(100));
+                    accepted = true;
+                    runtime_dispatch_connection(
+                        stream,
+                        ctx,
+                        &cli,
+                        &shared,
+                        &paths.runtime_events_path,
+                        max_connections,
+                        None,
+                    )?;
                 }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
                 Err(err) => {
+                    runtime_log(
+                        &shared,
+                        "warn",
+                        "accept failed",
+                        json!({"listener": "unix", "error": err.to_string()}),
+                    );
                     let _ = runtime_emit_warning(
                         &shared,
                         &paths.runtime_events_path,
@@ -6646,6 +10579,40 @@ fn runtime_serve(ctx: &Context) -> Result<(), LuxError> {
                     thread::sleep(Duration::from_millis(250));
                 }
             }
+            if let Some((tcp_listener, token)) = tcp.as_ref() {
+                match tcp_listener.accept() {
+                    Ok((stream, _addr)) => {
+                        accepted = true;
+                        let _ = stream.set_nonblocking(false);
+                        runtime_dispatch_connection(
+                            stream,
+                            ctx,
+                            &cli,
+                            &shared,
+                            &paths.runtime_events_path,
+                            max_connections,
+                            Some(token.clone()),
+                        )?;
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(err) => {
+                        runtime_log(
+                            &shared,
+                            "warn",
+                            "accept failed",
+                            json!({"listener": "tcp", "error": err.to_string()}),
+                        );
+                        let _ = runtime_emit_warning(
+                            &shared,
+                            &paths.runtime_events_path,
+                            &format!("runtime tcp listener accept failed: {err}"),
+                        );
+                    }
+                }
+            }
+            if !accepted {
+                thread::sleep(Duration::from_millis(100));
+            }
         }
 
         {
@@ -6657,19 +10624,106 @@ fn runtime_serve(ctx: &Context) -> Result<(), LuxError> {
         }
         let _ = scheduler_handle.join();
         runtime_cleanup_artifacts(&paths);
+        runtime_log(&shared, "info", "runtime stopped", json!({}));
         Ok(())
     }
 }
 
 fn handle_runtime(ctx: &Context, command: RuntimeCommand) -> Result<(), LuxError> {
     match command {
-        RuntimeCommand::Up => runtime_up_internal(ctx, true),
-        RuntimeCommand::Down => runtime_down_internal(ctx),
+        RuntimeCommand::Up { wait } => runtime_up_internal(ctx, true, wait),
+        RuntimeCommand::Down { timeout_sec } => runtime_down_internal(ctx, timeout_sec),
         RuntimeCommand::Status => output(ctx, runtime_status_payload(ctx)?),
+        RuntimeCommand::Events {
+            follow,
+            since_id,
+            event_type,
+        } => runtime_events(ctx, follow, since_id, &event_type),
+        RuntimeCommand::Logs { lines, follow } => runtime_logs(ctx, lines, follow),
         RuntimeCommand::Serve => runtime_serve(ctx),
     }
 }
 
+/// `lux runtime logs`: prints the last `lines` lines of `runtime_control_plane.log_file`, then
+/// with `--follow` polls for appended lines. Under `--json` every line is printed as its own
+/// object; lines that are not JSON (the daemon's redirected stderr) become `{"raw": line}`.
+fn runtime_logs(ctx: &Context, lines: usize, follow: bool) -> Result<(), LuxError> {
+    let cfg = read_config(&ctx.config_path)?;
+    let Some(log_file) = effective_runtime_log_file(&cfg) else {
+        return Err(LuxError::Config(
+            "runtime_control_plane.log_file is not set; set it in config.yaml and restart the runtime (`lux runtime down && lux runtime up`) to record daemon diagnostics"
+                .to_string(),
+        ));
+    };
+    if !follow && !log_file.exists() {
+        return Err(LuxError::Process(format!(
+            "runtime log not found: {} (the runtime has not written to it yet)",
+            log_file.display()
+        )));
+    }
+
+    let mut offset = 0u64;
+    let mut tail = VecDeque::new();
+    if let Ok(file) = fs::File::open(&log_file) {
+        let mut reader = BufReader::new(file);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)?;
+            // Under --follow an unterminated last line is left for the poll loop.
+            if read == 0 || (follow && !line.ends_with(b"\n")) {
+                break;
+            }
+            offset += read as u64;
+            if lines == 0 {
+                continue;
+            }
+            if tail.len() == lines {
+                tail.pop_front();
+            }
+            tail.push_back(String::from_utf8_lossy(&line).into_owned());
+        }
+    }
+    for line in &tail {
+        print_runtime_log_line(ctx, line)?;
+    }
+    if !follow {
+        return Ok(());
+    }
+
+    let mut pending = Vec::new();
+    loop {
+        let previous_offset = offset;
+        let chunk = read_appended(&log_file, &mut offset)?;
+        if offset < previous_offset {
+            // Truncated and rewritten: a buffered partial line belongs to the old file.
+            pending.clear();
+        }
+        pending.extend_from_slice(&chunk);
+        while let Some(end) = pending.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            print_runtime_log_line(ctx, &String::from_utf8_lossy(&line))?;
+        }
+        io::stdout().flush()?;
+        thread::sleep(Duration::from_millis(RUNTIME_LOGS_FOLLOW_INTERVAL_MS));
+    }
+}
+
+fn print_runtime_log_line(ctx: &Context, line: &str) -> Result<(), LuxError> {
+    let line = line.trim_end_matches(['\n', '\r']);
+    if line.trim().is_empty() {
+        return Ok(());
+    }
+    if !ctx.json {
+        println!("{line}");
+        return Ok(());
+    }
+    match serde_json::from_str::<serde_json::Value>(line) {
+        Ok(value @ serde_json::Value::Object(_)) => print_json(ctx, &value),
+        _ => print_json(ctx, &json!({ "raw": line })),
+    }
+}
+
 const SHIM_MARKER: &str = "# lux-shim";
 const SHIM_PATH_BEGIN_MARKER: &str = "# >>> lux-shim-path >>>";
 const SHIM_PATH_END_MARKER: &str = "# <<< lux-shim-path <<<";
@@ -6720,6 +10774,10 @@ fn resolve_shim_providers(cfg: &Config, providers: Vec<String>) -> Vec<String> {
     let mut seen = std::collections::BTreeSet::new();
     let mut ordered = Vec::new();
     for provider in providers {
+        // Unknown or ambiguous names are left as typed so provider_from_config reports them.
+        let provider = resolve_provider_name(cfg, &provider)
+            .map(str::to_string)
+            .unwrap_or(provider);
         if seen.insert(provider.clone()) {
             ordered.push(provider);
         }
@@ -7201,12 +11259,12 @@ fn ensure_provider_plane_for_shim<R: DockerRunner>(
     }
     handle_up(
         ctx,
-        Some(provider.to_string()),
-        false,
-        None,
-        Some("missing".to_string()),
-        true,
-        None,
+        UpOptions {
+            provider: Some(provider.to_string()),
+            pull: Some("missing".to_string()),
+            wait: true,
+            ..Default::default()
+        },
         runner,
     )?;
     let active_provider = load_active_provider_state(&state_root)?.ok_or_else(|| {
@@ -7215,16 +11273,179 @@ fn ensure_provider_plane_for_shim<R: DockerRunner>(
     Ok(active_provider.run_id)
 }
 
-fn shim_validate_exec_args(args: &[String]) -> Result<(), LuxError> {
+fn handle_secrets<R: DockerRunner>(
+    ctx: &Context,
+    command: SecretsCommand,
+    runner: &R,
+) -> Result<(), LuxError> {
+    match command {
+        SecretsCommand::Rotate {
+            provider,
+            value,
+            from_env,
+            restart,
+            no_restart,
+        } => {
+            let value = value.map(|_| read_secret_from_stdin()).transpose()?;
+            secrets_rotate(ctx, &provider, value, from_env, restart, no_restart, runner)
+        }
+    }
+}
+
+/// `secrets rotate --value -`: the whole of stdin is the key; `resolve_rotated_secret_value`
+/// trims the trailing newline.
+fn read_secret_from_stdin() -> Result<String, LuxError> {
+    let mut value = String::new();
+    io::stdin()
+        .read_to_string(&mut value)
+        .map_err(|err| LuxError::Process(format!("failed to read secret from stdin: {err}")))?;
+    Ok(value)
+}
+
+fn resolve_rotated_secret_value(
+    provider: &str,
+    env_key: &str,
+    value: Option<String>,
+    from_env: bool,
+) -> Result<String, LuxError> {
+    let value = if let Some(value) = value {
+        value
+    } else if from_env {
+        env::var(env_key).unwrap_or_default()
+    } else if io::stdin().is_terminal() {
+        Password::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("Enter new {env_key} for provider '{provider}'"))
+            .with_confirmation("Confirm value", "Values do not match")
+            .interact()?
+    } else {
+        return Err(LuxError::Config(format!(
+            "no secret value provided for provider '{provider}'; pipe it to --value -, use --from-env to read ${env_key}, or run interactively"
+        )));
+    };
+    let value = value.trim().to_string();
+    if value.is_empty() {
+        return Err(LuxError::Config(format!(
+            "new {env_key} value for provider '{provider}' is empty"
+        )));
+    }
+    Ok(value)
+}
+
+fn secrets_rotate<R: DockerRunner>(
+    ctx: &Context,
+    provider: &str,
+    value: Option<String>,
+    from_env: bool,
+    restart: bool,
+    no_restart: bool,
+    runner: &R,
+) -> Result<(), LuxError> {
+    let cfg = read_config(&ctx.config_path)?;
+    let provider = resolve_provider_name(&cfg, provider)?;
+    let provider_cfg = provider_from_config(&cfg, provider)?;
+    if provider_cfg.auth_mode != AuthMode::ApiKey {
+        return Err(LuxError::Config(format!(
+            "provider '{provider}' uses auth_mode={}; secrets rotation only applies to api_key providers (refresh host-state credentials by logging in again on the host)",
+            provider_cfg.auth_mode.as_str()
+        )));
+    }
+    let env_key = provider_cfg.auth.api_key.env_key.trim().to_string();
+    let secrets_file = PathBuf::from(expand_path(&provider_cfg.auth.api_key.secrets_file));
+    let value = resolve_rotated_secret_value(provider, &env_key, value, from_env)?;
+    write_provider_secrets_file(&secrets_file, &env_key, &value, true)?;
+
+    let state_root = resolve_config_policy_paths(&cfg)?.state_root;
+    let mut plane_running = false;
+    let mut run_env = BTreeMap::new();
+    if let Some(active_provider) = load_active_provider_state(&state_root)? {
+        if active_provider.provider == provider {
+            let active_workspace = load_active_run_state(&state_root)?
+                .filter(|state| state.run_id == active_provider.run_id)
+                .map(|state| resolve_active_run_workspace_root(&cfg, &state))
+                .transpose()?;
+            run_env =
+                compose_env_for_run(Some(&active_provider.run_id), active_workspace.as_deref());
+            plane_running = provider_plane_is_running(ctx, runner, &cfg, false, &run_env)?;
+        }
+    }
+
+    let should_restart = plane_running
+        && if restart {
+            true
+        } else if no_restart || ctx.json || !io::stdin().is_terminal() {
+            false
+        } else {
+            Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!(
+                    "Provider plane for '{provider}' is running; restart it so the new key takes effect?"
+                ))
+                .default(true)
+                .interact()?
+        };
+    if should_restart {
+        // The secrets file is bind-mounted into the agent, so the container must be recreated to
+        // observe the replaced file.
+        let runtime = generate_provider_runtime_compose(ctx, provider, provider_cfg, None)?;
+        for warning in &runtime.warnings {
+            eprintln!("warning: {warning}");
+        }
+        let mut args = compose_base_args(
+            ctx,
+            &cfg,
+            false,
+            std::slice::from_ref(&runtime.override_file),
+            &provider_cfg.compose_profiles,
+        )?;
+        args.push("up".to_string());
+        args.push("-d".to_string());
+        args.push("--force-recreate".to_string());
+        args.push("agent".to_string());
+        args.push("harness".to_string());
+        execute_docker(ctx, runner, &args, &run_env, true, true)?;
+    }
+
+    output(
+        ctx,
+        json!({
+            "action": "secrets_rotate",
+            "provider": provider,
+            "env_key": env_key,
+            "secrets_file": secrets_file,
+            "provider_plane_running": plane_running,
+            "restarted": should_restart,
+            "restart_required": plane_running && !should_restart,
+        }),
+    )
+}
+
+/// Rewrites absolute host-path arguments that fall under the workspace to their `/work/...`
+/// container path; relative arguments pass through and anything outside the workspace is
+/// rejected. Paths that do not exist yet (e.g. output files) resolve through their parent.
+fn shim_map_exec_args(args: &[String], workspace_root: &Path) -> Result<Vec<String>, LuxError> {
+    let mut mapped = Vec::with_capacity(args.len());
     for arg in args {
-        if Path::new(arg).is_absolute() {
+        let path = Path::new(arg);
+        if !path.is_absolute() {
+            mapped.push(arg.clone());
+            continue;
+        }
+        let resolved =
+            fs::canonicalize(path).unwrap_or_else(|_| match (path.parent(), path.file_name()) {
+                (Some(parent), Some(name)) => fs::canonicalize(parent)
+                    .map(|parent| parent.join(name))
+                    .unwrap_or_else(|_| path.to_path_buf()),
+                _ => path.to_path_buf(),
+            });
+        if !path_is_within(&resolved, workspace_root) {
             return Err(LuxError::Process(format!(
-                "absolute host path arguments are unsupported in shim v1: {}",
-                arg
+                "absolute host path arguments outside the workspace are unsupported in shim v1: {} (workspace={})",
+                arg,
+                workspace_root.display()
             )));
         }
+        mapped.push(map_host_start_dir_to_container(&resolved, workspace_root)?);
     }
-    Ok(())
+    Ok(mapped)
 }
 
 fn handle_shim<R: DockerRunner>(
@@ -7342,7 +11563,7 @@ fn handle_shim<R: DockerRunner>(
                             "shell startup file mutation failed during `{}`",
                             ShimPathAction::Enable.command_name()
                         ),
-                        details: ProcessErrorDetails {
+                        details: Box::new(ProcessErrorDetails {
                             error_code: "shim_path_mutation_failed".to_string(),
                             hint: Some(
                                 "Fix shell startup file permissions and retry `lux shim enable`."
@@ -7351,7 +11572,7 @@ fn handle_shim<R: DockerRunner>(
                             command: Some(ShimPathAction::Enable.command_name().to_string()),
                             raw_stderr: None,
                             partial_outcome: Some(partial_outcome),
-                        },
+                        }),
                     });
                 }
             };
@@ -7360,11 +11581,13 @@ fn handle_shim<R: DockerRunner>(
                 for warning in &warnings {
                     eprintln!("warning: {warning}");
                 }
-                emit_shim_current_session_guidance(
-                    ShimPathAction::Enable,
-                    &policy,
-                    path_phase.state == "no_startup_files",
-                );
+                if !ctx.quiet {
+                    emit_shim_current_session_guidance(
+                        ShimPathAction::Enable,
+                        &policy,
+                        path_phase.state == "no_startup_files",
+                    );
+                }
             }
 
             output(
@@ -7422,7 +11645,7 @@ fn handle_shim<R: DockerRunner>(
                             "shell startup file mutation failed during `{}`",
                             ShimPathAction::Disable.command_name()
                         ),
-                        details: ProcessErrorDetails {
+                        details: Box::new(ProcessErrorDetails {
                             error_code: "shim_path_mutation_failed".to_string(),
                             hint: Some(
                                 "Fix shell startup file permissions and retry `lux shim disable`."
@@ -7431,12 +11654,12 @@ fn handle_shim<R: DockerRunner>(
                             command: Some(ShimPathAction::Disable.command_name().to_string()),
                             raw_stderr: None,
                             partial_outcome: Some(partial_outcome),
-                        },
+                        }),
                     });
                 }
             };
 
-            if !ctx.json {
+            if !ctx.json && !ctx.quiet {
                 emit_shim_current_session_guidance(
                     ShimPathAction::Disable,
                     &policy,
@@ -7495,7 +11718,11 @@ fn handle_shim<R: DockerRunner>(
                 }),
             )
         }
-        ShimCommand::Exec { provider, argv } => {
+        ShimCommand::Exec {
+            provider,
+            timeout_sec,
+            argv,
+        } => {
             let mut passthrough = argv;
             if passthrough
                 .first()
@@ -7504,8 +11731,8 @@ fn handle_shim<R: DockerRunner>(
             {
                 passthrough.remove(0);
             }
-            shim_validate_exec_args(&passthrough)?;
             let cfg = read_config(&ctx.config_path)?;
+            let provider = resolve_provider_name(&cfg, &provider)?.to_string();
             let provider_cfg = provider_from_config(&cfg, &provider)?;
             let policy = resolve_config_policy_paths(&cfg)?;
             let shim_path = shim_path_for_provider(&policy.shims_bin_dir, &provider);
@@ -7521,23 +11748,12 @@ fn handle_shim<R: DockerRunner>(
             let state_root = policy.state_root;
             ensure_runtime_running(ctx)?;
             let run_id = ensure_provider_plane_for_shim(ctx, &provider, runner)?;
-            let mut tui_cmd = provider_cfg.commands.tui.clone();
-            for arg in &passthrough {
-                tui_cmd.push(' ');
-                tui_cmd.push_str(&shell_single_quote(arg));
-            }
-            let runtime =
-                generate_provider_runtime_compose(ctx, &provider, provider_cfg, Some(&tui_cmd))?;
-            for warning in &runtime.warnings {
-                eprintln!("warning: {warning}");
-            }
-            let mut args = compose_base_args(ctx, &cfg, false, &[runtime.override_file.clone()])?;
             let cwd = env::current_dir()?;
-            let active_workspace = load_active_run_state(&state_root)?
-                .filter(|state| state.run_id == run_id)
-                .map(|state| resolve_active_run_workspace_root(&cfg, &state))
-                .transpose()?
-                .unwrap_or_else(|| policy.workspace_root.clone());
+            let active_workspace =
+                match load_active_run_state(&state_root)?.filter(|state| state.run_id == run_id) {
+                    Some(state) => resolve_active_run_workspace_root(&cfg, &state)?,
+                    None => resolve_effective_workspace_root(&cfg, None, Some(&provider))?,
+                };
             let workspace_canon = fs::canonicalize(&active_workspace).unwrap_or(active_workspace);
             let cwd_canon = fs::canonicalize(&cwd).unwrap_or(cwd);
             if !cwd_canon.starts_with(&workspace_canon) {
@@ -7547,44 +11763,488 @@ fn handle_shim<R: DockerRunner>(
                 )));
             }
             let container_workdir = map_host_start_dir_to_container(&cwd_canon, &workspace_canon)?;
-            append_harness_tui_run_args(&mut args, &container_workdir);
-            run_docker_command(
+            let passthrough = shim_map_exec_args(&passthrough, &workspace_canon)?;
+            let mut tui_cmd = provider_cfg.commands.tui.clone();
+            for arg in &passthrough {
+                tui_cmd.push(' ');
+                tui_cmd.push_str(&shell_single_quote(arg));
+            }
+            let runtime =
+                generate_provider_runtime_compose(ctx, &provider, provider_cfg, Some(&tui_cmd))?;
+            for warning in &runtime.warnings {
+                eprintln!("warning: {warning}");
+            }
+            let mut args = compose_base_args(
+                ctx,
+                &cfg,
+                false,
+                std::slice::from_ref(&runtime.override_file),
+                &provider_cfg.compose_profiles,
+            )?;
+            let env_overrides = compose_env_for_run(Some(&run_id), Some(&workspace_canon));
+            let payload = json!({"action":"shim_exec", "provider": provider, "run_id": run_id});
+            let run_idx = args.len();
+            append_harness_tui_run_args(&mut args, &container_workdir, &[]);
+            let Some(timeout_sec) = timeout_sec else {
+                return run_docker_command(ctx, runner, &args, &env_overrides, payload, false);
+            };
+            // A fixed name lets the timeout cleanup target this one-off container only.
+            let container_name = format!(
+                "{}-shim-{}-{}",
+                compose_project_name(ctx, &cfg)?,
+                provider,
+                std::process::id()
+            );
+            args.splice(
+                run_idx + 1..run_idx + 1,
+                ["--name".to_string(), container_name.clone()],
+            );
+            execute_shim_exec_with_timeout(
                 ctx,
                 runner,
                 &args,
-                &compose_env_for_run(Some(&run_id), Some(&workspace_canon)),
-                json!({"action":"shim_exec", "provider": provider, "run_id": run_id}),
-                false,
+                &env_overrides,
+                &container_name,
+                timeout_sec,
+                payload,
             )
         }
     }
 }
 
-fn handle_up<R: DockerRunner>(
+/// Runs the interactive `compose run` for `lux shim exec --timeout-sec`. On expiry the
+/// client is killed and the named run container force-removed: killing the client does
+/// not stop the container, and `compose rm` would only reach it once stopped.
+fn execute_shim_exec_with_timeout<R: DockerRunner>(
     ctx: &Context,
-    provider: Option<String>,
-    collector_only: bool,
-    workspace: Option<String>,
-    pull: Option<String>,
-    wait: bool,
-    timeout_sec: Option<u64>,
     runner: &R,
+    args: &[String],
+    env_overrides: &BTreeMap<String, String>,
+    container_name: &str,
+    timeout_sec: u64,
+    json_payload: serde_json::Value,
 ) -> Result<(), LuxError> {
-    if timeout_sec.is_some() && !wait {
-        return Err(LuxError::Config(
-            "--timeout-sec requires --wait".to_string(),
-        ));
-    }
-    let cfg = read_config(&ctx.config_path)?;
-    let policy = resolve_config_policy_paths(&cfg)?;
+    let command = render_docker_command(args);
+    let envs = docker_env(ctx, env_overrides)?;
+    let cmd_output = runner
+        .run_with_timeout(
+            args,
+            &ctx.bundle_dir,
+            &envs,
+            false,
+            Duration::from_secs(timeout_sec),
+        )
+        .map_err(|err| {
+            let details = docker_spawn_error_details(&err, &command);
+            LuxError::ProcessDetailed {
+                message: format!("failed to run command `{command}`: {err}"),
+                details: Box::new(details),
+            }
+        })?;
+    let Some(cmd_output) = cmd_output else {
+        let cleanup_args = vec![
+            "rm".to_string(),
+            "-f".to_string(),
+            container_name.to_string(),
+        ];
+        let cleanup = runner.run(&cleanup_args, &ctx.bundle_dir, &envs, true);
+        let cleaned_up = matches!(&cleanup, Ok(output) if output.success());
+        return Err(LuxError::ProcessDetailed {
+            message: format!("shim exec did not finish within {timeout_sec}s; killed `{command}`"),
+            details: Box::new(ProcessErrorDetails {
+                error_code: "shim_exec_timeout".to_string(),
+                hint: Some(if cleaned_up {
+                    "Retry with a larger --timeout-sec, or omit it for an unbounded session."
+                        .to_string()
+                } else {
+                    format!(
+                        "Remove the leftover container with `docker rm -f {container_name}`, then retry with a larger --timeout-sec."
+                    )
+                }),
+                command: Some(command),
+                raw_stderr: None,
+                partial_outcome: Some(json!({
+                    "container": container_name,
+                    "cleanup": render_docker_command(&cleanup_args),
+                    "cleaned_up": cleaned_up,
+                })),
+            }),
+        });
+    };
+    if !cmd_output.success() {
+        return Err(docker_command_failure(command, &cmd_output));
+    }
+    output(ctx, json_payload)
+}
+
+/// `compose up` has no `--no-cache`, so `--build --no-cache` first runs an uncached
+/// `compose build` of the same services; the following `up --build` reuses those layers.
+fn compose_no_cache_build_args(base_args: &[String], services: &[&str]) -> Vec<String> {
+    let mut args = base_args.to_vec();
+    args.push("build".to_string());
+    args.push("--no-cache".to_string());
+    args.extend(services.iter().map(|service| service.to_string()));
+    args
+}
+
+/// `compose pull` for the pull phase split out by `--pull-timeout-sec`; `None` under
+/// `--pull never`. Without `--pull`, compose's default `missing` policy applies.
+fn compose_pull_args(
+    base_args: &[String],
+    pull: Option<&str>,
+    services: &[&str],
+) -> Option<Vec<String>> {
+    let policy = pull.unwrap_or("missing");
+    if policy == "never" {
+        return None;
+    }
+    let mut args = base_args.to_vec();
+    args.push("pull".to_string());
+    args.push("--policy".to_string());
+    args.push(policy.to_string());
+    args.extend(services.iter().map(|service| service.to_string()));
+    Some(args)
+}
+
+/// With `--pull-timeout-sec`, pulls `services` as a separate bounded step and returns
+/// `never` as the pull policy for the following `compose up`; otherwise returns `pull`.
+fn pull_images_with_timeout<R: DockerRunner>(
+    ctx: &Context,
+    runner: &R,
+    base_args: &[String],
+    pull: Option<String>,
+    pull_timeout_sec: Option<u64>,
+    services: &[&str],
+    env_overrides: &BTreeMap<String, String>,
+) -> Result<Option<String>, LuxError> {
+    let Some(pull_timeout_sec) = pull_timeout_sec else {
+        return Ok(pull);
+    };
+    if let Some(pull_args) = compose_pull_args(base_args, pull.as_deref(), services) {
+        execute_docker_pull(ctx, runner, &pull_args, env_overrides, pull_timeout_sec)?;
+    }
+    Ok(Some("never".to_string()))
+}
+
+fn warn_if_no_cache_without_build(build: bool, no_cache: bool) {
+    if no_cache && !build {
+        eprintln!("warning: --no-cache has no effect without --build");
+    }
+}
+
+fn push_compose_up_flags(
+    args: &mut Vec<String>,
+    pull: Option<String>,
+    build: bool,
+    wait: bool,
+    timeout_sec: Option<u64>,
+) {
+    if let Some(pull) = pull {
+        args.push("--pull".to_string());
+        args.push(pull);
+    }
+    if build {
+        args.push("--build".to_string());
+    }
+    if wait {
+        args.push("--wait".to_string());
+        if let Some(timeout_sec) = timeout_sec {
+            args.push("--wait-timeout".to_string());
+            args.push(timeout_sec.to_string());
+        }
+    }
+}
+
+#[derive(Default)]
+struct UpOptions {
+    provider: Option<String>,
+    collector_only: bool,
+    workspace: Option<String>,
+    pull: Option<String>,
+    pull_timeout_sec: Option<u64>,
+    build: bool,
+    no_cache: bool,
+    wait: bool,
+    timeout_sec: Option<u64>,
+    idle_timeout_min: Option<u64>,
+    mounts: Vec<String>,
+    foreground: bool,
+}
+
+impl UpOptions {
+    fn validate(&self) -> Result<(), LuxError> {
+        if self.timeout_sec.is_some() && !self.wait {
+            return Err(LuxError::Config(
+                "--timeout-sec requires --wait".to_string(),
+            ));
+        }
+        warn_if_no_cache_without_build(self.build, self.no_cache);
+        validate_idle_timeout_override(self.idle_timeout_min)
+    }
+
+    /// `compose up` for `services`; `pull` is the policy left after any separate pull step.
+    fn compose_up_args(
+        &self,
+        base_args: Vec<String>,
+        pull: Option<String>,
+        services: &[&str],
+    ) -> Vec<String> {
+        let mut args = base_args;
+        args.push("up".to_string());
+        if !self.foreground {
+            args.push("-d".to_string());
+        }
+        push_compose_up_flags(&mut args, pull, self.build, self.wait, self.timeout_sec);
+        args.extend(services.iter().map(|service| service.to_string()));
+        args
+    }
+}
+
+/// Resolves the compose invocation `lux up` would run without calling docker or touching
+/// active run/provider state. The env file and provider override are still (re)generated so the
+/// preview shows their real contents.
+fn handle_up_dry_run(ctx: &Context, options: UpOptions) -> Result<(), LuxError> {
+    options.validate()?;
+    let cfg = read_config(&ctx.config_path)?;
+    let policy = resolve_config_policy_paths(&cfg)?;
+    let run_mounts = parse_run_mounts(&options.mounts, &policy)?;
+    let target = resolve_lifecycle_target(&cfg, options.provider.clone(), options.collector_only)?;
+    let mut warnings: Vec<String> = Vec::new();
+
+    let (provider_name, run_id, run_id_source, workspace_root, runtime_overrides, services) =
+        match target {
+            LifecycleTarget::CollectorOnly => {
+                let workspace_root =
+                    resolve_effective_workspace_root(&cfg, options.workspace.as_deref(), None)?;
+                (
+                    None,
+                    Some(next_free_run_id(&policy.log_root, &run_id_from_now())),
+                    Some("new"),
+                    workspace_root,
+                    Vec::new(),
+                    vec!["collector"],
+                )
+            }
+            LifecycleTarget::Provider(provider_name) => {
+                let provider_cfg = provider_from_config(&cfg, &provider_name)?;
+                let active_run = load_active_run_state(&policy.state_root)?
+                    .filter(|state| run_root(&policy.log_root, &state.run_id).exists());
+                let (run_id, run_id_source, workspace_root) = match active_run {
+                    Some(state) => {
+                        let workspace_root = resolve_active_run_workspace_root(&cfg, &state)?;
+                        if options.mounts.is_empty() && !state.mounts.is_empty() {
+                            warnings.push(format!(
+                                "reusing {} mount(s) recorded on the active run",
+                                state.mounts.len()
+                            ));
+                        }
+                        (Some(state.run_id), Some("active"), workspace_root)
+                    }
+                    None => {
+                        let workspace_root = resolve_effective_workspace_root(
+                            &cfg,
+                            options.workspace.as_deref(),
+                            Some(&provider_name),
+                        )?;
+                        if cfg.collector.auto_start {
+                            (
+                                Some(next_free_run_id(&policy.log_root, &run_id_from_now())),
+                                Some("new"),
+                                workspace_root,
+                            )
+                        } else {
+                            warnings.push(
+                                "no active run found; start collector first with `lux up --collector-only`"
+                                    .to_string(),
+                            );
+                            (None, None, workspace_root)
+                        }
+                    }
+                };
+                let runtime =
+                    generate_provider_runtime_compose(ctx, &provider_name, provider_cfg, None)?;
+                warnings.extend(runtime.warnings);
+                (
+                    Some(provider_name),
+                    run_id,
+                    run_id_source,
+                    workspace_root,
+                    vec![runtime.override_file],
+                    vec!["agent", "harness"],
+                )
+            }
+        };
+
+    let override_file = runtime_overrides.first().cloned();
+    let override_yaml = match override_file.as_ref() {
+        Some(path) => Some(fs::read_to_string(path)?),
+        None => None,
+    };
+    let compose_files = compose_files(ctx, false, &runtime_overrides)?;
+    let profiles = provider_name
+        .as_deref()
+        .and_then(|name| cfg.providers.get(name))
+        .map(|provider| provider.compose_profiles.as_slice())
+        .unwrap_or_default();
+    let base_args = compose_base_args(ctx, &cfg, false, &runtime_overrides, profiles)?;
+    let build_command = (options.build && options.no_cache)
+        .then(|| render_docker_command(&compose_no_cache_build_args(&base_args, &services)));
+    let (pull_command, pull) = match options.pull_timeout_sec {
+        Some(_) => (
+            compose_pull_args(&base_args, options.pull.as_deref(), &services)
+                .map(|args| render_docker_command(&args)),
+            Some("never".to_string()),
+        ),
+        None => (None, options.pull.clone()),
+    };
+    let args = options.compose_up_args(base_args, pull, &services);
+    let project_name = Some(compose_project_name(ctx, &cfg)?).filter(|name| !name.is_empty());
+
+    if ctx.json {
+        return output(
+            ctx,
+            json!({
+                "action": "up",
+                "dry_run": true,
+                "collector_only": provider_name.is_none(),
+                "provider": provider_name,
+                "run_id": run_id,
+                "run_id_source": run_id_source,
+                "workspace_root": workspace_root,
+                "project_name": project_name,
+                "env_file": ctx.env_file,
+                "compose_files": compose_files,
+                "override_file": override_file,
+                "override_yaml": override_yaml,
+                "services": services,
+                "idle_timeout_min": options.idle_timeout_min,
+                "mounts": run_mounts.iter().map(RunMount::spec).collect::<Vec<_>>(),
+                "warnings": warnings,
+                "build_command": build_command,
+                "pull_command": pull_command,
+                "pull_timeout_sec": options.pull_timeout_sec,
+                "command": render_docker_command(&args),
+            }),
+        );
+    }
+
+    println!("dry run: no containers started, no run state written");
+    match provider_name.as_deref() {
+        Some(provider) => println!("target: provider {provider}"),
+        None => println!("target: collector"),
+    }
+    match (run_id.as_deref(), run_id_source) {
+        (Some(run_id), Some(source)) => println!("run_id: {run_id} ({source})"),
+        _ => println!("run_id: none"),
+    }
+    println!("workspace_root: {}", workspace_root.display());
+    if let Some(minutes) = options.idle_timeout_min {
+        println!("idle_timeout_min: {minutes} (run override)");
+    }
+    for mount in &run_mounts {
+        println!("mount: {}", mount.spec());
+    }
+    println!(
+        "project_name: {}",
+        project_name.as_deref().unwrap_or("(compose default)")
+    );
+    println!("env_file: {}", ctx.env_file.display());
+    println!("compose files:");
+    for file in &compose_files {
+        println!("  {}", file.display());
+    }
+    if let (Some(path), Some(body)) = (override_file.as_ref(), override_yaml.as_ref()) {
+        println!("override file: {}", path.display());
+        for line in body.lines() {
+            println!("  {line}");
+        }
+    }
+    for warning in &warnings {
+        println!("warning: {warning}");
+    }
+    if let Some(build_command) = &build_command {
+        println!("build command: {build_command}");
+    }
+    if let (Some(pull_command), Some(seconds)) = (&pull_command, options.pull_timeout_sec) {
+        println!("pull command (timeout {seconds}s): {pull_command}");
+    }
+    println!("command: {}", render_docker_command(&args));
+    Ok(())
+}
+
+/// A `host_state` provider with nothing to mount would start and then fail inside the
+/// container, so refuse up front. Partially missing paths only warn (see
+/// `generate_provider_runtime_compose`).
+fn ensure_host_state_available(provider_name: &str, provider: &Provider) -> Result<(), LuxError> {
+    if provider.auth_mode != AuthMode::HostState {
+        return Ok(());
+    }
+    if env::consts::OS == "macos" && !provider.auth.host_state.keychain_items.is_empty() {
+        return Ok(());
+    }
+    let paths: Vec<PathBuf> = provider
+        .auth
+        .host_state
+        .paths
+        .iter()
+        .map(|configured| PathBuf::from(expand_path(configured)))
+        .collect();
+    if paths.iter().any(|path| path.exists()) {
+        return Ok(());
+    }
+    let mut message = format!(
+        "provider '{provider_name}': auth_mode=host_state but none of its host-state paths exist"
+    );
+    if paths.is_empty() {
+        message.push_str(&format!(
+            "\n  (providers.{provider_name}.auth.host_state.paths is empty)"
+        ));
+    }
+    for path in &paths {
+        message.push_str(&format!("\n  missing: {}", path.display()));
+    }
+    message.push_str(&format!(
+        "\nhint: run `{provider_name}` on the host and complete its login flow first, or switch providers.{provider_name}.auth_mode to api_key"
+    ));
+    Err(LuxError::Config(message))
+}
+
+fn handle_up<R: DockerRunner>(
+    ctx: &Context,
+    options: UpOptions,
+    runner: &R,
+) -> Result<(), LuxError> {
+    options.validate()?;
+    let cfg = read_config(&ctx.config_path)?;
+    let policy = resolve_config_policy_paths(&cfg)?;
+    let run_mounts = parse_run_mounts(&options.mounts, &policy)?;
     let log_root = policy.log_root;
     let state_root = policy.state_root;
-    let target = resolve_lifecycle_target(provider, collector_only)?;
+    let target = resolve_lifecycle_target(&cfg, options.provider.clone(), options.collector_only)?;
 
     match target {
         LifecycleTarget::CollectorOnly => {
-            let effective_workspace = resolve_effective_workspace_root(&cfg, workspace.as_deref())?;
+            let effective_workspace =
+                resolve_effective_workspace_root(&cfg, options.workspace.as_deref(), None)?;
             let preflight_env = compose_env_for_run(None, Some(&effective_workspace));
+            let project_name = compose_project_name(ctx, &cfg)?;
+            if let Some(active) = load_active_run_state(&state_root)? {
+                // Another project's stopped collector leaves state behind that this run
+                // may replace; a running one still owns the trusted root.
+                if let Some(recorded) = active
+                    .project_name
+                    .as_deref()
+                    .filter(|recorded| *recorded != project_name)
+                {
+                    let other = Context {
+                        project_name: Some(recorded.to_string()),
+                        ..ctx.clone()
+                    };
+                    if collector_is_running(&other, runner, &cfg, false, &preflight_env)? {
+                        ensure_active_run_project(&active, &project_name)?;
+                    }
+                }
+            }
             if provider_plane_is_running(ctx, runner, &cfg, false, &preflight_env)? {
                 return Err(LuxError::Process(
                     "provider plane is still running; stop it before starting a new collector run"
@@ -7596,39 +12256,67 @@ fn handle_up<R: DockerRunner>(
                     "collector is already running".to_string(),
                 ));
             }
-            let run_id = run_id_from_now();
-            fs::create_dir_all(run_root(&log_root, &run_id))?;
-            write_active_run_state(&state_root, &run_id, &effective_workspace)?;
-
-            let mut args = compose_base_args(ctx, &cfg, false, &[])?;
-            args.push("up".to_string());
-            args.push("-d".to_string());
-            if let Some(pull) = pull {
-                args.push("--pull".to_string());
-                args.push(pull);
-            }
-            if wait {
-                args.push("--wait".to_string());
-                if let Some(timeout_sec) = timeout_sec {
-                    args.push("--wait-timeout".to_string());
-                    args.push(timeout_sec.to_string());
+            let run_id = claim_run_id(&log_root, &run_id_from_now())?;
+            write_active_run_state(
+                &state_root,
+                &run_id,
+                &effective_workspace,
+                &run_mounts,
+                &project_name,
+            )?;
+            if let Some(minutes) = options.idle_timeout_min {
+                if let Some(mut active_run) = load_active_run_state(&state_root)? {
+                    active_run.idle_timeout_min = Some(minutes);
+                    store_active_run_state(&state_root, &active_run)?;
                 }
             }
-            args.push("collector".to_string());
+
+            let base_args = compose_base_args(ctx, &cfg, false, &[], &[])?;
             let env_overrides = compose_env_for_run(Some(&run_id), Some(&effective_workspace));
-            let result = run_docker_command(
-                ctx,
-                runner,
-                &args,
-                &env_overrides,
-                json!({
-                    "action": "up",
-                    "collector_only": true,
-                    "run_id": run_id,
-                    "workspace_root": effective_workspace,
-                }),
-                true,
-            );
+            let result = if options.build && options.no_cache {
+                execute_docker(
+                    ctx,
+                    runner,
+                    &compose_no_cache_build_args(&base_args, &["collector"]),
+                    &env_overrides,
+                    true,
+                    true,
+                )
+                .map(|_| ())
+            } else {
+                Ok(())
+            };
+            let result = result
+                .and_then(|_| {
+                    pull_images_with_timeout(
+                        ctx,
+                        runner,
+                        &base_args,
+                        options.pull.clone(),
+                        options.pull_timeout_sec,
+                        &["collector"],
+                        &env_overrides,
+                    )
+                })
+                .and_then(|pull| {
+                    let args = options.compose_up_args(base_args.clone(), pull, &["collector"]);
+                    if options.foreground {
+                        return execute_docker_foreground(ctx, runner, &args, &env_overrides);
+                    }
+                    run_docker_command(
+                        ctx,
+                        runner,
+                        &args,
+                        &env_overrides,
+                        json!({
+                            "action": "up",
+                            "collector_only": true,
+                            "run_id": run_id,
+                            "workspace_root": effective_workspace,
+                        }),
+                        true,
+                    )
+                });
             if result.is_err() {
                 let _ = clear_active_run_state(&state_root);
             }
@@ -7636,6 +12324,7 @@ fn handle_up<R: DockerRunner>(
         }
         LifecycleTarget::Provider(provider_name) => {
             let provider_cfg = provider_from_config(&cfg, &provider_name)?;
+            ensure_host_state_available(&provider_name, provider_cfg)?;
             if cfg.collector.auto_start {
                 let collector_running =
                     collector_is_running(ctx, runner, &cfg, false, &BTreeMap::new())?;
@@ -7643,14 +12332,22 @@ fn handle_up<R: DockerRunner>(
                     .map(|state| run_root(&log_root, &state.run_id).exists())
                     .unwrap_or(false);
                 if !collector_running || !active_run_valid {
+                    // The auto-started run takes this provider's workspace so the
+                    // match check below holds.
                     handle_up(
                         ctx,
-                        None,
-                        true,
-                        None,
-                        Some("missing".to_string()),
-                        true,
-                        None,
+                        UpOptions {
+                            collector_only: true,
+                            workspace: options
+                                .workspace
+                                .clone()
+                                .or_else(|| provider_cfg.workspace_root.clone()),
+                            pull: Some("missing".to_string()),
+                            pull_timeout_sec: options.pull_timeout_sec,
+                            build: options.build,
+                            wait: true,
+                            ..Default::default()
+                        },
                         runner,
                     )?;
                 }
@@ -7661,13 +12358,26 @@ fn handle_up<R: DockerRunner>(
                         .to_string(),
                 )
             })?;
+            ensure_active_run_project(&active_run, &compose_project_name(ctx, &cfg)?)?;
             let active_workspace = resolve_active_run_workspace_root(&cfg, &active_run)?;
-            if let Some(raw_workspace) = workspace.as_deref() {
-                let requested_workspace =
-                    resolve_effective_workspace_root(&cfg, Some(raw_workspace))?;
+            let requested_field = if options.workspace.is_some() {
+                Some("--workspace".to_string())
+            } else {
+                provider_cfg
+                    .workspace_root
+                    .as_ref()
+                    .map(|_| format!("providers.{provider_name}.workspace_root"))
+            };
+            if let Some(field) = requested_field {
+                let requested_workspace = resolve_effective_workspace_root(
+                    &cfg,
+                    options.workspace.as_deref(),
+                    Some(&provider_name),
+                )?;
                 if requested_workspace != active_workspace {
                     return Err(LuxError::Config(format!(
-                        "--workspace must match active run workspace (active={}, requested={})",
+                        "{field} must match active run workspace (active={}, requested={}); \
+restart the collector with `lux up --collector-only` to switch workspaces",
                         active_workspace.display(),
                         requested_workspace.display()
                     )));
@@ -7702,34 +12412,82 @@ fn handle_up<R: DockerRunner>(
                 )));
             }
 
+            let mut active_run = active_run;
+            if !run_mounts.is_empty() {
+                active_run.mounts = run_mounts;
+                store_active_run_state(&state_root, &active_run)?;
+            }
             let runtime =
                 generate_provider_runtime_compose(ctx, &provider_name, provider_cfg, None)?;
             for warning in &runtime.warnings {
                 eprintln!("warning: {warning}");
             }
 
-            let mut args = compose_base_args(ctx, &cfg, false, &[runtime.override_file.clone()])?;
-            args.push("up".to_string());
-            args.push("-d".to_string());
-            if let Some(pull) = pull {
-                args.push("--pull".to_string());
-                args.push(pull);
-            }
-            if wait {
-                args.push("--wait".to_string());
-                if let Some(timeout_sec) = timeout_sec {
-                    args.push("--wait-timeout".to_string());
-                    args.push(timeout_sec.to_string());
-                }
+            let base_args = compose_base_args(
+                ctx,
+                &cfg,
+                false,
+                std::slice::from_ref(&runtime.override_file),
+                &provider_cfg.compose_profiles,
+            )?;
+            if options.build && options.no_cache {
+                execute_docker(
+                    ctx,
+                    runner,
+                    &compose_no_cache_build_args(&base_args, &["agent", "harness"]),
+                    &run_env,
+                    true,
+                    true,
+                )?;
             }
-            args.push("agent".to_string());
-            args.push("harness".to_string());
-
-            let result = run_docker_command(
+            let pull = pull_images_with_timeout(
                 ctx,
                 runner,
-                &args,
+                &base_args,
+                options.pull.clone(),
+                options.pull_timeout_sec,
+                &["agent", "harness"],
                 &run_env,
+            )?;
+            let args = options.compose_up_args(base_args, pull, &["agent", "harness"]);
+            if !options.foreground {
+                execute_docker(ctx, runner, &args, &run_env, true, true)?;
+            }
+            // Containers are up either way, so record them before the readiness poll
+            // to keep `lux down` working when the harness never answers. In the foreground
+            // this precedes attaching so status and rotation see the plane meanwhile.
+            write_active_provider_state(
+                &state_root,
+                &provider_name,
+                &provider_cfg.auth_mode,
+                &active_run.run_id,
+            )?;
+            if let Some(minutes) = options.idle_timeout_min {
+                active_run.idle_timeout_min = Some(minutes);
+                store_active_run_state(&state_root, &active_run)?;
+            }
+            if options.foreground {
+                // An interrupt is a normal detach; any other failure means the plane never
+                // came up, so the recorded provider must not outlive it.
+                let result = execute_docker_foreground(ctx, runner, &args, &run_env);
+                if result.is_err() {
+                    let _ = clear_active_provider_state(&state_root);
+                }
+                return result;
+            }
+            let (effective_idle_timeout, _) = effective_idle_timeout_min(&cfg, &active_run);
+            if options.wait {
+                wait_for_harness_ready(
+                    &cfg,
+                    Duration::from_secs(
+                        options
+                            .timeout_sec
+                            .unwrap_or(HARNESS_READY_DEFAULT_TIMEOUT_SEC),
+                    ),
+                )?;
+            }
+            output(
+                ctx,
                 json!({
                     "action": "up",
                     "collector_only": false,
@@ -7737,33 +12495,100 @@ fn handle_up<R: DockerRunner>(
                     "run_id": active_run.run_id,
                     "auth_mode": provider_cfg.auth_mode.as_str(),
                     "workspace_root": active_workspace,
+                    "idle_timeout_min": effective_idle_timeout,
+                    "resource_limits": runtime.resource_limits,
+                    "image": runtime.image,
                 }),
-                true,
-            );
-            if result.is_ok() {
-                write_active_provider_state(
-                    &state_root,
-                    &provider_name,
-                    &provider_cfg.auth_mode,
-                    &active_run.run_id,
-                )?;
-            }
-            result
+            )
         }
     }
 }
 
+/// Runs `compose up` attached for `up --foreground`. Ctrl-C reaches compose through the
+/// terminal's process group; lux only records it and waits, so compose can stop the services
+/// and the interrupted session is not reported as a failure.
+fn execute_docker_foreground<R: DockerRunner>(
+    ctx: &Context,
+    runner: &R,
+    args: &[String],
+    env_overrides: &BTreeMap<String, String>,
+) -> Result<(), LuxError> {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    #[cfg(unix)]
+    let hook = signal_hook::flag::register(signal_hook::consts::SIGINT, Arc::clone(&interrupted))?;
+    let result = execute_docker(ctx, runner, args, env_overrides, false, false);
+    #[cfg(unix)]
+    signal_hook::low_level::unregister(hook);
+    match result {
+        Err(_) if interrupted.load(Ordering::SeqCst) => Ok(()),
+        other => other.map(|_| ()),
+    }
+}
+
+fn harness_health_url(cfg: &Config) -> String {
+    format!(
+        "http://{}:{}{}",
+        cfg.harness.api_host, cfg.harness.api_port, cfg.harness.health_path
+    )
+}
+
+/// Polls `harness.health_path` until it answers 200 or `timeout` elapses. Compose `--wait`
+/// only covers container health, not whether the HTTP API is serving yet.
+fn wait_for_harness_ready(cfg: &Config, timeout: Duration) -> Result<(), LuxError> {
+    let token = resolve_token(cfg)?;
+    let url = harness_health_url(cfg);
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()?;
+    let deadline = Instant::now() + timeout;
+    let last_error = loop {
+        let last_error = match client.get(&url).header("X-Harness-Token", &token).send() {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => format!("HTTP {}", response.status()),
+            Err(err) => err.to_string(),
+        };
+        if Instant::now() >= deadline {
+            break last_error;
+        }
+        thread::sleep(Duration::from_millis(HARNESS_READY_POLL_INTERVAL_MS));
+    };
+    Err(LuxError::ProcessDetailed {
+        message: format!(
+            "harness API at {url} was not ready within {}s: {last_error}",
+            timeout.as_secs()
+        ),
+        details: Box::new(ProcessErrorDetails {
+            error_code: "harness_not_ready".to_string(),
+            hint: Some(
+                "Check harness logs with `docker compose logs harness`, confirm harness.api_port, \
+                 harness.health_path and harness.api_token, or raise --timeout-sec."
+                    .to_string(),
+            ),
+            command: None,
+            raw_stderr: None,
+            partial_outcome: None,
+        }),
+    })
+}
+
 fn handle_down<R: DockerRunner>(
     ctx: &Context,
     provider: Option<String>,
     collector_only: bool,
+    all: bool,
     runner: &R,
 ) -> Result<(), LuxError> {
     let cfg = read_config(&ctx.config_path)?;
     let policy = resolve_config_policy_paths(&cfg)?;
     let state_root = policy.state_root;
-    let target = resolve_lifecycle_target(provider, collector_only)?;
+    if all {
+        return handle_down_all(ctx, &cfg, &state_root, &policy.runtime_root, runner);
+    }
+    let target = resolve_lifecycle_target(&cfg, provider, collector_only)?;
     let active_run = load_active_run_state(&state_root)?;
+    if let Some(active) = &active_run {
+        ensure_active_run_project(active, &compose_project_name(ctx, &cfg)?)?;
+    }
     let run_id = active_run.as_ref().map(|state| state.run_id.clone());
     let workspace_root = active_run
         .as_ref()
@@ -7773,7 +12598,7 @@ fn handle_down<R: DockerRunner>(
 
     match target {
         LifecycleTarget::CollectorOnly => {
-            let mut args = compose_base_args(ctx, &cfg, false, &[])?;
+            let mut args = compose_base_args(ctx, &cfg, false, &[], &[])?;
             args.push("stop".to_string());
             args.push("collector".to_string());
             run_docker_command(
@@ -7786,7 +12611,7 @@ fn handle_down<R: DockerRunner>(
             )
         }
         LifecycleTarget::Provider(provider_name) => {
-            let _provider_cfg = provider_from_config(&cfg, &provider_name)?;
+            let provider_cfg = provider_from_config(&cfg, &provider_name)?;
             if let Some(active_provider) = load_active_provider_state(&state_root)? {
                 if active_provider.provider != provider_name {
                     return Err(provider_mismatch_error(
@@ -7795,10 +12620,19 @@ fn handle_down<R: DockerRunner>(
                     ));
                 }
             }
-            let mut args = compose_base_args(ctx, &cfg, false, &[])?;
+            let profile_services = compose_profile_services(
+                ctx,
+                runner,
+                &cfg,
+                &provider_cfg.compose_profiles,
+                &env_overrides,
+            )?;
+            let mut args =
+                compose_base_args(ctx, &cfg, false, &[], &provider_cfg.compose_profiles)?;
             args.push("stop".to_string());
             args.push("agent".to_string());
             args.push("harness".to_string());
+            args.extend(profile_services);
             let result = run_docker_command(
                 ctx,
                 runner,
@@ -7809,20 +12643,123 @@ fn handle_down<R: DockerRunner>(
             );
             if result.is_ok() {
                 clear_active_provider_state(&state_root)?;
+                clear_provider_keychain_material(&provider_keychain_dir(
+                    &policy.runtime_root,
+                    &provider_name,
+                ))?;
             }
             result
         }
     }
 }
 
+/// `lux down --all`: stops every running service of the compose project (including the UI)
+/// and clears active run/provider state. The host-side runtime control plane is left alone;
+/// `lux runtime down` owns that.
+fn handle_down_all<R: DockerRunner>(
+    ctx: &Context,
+    cfg: &Config,
+    state_root: &Path,
+    runtime_root: &Path,
+    runner: &R,
+) -> Result<(), LuxError> {
+    let active_run = load_active_run_state(state_root)?;
+    let run_id = active_run.as_ref().map(|state| state.run_id.clone());
+    let workspace_root = active_run
+        .as_ref()
+        .map(|state| resolve_active_run_workspace_root(cfg, state))
+        .transpose()?;
+    let env_overrides = compose_env_for_run(run_id.as_deref(), workspace_root.as_deref());
+    let active_provider = load_active_provider_state(state_root)?;
+
+    let mut ps_args = compose_base_args(ctx, cfg, true, &[], &[])?;
+    ps_args.push("ps".to_string());
+    ps_args.push("--format".to_string());
+    ps_args.push("json".to_string());
+    let ps_output = execute_docker(ctx, runner, &ps_args, &env_overrides, true, false)?;
+    let mut stopped: Vec<String> =
+        parse_compose_service_statuses(&String::from_utf8_lossy(&ps_output.stdout))
+            .into_iter()
+            .filter(|service| service.state == "running" || service.state == "restarting")
+            .map(|service| service.name)
+            .collect();
+    stopped.sort();
+    stopped.dedup();
+
+    if !stopped.is_empty() {
+        let mut args = compose_base_args(ctx, cfg, true, &[], &[])?;
+        args.push("stop".to_string());
+        args.extend(stopped.iter().cloned());
+        execute_docker(ctx, runner, &args, &env_overrides, true, true)?;
+    }
+
+    // Labelled containers of this project still running after `compose stop` are ones the
+    // compose files no longer name (e.g. a service renamed in an override); stop them by id.
+    let project_name = compose_project_name(ctx, cfg)?;
+    let leftovers: Vec<ManagedContainer> = if project_name.is_empty() {
+        Vec::new()
+    } else {
+        let filters = [
+            format!("label={COMPOSE_PROJECT_LABEL}={project_name}"),
+            "status=running".to_string(),
+        ];
+        list_managed_containers(ctx, runner, &filters)?
+            .into_iter()
+            .filter(|container| {
+                container.project.as_deref() == Some(project_name.as_str())
+                    && container.status.state == "running"
+            })
+            .collect()
+    };
+    if !leftovers.is_empty() {
+        let mut args = vec!["stop".to_string()];
+        args.extend(leftovers.iter().map(|container| container.id.clone()));
+        execute_docker(ctx, runner, &args, &env_overrides, true, true)?;
+        stopped.extend(leftovers.into_iter().map(|container| container.status.name));
+    }
+
+    clear_active_provider_state(state_root)?;
+    if let Some(active_provider) = active_provider.as_ref() {
+        clear_provider_keychain_material(&provider_keychain_dir(
+            runtime_root,
+            &active_provider.provider,
+        ))?;
+    }
+    clear_active_run_state(state_root)?;
+
+    if ctx.json {
+        return output(
+            ctx,
+            json!({
+                "action": "down",
+                "all": true,
+                "run_id": run_id,
+                "provider": active_provider.map(|state| state.provider),
+                "stopped": stopped,
+            }),
+        );
+    }
+    if ctx.quiet {
+        return Ok(());
+    }
+    if stopped.is_empty() {
+        println!("No running lux services.");
+    } else {
+        println!("Stopped: {}", stopped.join(", "));
+    }
+    Ok(())
+}
+
 fn handle_status<R: DockerRunner>(
     ctx: &Context,
     provider: Option<String>,
     collector_only: bool,
+    raw: bool,
     runner: &R,
 ) -> Result<(), LuxError> {
     let cfg = read_config(&ctx.config_path)?;
     let policy = resolve_config_policy_paths(&cfg)?;
+    warn_if_trusted_root_volatile(&policy.trusted_root);
     let state_root = policy.state_root;
     let active_run = load_active_run_state(&state_root)?;
     let run_id = active_run.as_ref().map(|state| state.run_id.clone());
@@ -7831,10 +12768,12 @@ fn handle_status<R: DockerRunner>(
         .map(|state| resolve_active_run_workspace_root(&cfg, state))
         .transpose()?;
     let env_overrides = compose_env_for_run(run_id.as_deref(), workspace_root.as_deref());
-    let target = resolve_lifecycle_target(provider, collector_only)?;
+    let target = resolve_lifecycle_target(&cfg, provider, collector_only)?;
 
-    let mut args = compose_base_args(ctx, &cfg, false, &[])?;
+    // `--all` keeps stopped services in the listing as `exited`/`created`.
+    let mut args = compose_base_args(ctx, &cfg, false, &[], &[])?;
     args.push("ps".to_string());
+    args.push("--all".to_string());
     args.push("--format".to_string());
     args.push("json".to_string());
     match target {
@@ -7858,38 +12797,198 @@ fn handle_status<R: DockerRunner>(
 
     let cmd_output = execute_docker(ctx, runner, &args, &env_overrides, true, false)?;
     let text = String::from_utf8_lossy(&cmd_output.stdout);
-    let rows = parse_compose_ps_output(&text);
-    if ctx.json {
-        let payload = JsonResult {
-            ok: true,
-            result: Some(rows),
-            error: None,
-            error_details: None,
-        };
-        print_json(&payload)?;
-        return Ok(());
+    let mut services = parse_compose_service_statuses(&text);
+    // Label discovery is best-effort: status still reports the project if it fails.
+    let project_name = compose_project_name(ctx, &cfg)?;
+    if !project_name.is_empty() {
+        let orphans = list_managed_containers(ctx, runner, &[]).unwrap_or_default();
+        services.extend(orphans.into_iter().filter_map(|container| {
+            let project = container.project.unwrap_or_default();
+            (project != project_name).then_some(ServiceStatus {
+                orphaned_project: Some(project),
+                ..container.status
+            })
+        }));
     }
-    if rows.as_array().map(|a| a.is_empty()).unwrap_or(true) {
-        println!("No containers running.");
+    print_service_statuses(ctx, services, raw.then_some(text.as_ref()))
+}
+
+fn run_job_stdout_path(log_root: &Path, run_id: &str, job_id: &str) -> PathBuf {
+    run_root(log_root, run_id)
+        .join("harness")
+        .join("jobs")
+        .join(job_id)
+        .join("stdout.log")
+}
+
+/// Timeout precedence for `lux run`: `--timeout-sec`, then
+/// `providers.<name>.commands.default_timeout_sec`, otherwise unbounded.
+fn resolve_run_timeout_sec(flag: Option<u64>, provider: &Provider) -> Option<u64> {
+    flag.or(provider.commands.default_timeout_sec)
+}
+
+fn run_failed_error(job_id: &str, job: &serde_json::Value, result: serde_json::Value) -> LuxError {
+    let reason = match (
+        job.get("exit_code").and_then(|v| v.as_i64()),
+        job.get("error").and_then(|v| v.as_str()),
+    ) {
+        (Some(code), _) => format!("exit code {code}"),
+        (None, Some(error)) => error.to_string(),
+        (None, None) => "no exit code".to_string(),
+    };
+    LuxError::ProcessDetailed {
+        message: format!("run job {job_id} failed ({reason})"),
+        details: Box::new(ProcessErrorDetails {
+            error_code: "run_failed".to_string(),
+            hint: Some(format!(
+                "Inspect the job output with `lux jobs logs {job_id}`."
+            )),
+            command: None,
+            raw_stderr: None,
+            partial_outcome: Some(result),
+        }),
+    }
+}
+
+fn run_timeout_error(
+    run_id: &str,
+    job_id: &str,
+    stdout_path: &Path,
+    timeout_sec: Option<u64>,
+) -> LuxError {
+    let captured = fs::read(stdout_path)
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+        .unwrap_or_default();
+    let limit = timeout_sec
+        .map(|value| format!("{value}s"))
+        .unwrap_or_else(|| {
+            "the harness limit (neither --timeout-sec nor providers.<name>.commands.default_timeout_sec is set)"
+                .to_string()
+        });
+    LuxError::ProcessDetailed {
+        message: format!("run job {job_id} timed out after {limit}"),
+        details: Box::new(ProcessErrorDetails {
+            error_code: "run_timeout".to_string(),
+            hint: Some(
+                "Timeout precedence is --timeout-sec, then providers.<name>.commands.default_timeout_sec; raise one or inspect the job with `lux jobs get <id>`."
+                    .to_string(),
+            ),
+            command: None,
+            raw_stderr: None,
+            partial_outcome: Some(json!({
+                "run_id": run_id,
+                "job_id": job_id,
+                "stdout_path": stdout_path,
+                "stdout": captured,
+            })),
+        }),
+    }
+}
+
+/// The wait gave up but the job itself has no timeout and keeps running in the harness.
+fn run_wait_timeout_error(
+    run_id: &str,
+    job_id: &str,
+    stdout_path: &Path,
+    wait_sec: u64,
+) -> LuxError {
+    let captured = fs::read(stdout_path)
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+        .unwrap_or_default();
+    LuxError::ProcessDetailed {
+        message: format!(
+            "stopped waiting for run job {job_id} after {wait_sec}s; the job is still running"
+        ),
+        details: Box::new(ProcessErrorDetails {
+            error_code: "run_wait_timeout".to_string(),
+            hint: Some(format!(
+                "Check on it with `lux jobs get {job_id}` or `lux jobs logs {job_id} --follow`, or bound the job with --timeout-sec or providers.<name>.commands.default_timeout_sec."
+            )),
+            command: None,
+            raw_stderr: None,
+            partial_outcome: Some(json!({
+                "run_id": run_id,
+                "job_id": job_id,
+                "stdout_path": stdout_path,
+                "stdout": captured,
+            })),
+        }),
+    }
+}
+
+fn resolve_run_prompt(
+    prompt: Option<String>,
+    input_file: Option<&str>,
+) -> Result<String, LuxError> {
+    let input_file = match (prompt, input_file) {
+        (Some(prompt), None) => return Ok(prompt),
+        (None, Some(input_file)) => input_file,
+        _ => {
+            return Err(LuxError::Config(
+                "provide exactly one of a positional prompt or --input-file".to_string(),
+            ))
+        }
+    };
+    let mut content = if input_file == "-" {
+        let mut buffer = String::new();
+        io::stdin()
+            .read_to_string(&mut buffer)
+            .map_err(|err| LuxError::Process(format!("failed to read prompt from stdin: {err}")))?;
+        buffer
     } else {
-        println!("{}", text.trim());
+        let path = PathBuf::from(expand_path(input_file));
+        fs::read_to_string(&path).map_err(|err| {
+            LuxError::Config(format!(
+                "failed to read --input-file {}: {}",
+                path.display(),
+                err
+            ))
+        })?
+    };
+    if content.ends_with('\n') {
+        content.pop();
+        if content.ends_with('\r') {
+            content.pop();
+        }
     }
-    Ok(())
+    if content.trim().is_empty() {
+        return Err(LuxError::Config(format!(
+            "--input-file {input_file} contains an empty prompt"
+        )));
+    }
+    Ok(content)
 }
 
-fn handle_run(
-    ctx: &Context,
+struct RunOptions {
     provider: String,
     prompt: String,
     capture_input: Option<bool>,
     start_dir: Option<String>,
     timeout_sec: Option<u64>,
-    env_list: Vec<String>,
-) -> Result<(), LuxError> {
+    env: Vec<String>,
+    run_env_file: Option<String>,
+    output_file: Option<String>,
+}
+
+fn handle_run(ctx: &Context, options: RunOptions) -> Result<(), LuxError> {
+    let RunOptions {
+        provider,
+        prompt,
+        capture_input,
+        start_dir,
+        timeout_sec,
+        env: env_list,
+        run_env_file,
+        output_file,
+    } = options;
+    let env_map = resolve_run_env(&env_list, run_env_file.as_deref())?;
     let cfg = read_config(&ctx.config_path)?;
-    let _provider_cfg = provider_from_config(&cfg, &provider)?;
+    let provider = resolve_provider_name(&cfg, &provider)?.to_string();
+    let provider_cfg = provider_from_config(&cfg, &provider)?;
+    let timeout_sec = resolve_run_timeout_sec(timeout_sec, provider_cfg);
     let policy = resolve_config_policy_paths(&cfg)?;
     let state_root = policy.state_root;
+    let log_root = policy.log_root;
     let active_provider = load_active_provider_state(&state_root)?.ok_or_else(|| {
         LuxError::Process(
             "no active provider plane found; start one with `lux up --provider <name>`".to_string(),
@@ -7916,29 +13015,22 @@ fn handle_run(
     let workspace_root = resolve_active_run_workspace_root(&cfg, &active_run)?;
     let host_start_dir = resolve_host_start_dir(&cfg, &workspace_root, start_dir.as_deref())?;
     let container_start_dir = map_host_start_dir_to_container(&host_start_dir, &workspace_root)?;
+    let output_file = output_file.map(|raw| PathBuf::from(expand_path(&raw)));
 
     let token = resolve_token(&cfg)?;
-    let mut env_map = BTreeMap::new();
-    for entry in env_list {
-        if let Some((key, value)) = entry.split_once('=') {
-            env_map.insert(key.to_string(), value.to_string());
-        }
-    }
+    let capture_input = capture_input.unwrap_or(true);
     let payload = json!({
         "prompt": prompt,
-        "capture_input": capture_input.unwrap_or(true),
+        "capture_input": capture_input,
         "cwd": container_start_dir,
         "timeout_sec": timeout_sec,
         "env": env_map,
     });
-    let url = format!(
-        "http://{}:{}/run",
-        cfg.harness.api_host, cfg.harness.api_port
-    );
+    let base_url = format!("http://{}:{}", cfg.harness.api_host, cfg.harness.api_port);
     let client = reqwest::blocking::Client::new();
     let response = client
-        .post(&url)
-        .header("X-Harness-Token", token)
+        .post(format!("{base_url}/run"))
+        .header("X-Harness-Token", &token)
         .json(&payload)
         .send()?;
     let status = response.status();
@@ -7949,18 +13041,117 @@ fn handle_run(
             status, body
         )));
     }
+    let submitted: serde_json::Value = serde_json::from_str(&body).unwrap_or(json!({"raw": body}));
+    let job_id = submitted
+        .get("job_id")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| {
+            LuxError::Process(format!(
+                "run failed: harness response is missing job_id: {}",
+                body
+            ))
+        })?;
+    let stdout_path = run_job_stdout_path(&log_root, &active_run.run_id, &job_id);
+
+    // The harness enforces timeout_sec itself; the grace window only covers
+    // polling latency so a wedged harness still surfaces as a timeout. Without
+    // a timeout the job may run indefinitely; a direct wait follows it (Ctrl-C
+    // stops waiting but leaves the job running), while a wait forwarded through
+    // the runtime daemon gives up after RUN_PROXIED_WAIT_SEC.
+    let deadline = timeout_sec
+        .map(|value| Instant::now() + Duration::from_secs(value + RUN_TIMEOUT_GRACE_SEC));
+    let wait_deadline = (timeout_sec.is_none() && runtime_bypass_enabled())
+        .then(|| Instant::now() + Duration::from_secs(RUN_PROXIED_WAIT_SEC));
+    let job = loop {
+        let response = client
+            .get(format!("{base_url}/jobs/{job_id}"))
+            .header("X-Harness-Token", &token)
+            .send()?;
+        let status = response.status();
+        let body = response.text()?;
+        if !status.is_success() {
+            return Err(LuxError::Process(format!(
+                "run status poll failed for {}: HTTP {}: {}",
+                job_id, status, body
+            )));
+        }
+        let job: serde_json::Value = serde_json::from_str(&body).unwrap_or(json!({"raw": body}));
+        let job_status = job.get("status").and_then(|v| v.as_str()).unwrap_or("");
+        if job_status == "complete" || job_status == "failed" {
+            break job;
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(run_timeout_error(
+                &active_run.run_id,
+                &job_id,
+                &stdout_path,
+                timeout_sec,
+            ));
+        }
+        if wait_deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(run_wait_timeout_error(
+                &active_run.run_id,
+                &job_id,
+                &stdout_path,
+                RUN_PROXIED_WAIT_SEC,
+            ));
+        }
+        thread::sleep(Duration::from_millis(RUN_JOB_POLL_INTERVAL_MS));
+    };
+    if job.get("error").and_then(|v| v.as_str()) == Some("timeout") {
+        return Err(run_timeout_error(
+            &active_run.run_id,
+            &job_id,
+            &stdout_path,
+            timeout_sec,
+        ));
+    }
+
+    let stdout = fs::read(&stdout_path).unwrap_or_default();
+    if let Some(path) = output_file.as_ref() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, &stdout)?;
+    }
+    let result = json!({
+        "run_id": active_run.run_id,
+        "job_id": job_id,
+        "status": job.get("status").cloned().unwrap_or(serde_json::Value::Null),
+        "status_code": job.get("exit_code").cloned().unwrap_or(serde_json::Value::Null),
+        "stdout_path": stdout_path,
+        "output_file": output_file,
+        "capture_input": capture_input,
+        "timeout_sec": timeout_sec,
+        "start_dir": host_start_dir,
+        "env_count": env_map.len(),
+    });
+    if job.get("status").and_then(|v| v.as_str()) == Some("failed") {
+        // Text mode still shows what the job printed before it failed.
+        if !ctx.json && output_file.is_none() {
+            print!("{}", String::from_utf8_lossy(&stdout));
+        }
+        return Err(run_failed_error(&job_id, &job, result));
+    }
     if ctx.json {
-        let payload: serde_json::Value =
-            serde_json::from_str(&body).unwrap_or(json!({"raw": body}));
         let wrapper = JsonResult {
             ok: true,
-            result: Some(payload),
+            result: Some(result),
             error: None,
             error_details: None,
         };
-        print_json(&wrapper)?;
+        print_json(ctx, &wrapper)?;
+    } else if let Some(path) = output_file.as_ref() {
+        if !ctx.quiet {
+            println!(
+                "job {} finished; stdout written to {}",
+                job_id,
+                path.display()
+            );
+        }
     } else {
-        println!("{}", body.trim());
+        print!("{}", String::from_utf8_lossy(&stdout));
     }
     Ok(())
 }
@@ -7969,9 +13160,12 @@ fn handle_tui<R: DockerRunner>(
     ctx: &Context,
     provider: String,
     start_dir: Option<String>,
+    env_list: &[String],
     runner: &R,
 ) -> Result<(), LuxError> {
+    let session_env = parse_env_entries(env_list)?;
     let cfg = read_config(&ctx.config_path)?;
+    let provider = resolve_provider_name(&cfg, &provider)?.to_string();
     let provider_cfg = provider_from_config(&cfg, &provider)?;
     let policy = resolve_config_policy_paths(&cfg)?;
     let state_root = policy.state_root;
@@ -8006,8 +13200,14 @@ fn handle_tui<R: DockerRunner>(
     for warning in &runtime.warnings {
         eprintln!("warning: {warning}");
     }
-    let mut args = compose_base_args(ctx, &cfg, false, &[runtime.override_file.clone()])?;
-    append_harness_tui_run_args(&mut args, &container_start_dir);
+    let mut args = compose_base_args(
+        ctx,
+        &cfg,
+        false,
+        std::slice::from_ref(&runtime.override_file),
+        &provider_cfg.compose_profiles,
+    )?;
+    append_harness_tui_run_args(&mut args, &container_start_dir, &session_env);
     let env_overrides = compose_env_for_run(Some(&active_provider.run_id), Some(&workspace_root));
     if !provider_plane_is_running(ctx, runner, &cfg, false, &env_overrides)? {
         return Err(LuxError::Process(format!(
@@ -8024,6 +13224,70 @@ fn handle_tui<R: DockerRunner>(
     )
 }
 
+/// `lux attach`: `docker compose exec` into the running `agent` service. Applies the same
+/// active provider/run match and workspace boundary as `lux shim exec`; the shell starts in
+/// the container path that mirrors the host cwd.
+fn handle_attach<R: DockerRunner>(
+    ctx: &Context,
+    provider: String,
+    runner: &R,
+) -> Result<(), LuxError> {
+    let cfg = read_config(&ctx.config_path)?;
+    let provider = resolve_provider_name(&cfg, &provider)?.to_string();
+    let provider_cfg = provider_from_config(&cfg, &provider)?;
+    let policy = resolve_config_policy_paths(&cfg)?;
+    let state_root = policy.state_root;
+    let active_run = load_active_run_state(&state_root)?.ok_or_else(|| {
+        LuxError::Process(format!(
+            "no active run found; start one with `lux up --provider {provider}`"
+        ))
+    })?;
+    let active_provider = load_active_provider_state(&state_root)?.ok_or_else(|| {
+        LuxError::Process(format!(
+            "no active provider plane found; start one with `lux up --provider {provider}`"
+        ))
+    })?;
+    if active_provider.provider != provider {
+        return Err(provider_mismatch_error(
+            &active_provider.provider,
+            &provider,
+        ));
+    }
+    if active_run.run_id != active_provider.run_id {
+        return Err(LuxError::Process(format!(
+            "active run mismatch (collector run_id={}, provider run_id={}); restart provider plane",
+            active_run.run_id, active_provider.run_id
+        )));
+    }
+    let workspace_root = resolve_active_run_workspace_root(&cfg, &active_run)?;
+    let workspace_canon = fs::canonicalize(&workspace_root).unwrap_or(workspace_root);
+    let cwd = env::current_dir()?;
+    let cwd_canon = fs::canonicalize(&cwd).unwrap_or(cwd);
+    if !cwd_canon.starts_with(&workspace_canon) {
+        return Err(LuxError::Process(format!(
+            "attach must run from within workspace root: {}",
+            workspace_canon.display()
+        )));
+    }
+    let container_workdir = map_host_start_dir_to_container(&cwd_canon, &workspace_canon)?;
+    let env_overrides = compose_env_for_run(Some(&active_run.run_id), Some(&workspace_canon));
+    if !provider_plane_is_running(ctx, runner, &cfg, false, &env_overrides)? {
+        return Err(LuxError::Process(format!(
+            "provider plane for '{provider}' is not running; start it with `lux up --provider {provider}`"
+        )));
+    }
+    let mut args = compose_base_args(ctx, &cfg, false, &[], &provider_cfg.compose_profiles)?;
+    append_agent_attach_args(&mut args, &container_workdir);
+    run_docker_command(
+        ctx,
+        runner,
+        &args,
+        &env_overrides,
+        json!({"action": "attach", "provider": provider, "run_id": active_run.run_id}),
+        false,
+    )
+}
+
 fn handle_jobs(ctx: &Context, command: JobsCommand) -> Result<(), LuxError> {
     let cfg = read_config(&ctx.config_path)?;
     let policy = resolve_config_policy_paths(&cfg)?;
@@ -8059,7 +13323,107 @@ fn handle_jobs(ctx: &Context, command: JobsCommand) -> Result<(), LuxError> {
                 serde_json::from_str(&content).unwrap_or(json!({"raw": content}));
             output(ctx, json!({"run_id": run_id, "job": data}))
         }
+        JobsCommand::Logs {
+            id,
+            run_id,
+            latest,
+            follow,
+        } => {
+            let run_id =
+                resolve_run_id_from_selector(&log_root, &state_root, run_id.as_deref(), latest)?;
+            let job_dir = run_root(&log_root, &run_id)
+                .join("harness")
+                .join("jobs")
+                .join(&id);
+            if !job_dir.is_dir() {
+                return Err(LuxError::Process(format!("job not found: {id}")));
+            }
+            jobs_logs(ctx, &run_id, &id, &job_dir, follow)
+        }
+    }
+}
+
+fn job_status(job_dir: &Path) -> Option<String> {
+    let content = fs::read_to_string(job_dir.join("status.json")).ok()?;
+    let data: serde_json::Value = serde_json::from_str(&content).ok()?;
+    data.get("status")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+}
+
+fn job_status_is_terminal(status: Option<&str>) -> bool {
+    matches!(status, Some("complete" | "failed"))
+}
+
+/// Returns bytes appended to `path` since `offset` and advances it. A file that shrank
+/// (rewritten by the harness) is re-read from the start.
+fn read_appended(path: &Path, offset: &mut u64) -> Result<Vec<u8>, LuxError> {
+    let mut file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let len = file.metadata()?.len();
+    if len < *offset {
+        *offset = 0;
+    }
+    file.seek(SeekFrom::Start(*offset))?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    *offset += buf.len() as u64;
+    Ok(buf)
+}
+
+/// `lux jobs logs`: stdout goes to stdout and stderr to stderr. With `--follow`, polls both
+/// files until `status.json` reports a terminal status; under `--json` that means waiting
+/// for the job to finish before emitting one result.
+fn jobs_logs(
+    ctx: &Context,
+    run_id: &str,
+    id: &str,
+    job_dir: &Path,
+    follow: bool,
+) -> Result<(), LuxError> {
+    let stdout_path = job_dir.join("stdout.log");
+    let stderr_path = job_dir.join("stderr.log");
+    let mut stdout_offset = 0u64;
+    let mut stderr_offset = 0u64;
+    let mut stdout_buf = Vec::new();
+    let mut stderr_buf = Vec::new();
+    let status = loop {
+        // Read the status before the files so output written just before completion
+        // is still drained on the final pass.
+        let status = job_status(job_dir);
+        let stdout_chunk = read_appended(&stdout_path, &mut stdout_offset)?;
+        let stderr_chunk = read_appended(&stderr_path, &mut stderr_offset)?;
+        if ctx.json {
+            stdout_buf.extend_from_slice(&stdout_chunk);
+            stderr_buf.extend_from_slice(&stderr_chunk);
+        } else {
+            io::stdout().write_all(&stdout_chunk)?;
+            io::stdout().flush()?;
+            io::stderr().write_all(&stderr_chunk)?;
+        }
+        if !follow || job_status_is_terminal(status.as_deref()) {
+            break status;
+        }
+        thread::sleep(Duration::from_millis(JOBS_LOGS_FOLLOW_INTERVAL_MS));
+    };
+    if !ctx.json {
+        return Ok(());
     }
+    output(
+        ctx,
+        json!({
+            "run_id": run_id,
+            "id": id,
+            "status": status,
+            "stdout_path": stdout_path,
+            "stderr_path": stderr_path.exists().then_some(&stderr_path),
+            "stdout": String::from_utf8_lossy(&stdout_buf),
+            "stderr": String::from_utf8_lossy(&stderr_buf),
+        }),
+    )
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -8093,9 +13457,117 @@ fn doctor_check(
     }
 }
 
-fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>, LuxError> {
-    let mut checks = Vec::new();
+struct DoctorCheckSpec {
+    id: &'static str,
+    description: &'static str,
+    run: fn(&Context, &Config) -> Result<DoctorCheck, LuxError>,
+}
+
+const DOCTOR_CHECKS: &[DoctorCheckSpec] = &[
+    DoctorCheckSpec {
+        id: "docker_runtime",
+        description: "Docker is installed and the daemon is reachable",
+        run: doctor_check_docker_runtime,
+    },
+    DoctorCheckSpec {
+        id: "docker_compose",
+        description: "docker compose plugin is available",
+        run: doctor_check_docker_compose,
+    },
+    DoctorCheckSpec {
+        id: "compose_contract",
+        description: "bundled compose/runtime contract files are present",
+        run: doctor_check_compose_contract,
+    },
+    DoctorCheckSpec {
+        id: "log_sink_permissions",
+        description: "paths.log_root is writable",
+        run: doctor_check_log_sink_permissions,
+    },
+    DoctorCheckSpec {
+        id: "path_config_coherence",
+        description: "workspace/log/trusted roots are writable and do not overlap",
+        run: doctor_check_path_config_coherence,
+    },
+    DoctorCheckSpec {
+        id: "shim_bin_path_policy",
+        description: "shims.bin_dir satisfies the trust-zone policy",
+        run: doctor_check_shim_bin_path_policy,
+    },
+    DoctorCheckSpec {
+        id: "shim_path_precedence",
+        description: "provider shims are installed and resolve first on PATH",
+        run: doctor_check_shim_path_precedence,
+    },
+    DoctorCheckSpec {
+        id: "shim_path_persistence",
+        description: "shell startup files carry the Lux PATH block when shims are installed",
+        run: doctor_check_shim_path_persistence,
+    },
+    DoctorCheckSpec {
+        id: "trusted_root_permissions",
+        description: "trusted root and its key subdirectories are writable",
+        run: doctor_check_trusted_root_permissions,
+    },
+    DoctorCheckSpec {
+        id: "trusted_root_durability",
+        description: "trusted root is not on a tmpfs/ramfs that is wiped on reboot",
+        run: doctor_check_trusted_root_durability,
+    },
+    DoctorCheckSpec {
+        id: "runtime_socket_ready",
+        description: "runtime socket directory is writable",
+        run: doctor_check_runtime_socket_ready,
+    },
+    DoctorCheckSpec {
+        id: "runtime_socket_permissions",
+        description:
+            "runtime socket path fits the unix limit and its directory is group-accessible",
+        run: doctor_check_runtime_socket_permissions,
+    },
+    DoctorCheckSpec {
+        id: "harness_token_sanity",
+        description: "harness API token is configured",
+        run: doctor_check_harness_token_sanity,
+    },
+    DoctorCheckSpec {
+        id: "attribution_prerequisites",
+        description: "every provider has ownership.root_comm entries",
+        run: doctor_check_attribution_prerequisites,
+    },
+    DoctorCheckSpec {
+        id: "contract_schema_compatibility",
+        description: "config schema version is supported",
+        run: doctor_check_contract_schema_compatibility,
+    },
+    DoctorCheckSpec {
+        id: "secrets_file_permissions",
+        description: "api_key secrets files and their directories are not group/other-readable",
+        run: doctor_check_secrets_file_permissions,
+    },
+    DoctorCheckSpec {
+        id: DOCTOR_NETWORK_EGRESS_CHECK,
+        description: "provider API endpoints accept TCP connections (opt-in: --network/--strict)",
+        run: doctor_check_network_egress,
+    },
+];
+
+// Makes outbound connections, so it only runs under `--network`, `--strict`, or `--check`.
+const DOCTOR_NETWORK_EGRESS_CHECK: &str = "network_egress";
+const DOCTOR_NETWORK_CONNECT_TIMEOUT_MS: u64 = 3000;
+
+fn find_doctor_check(id: &str) -> Result<&'static DoctorCheckSpec, LuxError> {
+    DOCTOR_CHECKS
+        .iter()
+        .find(|spec| spec.id == id)
+        .ok_or_else(|| {
+            LuxError::Config(format!(
+                "unknown doctor check '{id}'; run `lux doctor --list` for available ids"
+            ))
+        })
+}
 
+fn doctor_check_docker_runtime(_ctx: &Context, _cfg: &Config) -> Result<DoctorCheck, LuxError> {
     let docker_installed = which::which("docker").is_ok();
     let docker_ok = if docker_installed {
         Command::new("docker")
@@ -8108,7 +13580,7 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
     } else {
         false
     };
-    checks.push(doctor_check(
+    Ok(doctor_check(
         "docker_runtime",
         docker_ok,
         "error",
@@ -8122,8 +13594,11 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
         },
         "Install/start Docker Desktop (or compatible Docker runtime) and rerun `lux doctor`.",
         json!({"docker_installed": docker_installed}),
-    ));
+    ))
+}
 
+fn doctor_check_docker_compose(_ctx: &Context, _cfg: &Config) -> Result<DoctorCheck, LuxError> {
+    let docker_installed = which::which("docker").is_ok();
     let docker_compose_ok = if docker_installed {
         Command::new("docker")
             .arg("compose")
@@ -8136,7 +13611,7 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
     } else {
         false
     };
-    checks.push(doctor_check(
+    Ok(doctor_check(
         "docker_compose",
         docker_compose_ok,
         "error",
@@ -8148,15 +13623,17 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
         },
         "Install/enable Docker Compose and rerun `lux doctor`.",
         json!({"docker_installed": docker_installed}),
-    ));
+    ))
+}
 
+fn doctor_check_compose_contract(ctx: &Context, _cfg: &Config) -> Result<DoctorCheck, LuxError> {
     let compose_files = configured_compose_files(ctx, true, &[]);
     let missing_compose: Vec<String> = compose_files
         .iter()
         .filter(|path| !path.exists())
         .map(|path| path.to_string_lossy().to_string())
         .collect();
-    checks.push(doctor_check(
+    Ok(doctor_check(
         "compose_contract",
         missing_compose.is_empty(),
         "error",
@@ -8168,15 +13645,16 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
         },
         "Reinstall/update the CLI bundle or fix `--bundle-dir/--compose-file` overrides.",
         json!({"missing_files": missing_compose}),
-    ));
+    ))
+}
 
-    let policy = resolve_config_policy_paths(cfg)?;
-    let log_root = policy.log_root.clone();
-    let workspace_root = policy.workspace_root.clone();
-    let trusted_root = policy.trusted_root.clone();
-    let shims_bin_dir = policy.shims_bin_dir.clone();
+fn doctor_check_log_sink_permissions(
+    _ctx: &Context,
+    cfg: &Config,
+) -> Result<DoctorCheck, LuxError> {
+    let log_root = resolve_config_policy_paths(cfg)?.log_root;
     let log_writable = host_dir_writable(&log_root);
-    checks.push(doctor_check(
+    Ok(doctor_check(
         "log_sink_permissions",
         log_writable,
         "error",
@@ -8191,14 +13669,23 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
             log_root.display()
         ),
         json!({"log_root": log_root.clone()}),
-    ));
+    ))
+}
 
+fn doctor_check_path_config_coherence(
+    _ctx: &Context,
+    cfg: &Config,
+) -> Result<DoctorCheck, LuxError> {
+    let policy = resolve_config_policy_paths(cfg)?;
+    let log_root = policy.log_root;
+    let workspace_root = policy.workspace_root;
+    let trusted_root = policy.trusted_root;
     let workspace_ok = fs::create_dir_all(&workspace_root).is_ok();
     let path_coherent = workspace_ok
         && !path_is_within(&workspace_root, &log_root)
         && !path_is_within(&log_root, &workspace_root)
         && path_is_within(&log_root, &trusted_root);
-    checks.push(doctor_check(
+    Ok(doctor_check(
         "path_config_coherence",
         path_coherent,
         "warn",
@@ -8218,10 +13705,16 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
             "trusted_root": trusted_root.clone(),
             "log_root": log_root.clone()
         }),
-    ));
+    ))
+}
 
-    let shim_bin_policy_ok = shim_path_safe(&policy, &shims_bin_dir);
-    checks.push(doctor_check(
+fn doctor_check_shim_bin_path_policy(
+    _ctx: &Context,
+    cfg: &Config,
+) -> Result<DoctorCheck, LuxError> {
+    let policy = resolve_config_policy_paths(cfg)?;
+    let shim_bin_policy_ok = shim_path_safe(&policy, &policy.shims_bin_dir);
+    Ok(doctor_check(
         "shim_bin_path_policy",
         shim_bin_policy_ok,
         "error",
@@ -8233,12 +13726,58 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
         },
         "Set `shims.bin_dir` inside `paths.trusted_root` and outside `paths.workspace_root`.",
         json!({
-            "trusted_root": trusted_root.clone(),
-            "workspace_root": workspace_root.clone(),
-            "shims_bin_dir": shims_bin_dir.clone(),
+            "trusted_root": policy.trusted_root.clone(),
+            "workspace_root": policy.workspace_root.clone(),
+            "shims_bin_dir": policy.shims_bin_dir.clone(),
         }),
-    ));
+    ))
+}
+
+fn doctor_check_shim_path_persistence(
+    _ctx: &Context,
+    cfg: &Config,
+) -> Result<DoctorCheck, LuxError> {
+    let policy = resolve_config_policy_paths(cfg)?;
+    let installed: Vec<&String> = cfg
+        .providers
+        .keys()
+        .filter(|provider| {
+            let shim_path = shim_path_for_provider(&policy.shims_bin_dir, provider);
+            shim_path.exists() && is_lux_managed_shim(&shim_path)
+        })
+        .collect();
+    let path_status = inspect_shell_path_persistence(&policy)?;
+    let drifted =
+        !installed.is_empty() && matches!(path_status.state.as_str(), "partial" | "absent");
+    Ok(doctor_check(
+        "shim_path_persistence",
+        !drifted,
+        "warn",
+        true,
+        if installed.is_empty() {
+            "no provider shims installed; PATH persistence not required".to_string()
+        } else if drifted {
+            format!(
+                "shim PATH block is {} in shell startup files; new shells may bypass the shims",
+                path_status.state
+            )
+        } else {
+            format!("shim PATH persistence is {}", path_status.state)
+        },
+        "Run `lux shim enable` to restore the managed PATH block in every startup file.",
+        json!({
+            "state": path_status.state,
+            "installed_providers": installed,
+            "files": shim_path_files_json(&path_status.files, &policy.home, false, false),
+        }),
+    ))
+}
 
+fn doctor_check_shim_path_precedence(
+    _ctx: &Context,
+    cfg: &Config,
+) -> Result<DoctorCheck, LuxError> {
+    let policy = resolve_config_policy_paths(cfg)?;
     let mut shim_rows = Vec::new();
     let mut shim_precedence_ok = true;
     for provider in cfg.providers.keys() {
@@ -8257,7 +13796,7 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
             "resolved_candidates": candidates.into_iter().map(|path| path.to_string_lossy().to_string()).collect::<Vec<String>>(),
         }));
     }
-    checks.push(doctor_check(
+    Ok(doctor_check(
         "shim_path_precedence",
         shim_precedence_ok,
         "warn",
@@ -8269,8 +13808,14 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
         },
         "Run `lux shim enable` and ensure `<trusted_root>/bin` is first in PATH for configured providers.",
         json!({"providers": shim_rows}),
-    ));
+    ))
+}
 
+fn doctor_check_trusted_root_permissions(
+    _ctx: &Context,
+    cfg: &Config,
+) -> Result<DoctorCheck, LuxError> {
+    let policy = resolve_config_policy_paths(cfg)?;
     let trusted_root_permissions = vec![
         ("trusted_root", policy.trusted_root.clone()),
         ("log_root", policy.log_root.clone()),
@@ -8292,7 +13837,7 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
     let trusted_root_ok = trusted_permission_rows
         .iter()
         .all(|row| row["writable"].as_bool().unwrap_or(false));
-    checks.push(doctor_check(
+    Ok(doctor_check(
         "trusted_root_permissions",
         trusted_root_ok,
         "error",
@@ -8304,11 +13849,204 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
         },
         "Ensure trusted root and key subdirectories are writable by the Lux process user.",
         json!({"paths": trusted_permission_rows}),
-    ));
+    ))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct MountEntry {
+    mount_point: PathBuf,
+    fs_type: String,
+    options: String,
+}
+
+/// Undoes the octal escapes (`\040` etc.) `/proc/mounts` uses for whitespace in paths.
+fn unescape_proc_mounts_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'\\' && idx + 4 <= bytes.len() {
+            let digits = std::str::from_utf8(&bytes[idx + 1..idx + 4]).unwrap_or_default();
+            if let Ok(value) = u8::from_str_radix(digits, 8) {
+                out.push(value);
+                idx += 4;
+                continue;
+            }
+        }
+        out.push(bytes[idx]);
+        idx += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn parse_proc_mounts(text: &str) -> Vec<MountEntry> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let _source = fields.next()?;
+            let mount_point = unescape_proc_mounts_field(fields.next()?);
+            let fs_type = fields.next()?.to_string();
+            let options = fields.next().unwrap_or_default().to_string();
+            Some(MountEntry {
+                mount_point: PathBuf::from(mount_point),
+                fs_type,
+                options,
+            })
+        })
+        .collect()
+}
+
+/// Parses macOS `mount` output (`<source> on <mount point> (<fstype>, <options>...)`).
+fn parse_bsd_mount_output(text: &str) -> Vec<MountEntry> {
+    text.lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(" on ")?;
+            let (mount_point, attrs) = rest.rsplit_once(" (")?;
+            let attrs = attrs.trim_end_matches(')');
+            let (fs_type, options) = attrs.split_once(", ").unwrap_or((attrs, ""));
+            Some(MountEntry {
+                mount_point: PathBuf::from(mount_point),
+                fs_type: fs_type.trim().to_string(),
+                options: options.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+fn host_mount_table() -> Option<Vec<MountEntry>> {
+    match env::consts::OS {
+        "linux" => fs::read_to_string("/proc/mounts")
+            .ok()
+            .map(|text| parse_proc_mounts(&text)),
+        "macos" => Command::new("mount")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| parse_bsd_mount_output(&String::from_utf8_lossy(&output.stdout))),
+        _ => None,
+    }
+}
+
+/// Longest mount point containing `path` (later entries win ties, matching mount stacking).
+fn mount_entry_for<'a>(mounts: &'a [MountEntry], path: &Path) -> Option<&'a MountEntry> {
+    mounts
+        .iter()
+        .filter(|entry| path.starts_with(&entry.mount_point))
+        .fold(None, |best: Option<&MountEntry>, entry| match best {
+            Some(current)
+                if current.mount_point.components().count()
+                    > entry.mount_point.components().count() =>
+            {
+                Some(current)
+            }
+            _ => Some(entry),
+        })
+}
+
+/// Returns the volatile filesystem type backing `path` (`tmpfs`, `ramfs`, or
+/// `overlay-on-tmpfs` when an overlay's upper dir lives on one), if any.
+fn volatile_filesystem_for(mounts: &[MountEntry], path: &Path) -> Option<String> {
+    let entry = mount_entry_for(mounts, path)?;
+    match entry.fs_type.as_str() {
+        "tmpfs" | "ramfs" => Some(entry.fs_type.clone()),
+        "overlay" => {
+            let upper = entry
+                .options
+                .split(',')
+                .find_map(|option| option.strip_prefix("upperdir="))?;
+            let backing = mount_entry_for(mounts, Path::new(upper))?;
+            matches!(backing.fs_type.as_str(), "tmpfs" | "ramfs")
+                .then(|| format!("overlay-on-{}", backing.fs_type))
+        }
+        _ => None,
+    }
+}
+
+/// Resolves `path` through its nearest existing ancestor so not-yet-created roots are
+/// attributed to the mount they will land on.
+fn canonicalize_existing_prefix(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut suffix = Vec::new();
+    loop {
+        if let Ok(resolved) = fs::canonicalize(existing) {
+            let mut full = resolved;
+            for part in suffix.iter().rev() {
+                full.push(part);
+            }
+            return full;
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                suffix.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+fn trusted_root_volatile_message(trusted_root: &Path, fs_type: &str) -> String {
+    format!(
+        "trusted root {} is on {fs_type}; evidence and state are lost on reboot",
+        trusted_root.display()
+    )
+}
+
+/// `up` and `status` repeat the `trusted_root_durability` doctor warning, since evidence
+/// written by the run they start or report on is what would be lost.
+fn warn_if_trusted_root_volatile(trusted_root: &Path) {
+    let resolved = canonicalize_existing_prefix(trusted_root);
+    if let Some(fs_type) =
+        host_mount_table().and_then(|mounts| volatile_filesystem_for(&mounts, &resolved))
+    {
+        eprintln!(
+            "warning: {} (see `lux doctor --check trusted_root_durability`)",
+            trusted_root_volatile_message(trusted_root, &fs_type)
+        );
+    }
+}
+
+fn doctor_check_trusted_root_durability(
+    _ctx: &Context,
+    cfg: &Config,
+) -> Result<DoctorCheck, LuxError> {
+    let policy = resolve_config_policy_paths(cfg)?;
+    let resolved = canonicalize_existing_prefix(&policy.trusted_root);
+    let mounts = host_mount_table();
+    let entry = mounts
+        .as_deref()
+        .and_then(|mounts| mount_entry_for(mounts, &resolved));
+    let volatile = mounts
+        .as_deref()
+        .and_then(|mounts| volatile_filesystem_for(mounts, &resolved));
+    let ok = volatile.is_none();
+    Ok(doctor_check(
+        "trusted_root_durability",
+        ok,
+        "warn",
+        false,
+        match &volatile {
+            Some(fs_type) => trusted_root_volatile_message(&policy.trusted_root, fs_type),
+            None => "trusted root is on a durable filesystem".to_string(),
+        },
+        "Move paths.trusted_root to a disk-backed location (e.g. under your home directory), or ignore this if an ephemeral setup is intended.",
+        json!({
+            "path": policy.trusted_root,
+            "resolved_path": resolved,
+            "mount_point": entry.map(|entry| entry.mount_point.clone()),
+            "fs_type": entry.map(|entry| entry.fs_type.clone()),
+            "volatile_fs_type": volatile,
+        }),
+    ))
+}
 
+fn doctor_check_runtime_socket_ready(
+    ctx: &Context,
+    _cfg: &Config,
+) -> Result<DoctorCheck, LuxError> {
     let (paths, _) = resolve_runtime_paths(ctx)?;
     let runtime_dir_ok = fs::create_dir_all(&paths.runtime_dir).is_ok();
-    checks.push(doctor_check(
+    Ok(doctor_check(
         "runtime_socket_ready",
         runtime_dir_ok,
         "warn",
@@ -8323,11 +14061,130 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
             paths.runtime_dir.display()
         ),
         json!({"runtime_dir": paths.runtime_dir}),
-    ));
+    ))
+}
+
+/// `runtime up` chmods the socket dir to 0770 and chgrps it to the socket gid; drift
+/// or a too-long path only shows up later as a bind or connect failure.
+fn doctor_check_runtime_socket_permissions(
+    _ctx: &Context,
+    cfg: &Config,
+) -> Result<DoctorCheck, LuxError> {
+    #[cfg(not(unix))]
+    {
+        let _ = cfg;
+        Ok(doctor_check(
+            "runtime_socket_permissions",
+            true,
+            "error",
+            true,
+            "runtime socket permission check skipped on non-unix host",
+            "",
+            json!({"skipped": true}),
+        ))
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        let socket_path = effective_runtime_socket_path(cfg);
+        let runtime_dir = socket_path
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."));
+        let expected_gid = effective_runtime_socket_gid(cfg);
+        let path_too_long = unix_socket_path_too_long(&socket_path);
+        let user_in_group = current_group_ids().contains(&expected_gid);
+        let dir_meta = fs::metadata(&runtime_dir).ok();
+        let dir_mode = dir_meta
+            .as_ref()
+            .map(|meta| meta.permissions().mode() & 0o7777);
+        let dir_gid = dir_meta.as_ref().map(|meta| meta.gid());
+        let socket_meta = fs::metadata(&socket_path).ok();
+        let socket_mode = socket_meta
+            .as_ref()
+            .map(|meta| meta.permissions().mode() & 0o7777);
+        let socket_gid = socket_meta.as_ref().map(|meta| meta.gid());
+
+        let quoted_dir = shell_single_quote(&runtime_dir.to_string_lossy());
+        let mut problems = Vec::new();
+        let mut fixes = Vec::new();
+        if path_too_long {
+            problems.push(format!(
+                "socket path is {} bytes (limit {})",
+                socket_path.as_os_str().len(),
+                UNIX_SOCKET_PATH_LIMIT_BYTES - 1
+            ));
+            fixes.push(
+                "set a shorter `runtime_control_plane.socket_path` (for example under /tmp)"
+                    .to_string(),
+            );
+        }
+        if !user_in_group {
+            problems.push(format!("current user is not in group {expected_gid}"));
+            fixes.push(format!(
+                "add the current user to group {expected_gid} or set `runtime_control_plane.socket_gid` to one of your groups"
+            ));
+        }
+        if dir_mode.is_some_and(|mode| mode != 0o770) {
+            problems.push(format!(
+                "runtime dir mode is {:04o}, expected 0770",
+                dir_mode.unwrap_or_default()
+            ));
+            fixes.push(format!("chmod 770 {quoted_dir}"));
+        }
+        if dir_gid.is_some_and(|gid| gid != expected_gid) {
+            problems.push(format!(
+                "runtime dir group is {}, expected {expected_gid}",
+                dir_gid.unwrap_or_default()
+            ));
+            fixes.push(format!("chgrp {expected_gid} {quoted_dir}"));
+        }
+        // Path length and group membership break the socket outright; mode/group drift is
+        // repaired by the next `runtime up` when the directory is ours.
+        let blocking = path_too_long || !user_in_group;
+        let ok = problems.is_empty();
+        Ok(doctor_check(
+            "runtime_socket_permissions",
+            ok,
+            if ok || blocking { "error" } else { "warn" },
+            ok || blocking,
+            if ok {
+                "runtime socket path and directory permissions look correct".to_string()
+            } else {
+                problems.join("; ")
+            },
+            if ok {
+                "Keep the runtime dir at 0770 owned by the runtime socket group.".to_string()
+            } else {
+                format!("Fix: {}", fixes.join("; "))
+            },
+            json!({
+                "skipped": false,
+                "socket_path": socket_path,
+                "socket_path_bytes": socket_path.as_os_str().len(),
+                "limit_bytes": UNIX_SOCKET_PATH_LIMIT_BYTES - 1,
+                "path_too_long": path_too_long,
+                "runtime_dir": runtime_dir,
+                "runtime_dir_exists": dir_meta.is_some(),
+                "runtime_dir_mode": dir_mode.map(|mode| format!("{mode:04o}")),
+                "runtime_dir_gid": dir_gid,
+                "socket_exists": socket_meta.is_some(),
+                "socket_mode": socket_mode.map(|mode| format!("{mode:04o}")),
+                "socket_gid": socket_gid,
+                "expected_gid": expected_gid,
+                "user_in_group": user_in_group,
+            }),
+        ))
+    }
+}
 
+fn doctor_check_harness_token_sanity(
+    _ctx: &Context,
+    cfg: &Config,
+) -> Result<DoctorCheck, LuxError> {
     let token_ok =
         !cfg.harness.api_token.trim().is_empty() || env::var("HARNESS_API_TOKEN").is_ok();
-    checks.push(doctor_check(
+    Ok(doctor_check(
         "harness_token_sanity",
         token_ok,
         "warn",
@@ -8338,14 +14195,105 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
             "harness token is empty"
         },
         "Set `harness.api_token` in config or `HARNESS_API_TOKEN` env before non-interactive `lux run`.",
-        json!({}),
-    ));
+        json!({ "health_url": harness_health_url(cfg) }),
+    ))
+}
+
+/// Secrets files written by lux are 0600 in a 0700 directory; hand-made ones may not be.
+fn doctor_check_secrets_file_permissions(
+    _ctx: &Context,
+    cfg: &Config,
+) -> Result<DoctorCheck, LuxError> {
+    #[cfg(not(unix))]
+    {
+        let _ = cfg;
+        Ok(doctor_check(
+            "secrets_file_permissions",
+            true,
+            "error",
+            true,
+            "secrets file permission check skipped on non-unix host",
+            "",
+            json!({"skipped": true, "entries": []}),
+        ))
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut entries = Vec::new();
+        let mut commands = Vec::new();
+        for (name, provider) in &cfg.providers {
+            if provider.auth_mode != AuthMode::ApiKey {
+                continue;
+            }
+            let secrets_file = PathBuf::from(expand_path(&provider.auth.api_key.secrets_file));
+            let Ok(file_meta) = fs::metadata(&secrets_file) else {
+                continue;
+            };
+            let parent = secrets_file
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .to_path_buf();
+            for (kind, path, mode, want) in [
+                (
+                    "file",
+                    secrets_file.clone(),
+                    file_meta.permissions().mode(),
+                    "600",
+                ),
+                (
+                    "dir",
+                    parent.clone(),
+                    fs::metadata(&parent)?.permissions().mode(),
+                    "700",
+                ),
+            ] {
+                let exposed = mode & 0o044 != 0;
+                if exposed {
+                    commands.push(format!(
+                        "chmod {want} {}",
+                        shell_single_quote(&path.to_string_lossy())
+                    ));
+                }
+                entries.push(json!({
+                    "provider": name,
+                    "kind": kind,
+                    "path": path,
+                    "mode": format!("{:04o}", mode & 0o7777),
+                    "group_or_other_readable": exposed,
+                }));
+            }
+        }
+        let ok = commands.is_empty();
+        Ok(doctor_check(
+            "secrets_file_permissions",
+            ok,
+            "error",
+            true,
+            if ok {
+                "api_key secrets files are private to their owner"
+            } else {
+                "api_key secrets files or directories are group/other-readable"
+            },
+            if ok {
+                "Keep secrets files at 0600 inside a 0700 directory.".to_string()
+            } else {
+                format!("Run: {}", commands.join(" && "))
+            },
+            json!({"skipped": false, "entries": entries}),
+        ))
+    }
+}
 
+fn doctor_check_attribution_prerequisites(
+    _ctx: &Context,
+    cfg: &Config,
+) -> Result<DoctorCheck, LuxError> {
     let attribution_ok = cfg
         .providers
         .values()
         .all(|provider| !provider.ownership.root_comm.is_empty());
-    checks.push(doctor_check(
+    Ok(doctor_check(
         "attribution_prerequisites",
         attribution_ok,
         "error",
@@ -8357,9 +14305,14 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
         },
         "Ensure each provider has non-empty `ownership.root_comm` entries.",
         json!({}),
-    ));
+    ))
+}
 
-    checks.push(doctor_check(
+fn doctor_check_contract_schema_compatibility(
+    _ctx: &Context,
+    cfg: &Config,
+) -> Result<DoctorCheck, LuxError> {
+    Ok(doctor_check(
         "contract_schema_compatibility",
         cfg.version == 2,
         "error",
@@ -8371,42 +14324,223 @@ fn collect_doctor_checks(ctx: &Context, cfg: &Config) -> Result<Vec<DoctorCheck>
         },
         "Set `version: 2` in config.yaml and migrate provider blocks as needed.",
         json!({"config_version": cfg.version}),
-    ));
+    ))
+}
 
-    Ok(checks)
+/// Splits `host:port`, `[v6]:port`, or an `http(s)://host[:port][/path]` URL. Bare hosts
+/// and `https://` default to port 443, `http://` to 80.
+fn parse_api_endpoint(raw: &str) -> Result<(String, u16), String> {
+    let trimmed = raw.trim();
+    let (rest, default_port) = if let Some(rest) = trimmed.strip_prefix("https://") {
+        (rest, 443)
+    } else if let Some(rest) = trimmed.strip_prefix("http://") {
+        (rest, 80)
+    } else {
+        (trimmed, 443)
+    };
+    let authority = rest.split('/').next().unwrap_or_default();
+    let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+        let (host, tail) = bracketed
+            .split_once(']')
+            .ok_or_else(|| format!("has an unterminated IPv6 literal: '{raw}'"))?;
+        (host, tail.strip_prefix(':'))
+    } else if authority.matches(':').count() == 1 {
+        let (host, port) = authority.split_once(':').unwrap_or((authority, ""));
+        (host, Some(port))
+    } else {
+        (authority, None)
+    };
+    if host.is_empty() {
+        return Err(format!("must name a host (got '{raw}')"));
+    }
+    let port = match port {
+        Some(port) => port
+            .parse::<u16>()
+            .ok()
+            .filter(|port| *port > 0)
+            .ok_or_else(|| format!("has an invalid port (got '{raw}')"))?,
+        None => default_port,
+    };
+    Ok((host.to_string(), port))
 }
 
-fn handle_doctor(ctx: &Context, strict: bool) -> Result<(), LuxError> {
-    let cfg = read_config(&ctx.config_path)?;
-    let checks = collect_doctor_checks(ctx, &cfg)?;
-    let has_error = checks
-        .iter()
-        .any(|check| !check.ok && check.severity == "error");
-    let has_strict_warning = checks.iter().any(|check| !check.ok && check.strict_fail);
-    let ok = !has_error && (!strict || !has_strict_warning);
-    let primary_error = checks
-        .iter()
-        .find(|check| !check.ok && check.severity == "error")
-        .or_else(|| {
-            checks
-                .iter()
-                .find(|check| !check.ok && strict && check.strict_fail)
-        })
-        .or_else(|| checks.iter().find(|check| !check.ok))
-        .map(|check| check.message.clone());
+/// Resolves `host:port` and tries each address with a bounded TCP connect. Returns the
+/// connect latency in milliseconds.
+fn probe_tcp_endpoint(host: &str, port: u16, timeout: Duration) -> Result<u128, String> {
+    let addrs: Vec<std::net::SocketAddr> = (host, port)
+        .to_socket_addrs()
+        .map_err(|err| format!("resolve failed: {err}"))?
+        .collect();
+    if addrs.is_empty() {
+        return Err("resolve returned no addresses".to_string());
+    }
+    let mut last_error = String::new();
+    for addr in addrs {
+        let started = Instant::now();
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(_) => return Ok(started.elapsed().as_millis()),
+            Err(err) => last_error = format!("{addr}: {err}"),
+        }
+    }
+    Err(last_error)
+}
+
+/// Probes from the host, which shares the agent container's default egress path; it does
+/// not exercise container-specific network policy.
+fn doctor_check_network_egress(_ctx: &Context, cfg: &Config) -> Result<DoctorCheck, LuxError> {
+    let timeout = Duration::from_millis(DOCTOR_NETWORK_CONNECT_TIMEOUT_MS);
+    let mut endpoints = Vec::new();
+    let mut unreachable = Vec::new();
+    for (name, provider) in &cfg.providers {
+        let Some(raw) = provider.commands.api_endpoint.as_deref() else {
+            continue;
+        };
+        let (host, port) = parse_api_endpoint(raw).map_err(|err| {
+            LuxError::Config(format!("providers.{name}.commands.api_endpoint {err}"))
+        })?;
+        let probe = probe_tcp_endpoint(&host, port, timeout);
+        if probe.is_err() {
+            unreachable.push(format!("{name} ({host}:{port})"));
+        }
+        endpoints.push(json!({
+            "provider": name,
+            "endpoint": raw,
+            "host": host,
+            "port": port,
+            "reachable": probe.is_ok(),
+            "latency_ms": probe.as_ref().ok(),
+            "error": probe.err(),
+        }));
+    }
+    let ok = unreachable.is_empty();
+    let message = if endpoints.is_empty() {
+        "no provider commands.api_endpoint configured".to_string()
+    } else if ok {
+        format!("{} provider endpoint(s) reachable", endpoints.len())
+    } else {
+        format!(
+            "unreachable provider endpoint(s): {}",
+            unreachable.join(", ")
+        )
+    };
+    Ok(doctor_check(
+        DOCTOR_NETWORK_EGRESS_CHECK,
+        ok,
+        "warn",
+        true,
+        message,
+        "Check outbound firewall/proxy settings for the listed hosts, or correct `providers.<name>.commands.api_endpoint`.",
+        json!({
+            "timeout_ms": DOCTOR_NETWORK_CONNECT_TIMEOUT_MS,
+            "endpoints": endpoints,
+        }),
+    ))
+}
+
+fn collect_doctor_checks(
+    ctx: &Context,
+    cfg: &Config,
+    include_network: bool,
+) -> Result<Vec<DoctorCheck>, LuxError> {
+    let mut checks = Vec::new();
+    for spec in DOCTOR_CHECKS {
+        if spec.id == DOCTOR_NETWORK_EGRESS_CHECK && !include_network {
+            continue;
+        }
+        checks.push((spec.run)(ctx, cfg)?);
+    }
+    Ok(checks)
+}
 
+fn handle_doctor_single(ctx: &Context, cfg: &Config, id: &str) -> Result<(), LuxError> {
+    let spec = find_doctor_check(id)?;
+    let check = (spec.run)(ctx, cfg)?;
     if ctx.json {
         let payload = JsonResult {
-            ok,
-            result: Some(json!({ "checks": checks, "strict": strict })),
-            error: if ok { None } else { primary_error },
+            ok: check.ok,
+            error: if check.ok {
+                None
+            } else {
+                Some(check.message.clone())
+            },
+            result: Some(check),
             error_details: None,
         };
-        print_json(&payload)?;
+        print_json(ctx, &payload)?;
+        if !payload.ok {
+            std::process::exit(1);
+        }
         return Ok(());
     }
-
-    for check in &checks {
+    let state = if check.ok { "ok" } else { "fail" };
+    println!(
+        "[{}] {} ({}) - {}",
+        state, check.id, check.severity, check.message
+    );
+    if check.ok {
+        return Ok(());
+    }
+    println!("  remediation: {}", check.remediation);
+    Err(LuxError::Process(check.message))
+}
+
+fn handle_doctor_list(ctx: &Context) -> Result<(), LuxError> {
+    if ctx.json {
+        let rows: Vec<serde_json::Value> = DOCTOR_CHECKS
+            .iter()
+            .map(|spec| json!({"id": spec.id, "description": spec.description}))
+            .collect();
+        return output(ctx, json!({ "checks": rows }));
+    }
+    for spec in DOCTOR_CHECKS {
+        println!("{:<30} {}", spec.id, spec.description);
+    }
+    Ok(())
+}
+
+fn handle_doctor(
+    ctx: &Context,
+    strict: bool,
+    check: Option<String>,
+    list: bool,
+    network: bool,
+) -> Result<(), LuxError> {
+    if list {
+        return handle_doctor_list(ctx);
+    }
+    let cfg = read_config(&ctx.config_path)?;
+    if let Some(id) = check {
+        return handle_doctor_single(ctx, &cfg, &id);
+    }
+    let checks = collect_doctor_checks(ctx, &cfg, network || strict)?;
+    let has_error = checks
+        .iter()
+        .any(|check| !check.ok && check.severity == "error");
+    let has_strict_warning = checks.iter().any(|check| !check.ok && check.strict_fail);
+    let ok = !has_error && (!strict || !has_strict_warning);
+    let primary_error = checks
+        .iter()
+        .find(|check| !check.ok && check.severity == "error")
+        .or_else(|| {
+            checks
+                .iter()
+                .find(|check| !check.ok && strict && check.strict_fail)
+        })
+        .or_else(|| checks.iter().find(|check| !check.ok))
+        .map(|check| check.message.clone());
+
+    if ctx.json {
+        let payload = JsonResult {
+            ok,
+            result: Some(json!({ "checks": checks, "strict": strict })),
+            error: if ok { None } else { primary_error },
+            error_details: None,
+        };
+        print_json(ctx, &payload)?;
+        return Ok(());
+    }
+
+    for check in &checks {
         let state = if check.ok { "ok" } else { "fail" };
         println!(
             "[{}] {} ({}) - {}",
@@ -8685,6 +14819,43 @@ fn handle_info(ctx: &Context) -> Result<(), LuxError> {
     Ok(())
 }
 
+/// Everything support asks for in one place. Path resolution is best-effort so a broken
+/// config still yields the build details.
+fn handle_version(ctx: &Context) -> Result<(), LuxError> {
+    let compose_file = ctx.bundle_dir.join("compose.yml");
+    let compose_found = compose_file.is_file();
+    let installed_version = resolve_runtime_paths(ctx)
+        .ok()
+        .and_then(|(paths, _)| read_current_version(&paths));
+    if ctx.json {
+        return output(
+            ctx,
+            json!({
+                "version": env!("CARGO_PKG_VERSION"),
+                "git_commit": LUX_GIT_COMMIT,
+                "target": LUX_BUILD_TARGET,
+                "bundle_dir": ctx.bundle_dir,
+                "compose_file": compose_file,
+                "compose_found": compose_found,
+                "installed_version": installed_version,
+            }),
+        );
+    }
+    println!("lux {}", env!("CARGO_PKG_VERSION"));
+    println!("commit: {LUX_GIT_COMMIT}");
+    println!("target: {LUX_BUILD_TARGET}");
+    println!(
+        "bundle_dir: {} (compose.yml {})",
+        ctx.bundle_dir.display(),
+        if compose_found { "found" } else { "missing" }
+    );
+    println!(
+        "installed_version: {}",
+        installed_version.as_deref().unwrap_or("none")
+    );
+    Ok(())
+}
+
 fn handle_paths(ctx: &Context) -> Result<(), LuxError> {
     let (paths, config_exists) = resolve_runtime_paths(ctx)?;
     let env_exists = paths.env_file.exists();
@@ -8696,11 +14867,31 @@ fn handle_paths(ctx: &Context) -> Result<(), LuxError> {
         .into_iter()
         .map(|p| p.to_string_lossy().to_string())
         .collect();
+    let exists: BTreeMap<&str, bool> = [
+        ("config_dir", &paths.config_dir),
+        ("bundle_dir", &paths.bundle_dir),
+        ("trusted_root", &paths.trusted_root),
+        ("runtime_dir", &paths.runtime_dir),
+        ("runtime_socket_path", &paths.runtime_socket_path),
+        ("state_dir", &paths.state_dir),
+        ("secrets_dir", &paths.secrets_dir),
+        ("shim_bin_dir", &paths.shim_bin_dir),
+        ("log_root", &paths.log_root),
+        ("workspace_root", &paths.workspace_root),
+        ("install_dir", &paths.install_dir),
+        ("versions_dir", &paths.versions_dir),
+        ("current_link", &paths.current_link),
+        ("bin_dir", &paths.bin_dir),
+    ]
+    .into_iter()
+    .map(|(key, path)| (key, path.exists()))
+    .collect();
     output(
         ctx,
         json!({
             "config_exists": config_exists,
             "env_file_exists": env_exists,
+            "exists": exists,
             "config_dir": paths.config_dir,
             "config_path": paths.config_path,
             "env_file": paths.env_file,
@@ -8708,6 +14899,7 @@ fn handle_paths(ctx: &Context) -> Result<(), LuxError> {
             "trusted_root": paths.trusted_root,
             "runtime_dir": paths.runtime_dir,
             "runtime_socket_path": paths.runtime_socket_path,
+            "runtime_socket_gid": paths.runtime_socket_gid,
             "runtime_pid_path": paths.runtime_pid_path,
             "runtime_events_path": paths.runtime_events_path,
             "state_dir": paths.state_dir,
@@ -8731,12 +14923,14 @@ fn handle_paths(ctx: &Context) -> Result<(), LuxError> {
 fn handle_update(ctx: &Context, command: UpdateCommand) -> Result<(), LuxError> {
     match command {
         UpdateCommand::Check => update_check(ctx),
+        UpdateCommand::List { offline } => update_list(ctx, offline),
         UpdateCommand::Apply {
             to,
             latest,
             yes,
             dry_run,
-        } => update_apply(ctx, to, latest, yes, dry_run),
+            verify_only,
+        } => update_apply(ctx, to, latest, yes, dry_run, verify_only),
         UpdateCommand::Rollback {
             to,
             previous,
@@ -8746,6 +14940,74 @@ fn handle_update(ctx: &Context, command: UpdateCommand) -> Result<(), LuxError>
     }
 }
 
+fn update_list(ctx: &Context, offline: bool) -> Result<(), LuxError> {
+    let (paths, _) = resolve_runtime_paths(ctx)?;
+    let current_version = read_current_version(&paths);
+    let current_tag = current_version
+        .as_deref()
+        .map(|version| version.trim_start_matches('v').to_string());
+    let installed: Vec<serde_json::Value> = list_installed_version_tags(&paths)?
+        .into_iter()
+        .map(|tag| {
+            json!({
+                "version": format!("v{tag}"),
+                "current": current_tag.as_deref() == Some(tag.as_str()),
+            })
+        })
+        .collect();
+    // The remote lookup is advisory; being offline should not fail the listing.
+    let (latest_remote, remote_error) = if offline {
+        (None, None)
+    } else {
+        match fetch_latest_release_tag() {
+            Ok(tag) => (Some(tag), None),
+            Err(err) => (None, Some(err.to_string())),
+        }
+    };
+    let update_available = match (latest_remote.as_deref(), current_tag.as_deref()) {
+        (Some(latest), Some(current)) => {
+            compare_version_tags(latest.trim_start_matches('v'), current).is_gt()
+        }
+        (Some(_), None) => true,
+        _ => false,
+    };
+    if ctx.json {
+        return output(
+            ctx,
+            json!({
+                "current": current_version,
+                "installed": installed,
+                "latest_remote": latest_remote,
+                "update_available": update_available,
+                "remote_error": remote_error,
+            }),
+        );
+    }
+    if installed.is_empty() {
+        println!(
+            "no installed versions under {}",
+            paths.versions_dir.display()
+        );
+    }
+    for item in &installed {
+        let version = item["version"].as_str().unwrap_or_default();
+        if item["current"].as_bool().unwrap_or(false) {
+            println!("* {version} (current)");
+        } else {
+            println!("  {version}");
+        }
+    }
+    match (latest_remote, remote_error) {
+        (Some(latest), _) if update_available => {
+            println!("latest remote: {latest} (newer; run `lux update apply --latest --yes`)")
+        }
+        (Some(latest), _) => println!("latest remote: {latest}"),
+        (None, Some(err)) => println!("latest remote: unavailable ({err})"),
+        (None, None) => {}
+    }
+    Ok(())
+}
+
 fn update_check(ctx: &Context) -> Result<(), LuxError> {
     let (paths, _) = resolve_runtime_paths(ctx)?;
     let current_version = read_current_version(&paths);
@@ -8767,6 +15029,7 @@ fn update_apply(
     latest: bool,
     yes: bool,
     dry_run: bool,
+    verify_only: bool,
 ) -> Result<(), LuxError> {
     let (paths, _) = resolve_runtime_paths(ctx)?;
     let current_version = read_current_version(&paths);
@@ -8797,7 +15060,7 @@ fn update_apply(
             }),
         );
     }
-    if !yes {
+    if !yes && !verify_only {
         return Err(LuxError::Config(
             "update apply requires --yes (or use --dry-run to preview)".to_string(),
         ));
@@ -8815,12 +15078,85 @@ fn update_apply(
         );
     }
 
+    let reused_staged = stage_update_bundle(&plan)?;
+    if verify_only {
+        return output(
+            ctx,
+            json!({
+                "action": "update_apply",
+                "verify_only": true,
+                "updated": false,
+                "verified": true,
+                "current_version": current_version,
+                "target_version": target_version,
+                "staged_dir": plan.target_dir,
+                "reused_staged": reused_staged,
+            }),
+        );
+    }
+    // The staged dir may have changed since it was verified; check again right before the
+    // `current` link points at it.
+    if !staged_update_binary_verified(&plan.target_dir)? {
+        return Err(LuxError::Process(format!(
+            "staged lux binary in {} no longer matches its verified SHA256; rerun `lux update apply` to download it again",
+            plan.target_dir.display()
+        )));
+    }
+    fs::create_dir_all(&paths.install_dir)?;
+    fs::create_dir_all(&paths.bin_dir)?;
+    force_symlink(&plan.target_dir, &paths.current_link)?;
+    force_symlink(&paths.current_link.join("lux"), &paths.bin_path)?;
+
+    output(
+        ctx,
+        json!({
+            "action": "update_apply",
+            "updated": true,
+            "from_version": current_version,
+            "to_version": target_version,
+            "target_dir": plan.target_dir,
+            "bin_path": paths.bin_path,
+            "reused_staged": reused_staged,
+        }),
+    )
+}
+
+/// Whether `target_dir/lux` still hashes to the `lux <sha256>` line `UPDATE_VERIFIED_MARKER`
+/// recorded at extraction. A missing or older marker without that line never matches.
+fn staged_update_binary_verified(target_dir: &Path) -> Result<bool, LuxError> {
+    let Ok(marker) = fs::read_to_string(target_dir.join(UPDATE_VERIFIED_MARKER)) else {
+        return Ok(false);
+    };
+    let Some(expected) = marker
+        .lines()
+        .find_map(|line| line.strip_prefix("lux "))
+        .map(str::trim)
+    else {
+        return Ok(false);
+    };
+    let binary = target_dir.join("lux");
+    if !binary.is_file() {
+        return Ok(false);
+    }
+    Ok(sha256_file(&binary)?.eq_ignore_ascii_case(expected))
+}
+
+/// Downloads, verifies, and extracts the bundle into `plan.target_dir`, then writes
+/// `UPDATE_VERIFIED_MARKER` with the bundle's SHA256 and the extracted `lux` binary's.
+/// A dir whose binary still matches its marker is reused; returns whether that happened.
+/// Anything else (no marker, old marker, tampered binary) is downloaded and checked again.
+fn stage_update_bundle(plan: &UpdatePlan) -> Result<bool, LuxError> {
+    let marker = plan.target_dir.join(UPDATE_VERIFIED_MARKER);
+    if staged_update_binary_verified(&plan.target_dir)? {
+        return Ok(true);
+    }
+
     let download_dir = temp_download_dir();
     fs::create_dir_all(&download_dir)?;
     let bundle_path = download_dir.join(&plan.bundle_name);
     let checksum_path = download_dir.join(&plan.checksum_name);
 
-    let update_result = (|| -> Result<(), LuxError> {
+    let stage_result = (|| -> Result<(), LuxError> {
         download_file(&plan.bundle_url, &bundle_path)?;
         download_file(&plan.checksum_url, &checksum_path)?;
         verify_bundle_checksum(&bundle_path, &checksum_path)?;
@@ -8835,26 +15171,20 @@ fn update_apply(
                 lux_binary.display()
             )));
         }
-        fs::create_dir_all(&paths.install_dir)?;
-        fs::create_dir_all(&paths.bin_dir)?;
-        force_symlink(&plan.target_dir, &paths.current_link)?;
-        force_symlink(&paths.current_link.join("lux"), &paths.bin_path)?;
+        // Written last so an interrupted extraction is never mistaken for a verified one.
+        fs::write(
+            &marker,
+            format!(
+                "bundle {}\nlux {}\n",
+                cached_sha256_file(&bundle_path)?,
+                sha256_file(&lux_binary)?
+            ),
+        )?;
         Ok(())
     })();
     let _ = fs::remove_dir_all(&download_dir);
-    update_result?;
-
-    output(
-        ctx,
-        json!({
-            "action": "update_apply",
-            "updated": true,
-            "from_version": current_version,
-            "to_version": target_version,
-            "target_dir": plan.target_dir,
-            "bin_path": paths.bin_path,
-        }),
-    )
+    stage_result?;
+    Ok(false)
 }
 
 fn update_rollback(
@@ -8981,15 +15311,95 @@ fn prune_empty_dir(path: &Path) {
     let _ = fs::remove_dir(path);
 }
 
-fn handle_uninstall<R: DockerRunner>(
-    ctx: &Context,
+struct UninstallOptions {
     remove_config: bool,
     all_versions: bool,
     yes: bool,
     dry_run: bool,
     force: bool,
+    keep_logs: bool,
+    keep_secrets: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct UninstallManifestEntry {
+    path: PathBuf,
+    /// `install` (binaries/links), `config`, `log_root`, or `trusted_root` (evidence, keys, state).
+    scope: &'static str,
+    kind: &'static str,
+    action: &'static str,
+    exists: bool,
+    size_bytes: u64,
+}
+
+fn uninstall_path_size(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => dir_size(path.to_path_buf()).unwrap_or(0),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Builds the uninstall manifest. A path slated for removal that contains a kept path is
+/// downgraded to `keep` so keep flags can never be bypassed by an enclosing directory.
+fn build_uninstall_manifest(
+    targets: Vec<(PathBuf, &'static str, &'static str, bool)>,
+    warnings: &mut Vec<String>,
+) -> Vec<UninstallManifestEntry> {
+    let mut dedup: BTreeMap<PathBuf, (&'static str, &'static str, bool)> = BTreeMap::new();
+    for (path, scope, kind, remove) in targets {
+        dedup
+            .entry(path)
+            .and_modify(|entry| entry.2 &= remove)
+            .or_insert((scope, kind, remove));
+    }
+    let kept: Vec<PathBuf> = dedup
+        .iter()
+        .filter(|(path, (_, _, remove))| !remove && path_exists(path))
+        .map(|(path, _)| path.clone())
+        .collect();
+    dedup
+        .into_iter()
+        .map(|(path, (scope, kind, mut remove))| {
+            if remove {
+                if let Some(inner) = kept
+                    .iter()
+                    .find(|inner| *inner != &path && inner.starts_with(&path))
+                {
+                    warnings.push(format!(
+                        "keeping {} because it contains kept path {}",
+                        path.display(),
+                        inner.display()
+                    ));
+                    remove = false;
+                }
+            }
+            UninstallManifestEntry {
+                exists: path_exists(&path),
+                size_bytes: uninstall_path_size(&path),
+                path,
+                scope,
+                kind,
+                action: if remove { "remove" } else { "keep" },
+            }
+        })
+        .collect()
+}
+
+fn handle_uninstall<R: DockerRunner>(
+    ctx: &Context,
+    options: UninstallOptions,
     runner: &R,
 ) -> Result<(), LuxError> {
+    let UninstallOptions {
+        remove_config,
+        all_versions,
+        yes,
+        dry_run,
+        force,
+        keep_logs,
+        keep_secrets,
+    } = options;
     if !dry_run && !yes {
         return Err(LuxError::Config(
             "uninstall requires --yes (or use --dry-run to preview)".to_string(),
@@ -9030,14 +15440,20 @@ fn handle_uninstall<R: DockerRunner>(
             } else {
                 down_attempted = true;
                 let project_name = match read_config(&ctx.config_path) {
-                    Ok(cfg) => cfg.docker.project_name,
-                    Err(err) => {
-                        warnings.push(format!(
-                            "unable to read config for compose project name; proceeding without -p ({})",
-                            err
-                        ));
-                        String::new()
-                    }
+                    Ok(cfg) => compose_project_name(ctx, &cfg)?,
+                    Err(err) => match &ctx.project_name {
+                        Some(name) => {
+                            validate_compose_project_name(name)?;
+                            name.clone()
+                        }
+                        None => {
+                            warnings.push(format!(
+                                "unable to read config for compose project name; proceeding without -p ({})",
+                                err
+                            ));
+                            String::new()
+                        }
+                    },
                 };
 
                 let mut down_args = vec![
@@ -9069,33 +15485,62 @@ fn handle_uninstall<R: DockerRunner>(
         }
     }
 
-    let mut targets: Vec<PathBuf> = Vec::new();
+    let mut targets: Vec<(PathBuf, &'static str, &'static str, bool)> = Vec::new();
     if all_versions {
-        targets.push(versions_dir.clone());
+        targets.push((versions_dir.clone(), "install", "versions", true));
     } else if let Some(current_target) = safe_current_target(&current_link, &versions_dir) {
-        targets.push(current_target);
-    }
-    targets.push(current_link.clone());
-    targets.push(bin_path.clone());
-    if remove_config {
-        targets.push(ctx.env_file.clone());
-        targets.push(ctx.config_path.clone());
-    }
-
-    let mut dedup: BTreeMap<String, PathBuf> = BTreeMap::new();
-    for path in targets {
-        dedup.insert(path.to_string_lossy().to_string(), path);
+        targets.push((current_target, "install", "version", true));
+    }
+    targets.push((current_link.clone(), "install", "current_link", true));
+    targets.push((bin_path.clone(), "install", "bin_link", true));
+    targets.push((ctx.env_file.clone(), "config", "env_file", remove_config));
+    targets.push((ctx.config_path.clone(), "config", "config", remove_config));
+
+    // Trusted-root data is kept unless a `--remove-config` uninstall also passes
+    // `--keep-logs=false` or `--keep-secrets=false`, and then only the lux-owned
+    // subdirectories are removed, never the trusted root itself.
+    let mut trusted_root: Option<PathBuf> = None;
+    match read_config(&ctx.config_path).and_then(|cfg| resolve_config_policy_paths(&cfg)) {
+        Ok(policy) => {
+            targets.push((
+                policy.log_root,
+                "log_root",
+                "logs",
+                remove_config && !keep_logs,
+            ));
+            targets.push((
+                policy.secrets_root,
+                "trusted_root",
+                "secrets",
+                remove_config && !keep_secrets,
+            ));
+            targets.push((
+                policy.state_root,
+                "trusted_root",
+                "state",
+                remove_config && !keep_logs,
+            ));
+            trusted_root = Some(policy.trusted_root);
+        }
+        Err(err) => {
+            if remove_config {
+                warnings.push(format!(
+                    "unable to resolve trusted_root from config; leaving trusted-root data in place ({})",
+                    err
+                ));
+            }
+        }
     }
-    let targets: Vec<PathBuf> = dedup.into_values().collect();
+    let manifest = build_uninstall_manifest(targets, &mut warnings);
 
     let mut planned: Vec<String> = Vec::new();
     let mut removed: Vec<String> = Vec::new();
     let mut missing: Vec<String> = Vec::new();
-    for path in &targets {
-        let display = path.to_string_lossy().to_string();
-        if path_exists(path) {
+    for entry in manifest.iter().filter(|entry| entry.action == "remove") {
+        let display = entry.path.to_string_lossy().to_string();
+        if entry.exists {
             planned.push(display.clone());
-            if !dry_run && remove_path(path)? {
+            if !dry_run && remove_path(&entry.path)? {
                 removed.push(display);
             }
         } else {
@@ -9106,6 +15551,9 @@ fn handle_uninstall<R: DockerRunner>(
     if !dry_run {
         if remove_config {
             prune_empty_dir(&config_dir);
+            if let Some(trusted_root) = trusted_root.as_ref() {
+                prune_empty_dir(trusted_root);
+            }
         }
         prune_empty_dir(&bin_dir);
         prune_empty_dir(&install_dir);
@@ -9118,11 +15566,14 @@ fn handle_uninstall<R: DockerRunner>(
             "dry_run": dry_run,
             "remove_config": remove_config,
             "all_versions": all_versions,
+            "keep_logs": keep_logs,
+            "keep_secrets": keep_secrets,
             "down_attempted": down_attempted,
             "down_skipped": down_skipped,
             "planned": planned,
             "removed": removed,
             "missing": missing,
+            "manifest": manifest,
             "warnings": warnings,
         }),
     )
@@ -9130,328 +15581,1151 @@ fn handle_uninstall<R: DockerRunner>(
 
 fn handle_logs(ctx: &Context, command: LogsCommand) -> Result<(), LuxError> {
     match command {
-        LogsCommand::Stats { run_id, latest } => logs_stats(ctx, run_id, latest),
+        LogsCommand::Stats {
+            run_id,
+            latest,
+            since,
+            until,
+        } => {
+            let window = LogTimeWindow::parse(since.as_deref(), until.as_deref(), Utc::now())?;
+            logs_stats(ctx, run_id, latest, &window)
+        }
         LogsCommand::Tail {
             lines,
             file,
             run_id,
             latest,
-        } => logs_tail(ctx, lines, file, run_id, latest),
+            since,
+            until,
+            format,
+        } => {
+            let window = LogTimeWindow::parse(since.as_deref(), until.as_deref(), Utc::now())?;
+            logs_tail(ctx, lines, file, run_id, latest, &window, format.as_deref())
+        }
+        LogsCommand::Grep {
+            pattern,
+            run_id,
+            latest,
+            ignore_case,
+            regex,
+            raw,
+        } => {
+            let matcher = LogGrepMatcher::new(&pattern, regex, ignore_case)?;
+            logs_grep(ctx, &pattern, &matcher, run_id, latest, raw)
+        }
     }
 }
 
-fn logs_stats(ctx: &Context, run_id: Option<String>, latest: bool) -> Result<(), LuxError> {
-    let cfg = read_config(&ctx.config_path)?;
-    let policy = resolve_config_policy_paths(&cfg)?;
-    let log_root = policy.log_root;
-    let run_id =
-        resolve_run_id_from_selector(&log_root, &policy.state_root, run_id.as_deref(), latest)?;
-    let run_root = run_root(&log_root, &run_id);
-    let sessions_dir = run_root.join("harness").join("sessions");
-    let mut total_bytes: u64 = 0;
-    let mut total_hours: f64 = 0.0;
-    let mut session_count = 0u64;
-
-    if sessions_dir.exists() {
-        for entry in fs::read_dir(&sessions_dir)? {
-            let entry = entry?;
-            if !entry.file_type()?.is_dir() {
-                continue;
-            }
-            let meta_path = entry.path().join("meta.json");
-            if !meta_path.exists() {
-                continue;
-            }
-            let meta_raw = fs::read_to_string(&meta_path)?;
-            let meta: serde_json::Value = serde_json::from_str(&meta_raw).unwrap_or(json!({}));
-            let started = meta.get("started_at").and_then(|v| v.as_str());
-            let ended = meta.get("ended_at").and_then(|v| v.as_str());
-            let (Some(started), Some(ended)) = (started, ended) else {
-                continue;
-            };
-            let start_dt = DateTime::parse_from_rfc3339(started).ok();
-            let end_dt = DateTime::parse_from_rfc3339(ended).ok();
-            let (Some(start_dt), Some(end_dt)) = (start_dt, end_dt) else {
-                continue;
-            };
-            let duration = end_dt.with_timezone(&Utc) - start_dt.with_timezone(&Utc);
-            let hours = duration.num_seconds() as f64 / 3600.0;
-            if hours <= 0.0 {
-                continue;
-            }
-            let size = dir_size(entry.path())?;
-            total_bytes += size;
-            total_hours += hours;
-            session_count += 1;
-        }
+fn handle_collector(ctx: &Context, command: CollectorCommand) -> Result<(), LuxError> {
+    match command {
+        CollectorCommand::Stats { run_id, latest } => collector_stats(ctx, run_id, latest),
     }
+}
 
-    let avg_mb_per_hour = if total_hours > 0.0 {
-        (total_bytes as f64 / (1024.0 * 1024.0)) / total_hours
-    } else {
-        0.0
-    };
+const EVIDENCE_MANIFEST_FILE: &str = "manifest.sha256";
 
-    let payload = json!({
-        "run_id": run_id,
-        "sessions": session_count,
-        "total_bytes": total_bytes,
-        "avg_mb_per_hour": avg_mb_per_hour,
-    });
-    output(ctx, payload)
+#[derive(Debug, Clone, Serialize)]
+struct EvidenceFileStatus {
+    path: String,
+    // ok | modified | removed | added
+    status: String,
+    expected_sha256: Option<String>,
+    actual_sha256: Option<String>,
 }
 
-fn logs_tail(
-    ctx: &Context,
-    lines: usize,
-    file: Option<String>,
-    run_id: Option<String>,
-    latest: bool,
-) -> Result<(), LuxError> {
-    let cfg = read_config(&ctx.config_path)?;
-    let policy = resolve_config_policy_paths(&cfg)?;
-    let log_root = policy.log_root;
-    let run_id =
-        resolve_run_id_from_selector(&log_root, &policy.state_root, run_id.as_deref(), latest)?;
-    let run_root = run_root(&log_root, &run_id);
-    let target = match file.as_deref() {
-        Some("audit") => run_root.join("collector").join("raw").join("audit.log"),
-        Some("ebpf") => run_root.join("collector").join("raw").join("ebpf.jsonl"),
-        Some("timeline") | None => run_root
-            .join("collector")
-            .join("filtered")
-            .join("filtered_timeline.jsonl"),
-        Some(name) => run_root.join(name),
-    };
-    if !target.exists() {
+/// Parses `sha256sum` output (`<hex>  <path>`, `*` marking binary mode). Paths must stay
+/// inside the collector tree.
+fn parse_evidence_manifest(raw: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut entries = BTreeMap::new();
+    for (index, line) in raw.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let parsed = line.split_once(' ').and_then(|(hash, rest)| {
+            let path = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*'))?;
+            let valid_hash = hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit());
+            valid_hash.then(|| (hash.to_ascii_lowercase(), path.to_string()))
+        });
+        let Some((hash, path)) = parsed else {
+            return Err(format!("line {}: expected `<sha256>  <path>`", index + 1));
+        };
+        let relative = Path::new(&path);
+        if relative.is_absolute()
+            || relative
+                .components()
+                .any(|part| matches!(part, std::path::Component::ParentDir))
+        {
+            return Err(format!(
+                "line {}: path must be relative to collector/: {path}",
+                index + 1
+            ));
+        }
+        entries.insert(path.trim_start_matches("./").to_string(), hash);
+    }
+    Ok(entries)
+}
+
+fn collect_evidence_files(root: &Path, dir: &Path, out: &mut Vec<String>) -> Result<(), LuxError> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_evidence_files(root, &path, out)?;
+        } else if file_type.is_file() {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let relative = relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+            if relative != EVIDENCE_MANIFEST_FILE {
+                out.push(relative);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Compares every file under `collector_dir` with the latest manifest the collector wrote.
+/// Results are sorted by path.
+fn verify_evidence_manifest(collector_dir: &Path) -> Result<Vec<EvidenceFileStatus>, LuxError> {
+    let manifest_path = collector_dir.join(EVIDENCE_MANIFEST_FILE);
+    if !manifest_path.exists() {
         return Err(LuxError::Process(format!(
-            "log not found: {}",
-            target.display()
+            "evidence manifest not found: {} (the collector writes it when it starts and refreshes it while running)",
+            manifest_path.display()
         )));
     }
+    let expected =
+        parse_evidence_manifest(&fs::read_to_string(&manifest_path)?).map_err(|err| {
+            LuxError::Process(format!(
+                "invalid evidence manifest {}: {err}",
+                manifest_path.display()
+            ))
+        })?;
+    let mut present = Vec::new();
+    collect_evidence_files(collector_dir, collector_dir, &mut present)?;
+    let present: BTreeSet<String> = present.into_iter().collect();
+
+    let mut files = Vec::new();
+    for (path, expected_sha256) in &expected {
+        if !present.contains(path) {
+            files.push(EvidenceFileStatus {
+                path: path.clone(),
+                status: "removed".to_string(),
+                expected_sha256: Some(expected_sha256.clone()),
+                actual_sha256: None,
+            });
+            continue;
+        }
+        let actual = sha256_file_native(&collector_dir.join(path))?;
+        let status = if &actual == expected_sha256 {
+            "ok"
+        } else {
+            "modified"
+        };
+        files.push(EvidenceFileStatus {
+            path: path.clone(),
+            status: status.to_string(),
+            expected_sha256: Some(expected_sha256.clone()),
+            actual_sha256: Some(actual),
+        });
+    }
+    for path in present.iter().filter(|path| !expected.contains_key(*path)) {
+        files.push(EvidenceFileStatus {
+            path: path.clone(),
+            status: "added".to_string(),
+            expected_sha256: None,
+            actual_sha256: Some(sha256_file_native(&collector_dir.join(path))?),
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+fn handle_verify(ctx: &Context, run_id: Option<String>, latest: bool) -> Result<(), LuxError> {
+    let cfg = read_config(&ctx.config_path)?;
+    let policy = resolve_config_policy_paths(&cfg)?;
+    let run_id = resolve_run_id_from_selector(
+        &policy.log_root,
+        &policy.state_root,
+        run_id.as_deref(),
+        latest,
+    )?;
+    let collector_dir = run_root(&policy.log_root, &run_id).join("collector");
+    let files = verify_evidence_manifest(&collector_dir)?;
+    let count = |status: &str| files.iter().filter(|file| file.status == status).count();
+    let (modified, removed, added) = (count("modified"), count("removed"), count("added"));
+    let verified = modified + removed + added == 0;
+    let summary = format!(
+        "{} file(s) checked: {} ok, {modified} modified, {removed} removed, {added} added",
+        files.len(),
+        count("ok")
+    );
     if ctx.json {
         let payload = JsonResult {
-            ok: true,
-            result: Some(json!({"run_id": run_id, "path": target})),
-            error: None,
+            ok: verified,
+            result: Some(json!({
+                "run_id": run_id,
+                "manifest_path": collector_dir.join(EVIDENCE_MANIFEST_FILE),
+                "verified": verified,
+                "summary": {
+                    "ok": count("ok"),
+                    "modified": modified,
+                    "removed": removed,
+                    "added": added,
+                },
+                "files": files,
+            })),
+            error: (!verified).then(|| format!("evidence verification failed: {summary}")),
             error_details: None,
         };
-        print_json(&payload)?;
+        print_json(ctx, &payload)?;
+        if !verified {
+            std::process::exit(1);
+        }
         return Ok(());
     }
-    let content = fs::read_to_string(&target)?;
-    let lines_vec: Vec<&str> = content.lines().collect();
-    let start = lines_vec.len().saturating_sub(lines);
-    for line in &lines_vec[start..] {
-        println!("{}", line);
+    for file in files.iter().filter(|file| file.status != "ok") {
+        println!("{:<9} {}", file.status, file.path);
+    }
+    if !verified {
+        return Err(LuxError::Process(format!(
+            "evidence verification failed for {run_id}: {summary}"
+        )));
     }
+    println!("{run_id}: {summary}");
     Ok(())
 }
 
-fn dir_size(path: PathBuf) -> Result<u64, LuxError> {
-    let mut size = 0;
-    if path.is_file() {
-        return Ok(fs::metadata(path)?.len());
+/// Start time encoded in a `lux__%Y_%m_%d_%H_%M_%S` run id, with or without a `_<n>`
+/// collision suffix; `None` for other names.
+fn run_id_started_at(run_id: &str) -> Option<DateTime<Utc>> {
+    let stamp = run_id.strip_prefix("lux__")?;
+    let parse = |stamp: &str| {
+        chrono::NaiveDateTime::parse_from_str(stamp, "%Y_%m_%d_%H_%M_%S")
+            .ok()
+            .map(|naive| naive.and_utc())
+    };
+    parse(stamp).or_else(|| {
+        let (stamp, suffix) = stamp.rsplit_once('_')?;
+        suffix
+            .chars()
+            .all(|c| c.is_ascii_digit())
+            .then(|| parse(stamp))
+            .flatten()
+    })
+}
+
+/// Picks the runs to delete from `run_ids` (sorted oldest first). `--keep-days` never
+/// selects runs whose start time cannot be read from the id.
+fn plan_run_prune(
+    run_ids: &[String],
+    max_runs: Option<u64>,
+    keep_days: Option<u64>,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    if let Some(keep_days) = keep_days {
+        let cutoff = now - chrono::Duration::days(keep_days as i64);
+        return run_ids
+            .iter()
+            .filter(|run_id| run_id_started_at(run_id).is_some_and(|started| started < cutoff))
+            .cloned()
+            .collect();
     }
-    if path.is_dir() {
-        for entry in fs::read_dir(path)? {
-            let entry = entry?;
-            let file_type = entry.file_type()?;
-            if file_type.is_dir() {
-                size += dir_size(entry.path())?;
-            } else if file_type.is_file() {
-                size += entry.metadata()?.len();
-            }
-        }
+    let keep = max_runs.unwrap_or(0) as usize;
+    let excess = run_ids.len().saturating_sub(keep);
+    run_ids[..excess].to_vec()
+}
+
+fn handle_runs(ctx: &Context, command: RunsCommand) -> Result<(), LuxError> {
+    match command {
+        RunsCommand::Prune {
+            max_runs,
+            keep_days,
+            dry_run,
+        } => runs_prune(ctx, max_runs, keep_days, dry_run),
+        RunsCommand::Show { run_id, latest } => runs_show(ctx, run_id, latest),
     }
-    Ok(size)
 }
 
-fn resolve_token(cfg: &Config) -> Result<String, LuxError> {
-    if !cfg.harness.api_token.trim().is_empty() {
-        return Ok(cfg.harness.api_token.clone());
+fn runs_show(ctx: &Context, run_id: Option<String>, latest: bool) -> Result<(), LuxError> {
+    let cfg = read_config(&ctx.config_path)?;
+    let policy = resolve_config_policy_paths(&cfg)?;
+    let log_root = policy.log_root;
+    let run_id =
+        resolve_run_id_from_selector(&log_root, &policy.state_root, run_id.as_deref(), latest)?;
+    // Only the active run records its workspace; historical runs fall back to the id.
+    let active = load_active_run_state(&policy.state_root)?.filter(|state| state.run_id == run_id);
+    let started_at = active
+        .as_ref()
+        .map(|state| state.started_at.clone())
+        .or_else(|| run_id_started_at(&run_id).map(|dt| dt.to_rfc3339()));
+    let workspace_root = active
+        .as_ref()
+        .and_then(|state| state.workspace_root.clone());
+    let (sessions, jobs) = run_session_job_counts(&log_root, &run_id);
+    let pipeline = run_pipeline_files(&log_root, &run_id);
+    let payload = json!({
+        "run_id": run_id,
+        "path": run_root(&log_root, &run_id),
+        "active": active.is_some(),
+        "started_at": started_at,
+        "workspace_root": workspace_root,
+        "pipeline": pipeline,
+        "sessions": sessions,
+        "jobs": jobs,
+    });
+    if ctx.json {
+        return output(ctx, payload);
     }
-    if let Ok(token) = env::var("HARNESS_API_TOKEN") {
-        if !token.trim().is_empty() {
-            return Ok(token);
-        }
+    let status = if payload["active"] == json!(true) {
+        " (active)"
+    } else {
+        ""
+    };
+    println!("{run_id}{status}");
+    println!("  path:        {}", run_root(&log_root, &run_id).display());
+    println!(
+        "  started_at:  {}",
+        payload["started_at"].as_str().unwrap_or("-")
+    );
+    println!(
+        "  workspace:   {}",
+        payload["workspace_root"].as_str().unwrap_or("-")
+    );
+    println!(
+        "  sessions:    {}",
+        payload["sessions"]["count"].as_u64().unwrap_or(0)
+    );
+    println!(
+        "  jobs:        {} ({} running, {} finished)",
+        payload["jobs"]["count"].as_u64().unwrap_or(0),
+        payload["jobs"]["running"].as_u64().unwrap_or(0),
+        payload["jobs"]["finished"].as_u64().unwrap_or(0)
+    );
+    for row in &pipeline {
+        let present = row["present"].as_bool().unwrap_or(false);
+        println!(
+            "  {:<18} {}",
+            row["name"].as_str().unwrap_or_default(),
+            if present {
+                format!(
+                    "{} bytes, modified {}",
+                    row["size_bytes"].as_u64().unwrap_or(0),
+                    row["modified_at"].as_str().unwrap_or("-")
+                )
+            } else {
+                "missing".to_string()
+            }
+        );
     }
-    Err(LuxError::Config(
-        "HARNESS_API_TOKEN is required for server runs; set it in config.yaml or env".to_string(),
-    ))
+    Ok(())
 }
 
-fn extract_process_error_details(err: &LuxError) -> Option<ProcessErrorDetails> {
-    match err {
-        LuxError::ProcessDetailed { details, .. } => Some(details.clone()),
-        _ => None,
+fn runs_prune(
+    ctx: &Context,
+    max_runs: Option<u64>,
+    keep_days: Option<u64>,
+    dry_run: bool,
+) -> Result<(), LuxError> {
+    let cfg = read_config(&ctx.config_path)?;
+    let policy = resolve_config_policy_paths(&cfg)?;
+    let max_runs = max_runs.or((cfg.collector.max_runs > 0).then_some(cfg.collector.max_runs));
+    let retention = match (max_runs, keep_days) {
+        (_, Some(keep_days)) => json!({"keep_days": keep_days}),
+        (Some(max_runs), None) => json!({"max_runs": max_runs}),
+        (None, None) => {
+            return Err(LuxError::Config(
+                "no retention policy: set collector.max_runs or pass --max-runs/--keep-days"
+                    .to_string(),
+            ))
+        }
+    };
+    if max_runs == Some(0) && keep_days.is_none() {
+        return Err(LuxError::Config(
+            "--max-runs must be greater than 0".to_string(),
+        ));
+    }
+    let run_ids = list_run_ids(&policy.log_root)?;
+    let active_run_id = load_active_run_state(&policy.state_root)?.map(|state| state.run_id);
+    let doomed = plan_run_prune(&run_ids, max_runs, keep_days, Utc::now());
+    let pruned: Vec<serde_json::Value> = doomed
+        .iter()
+        .map(|run_id| {
+            let path = run_root(&policy.log_root, run_id);
+            json!({
+                "run_id": run_id,
+                "size_bytes": dir_size(path.clone()).unwrap_or(0),
+                "path": path,
+            })
+        })
+        .collect();
+    let freed_bytes: u64 = pruned
+        .iter()
+        .filter_map(|entry| entry["size_bytes"].as_u64())
+        .sum();
+    let mut plan = json!({
+        "dry_run": dry_run,
+        "log_root": policy.log_root,
+        "retention": retention,
+        "active_run_id": active_run_id,
+        "kept": run_ids.iter().filter(|run_id| !doomed.contains(run_id)).collect::<Vec<_>>(),
+        "pruned": pruned,
+        "freed_bytes": freed_bytes,
+    });
+    if let Some(active) = active_run_id
+        .as_ref()
+        .filter(|active| doomed.contains(active))
+    {
+        return Err(LuxError::ProcessDetailed {
+            message: format!("refusing to prune: the active run {active} would be deleted"),
+            details: Box::new(ProcessErrorDetails {
+                error_code: "runs_prune_active_run".to_string(),
+                hint: Some(
+                    "Stop the active run with `lux down`, or loosen --max-runs/--keep-days."
+                        .to_string(),
+                ),
+                command: None,
+                raw_stderr: None,
+                partial_outcome: Some(plan),
+            }),
+        });
+    }
+    if !dry_run {
+        for run_id in &doomed {
+            fs::remove_dir_all(run_root(&policy.log_root, run_id))?;
+        }
+    }
+    if ctx.json {
+        return output(ctx, plan.take());
     }
+    let verb = if dry_run { "would prune" } else { "pruned" };
+    for entry in plan["pruned"].as_array().into_iter().flatten() {
+        println!(
+            "{verb} {} ({} bytes)",
+            entry["run_id"].as_str().unwrap_or_default(),
+            entry["size_bytes"]
+        );
+    }
+    println!(
+        "{verb} {} run(s), {freed_bytes} bytes; kept {}",
+        doomed.len(),
+        run_ids.len() - doomed.len()
+    );
+    Ok(())
 }
 
-fn output(ctx: &Context, payload: serde_json::Value) -> Result<(), LuxError> {
+/// Reads the `ebpf.stats.json` snapshot the collector's eBPF loader rewrites every few
+/// seconds next to the run's raw eBPF log.
+fn collector_stats(ctx: &Context, run_id: Option<String>, latest: bool) -> Result<(), LuxError> {
+    let cfg = read_config(&ctx.config_path)?;
+    let policy = resolve_config_policy_paths(&cfg)?;
+    let log_root = policy.log_root;
+    let run_id =
+        resolve_run_id_from_selector(&log_root, &policy.state_root, run_id.as_deref(), latest)?;
+    let stats_path = run_root(&log_root, &run_id)
+        .join("collector")
+        .join("raw")
+        .join("ebpf.stats.json");
+    if !stats_path.exists() {
+        return Err(LuxError::Process(format!(
+            "collector stats not found: {} (the collector publishes them every few seconds while running)",
+            stats_path.display()
+        )));
+    }
+    let raw = fs::read_to_string(&stats_path)?;
+    let stats: serde_json::Value = serde_json::from_str(&raw).map_err(|err| {
+        LuxError::Process(format!(
+            "invalid collector stats {}: {err}",
+            stats_path.display()
+        ))
+    })?;
+    let mut payload = json!({"run_id": run_id, "stats_path": stats_path});
+    if let (Some(target), Some(fields)) = (payload.as_object_mut(), stats.as_object()) {
+        for (key, value) in fields {
+            target.insert(key.clone(), value.clone());
+        }
+    }
     if ctx.json {
-        let wrapper = JsonResult {
-            ok: true,
-            result: Some(payload),
-            error: None,
-            error_details: None,
-        };
-        print_json(&wrapper)?;
-    } else {
-        println!("{}", payload);
+        return output(ctx, payload);
+    }
+    for line in render_collector_stats(&payload) {
+        println!("{line}");
     }
     Ok(())
 }
 
-fn print_json<T: Serialize>(payload: &T) -> Result<(), LuxError> {
-    let text = serde_json::to_string_pretty(payload)?;
-    println!("{}", text);
-    Ok(())
+fn render_collector_stats(payload: &serde_json::Value) -> Vec<String> {
+    let mut lines = Vec::new();
+    for key in [
+        "run_id",
+        "updated_at",
+        "run_tag",
+        "drops",
+        "events_emitted_total",
+        "events_written",
+        "ring_buffer_bytes",
+        "rate_limited_total",
+    ] {
+        let Some(value) = payload.get(key) else {
+            continue;
+        };
+        let text = value
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| value.to_string());
+        lines.push(format!("{key}: {text}"));
+    }
+    if let Some(by_type) = payload.get("events_emitted").and_then(|v| v.as_object()) {
+        lines.push("events_emitted:".to_string());
+        for (event_type, count) in by_type {
+            lines.push(format!("  {event_type}: {count}"));
+        }
+    }
+    lines
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::cell::RefCell;
-    use tempfile::tempdir;
+/// Inclusive time bounds applied to log records by `logs tail`/`logs stats`.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct LogTimeWindow {
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
 
-    #[derive(Debug, Clone)]
-    struct RecordedCall {
-        args: Vec<String>,
-        env_overrides: BTreeMap<String, String>,
-        capture_output: bool,
+impl LogTimeWindow {
+    fn parse(
+        since: Option<&str>,
+        until: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Self, LuxError> {
+        let since = since
+            .map(|value| parse_log_time_bound("--since", value, now))
+            .transpose()?;
+        let until = until
+            .map(|value| parse_log_time_bound("--until", value, now))
+            .transpose()?;
+        if let (Some(since), Some(until)) = (since, until) {
+            if since > until {
+                return Err(LuxError::Config(format!(
+                    "--since ({}) must not be later than --until ({})",
+                    since.to_rfc3339(),
+                    until.to_rfc3339()
+                )));
+            }
+        }
+        Ok(Self { since, until })
     }
 
-    #[derive(Default)]
-    struct MockDockerRunner {
-        calls: RefCell<Vec<RecordedCall>>,
-        outputs: RefCell<Vec<CommandOutput>>,
+    fn is_bounded(&self) -> bool {
+        self.since.is_some() || self.until.is_some()
     }
 
-    impl MockDockerRunner {
-        fn push_output(&self, output: CommandOutput) {
-            self.outputs.borrow_mut().push(output);
+    fn contains(&self, ts: Option<DateTime<Utc>>) -> bool {
+        if !self.is_bounded() {
+            return true;
         }
+        let Some(ts) = ts else {
+            return false;
+        };
+        self.since.is_none_or(|since| ts >= since) && self.until.is_none_or(|until| ts <= until)
+    }
 
-        fn calls(&self) -> Vec<RecordedCall> {
-            self.calls.borrow().clone()
+    /// Records are JSONL with an RFC3339 `ts`; anything else has no timestamp.
+    fn contains_line(&self, line: &str) -> bool {
+        if !self.is_bounded() {
+            return true;
         }
+        let ts = serde_json::from_str::<serde_json::Value>(line)
+            .ok()
+            .and_then(|value| {
+                value
+                    .get("ts")
+                    .and_then(|v| v.as_str())
+                    .and_then(parse_rfc3339_utc)
+            });
+        self.contains(ts)
     }
 
-    impl DockerRunner for MockDockerRunner {
-        fn run(
-            &self,
-            args: &[String],
-            _cwd: &Path,
-            env_overrides: &BTreeMap<String, String>,
-            capture_output: bool,
-        ) -> Result<CommandOutput, io::Error> {
-            self.calls.borrow_mut().push(RecordedCall {
-                args: args.to_vec(),
-                env_overrides: env_overrides.clone(),
-                capture_output,
-            });
-            let mut queued = self.outputs.borrow_mut();
-            if queued.is_empty() {
-                return Ok(CommandOutput {
-                    status_code: 0,
-                    stdout: Vec::new(),
-                    stderr: Vec::new(),
-                });
-            }
-            Ok(queued.remove(0))
-        }
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "since": self.since.map(|ts| ts.to_rfc3339()),
+            "until": self.until.map(|ts| ts.to_rfc3339()),
+        })
     }
+}
 
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-    enum SetupRunnerFailurePoint {
-        ShimEnable,
-        ProviderPlaneActive,
-        CollectorRunning,
-        CollectorStop,
-        CollectorStart,
-        UiStart,
-    }
+fn parse_log_time_bound(
+    flag: &str,
+    value: &str,
+    now: DateTime<Utc>,
+) -> Result<DateTime<Utc>, LuxError> {
+    let trimmed = value.trim();
+    if let Some(ts) = parse_rfc3339_utc(trimmed) {
+        return Ok(ts);
+    }
+    parse_relative_duration(trimmed)
+        .map(|ago| now - ago)
+        .ok_or_else(|| {
+            LuxError::Config(format!(
+                "{flag} must be RFC3339 or a relative duration like 30s, 10m, 2h, 1d (got '{value}')"
+            ))
+        })
+}
 
-    #[derive(Debug, Default)]
-    struct MockSetupPostActionRunner {
-        calls: RefCell<Vec<String>>,
-        fail_at: Option<SetupRunnerFailurePoint>,
-        provider_active: Option<String>,
-        collector_running: bool,
+fn parse_relative_duration(value: &str) -> Option<chrono::Duration> {
+    let unit = value.chars().last()?;
+    let amount: i64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    if amount < 0 {
+        return None;
     }
+    match unit {
+        's' => chrono::Duration::try_seconds(amount),
+        'm' => chrono::Duration::try_minutes(amount),
+        'h' => chrono::Duration::try_hours(amount),
+        'd' => chrono::Duration::try_days(amount),
+        _ => None,
+    }
+}
 
-    impl MockSetupPostActionRunner {
-        fn with_failure(mut self, point: SetupRunnerFailurePoint) -> Self {
-            self.fail_at = Some(point);
-            self
-        }
+fn logs_stats(
+    ctx: &Context,
+    run_id: Option<String>,
+    latest: bool,
+    window: &LogTimeWindow,
+) -> Result<(), LuxError> {
+    let cfg = read_config(&ctx.config_path)?;
+    let policy = resolve_config_policy_paths(&cfg)?;
+    let log_root = policy.log_root;
+    let run_id =
+        resolve_run_id_from_selector(&log_root, &policy.state_root, run_id.as_deref(), latest)?;
+    let run_root = run_root(&log_root, &run_id);
+    let sessions_dir = run_root.join("harness").join("sessions");
+    let mut total_bytes: u64 = 0;
+    let mut total_hours: f64 = 0.0;
+    let mut session_count = 0u64;
 
-        fn with_provider_active(mut self, provider: &str) -> Self {
-            self.provider_active = Some(provider.to_string());
-            self
+    if sessions_dir.exists() {
+        for entry in fs::read_dir(&sessions_dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let meta_path = entry.path().join("meta.json");
+            if !meta_path.exists() {
+                continue;
+            }
+            let meta_raw = fs::read_to_string(&meta_path)?;
+            let meta: serde_json::Value = serde_json::from_str(&meta_raw).unwrap_or(json!({}));
+            let started = meta.get("started_at").and_then(|v| v.as_str());
+            let ended = meta.get("ended_at").and_then(|v| v.as_str());
+            let (Some(started), Some(ended)) = (started, ended) else {
+                continue;
+            };
+            let start_dt = DateTime::parse_from_rfc3339(started).ok();
+            let end_dt = DateTime::parse_from_rfc3339(ended).ok();
+            let (Some(start_dt), Some(end_dt)) = (start_dt, end_dt) else {
+                continue;
+            };
+            if !window.contains(Some(start_dt.with_timezone(&Utc))) {
+                continue;
+            }
+            let duration = end_dt.with_timezone(&Utc) - start_dt.with_timezone(&Utc);
+            let hours = duration.num_seconds() as f64 / 3600.0;
+            if hours <= 0.0 {
+                continue;
+            }
+            let size = dir_size(entry.path())?;
+            total_bytes += size;
+            total_hours += hours;
+            session_count += 1;
         }
+    }
 
-        fn with_collector_running(mut self, running: bool) -> Self {
-            self.collector_running = running;
-            self
-        }
+    let avg_mb_per_hour = if total_hours > 0.0 {
+        (total_bytes as f64 / (1024.0 * 1024.0)) / total_hours
+    } else {
+        0.0
+    };
 
-        fn maybe_fail(&self, point: SetupRunnerFailurePoint) -> Result<(), LuxError> {
-            if self.fail_at == Some(point) {
-                return Err(LuxError::Process(format!("mock failure at {:?}", point)));
-            }
-            Ok(())
-        }
+    let timeline_path = run_root
+        .join("collector")
+        .join("filtered")
+        .join("filtered_timeline.jsonl");
+    let timeline_events = if timeline_path.exists() {
+        fs::read_to_string(&timeline_path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty() && window.contains_line(line))
+            .count()
+    } else {
+        0
+    };
 
-        fn calls(&self) -> Vec<String> {
-            self.calls.borrow().clone()
-        }
+    let payload = json!({
+        "run_id": run_id,
+        "sessions": session_count,
+        "total_bytes": total_bytes,
+        "avg_mb_per_hour": avg_mb_per_hour,
+        "timeline_events": timeline_events,
+        "window": window.to_json(),
+    });
+    output(ctx, payload)
+}
 
-        fn record_call(&self, name: &str) {
-            self.calls.borrow_mut().push(name.to_string());
-        }
+fn logs_tail(
+    ctx: &Context,
+    lines: usize,
+    file: Option<String>,
+    run_id: Option<String>,
+    latest: bool,
+    window: &LogTimeWindow,
+    format: Option<&str>,
+) -> Result<(), LuxError> {
+    let cfg = read_config(&ctx.config_path)?;
+    let policy = resolve_config_policy_paths(&cfg)?;
+    let log_root = policy.log_root;
+    let run_id =
+        resolve_run_id_from_selector(&log_root, &policy.state_root, run_id.as_deref(), latest)?;
+    let run_root = run_root(&log_root, &run_id);
+    let target = match file.as_deref() {
+        Some("audit") => run_root.join("collector").join("raw").join("audit.log"),
+        Some("ebpf") => run_root.join("collector").join("raw").join("ebpf.jsonl"),
+        Some("timeline") | None => run_root
+            .join("collector")
+            .join("filtered")
+            .join("filtered_timeline.jsonl"),
+        Some(name) => run_root.join(name),
+    };
+    if !target.exists() {
+        return Err(LuxError::Process(format!(
+            "log not found: {}",
+            target.display()
+        )));
+    }
+    if ctx.json && format.is_some() && !ctx.quiet {
+        eprintln!("note: --format overrides --json for logs tail");
+    }
+    if ctx.json && format.is_none() {
+        let payload = JsonResult {
+            ok: true,
+            result: Some(json!({"run_id": run_id, "path": target, "window": window.to_json()})),
+            error: None,
+            error_details: None,
+        };
+        print_json(ctx, &payload)?;
+        return Ok(());
+    }
+    let content = fs::read_to_string(&target)?;
+    let lines_vec: Vec<&str> = content
+        .lines()
+        .filter(|line| window.contains_line(line))
+        .collect();
+    let start = lines_vec.len().saturating_sub(lines);
+    for line in &lines_vec[start..] {
+        println!("{}", render_log_tail_line(line, format.unwrap_or("raw"))?);
     }
+    Ok(())
+}
 
-    impl SetupPostActionRunner for MockSetupPostActionRunner {
-        fn shim_enable(&self, _providers: &[String]) -> Result<(), LuxError> {
-            self.record_call("shim_enable");
-            self.maybe_fail(SetupRunnerFailurePoint::ShimEnable)
-        }
+/// `ndjson` re-serializes each JSON record compactly and quotes non-JSON lines (e.g.
+/// audit.log) as JSON strings so the output stays valid for `jq`; `pretty` indents JSON
+/// records and leaves other lines untouched.
+fn render_log_tail_line(line: &str, format: &str) -> Result<String, LuxError> {
+    let parsed = serde_json::from_str::<serde_json::Value>(line).ok();
+    Ok(match (format, parsed) {
+        ("ndjson", Some(value)) => serde_json::to_string(&value)?,
+        ("ndjson", None) => serde_json::to_string(line)?,
+        ("pretty", Some(value)) => serde_json::to_string_pretty(&value)?,
+        _ => line.to_string(),
+    })
+}
 
-        fn provider_plane_active(&self) -> Result<Option<String>, LuxError> {
-            self.record_call("provider_plane_active");
-            self.maybe_fail(SetupRunnerFailurePoint::ProviderPlaneActive)?;
-            Ok(self.provider_active.clone())
-        }
+enum LogGrepMatcher {
+    Substring { needle: String, ignore_case: bool },
+    Regex(regex::Regex),
+}
 
-        fn collector_running(&self) -> Result<bool, LuxError> {
-            self.record_call("collector_running");
-            self.maybe_fail(SetupRunnerFailurePoint::CollectorRunning)?;
-            Ok(self.collector_running)
+impl LogGrepMatcher {
+    fn new(pattern: &str, regex: bool, ignore_case: bool) -> Result<Self, LuxError> {
+        if pattern.is_empty() {
+            return Err(LuxError::Config(
+                "logs grep pattern must not be empty".to_string(),
+            ));
         }
+        if regex {
+            let compiled = regex::RegexBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .build()
+                .map_err(|err| LuxError::Config(format!("invalid --regex pattern: {err}")))?;
+            return Ok(Self::Regex(compiled));
+        }
+        let needle = if ignore_case {
+            pattern.to_lowercase()
+        } else {
+            pattern.to_string()
+        };
+        Ok(Self::Substring {
+            needle,
+            ignore_case,
+        })
+    }
 
-        fn collector_stop(&self) -> Result<(), LuxError> {
-            self.record_call("collector_stop");
-            self.maybe_fail(SetupRunnerFailurePoint::CollectorStop)
+    fn is_match(&self, line: &str) -> bool {
+        match self {
+            Self::Substring {
+                needle,
+                ignore_case: true,
+            } => line.to_lowercase().contains(needle.as_str()),
+            Self::Substring { needle, .. } => line.contains(needle.as_str()),
+            Self::Regex(compiled) => compiled.is_match(line),
         }
+    }
+}
 
-        fn collector_start(&self) -> Result<(), LuxError> {
-            self.record_call("collector_start");
-            self.maybe_fail(SetupRunnerFailurePoint::CollectorStart)
+/// Streams each target line by line so large timelines are never held in memory; only
+/// matches are buffered, and only under `--json`. Missing raw logs are skipped, but at
+/// least one target has to exist.
+fn logs_grep(
+    ctx: &Context,
+    pattern: &str,
+    matcher: &LogGrepMatcher,
+    run_id: Option<String>,
+    latest: bool,
+    raw: bool,
+) -> Result<(), LuxError> {
+    let cfg = read_config(&ctx.config_path)?;
+    let policy = resolve_config_policy_paths(&cfg)?;
+    let log_root = policy.log_root;
+    let run_id =
+        resolve_run_id_from_selector(&log_root, &policy.state_root, run_id.as_deref(), latest)?;
+    let collector_root = run_root(&log_root, &run_id).join("collector");
+    let mut targets = vec![(
+        "timeline",
+        collector_root
+            .join("filtered")
+            .join("filtered_timeline.jsonl"),
+    )];
+    if raw {
+        targets.push(("audit", collector_root.join("raw").join("audit.log")));
+        targets.push(("ebpf", collector_root.join("raw").join("ebpf.jsonl")));
+    }
+    let existing: Vec<_> = targets.iter().filter(|(_, path)| path.exists()).collect();
+    if existing.is_empty() {
+        return Err(LuxError::Process(format!(
+            "log not found: {}",
+            targets[0].1.display()
+        )));
+    }
+    let label_lines = existing.len() > 1;
+    let mut matches = Vec::new();
+    let mut scanned = Vec::new();
+    for (label, path) in existing {
+        let reader = BufReader::new(fs::File::open(path)?);
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if !matcher.is_match(&line) {
+                continue;
+            }
+            if ctx.json {
+                let record = serde_json::from_str::<serde_json::Value>(&line)
+                    .unwrap_or_else(|_| serde_json::Value::String(line.clone()));
+                matches.push(json!({"file": label, "line": index + 1, "record": record}));
+            } else if label_lines {
+                println!("{label}:{line}");
+            } else {
+                println!("{line}");
+            }
         }
+        scanned.push(json!({"file": label, "path": path}));
+    }
+    if ctx.json {
+        output(
+            ctx,
+            json!({
+                "run_id": run_id,
+                "pattern": pattern,
+                "files": scanned,
+                "matches": matches,
+            }),
+        )?;
+    }
+    Ok(())
+}
 
-        fn ui_start(&self) -> Result<(), LuxError> {
-            self.record_call("ui_start");
-            self.maybe_fail(SetupRunnerFailurePoint::UiStart)
+fn dir_size(path: PathBuf) -> Result<u64, LuxError> {
+    let mut size = 0;
+    if path.is_file() {
+        return Ok(fs::metadata(path)?.len());
+    }
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                size += dir_size(entry.path())?;
+            } else if file_type.is_file() {
+                size += entry.metadata()?.len();
+            }
         }
     }
+    Ok(size)
+}
 
-    fn write_minimal_config(path: &Path) {
-        let base = path.parent().unwrap_or_else(|| Path::new("."));
-        let home = required_home_dir().expect("home");
-        let mut cfg = Config::default();
-        let trusted_root = base.join("trusted");
+/// The TCP listener has its own token so a leaked harness token (which agents can reach)
+/// does not also grant control of the runtime.
+fn resolve_runtime_tcp_token(cfg: &Config) -> Result<String, LuxError> {
+    let configured = cfg.runtime_control_plane.tcp_token.trim();
+    if !configured.is_empty() {
+        return Ok(configured.to_string());
+    }
+    if let Ok(token) = env::var(RUNTIME_TOKEN_ENV) {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_string());
+        }
+    }
+    Err(LuxError::Config(format!(
+        "runtime_control_plane.tcp_listen requires runtime_control_plane.tcp_token or {RUNTIME_TOKEN_ENV}"
+    )))
+}
+
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    left.iter()
+        .zip(right)
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+fn resolve_token(cfg: &Config) -> Result<String, LuxError> {
+    if !cfg.harness.api_token.trim().is_empty() {
+        return Ok(cfg.harness.api_token.clone());
+    }
+    if let Ok(token) = env::var("HARNESS_API_TOKEN") {
+        if !token.trim().is_empty() {
+            return Ok(token);
+        }
+    }
+    Err(LuxError::Config(
+        "HARNESS_API_TOKEN is required for server runs; set it in config.yaml or env".to_string(),
+    ))
+}
+
+fn extract_process_error_details(err: &LuxError) -> Option<ProcessErrorDetails> {
+    match err {
+        LuxError::ProcessDetailed { details, .. } => Some(details.as_ref().clone()),
+        _ => None,
+    }
+}
+
+fn output(ctx: &Context, payload: serde_json::Value) -> Result<(), LuxError> {
+    if ctx.json {
+        let wrapper = JsonResult {
+            ok: true,
+            result: Some(payload),
+            error: None,
+            error_details: None,
+        };
+        print_json(ctx, &wrapper)?;
+    } else {
+        println!("{}", payload);
+    }
+    Ok(())
+}
+
+fn print_json<T: Serialize>(ctx: &Context, payload: &T) -> Result<(), LuxError> {
+    let text = if ctx.json_pretty {
+        serde_json::to_string_pretty(payload)?
+    } else {
+        serde_json::to_string(payload)?
+    };
+    println!("{}", text);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use tempfile::tempdir;
+
+    #[derive(Debug, Clone)]
+    struct RecordedCall {
+        args: Vec<String>,
+        env_overrides: BTreeMap<String, String>,
+        capture_output: bool,
+    }
+
+    #[derive(Default)]
+    struct MockDockerRunner {
+        calls: RefCell<Vec<RecordedCall>>,
+        outputs: RefCell<Vec<CommandOutput>>,
+    }
+
+    impl MockDockerRunner {
+        fn push_output(&self, output: CommandOutput) {
+            self.outputs.borrow_mut().push(output);
+        }
+
+        fn calls(&self) -> Vec<RecordedCall> {
+            self.calls.borrow().clone()
+        }
+    }
+
+    impl DockerRunner for MockDockerRunner {
+        fn run(
+            &self,
+            args: &[String],
+            _cwd: &Path,
+            env_overrides: &BTreeMap<String, String>,
+            capture_output: bool,
+        ) -> Result<CommandOutput, io::Error> {
+            self.calls.borrow_mut().push(RecordedCall {
+                args: args.to_vec(),
+                env_overrides: env_overrides.clone(),
+                capture_output,
+            });
+            let mut queued = self.outputs.borrow_mut();
+            if queued.is_empty() {
+                return Ok(CommandOutput {
+                    status_code: 0,
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                });
+            }
+            Ok(queued.remove(0))
+        }
+    }
+
+    #[derive(Default)]
+    struct MockRuntimeCliRunner {
+        calls: Mutex<Vec<Vec<String>>>,
+        cwds: Mutex<Vec<Option<PathBuf>>>,
+        outputs: Mutex<Vec<CommandOutput>>,
+    }
+
+    impl MockRuntimeCliRunner {
+        fn push_output(&self, output: CommandOutput) {
+            self.outputs.lock().unwrap().push(output);
+        }
+
+        fn calls(&self) -> Vec<Vec<String>> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    impl RuntimeCliRunner for MockRuntimeCliRunner {
+        fn run(
+            &self,
+            _ctx: &Context,
+            argv: &[String],
+            cwd: Option<&Path>,
+        ) -> Result<CommandOutput, LuxError> {
+            self.calls.lock().unwrap().push(argv.to_vec());
+            self.cwds.lock().unwrap().push(cwd.map(Path::to_path_buf));
+            let mut queued = self.outputs.lock().unwrap();
+            if queued.is_empty() {
+                return Ok(CommandOutput {
+                    status_code: 0,
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                });
+            }
+            Ok(queued.remove(0))
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum SetupRunnerFailurePoint {
+        ShimEnable,
+        ProviderPlaneActive,
+        CollectorRunning,
+        CollectorStop,
+        CollectorStart,
+        UiStart,
+    }
+
+    #[derive(Debug, Default)]
+    struct MockSetupPostActionRunner {
+        calls: RefCell<Vec<String>>,
+        fail_at: Option<SetupRunnerFailurePoint>,
+        provider_active: Option<String>,
+        collector_running: bool,
+    }
+
+    impl MockSetupPostActionRunner {
+        fn with_failure(mut self, point: SetupRunnerFailurePoint) -> Self {
+            self.fail_at = Some(point);
+            self
+        }
+
+        fn with_provider_active(mut self, provider: &str) -> Self {
+            self.provider_active = Some(provider.to_string());
+            self
+        }
+
+        fn with_collector_running(mut self, running: bool) -> Self {
+            self.collector_running = running;
+            self
+        }
+
+        fn maybe_fail(&self, point: SetupRunnerFailurePoint) -> Result<(), LuxError> {
+            if self.fail_at == Some(point) {
+                return Err(LuxError::Process(format!("mock failure at {:?}", point)));
+            }
+            Ok(())
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.borrow().clone()
+        }
+
+        fn record_call(&self, name: &str) {
+            self.calls.borrow_mut().push(name.to_string());
+        }
+    }
+
+    impl SetupPostActionRunner for MockSetupPostActionRunner {
+        fn shim_enable(&self, _providers: &[String]) -> Result<(), LuxError> {
+            self.record_call("shim_enable");
+            self.maybe_fail(SetupRunnerFailurePoint::ShimEnable)
+        }
+
+        fn provider_plane_active(&self) -> Result<Option<String>, LuxError> {
+            self.record_call("provider_plane_active");
+            self.maybe_fail(SetupRunnerFailurePoint::ProviderPlaneActive)?;
+            Ok(self.provider_active.clone())
+        }
+
+        fn collector_running(&self) -> Result<bool, LuxError> {
+            self.record_call("collector_running");
+            self.maybe_fail(SetupRunnerFailurePoint::CollectorRunning)?;
+            Ok(self.collector_running)
+        }
+
+        fn collector_stop(&self) -> Result<(), LuxError> {
+            self.record_call("collector_stop");
+            self.maybe_fail(SetupRunnerFailurePoint::CollectorStop)
+        }
+
+        fn collector_start(&self) -> Result<(), LuxError> {
+            self.record_call("collector_start");
+            self.maybe_fail(SetupRunnerFailurePoint::CollectorStart)
+        }
+
+        fn ui_start(&self) -> Result<(), LuxError> {
+            self.record_call("ui_start");
+            self.maybe_fail(SetupRunnerFailurePoint::UiStart)
+        }
+    }
+
+    fn write_minimal_config(path: &Path) {
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+        let home = required_home_dir().expect("home");
+        let mut cfg = Config::default();
+        let trusted_root = base.join("trusted");
         cfg.paths.trusted_root = trusted_root.to_string_lossy().to_string();
         cfg.paths.log_root = trusted_root.join("logs").to_string_lossy().to_string();
         cfg.shims.bin_dir = trusted_root.join("bin").to_string_lossy().to_string();
@@ -9462,698 +16736,3514 @@ mod tests {
         fs::write(path, serde_yaml::to_string(&cfg).unwrap()).unwrap();
     }
 
-    fn write_default_compose_files(dir: &Path) {
-        fs::write(dir.join("compose.yml"), "services: {}\n").unwrap();
-        fs::write(dir.join("compose.ui.yml"), "services: {}\n").unwrap();
-    }
+    fn write_default_compose_files(dir: &Path) {
+        fs::write(dir.join("compose.yml"), "services: {}\n").unwrap();
+        fs::write(dir.join("compose.ui.yml"), "services: {}\n").unwrap();
+    }
+
+    fn make_context(dir: &Path) -> Context {
+        let config_path = dir.join("config.yaml");
+        let env_file = dir.join("compose.env");
+        Context {
+            config_path,
+            env_file,
+            bundle_dir: dir.to_path_buf(),
+            compose_file_overrides: Vec::new(),
+            project_name: None,
+            json: true,
+            json_pretty: true,
+            quiet: false,
+        }
+    }
+
+    #[test]
+    fn color_output_honors_flag_no_color_env_and_tty() {
+        assert!(resolve_color_enabled(false, None, true));
+        assert!(!resolve_color_enabled(false, None, false));
+        assert!(!resolve_color_enabled(true, None, true));
+        assert!(!resolve_color_enabled(false, Some("1"), true));
+        assert!(resolve_color_enabled(false, Some(""), true));
+    }
+
+    #[test]
+    fn config_unknown_field_errors() {
+        let yaml = r#"
+version: 2
+unknown: true
+paths:
+  log_root: ~/lux-logs
+  workspace_root: ~/lux-workspace
+"#;
+        let result: Result<Config, _> = serde_yaml::from_str(yaml);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn setup_post_actions_plan_respects_apply_and_interactive_flags() {
+        let choices = SetupPostSetupChoices::default();
+        assert!(plan_setup_post_actions(true, false, &choices).is_empty());
+        assert!(plan_setup_post_actions(false, true, &choices).is_empty());
+        assert!(plan_setup_post_actions(false, false, &choices).is_empty());
+    }
+
+    #[test]
+    fn setup_post_actions_plan_orders_shim_then_startup_actions() {
+        let mut choices = SetupPostSetupChoices::default();
+        choices.shim_choice = SetupShimChoice::EnableSelected;
+        choices.shim_providers = vec!["codex".to_string()];
+        choices.auto_start_services = true;
+
+        let actions = plan_setup_post_actions(true, true, &choices);
+        assert_eq!(
+            actions,
+            vec![
+                SetupPostAction::ShimEnable {
+                    providers: vec!["codex".to_string()],
+                },
+                SetupPostAction::StartupPreflightProviderInactive,
+                SetupPostAction::CollectorRefresh,
+                SetupPostAction::UiUp,
+            ]
+        );
+    }
+
+    #[test]
+    fn setup_post_actions_plan_skips_empty_selected_provider_list() {
+        let mut choices = SetupPostSetupChoices::default();
+        choices.shim_choice = SetupShimChoice::EnableSelected;
+        choices.shim_providers = Vec::new();
+        choices.auto_start_services = false;
+
+        let actions = plan_setup_post_actions(true, true, &choices);
+        assert!(actions.is_empty());
+    }
+
+    #[test]
+    fn setup_post_actions_stop_immediately_when_shim_enable_fails() {
+        let runner =
+            MockSetupPostActionRunner::default().with_failure(SetupRunnerFailurePoint::ShimEnable);
+        let actions = vec![
+            SetupPostAction::ShimEnable {
+                providers: vec!["codex".to_string()],
+            },
+            SetupPostAction::UiUp,
+        ];
+
+        let err = execute_setup_post_actions(&runner, &actions)
+            .expect_err("shim failure should abort subsequent actions");
+        let outcomes = err.1;
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].action, "shim_enable");
+        assert_eq!(outcomes[0].status, "failed");
+        assert_eq!(runner.calls(), vec!["shim_enable".to_string()]);
+    }
+
+    #[test]
+    fn setup_post_actions_preflight_blocks_when_provider_plane_active() {
+        let runner = MockSetupPostActionRunner::default().with_provider_active("codex");
+        let actions = vec![
+            SetupPostAction::StartupPreflightProviderInactive,
+            SetupPostAction::CollectorRefresh,
+            SetupPostAction::UiUp,
+        ];
+
+        let err = execute_setup_post_actions(&runner, &actions)
+            .expect_err("active provider plane should block collector refresh");
+        let outcomes = err.1;
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].action, "startup_preflight_provider_inactive");
+        assert_eq!(outcomes[0].status, "failed");
+        assert_eq!(runner.calls(), vec!["provider_plane_active".to_string()]);
+    }
+
+    #[test]
+    fn setup_post_actions_collector_refresh_restarts_when_already_running() {
+        let runner = MockSetupPostActionRunner::default().with_collector_running(true);
+        let actions = vec![SetupPostAction::CollectorRefresh];
+
+        let outcomes = execute_setup_post_actions(&runner, &actions)
+            .expect("collector refresh should succeed");
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].action, "collector_refresh");
+        assert_eq!(outcomes[0].status, "ok");
+        assert_eq!(
+            runner.calls(),
+            vec![
+                "collector_running".to_string(),
+                "collector_stop".to_string(),
+                "collector_start".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn setup_post_actions_ui_up_reports_local_url_and_port() {
+        let runner = MockSetupPostActionRunner::default();
+        let actions = vec![SetupPostAction::UiUp];
+
+        let outcomes = execute_setup_post_actions(&runner, &actions).expect("ui up should succeed");
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].action, "ui_up");
+        assert_eq!(outcomes[0].status, "ok");
+        assert!(outcomes[0].detail.contains("http://127.0.0.1:8090"));
+        assert!(outcomes[0].detail.contains("port 8090"));
+    }
+
+    #[test]
+    fn config_defaults_apply() {
+        let yaml = r#"version: 2"#;
+        let cfg: Config = serde_yaml::from_str(yaml).expect("config");
+        assert_eq!(cfg.version, 2);
+        let home = required_home_dir().expect("home");
+        let expected = default_paths_for_os(env::consts::OS, &home).expect("default paths");
+        assert_eq!(cfg.paths.log_root, expected.log_root);
+        assert_eq!(cfg.paths.workspace_root, expected.workspace_root);
+        assert!(cfg.collector.auto_start);
+        assert_eq!(cfg.collector.idle_timeout_min, 10_080);
+        assert_eq!(cfg.collector.rotate_every_min, 1_440);
+        assert_eq!(cfg.runtime_control_plane.socket_path, "");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runtime_socket_path_falls_back_when_default_is_too_long() {
+        let mut cfg: Config = serde_yaml::from_str("version: 2").expect("config");
+        let deep_trusted_root = PathBuf::from(format!("/tmp/{}", "a".repeat(180)));
+        cfg.paths.trusted_root = deep_trusted_root.to_string_lossy().to_string();
+        let preferred = deep_trusted_root.join("runtime").join("control_plane.sock");
+        assert!(unix_socket_path_too_long(&preferred));
+        let effective = effective_runtime_socket_path(&cfg);
+        assert!(!unix_socket_path_too_long(&effective));
+        assert_ne!(effective, preferred);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn config_validate_rejects_overlong_runtime_socket_path() {
+        let mut cfg = Config::default();
+        cfg.runtime_control_plane.socket_path = format!("/tmp/{}", "b".repeat(180));
+        let yaml = serde_yaml::to_string(&cfg).expect("serialize config");
+        let err = read_config_from_str(&yaml, Path::new("config.yaml"))
+            .expect_err("long socket path should fail");
+        assert!(err
+            .to_string()
+            .contains("runtime_control_plane.socket_path is too long"));
+    }
+
+    #[test]
+    fn config_include_merges_layers_with_including_file_winning() {
+        let dir = tempdir().unwrap();
+        let shared = dir.path().join("shared");
+        fs::create_dir_all(&shared).unwrap();
+        write_minimal_config(&shared.join("base.yaml"));
+        let config_path = dir.path().join("config.yaml");
+        fs::write(
+            &config_path,
+            "include:\n  - shared/base.yaml\nproviders:\n  codex:\n    commands:\n      default_timeout_sec: 300\n",
+        )
+        .unwrap();
+
+        let cfg = read_config(&config_path).expect("composed config");
+        assert_eq!(
+            cfg.paths.trusted_root,
+            shared.join("trusted").to_string_lossy()
+        );
+        assert_eq!(
+            cfg.providers["codex"].commands.default_timeout_sec,
+            Some(300)
+        );
+        assert!(!cfg.providers["codex"].ownership.root_comm.is_empty());
+        assert!(cfg.providers.contains_key("claude"));
+    }
+
+    #[test]
+    fn config_include_cycle_is_config_error() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.yaml"), "include: [b.yaml]\n").unwrap();
+        fs::write(dir.path().join("b.yaml"), "include: [a.yaml]\n").unwrap();
+
+        let err = read_config(&dir.path().join("a.yaml")).expect_err("cycle should fail");
+        assert!(matches!(err, LuxError::Config(_)));
+        assert!(err.to_string().contains("config include cycle detected"));
+    }
+
+    #[test]
+    fn config_validate_rejects_zero_default_timeout_sec() {
+        let mut cfg = Config::default();
+        cfg.providers
+            .get_mut("codex")
+            .unwrap()
+            .commands
+            .default_timeout_sec = Some(0);
+        let yaml = serde_yaml::to_string(&cfg).expect("serialize config");
+        let err = read_config_from_str(&yaml, Path::new("config.yaml"))
+            .expect_err("zero timeout should fail");
+        assert!(err
+            .to_string()
+            .contains("providers.codex.commands.default_timeout_sec must be greater than 0"));
+    }
+
+    #[test]
+    fn config_validate_checks_run_template_placeholders() {
+        for template in [
+            "agent --cwd {start_dir} --root {workspace} --run {run_id} {env:MODEL} {prompt}",
+            "bash -lc 'echo ${HOME} {a,b} {}' {prompt}",
+            "codex exec",
+        ] {
+            assert_eq!(run_template_placeholder_error(template), None, "{template}");
+        }
+
+        let mut cfg = Config::default();
+        cfg.providers
+            .get_mut("codex")
+            .unwrap()
+            .commands
+            .run_template = "codex exec --model {model} {prompt}".to_string();
+        let yaml = serde_yaml::to_string(&cfg).expect("serialize config");
+        let err = read_config_from_str(&yaml, Path::new("config.yaml"))
+            .expect_err("unknown placeholder should fail");
+        assert!(err
+            .to_string()
+            .contains("providers.codex.commands.run_template has unknown placeholder {model}"));
+
+        let err = run_template_placeholder_error("run {env:1BAD}").expect("invalid env key");
+        assert!(err.contains("invalid env placeholder {env:1BAD}"), "{err}");
+    }
+
+    #[test]
+    fn provider_resources_are_validated_and_rendered_as_deploy_limits() {
+        for memory in ["512m", "2g", "1GiB", "256mb", "1048576"] {
+            assert!(valid_memory_limit(memory), "{memory}");
+        }
+        for memory in ["", "0m", "m", "1.5g", "2tb", "1gig"] {
+            assert!(!valid_memory_limit(memory), "{memory}");
+        }
+        assert!(valid_cpus_limit("0.5"));
+        assert!(!valid_cpus_limit("0"));
+        assert!(!valid_cpus_limit("two"));
+
+        let mut cfg = Config::default();
+        cfg.providers.get_mut("codex").unwrap().resources = Some(ProviderResources {
+            cpus: Some("2".to_string()),
+            memory: Some("lots".to_string()),
+        });
+        let yaml = serde_yaml::to_string(&cfg).expect("serialize config");
+        let err = read_config_from_str(&yaml, Path::new("config.yaml"))
+            .expect_err("bad memory should fail");
+        assert!(err
+            .to_string()
+            .contains("providers.codex.resources.memory must be a size"));
+
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        let ctx = make_context(dir.path());
+        let mut provider = Provider {
+            auth_mode: AuthMode::HostState,
+            ..Provider::default()
+        };
+        let runtime = generate_provider_runtime_compose(&ctx, "claude", &provider, None).unwrap();
+        assert_eq!(runtime.resource_limits, None);
+        let content = fs::read_to_string(&runtime.override_file).unwrap();
+        assert!(!content.contains("deploy"));
+
+        provider.resources = Some(ProviderResources {
+            cpus: Some("1.5".to_string()),
+            memory: Some("2g".to_string()),
+        });
+        let runtime = generate_provider_runtime_compose(&ctx, "claude", &provider, None).unwrap();
+        assert_eq!(runtime.resource_limits, provider.resources);
+        let content: serde_yaml::Value =
+            serde_yaml::from_str(&fs::read_to_string(&runtime.override_file).unwrap()).unwrap();
+        let limits = &content["services"]["agent"]["deploy"]["resources"]["limits"];
+        assert_eq!(limits["cpus"], "1.5");
+        assert_eq!(limits["memory"], "2g");
+        assert!(content["services"]["harness"].get("deploy").is_none());
+    }
+
+    #[test]
+    fn provider_image_override_is_validated_and_rendered() {
+        for image in [
+            "agent",
+            "myorg/lux-agent:dev",
+            "ghcr.io/me/lux-agent:v1.2.3",
+            "localhost:5000/agent",
+            "registry.example.com:443/team/agent:2026_10",
+            "agent@sha256:0123abcd",
+        ] {
+            assert!(valid_image_reference(image), "{image}");
+        }
+        for image in [
+            "",
+            "MyOrg/agent",
+            "agent:",
+            "agent:-dev",
+            "agent:bad tag",
+            "-agent",
+            "org//agent",
+            "agent@sha256:",
+            "host:port/agent",
+        ] {
+            assert!(!valid_image_reference(image), "{image}");
+        }
+
+        let mut cfg = Config::default();
+        cfg.providers.get_mut("codex").unwrap().image = Some("My Image".to_string());
+        let yaml = serde_yaml::to_string(&cfg).expect("serialize config");
+        let err = read_config_from_str(&yaml, Path::new("config.yaml"))
+            .expect_err("bad image should fail");
+        assert!(err
+            .to_string()
+            .contains("providers.codex.image must be an image reference"));
+
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        let ctx = make_context(dir.path());
+        let mut provider = Provider {
+            auth_mode: AuthMode::HostState,
+            ..Provider::default()
+        };
+        let runtime = generate_provider_runtime_compose(&ctx, "claude", &provider, None).unwrap();
+        assert!(runtime.image.starts_with("ghcr.io/scottmaran/lux-agent:"));
+        let content = fs::read_to_string(&runtime.override_file).unwrap();
+        assert!(!content.contains("image"));
+
+        provider.image = Some("myorg/lux-agent:fork".to_string());
+        let runtime = generate_provider_runtime_compose(&ctx, "claude", &provider, None).unwrap();
+        assert_eq!(runtime.image, "myorg/lux-agent:fork");
+        let content: serde_yaml::Value =
+            serde_yaml::from_str(&fs::read_to_string(&runtime.override_file).unwrap()).unwrap();
+        assert_eq!(
+            content["services"]["agent"]["image"],
+            "myorg/lux-agent:fork"
+        );
+        assert!(content["services"]["harness"].get("image").is_none());
+    }
+
+    #[test]
+    fn healthchecks_are_validated_and_rendered_into_provider_override() {
+        for duration in ["10s", "1m30s", "500ms", "2h", "0s"] {
+            assert!(valid_compose_duration(duration), "{duration}");
+        }
+        for duration in ["", "10", "s", "1.5s", "10 s", "1d", "-1s"] {
+            assert!(!valid_compose_duration(duration), "{duration}");
+        }
+
+        let mut cfg = Config::default();
+        cfg.providers.get_mut("codex").unwrap().healthcheck = Some(ServiceHealthcheck {
+            command: "test -S /run/agent.sock".to_string(),
+            interval: Some("5 seconds".to_string()),
+            ..ServiceHealthcheck::default()
+        });
+        let yaml = serde_yaml::to_string(&cfg).expect("serialize config");
+        let err = read_config_from_str(&yaml, Path::new("config.yaml"))
+            .expect_err("bad interval should fail");
+        assert!(err
+            .to_string()
+            .contains("providers.codex.healthcheck.interval must be a duration"));
+
+        let mut cfg = Config::default();
+        cfg.harness.healthcheck = Some(ServiceHealthcheck::default());
+        let yaml = serde_yaml::to_string(&cfg).expect("serialize config");
+        let err = read_config_from_str(&yaml, Path::new("config.yaml"))
+            .expect_err("empty command should fail");
+        assert!(err
+            .to_string()
+            .contains("harness.healthcheck.command must be non-empty"));
+
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.yaml");
+        write_minimal_config(&config_path);
+        let ctx = make_context(dir.path());
+        let mut provider = Provider {
+            auth_mode: AuthMode::HostState,
+            ..Provider::default()
+        };
+        let runtime = generate_provider_runtime_compose(&ctx, "claude", &provider, None).unwrap();
+        let content = fs::read_to_string(&runtime.override_file).unwrap();
+        assert!(!content.contains("healthcheck"));
+
+        let mut cfg = read_config(&config_path).unwrap();
+        cfg.harness.healthcheck = Some(ServiceHealthcheck {
+            command: "curl -fsS http://127.0.0.1:8081/healthz".to_string(),
+            ..ServiceHealthcheck::default()
+        });
+        fs::write(&config_path, serde_yaml::to_string(&cfg).unwrap()).unwrap();
+        provider.healthcheck = Some(ServiceHealthcheck {
+            command: "test -f /tmp/ready".to_string(),
+            interval: Some("5s".to_string()),
+            timeout: Some("2s".to_string()),
+            retries: Some(12),
+            start_period: Some("1m".to_string()),
+        });
+        let runtime = generate_provider_runtime_compose(&ctx, "claude", &provider, None).unwrap();
+        let content: serde_yaml::Value =
+            serde_yaml::from_str(&fs::read_to_string(&runtime.override_file).unwrap()).unwrap();
+        let agent = &content["services"]["agent"]["healthcheck"];
+        assert_eq!(agent["test"][0], "CMD-SHELL");
+        assert_eq!(agent["test"][1], "test -f /tmp/ready");
+        assert_eq!(agent["interval"], "5s");
+        assert_eq!(agent["timeout"], "2s");
+        assert_eq!(agent["retries"], 12);
+        assert_eq!(agent["start_period"], "1m");
+        assert!(agent.get("command").is_none());
+        let harness = &content["services"]["harness"]["healthcheck"];
+        assert_eq!(
+            harness["test"][1],
+            "curl -fsS http://127.0.0.1:8081/healthz"
+        );
+        assert!(harness.get("interval").is_none());
+    }
+
+    #[test]
+    fn run_mounts_guard_trusted_root_reached_through_symlink() {
+        let dir = tempdir().unwrap();
+        let real_trusted = dir.path().join("real-trusted");
+        fs::create_dir_all(&real_trusted).unwrap();
+        std::os::unix::fs::symlink(&real_trusted, dir.path().join("trusted")).unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        let cfg = read_config(&dir.path().join("config.yaml")).unwrap();
+        let policy = resolve_config_policy_paths(&cfg).unwrap();
+        for path in [
+            &policy.runtime_root,
+            &policy.secrets_root,
+            &policy.state_root,
+            &policy.log_root,
+        ] {
+            fs::create_dir_all(path).unwrap();
+        }
+        let real_trusted = fs::canonicalize(&real_trusted).unwrap();
+
+        let reject = |spec: String, needle: &str| {
+            let err = parse_run_mounts(&[spec], &policy).unwrap_err();
+            assert!(err.to_string().contains(needle), "{err}");
+        };
+        for name in ["runtime", "secrets"] {
+            let host = fs::canonicalize(policy.trusted_root.join(name)).unwrap();
+            assert!(host.starts_with(&real_trusted));
+            reject(format!("{}:/x:rw", host.display()), "runtime or secrets");
+            reject(format!("{}:/x:ro", host.display()), "runtime or secrets");
+        }
+        reject(
+            format!("{}:/x:rw", real_trusted.join("state").display()),
+            "must be read-only",
+        );
+        reject(
+            format!("{}:/x:rw", real_trusted.join("logs").display()),
+            "must be read-only",
+        );
+        let mounts = parse_run_mounts(
+            &[format!("{}:/x:ro", real_trusted.join("logs").display())],
+            &policy,
+        )
+        .unwrap();
+        assert!(mounts[0].read_only);
+    }
+
+    #[test]
+    fn run_mounts_are_validated_and_applied_to_agent_override() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        let ctx = make_context(dir.path());
+        let cfg = read_config(&ctx.config_path).unwrap();
+        let policy = resolve_config_policy_paths(&cfg).unwrap();
+        let reference = policy.trusted_root.join("reference");
+        fs::create_dir_all(&reference).unwrap();
+        fs::create_dir_all(&policy.log_root).unwrap();
+        let reference = fs::canonicalize(&reference).unwrap();
+        let log_root = fs::canonicalize(&policy.log_root).unwrap();
+
+        let mounts = parse_run_mounts(
+            &[
+                format!("{}:/ref:ro", reference.display()),
+                format!("{}:/evidence:ro", log_root.display()),
+            ],
+            &policy,
+        )
+        .unwrap();
+        assert_eq!(mounts[0].spec(), format!("{}:/ref:ro", reference.display()));
+        assert!(mounts[1].read_only);
+
+        let reject = |spec: String, needle: &str| {
+            let err = parse_run_mounts(&[spec], &policy).unwrap_err();
+            assert!(err.to_string().contains(needle), "{err}");
+        };
+        reject(
+            format!("{}:/evidence", log_root.display()),
+            "must be read-only",
+        );
+        reject(
+            format!("{}:/evidence:rw", log_root.display()),
+            "must be read-only",
+        );
+        reject(format!("{}:/work/ref:ro", reference.display()), "reserved");
+        reject(
+            format!("{}:relative:ro", reference.display()),
+            "must be absolute",
+        );
+        reject(
+            format!("{}/missing:/ref", reference.display()),
+            "does not exist",
+        );
+        reject(
+            "/:/host:ro".to_string(),
+            "under $HOME or paths.trusted_root",
+        );
+        reject(format!("{}:/ref:rx", reference.display()), "HOST:CONTAINER");
+        reject(format!("{}:/ref", reference.display()), "must be read-only");
+
+        write_active_run_state(
+            &policy.state_root,
+            "lux__2026_02_12_12_00_00",
+            &policy.workspace_root,
+            &mounts,
+            "lux",
+        )
+        .unwrap();
+        let provider = Provider {
+            auth_mode: AuthMode::HostState,
+            ..Provider::default()
+        };
+        let runtime = generate_provider_runtime_compose(&ctx, "claude", &provider, None).unwrap();
+        let content: serde_yaml::Value =
+            serde_yaml::from_str(&fs::read_to_string(&runtime.override_file).unwrap()).unwrap();
+        let volumes = content["services"]["agent"]["volumes"]
+            .as_sequence()
+            .unwrap();
+        assert!(volumes
+            .iter()
+            .any(|volume| volume.as_str() == Some(mounts[0].spec().as_str())));
+    }
+
+    #[test]
+    fn run_timeout_prefers_flag_over_provider_default() {
+        let mut provider = Provider::default();
+        assert_eq!(resolve_run_timeout_sec(None, &provider), None);
+        provider.commands.default_timeout_sec = Some(600);
+        assert_eq!(resolve_run_timeout_sec(None, &provider), Some(600));
+        assert_eq!(resolve_run_timeout_sec(Some(30), &provider), Some(30));
+    }
+
+    #[test]
+    fn provider_runtime_compose_sets_harness_run_timeout() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        let ctx = make_context(dir.path());
+        let mut provider = Provider {
+            auth_mode: AuthMode::HostState,
+            ..Provider::default()
+        };
+
+        let runtime = generate_provider_runtime_compose(&ctx, "claude", &provider, None).unwrap();
+        let content = fs::read_to_string(&runtime.override_file).unwrap();
+        assert!(!content.contains("HARNESS_RUN_TIMEOUT_SEC"));
+
+        provider.commands.default_timeout_sec = Some(900);
+        let runtime = generate_provider_runtime_compose(&ctx, "claude", &provider, None).unwrap();
+        let content = fs::read_to_string(&runtime.override_file).unwrap();
+        assert!(content.contains("HARNESS_RUN_TIMEOUT_SEC=900"));
+    }
+
+    #[test]
+    fn expand_tilde_works() {
+        let expanded = expand_path("~/lux-logs");
+        assert!(!expanded.starts_with("~/"));
+    }
+
+    #[test]
+    fn display_path_with_home_rewrites_home_prefix() {
+        let home = PathBuf::from("/tmp/lux-home");
+        assert_eq!(display_path_with_home(&home, Some(&home)), "$HOME");
+        assert_eq!(
+            display_path_with_home(&home.join("workspace"), Some(&home)),
+            "$HOME/workspace"
+        );
+        assert_eq!(
+            display_path_with_home(Path::new("/var/lib/lux/logs"), Some(&home)),
+            "/var/lib/lux/logs"
+        );
+        assert_eq!(
+            display_path_with_home(&home.join("workspace"), None),
+            "/tmp/lux-home/workspace"
+        );
+    }
+
+    #[test]
+    fn env_file_written() {
+        let dir = tempdir().unwrap();
+        let env_path = dir.path().join("compose.env");
+        let cfg: Config = serde_yaml::from_str("version: 2").unwrap();
+        let envs = config_to_env(&cfg);
+        write_env_file(&env_path, &envs).unwrap();
+        let content = fs::read_to_string(&env_path).unwrap();
+        assert!(content.contains("LUX_VERSION="));
+        assert!(content.contains("LUX_LOG_ROOT="));
+        assert!(content.contains("LUX_RUNTIME_DIR="));
+        assert!(content.contains("LUX_RUNTIME_GID="));
+        assert!(content.contains("COLLECTOR_DNS_CAPTURE_BYTES=512"));
+        assert!(content.contains("COLLECTOR_SAMPLING_RATE=1"));
+        assert!(content.contains("COLLECTOR_PID_RATE_MAX_EVENTS=0"));
+        assert!(content.contains("COLLECTOR_PID_RATE_WINDOW_MS=1000"));
+        assert!(content.contains("COLLECTOR_RINGBUF_BYTES=16777216"));
+        assert!(content.contains("COLLECTOR_SLOW_CONNECT_MS=1000"));
+        assert!(content.contains("COLLECTOR_EXCLUDE_COMM="));
+        assert!(content.contains("COLLECTOR_IGNORE_LOOPBACK=false"));
+        assert!(content.contains("COLLECTOR_BATCH_EVENTS=false"));
+    }
+
+    #[test]
+    fn env_values_with_control_characters_are_rejected_and_specials_quoted() {
+        let dir = tempdir().unwrap();
+        let env_path = dir.path().join("compose.env");
+        let mut envs = BTreeMap::new();
+        envs.insert(
+            "LUX_WORKSPACE_ROOT".to_string(),
+            "/home/me/work\nHARNESS_API_TOKEN=stolen".to_string(),
+        );
+        let err = write_env_file(&env_path, &envs).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("LUX_WORKSPACE_ROOT contains a newline"));
+        assert!(!env_path.exists());
+        envs.clear();
+        envs.insert("BAD KEY".to_string(), "x".to_string());
+        assert!(write_env_file(&env_path, &envs).is_err());
+        for (value, written) in [
+            ("tok$en", r#""tok\$en""#),
+            ("${HOME}", r#""\${HOME}""#),
+            ("it's", r#""it's""#),
+            ("say \"hi\"", r#""say \"hi\"""#),
+            ("/work #comment", r#""/work #comment""#),
+            ("/work\t#comment", "\"/work\t#comment\""),
+            (r"C:\a$b", r#""C:\\a\$b""#),
+            (" padded ", r#"" padded ""#),
+        ] {
+            envs.clear();
+            envs.insert("HARNESS_API_TOKEN".to_string(), value.to_string());
+            write_env_file(&env_path, &envs).unwrap();
+            assert_eq!(
+                fs::read_to_string(&env_path).unwrap(),
+                format!("HARNESS_API_TOKEN={written}\n")
+            );
+            assert_eq!(
+                parse_dotenv_file(&env_path, "--run-env-file").unwrap(),
+                vec![format!("HARNESS_API_TOKEN={value}")]
+            );
+            fs::remove_file(&env_path).unwrap();
+        }
+        envs.clear();
+        envs.insert(
+            "LUX_WORKSPACE_ROOT".to_string(),
+            "/home/me/a#b c".to_string(),
+        );
+        write_env_file(&env_path, &envs).unwrap();
+        assert_eq!(
+            fs::read_to_string(&env_path).unwrap(),
+            "LUX_WORKSPACE_ROOT=/home/me/a#b c\n"
+        );
+        fs::remove_file(&env_path).unwrap();
+
+        let mut cfg = Config::default();
+        cfg.providers
+            .get_mut("codex")
+            .unwrap()
+            .commands
+            .run_template = "bash -lc {prompt}\r\nevil".to_string();
+        let yaml = serde_yaml::to_string(&cfg).expect("serialize config");
+        let err = read_config_from_str(&yaml, Path::new("config.yaml"))
+            .expect_err("control characters should fail");
+        assert!(err
+            .to_string()
+            .contains("providers.codex.commands.run_template contains a newline"));
+        let mut cfg = Config::default();
+        cfg.paths.log_root = "/var/lib/lux/logs\u{1b}[2J".to_string();
+        let yaml = serde_yaml::to_string(&cfg).expect("serialize config");
+        assert!(read_config_from_str(&yaml, Path::new("config.yaml"))
+            .unwrap_err()
+            .to_string()
+            .contains("paths.log_root contains a newline or control character"));
+
+        let err = compose_environment_entries(vec!["HARNESS_RUN_CMD_TEMPLATE=a\nb".to_string()])
+            .unwrap_err();
+        assert!(err.to_string().contains("HARNESS_RUN_CMD_TEMPLATE"));
+        assert!(compose_environment_entries(vec!["NOEQUALS".to_string()]).is_err());
+
+        write_minimal_config(&dir.path().join("config.yaml"));
+        let ctx = make_context(dir.path());
+        let mut provider = Provider {
+            auth_mode: AuthMode::HostState,
+            ..Provider::default()
+        };
+        provider.commands.run_template =
+            "bash -lc 'echo $HOME: \"quoted\" #not-a-comment' {prompt}".to_string();
+        let runtime = generate_provider_runtime_compose(&ctx, "claude", &provider, None).unwrap();
+        let content: serde_yaml::Value =
+            serde_yaml::from_str(&fs::read_to_string(&runtime.override_file).unwrap()).unwrap();
+        let harness_env = content["services"]["harness"]["environment"]
+            .as_sequence()
+            .unwrap();
+        assert!(harness_env.iter().any(|entry| entry.as_str()
+            == Some("HARNESS_RUN_CMD_TEMPLATE=bash -lc 'echo $$HOME: \"quoted\" #not-a-comment' {prompt}")));
+
+        provider.commands.tui = "bash -l\nHARNESS_API_TOKEN=x".to_string();
+        assert!(generate_provider_runtime_compose(&ctx, "claude", &provider, None).is_err());
+    }
+
+    #[test]
+    fn collector_exclude_comm_reaches_env_and_rejects_commas() {
+        let mut cfg: Config = serde_yaml::from_str(
+            "version: 2\ncollector:\n  exclude_comm: [\"node_exporter\", \" chronyd \"]\n",
+        )
+        .unwrap();
+        let envs = config_to_env(&cfg);
+        assert_eq!(
+            envs.get("COLLECTOR_EXCLUDE_COMM").map(String::as_str),
+            Some("node_exporter,chronyd")
+        );
+
+        cfg.collector.exclude_comm = vec!["a,b".to_string()];
+        let err = validate_config(&cfg).unwrap_err().to_string();
+        assert!(err.contains("collector.exclude_comm"), "{err}");
+    }
+
+    #[test]
+    fn docker_project_name_must_be_a_valid_compose_name() {
+        let mut cfg = Config::default();
+        for name in ["", "  ", "My_Stack", "-lux", "lux stack"] {
+            cfg.docker.project_name = name.to_string();
+            let err = validate_config(&cfg).unwrap_err().to_string();
+            assert!(err.contains("invalid docker.project_name"), "{err}");
+        }
+        cfg.docker.project_name = "lux-dev_2".to_string();
+        validate_config(&cfg).unwrap();
+    }
+
+    #[test]
+    fn yaml_patch_preserves_comments_and_spacing() {
+        let input = r#"# top comment
+version: 2
+
+paths:   # paths comment
+  log_root : ~/lux-logs   # inline
+  workspace_root: "~/lux-workspace"   # keep quotes
+
+providers:
+  codex:
+    auth_mode: api_key  # keep
+    mount_host_state_in_api_mode: false
+    commands:
+      tui: "codex"
+      run_template: "codex exec {prompt}"
+    auth:
+      api_key:
+        secrets_file: ~/.config/lux/secrets/codex.env
+        env_key: OPENAI_API_KEY
+      host_state:
+        paths:
+          - ~/.codex/auth.json
+    ownership:
+      root_comm:
+        - codex
+"#;
+
+        let mut edits = SetupYamlEdits::default();
+        edits.log_root = Some("/tmp/new-logs".to_string());
+
+        let (patched, changed) = patch_setup_config_yaml(input, &edits).unwrap();
+        assert!(changed);
+        assert!(patched.ends_with('\n'));
+
+        // Comments must remain byte-for-byte.
+        assert!(patched.contains("# top comment"));
+        assert!(patched.contains("paths:   # paths comment"));
+        assert!(patched.contains("  log_root : /tmp/new-logs   # inline"));
+        assert!(patched.contains("  workspace_root: \"~/lux-workspace\"   # keep quotes"));
+
+        // Unchanged provider line should remain unchanged.
+        assert!(patched.contains("    auth_mode: api_key  # keep"));
+    }
+
+    #[test]
+    fn up_wait_timeout_builds_expected_compose_args() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let runner = MockDockerRunner::default();
+
+        handle_up(
+            &ctx,
+            UpOptions {
+                collector_only: true,
+                wait: true,
+                timeout_sec: Some(45),
+                ..Default::default()
+            },
+            &runner,
+        )
+        .unwrap();
+
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 3);
+
+        let ps_args = &calls[0].args;
+        assert!(ps_args.iter().any(|x| x == "ps"));
+        assert!(calls[0].env_overrides.contains_key("LUX_WORKSPACE_ROOT"));
+        assert!(!calls[0].env_overrides.contains_key("LUX_RUN_ID"));
+
+        let args = &calls[2].args;
+        assert!(calls[2].capture_output);
+        assert!(args.iter().any(|x| x == "up"));
+        assert!(args.iter().any(|x| x == "--wait"));
+        let idx = args.iter().position(|x| x == "--wait-timeout").unwrap();
+        assert_eq!(args[idx + 1], "45");
+        assert!(calls[2].env_overrides.contains_key("LUX_RUN_ID"));
+        assert!(calls[2].env_overrides.contains_key("LUX_WORKSPACE_ROOT"));
+    }
+
+    #[test]
+    fn provider_down_passes_provider_compose_profiles() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.yaml");
+        write_minimal_config(&config_path);
+        write_default_compose_files(dir.path());
+        let mut cfg = read_config(&config_path).unwrap();
+        cfg.providers.get_mut("codex").unwrap().compose_profiles =
+            vec!["proxy".to_string(), "tracing".to_string()];
+        fs::write(&config_path, serde_yaml::to_string(&cfg).unwrap()).unwrap();
+        let ctx = make_context(dir.path());
+
+        let runner = MockDockerRunner::default();
+        runner.push_output(CommandOutput {
+            status_code: 0,
+            stdout: b"collector\nagent\nharness\nproxy-sidecar\n".to_vec(),
+            stderr: Vec::new(),
+        });
+        runner.push_output(CommandOutput {
+            status_code: 0,
+            stdout: b"collector\nagent\nharness\n".to_vec(),
+            stderr: Vec::new(),
+        });
+        handle_down(&ctx, Some("codex".to_string()), false, false, &runner).unwrap();
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 3);
+        assert!(calls[0].args.iter().any(|x| x == "--profile"));
+        assert!(calls[0]
+            .args
+            .ends_with(&["config".to_string(), "--services".to_string()]));
+        assert!(!calls[1].args.iter().any(|x| x == "--profile"));
+        let args = &calls[2].args;
+        let stop_idx = args.iter().position(|x| x == "stop").unwrap();
+        assert_eq!(
+            &args[stop_idx + 1..],
+            &["agent", "harness", "proxy-sidecar"]
+        );
+        let profiles: Vec<&str> = args
+            .windows(2)
+            .filter(|pair| pair[0] == "--profile")
+            .map(|pair| pair[1].as_str())
+            .collect();
+        assert_eq!(profiles, vec!["proxy", "tracing"]);
+        let profile_idx = args.iter().position(|x| x == "--profile").unwrap();
+        assert!(profile_idx < stop_idx);
+
+        let runner = MockDockerRunner::default();
+        handle_down(&ctx, Some("claude".to_string()), false, false, &runner).unwrap();
+        assert_eq!(runner.calls().len(), 1);
+        assert!(!runner.calls()[0].args.iter().any(|x| x == "--profile"));
+
+        let runner = MockDockerRunner::default();
+        handle_down(&ctx, None, true, false, &runner).unwrap();
+        assert!(!runner.calls()[0].args.iter().any(|x| x == "--profile"));
+
+        cfg.providers.get_mut("codex").unwrap().compose_profiles = vec![" ".to_string()];
+        let yaml = serde_yaml::to_string(&cfg).unwrap();
+        let err = read_config_from_str(&yaml, Path::new("config.yaml"))
+            .expect_err("blank profile should fail");
+        assert!(err
+            .to_string()
+            .contains("providers.codex.compose_profiles entries must be non-empty"));
+    }
+
+    #[test]
+    fn provider_aliases_resolve_to_canonical_name() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.yaml");
+        write_minimal_config(&config_path);
+        write_default_compose_files(dir.path());
+        let mut cfg = read_config(&config_path).unwrap();
+        cfg.providers.get_mut("codex").unwrap().aliases = vec!["c".to_string()];
+        fs::write(&config_path, serde_yaml::to_string(&cfg).unwrap()).unwrap();
+        let cfg = read_config(&config_path).unwrap();
+        assert_eq!(resolve_provider_name(&cfg, "c").unwrap(), "codex");
+        assert_eq!(resolve_provider_name(&cfg, "claude").unwrap(), "claude");
+        assert!(resolve_provider_name(&cfg, "x")
+            .unwrap_err()
+            .to_string()
+            .contains("provider 'x' is not defined"));
+        assert!(matches!(
+            resolve_lifecycle_target(&cfg, Some("c".to_string()), false).unwrap(),
+            LifecycleTarget::Provider(name) if name == "codex"
+        ));
+        assert_eq!(
+            resolve_shim_providers(&cfg, vec!["c".to_string(), "codex".to_string()]),
+            vec!["codex".to_string()]
+        );
+
+        let mut ambiguous = cfg.clone();
+        ambiguous.providers.get_mut("claude").unwrap().aliases = vec!["c".to_string()];
+        assert!(resolve_provider_name(&ambiguous, "c")
+            .unwrap_err()
+            .to_string()
+            .contains("provider alias 'c' is ambiguous (matches claude, codex)"));
+        let yaml = serde_yaml::to_string(&ambiguous).unwrap();
+        let err = read_config_from_str(&yaml, Path::new("config.yaml"))
+            .expect_err("shared alias should fail");
+        assert!(err
+            .to_string()
+            .contains("aliases entry 'c' is already used by provider"));
+
+        let mut colliding = cfg.clone();
+        colliding.providers.get_mut("codex").unwrap().aliases = vec!["claude".to_string()];
+        let yaml = serde_yaml::to_string(&colliding).unwrap();
+        let err = read_config_from_str(&yaml, Path::new("config.yaml"))
+            .expect_err("alias shadowing a provider should fail");
+        assert!(err
+            .to_string()
+            .contains("providers.codex.aliases entry 'claude' collides with provider 'claude'"));
+    }
+
+    #[test]
+    fn up_build_appends_build_after_pull() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let runner = MockDockerRunner::default();
+
+        handle_up(
+            &ctx,
+            UpOptions {
+                collector_only: true,
+                pull: Some("always".to_string()),
+                build: true,
+                ..Default::default()
+            },
+            &runner,
+        )
+        .unwrap();
+
+        let calls = runner.calls();
+        let args = &calls[2].args;
+        let up_idx = args.iter().position(|x| x == "up").unwrap();
+        let pull_idx = args.iter().position(|x| x == "--pull").unwrap();
+        let build_idx = args.iter().position(|x| x == "--build").unwrap();
+        assert!(up_idx < pull_idx);
+        assert_eq!(args[pull_idx + 1], "always");
+        assert!(pull_idx < build_idx);
+        assert_eq!(args.last().map(String::as_str), Some("collector"));
+    }
+
+    #[test]
+    fn up_no_cache_runs_uncached_build_only_with_build() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let cfg = read_config(&ctx.config_path).unwrap();
+        let policy = resolve_config_policy_paths(&cfg).unwrap();
+
+        for (build, no_cache, expect_build_call) in [
+            (true, true, true),
+            (true, false, false),
+            (false, true, false),
+        ] {
+            let runner = MockDockerRunner::default();
+            handle_up(
+                &ctx,
+                UpOptions {
+                    collector_only: true,
+                    build,
+                    no_cache,
+                    ..Default::default()
+                },
+                &runner,
+            )
+            .unwrap();
+            clear_active_run_state(&policy.state_root).unwrap();
+
+            let calls = runner.calls();
+            let no_cache_calls: Vec<&RecordedCall> = calls
+                .iter()
+                .filter(|call| call.args.iter().any(|arg| arg == "--no-cache"))
+                .collect();
+            assert_eq!(no_cache_calls.len(), usize::from(expect_build_call));
+            if expect_build_call {
+                let args = &no_cache_calls[0].args;
+                let build_idx = args.iter().position(|x| x == "build").unwrap();
+                assert_eq!(args[build_idx + 1], "--no-cache");
+                assert_eq!(args.last().map(String::as_str), Some("collector"));
+                assert!(no_cache_calls[0].env_overrides.contains_key("LUX_RUN_ID"));
+            }
+            let up = calls.last().unwrap();
+            assert!(up.args.iter().any(|x| x == "up"));
+            assert_eq!(up.args.iter().any(|x| x == "--build"), build);
+            assert!(!up.args.iter().any(|x| x == "--no-cache"));
+        }
+    }
+
+    fn write_config_with_local_secrets(dir: &Path) -> PathBuf {
+        write_minimal_config(&dir.join("config.yaml"));
+        let mut cfg: Config =
+            serde_yaml::from_str(&fs::read_to_string(dir.join("config.yaml")).unwrap()).unwrap();
+        let secrets_file = dir.join("secrets").join("codex.env");
+        cfg.providers
+            .get_mut("codex")
+            .unwrap()
+            .auth
+            .api_key
+            .secrets_file = secrets_file.to_string_lossy().to_string();
+        fs::write(
+            dir.join("config.yaml"),
+            serde_yaml::to_string(&cfg).unwrap(),
+        )
+        .unwrap();
+        secrets_file
+    }
+
+    #[test]
+    fn secrets_rotate_replaces_file_without_touching_idle_plane() {
+        let dir = tempdir().unwrap();
+        let secrets_file = write_config_with_local_secrets(dir.path());
+        write_provider_secrets_file(&secrets_file, "OPENAI_API_KEY", "old-key", false).unwrap();
+        let ctx = make_context(dir.path());
+        let runner = MockDockerRunner::default();
+
+        secrets_rotate(
+            &ctx,
+            "codex",
+            Some("new-key".to_string()),
+            false,
+            true,
+            false,
+            &runner,
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&secrets_file).unwrap(),
+            "OPENAI_API_KEY='new-key'\n"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&secrets_file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert!(runner.calls().is_empty());
+    }
+
+    #[test]
+    fn secrets_rotate_rejects_host_state_provider() {
+        let dir = tempdir().unwrap();
+        write_config_with_local_secrets(dir.path());
+        let ctx = make_context(dir.path());
+        let runner = MockDockerRunner::default();
+
+        let err = secrets_rotate(
+            &ctx,
+            "claude",
+            Some("new-key".to_string()),
+            false,
+            false,
+            true,
+            &runner,
+        )
+        .expect_err("host_state provider should be refused");
+        assert!(err.to_string().contains("auth_mode=host_state"));
+    }
+
+    #[test]
+    fn up_dry_run_resolves_provider_plan_without_writing_run_state() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+
+        handle_up_dry_run(
+            &ctx,
+            UpOptions {
+                provider: Some("claude".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let cfg = read_config(&ctx.config_path).unwrap();
+        let policy = resolve_config_policy_paths(&cfg).unwrap();
+        assert!(load_active_run_state(&policy.state_root).unwrap().is_none());
+        assert!(load_active_provider_state(&policy.state_root)
+            .unwrap()
+            .is_none());
+        assert!(policy
+            .runtime_root
+            .join("compose.provider.claude.yml")
+            .exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keychain_items_materialize_private_files_and_skip_unreadable_items() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempdir().unwrap();
+        let dir = provider_keychain_dir(tmp.path(), "claude");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("stale"), "old").unwrap();
+        let items = vec![
+            ProviderKeychainItem {
+                service: "Claude Code-credentials".to_string(),
+                path: "~/.claude/.credentials.json".to_string(),
+            },
+            ProviderKeychainItem {
+                service: "missing".to_string(),
+                path: "/etc/missing".to_string(),
+            },
+        ];
+        let mut warnings = Vec::new();
+        let imported = materialize_keychain_items(
+            &dir,
+            "claude",
+            &items,
+            |service| {
+                if service == "missing" {
+                    Err(LuxError::Process("item not found".to_string()))
+                } else {
+                    Ok("{\"token\":\"s3cret\"}".to_string())
+                }
+            },
+            &mut warnings,
+        )
+        .unwrap();
+
+        assert_eq!(imported.len(), 1);
+        let (host_path, destination) = &imported[0];
+        assert_eq!(destination, "/home/agent/.claude/.credentials.json");
+        assert_eq!(
+            fs::read_to_string(host_path).unwrap(),
+            "{\"token\":\"s3cret\"}"
+        );
+        let mode = fs::metadata(host_path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+        assert!(!dir.join("stale").exists());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("'missing'"));
+        assert!(!warnings[0].contains("s3cret"));
+
+        clear_provider_keychain_material(&dir).unwrap();
+        assert!(!dir.exists());
+        clear_provider_keychain_material(&dir).unwrap();
+    }
+
+    #[test]
+    fn up_dry_run_is_not_routed_through_runtime() {
+        let cli = Cli::parse_from(["lux", "up", "--collector-only", "--dry-run"]);
+        assert!(!should_route_through_runtime(&cli.command));
+        let cli = Cli::parse_from(["lux", "up", "--collector-only"]);
+        assert!(should_route_through_runtime(&cli.command));
+    }
+
+    #[test]
+    fn log_tail_formats_render_json_and_plain_lines() {
+        let record = "{ \"ts\": \"2026-02-12T12:00:00Z\",  \"n\": 1 }";
+        assert_eq!(render_log_tail_line(record, "raw").unwrap(), record);
+        assert_eq!(
+            render_log_tail_line(record, "ndjson").unwrap(),
+            "{\"n\":1,\"ts\":\"2026-02-12T12:00:00Z\"}"
+        );
+        assert!(render_log_tail_line(record, "pretty")
+            .unwrap()
+            .contains("\n  \"n\": 1"));
+        let audit = "type=SYSCALL msg=audit(1.0:1): syscall=59";
+        assert_eq!(
+            render_log_tail_line(audit, "ndjson").unwrap(),
+            "\"type=SYSCALL msg=audit(1.0:1): syscall=59\""
+        );
+        assert_eq!(render_log_tail_line(audit, "pretty").unwrap(), audit);
+    }
+
+    #[test]
+    fn log_time_window_parses_bounds_and_filters_records() {
+        let now = parse_rfc3339_utc("2026-02-12T12:00:00Z").unwrap();
+        let window = LogTimeWindow::parse(Some("10m"), Some("2026-02-12T11:58:00Z"), now).unwrap();
+        assert_eq!(window.since, parse_rfc3339_utc("2026-02-12T11:50:00Z"));
+        assert!(window.contains_line("{\"ts\":\"2026-02-12T11:55:00Z\"}"));
+        assert!(!window.contains_line("{\"ts\":\"2026-02-12T11:59:00Z\"}"));
+        assert!(!window.contains_line("{\"ts\":\"2026-02-12T11:40:00Z\"}"));
+        assert!(!window.contains_line("type=SYSCALL msg=audit(1.0:1)"));
+
+        let unbounded = LogTimeWindow::parse(None, None, now).unwrap();
+        assert!(unbounded.contains_line("not json"));
+
+        assert!(LogTimeWindow::parse(Some("yesterday"), None, now).is_err());
+        assert!(LogTimeWindow::parse(Some("1m"), Some("2h"), now).is_err());
+    }
+
+    #[test]
+    fn run_input_file_is_inlined_as_prompt_for_runtime_proxy() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("prompt.txt");
+        fs::write(&input, "say \"hi\"\nthen $(exit)\n").unwrap();
+        let input_arg = input.to_string_lossy().to_string();
+        let raw_args: Vec<String> = ["run", "--provider", "codex", "--input-file", &input_arg]
+            .iter()
+            .map(|value| value.to_string())
+            .collect();
+        let cli = Cli::parse_from(std::iter::once("lux".to_string()).chain(raw_args.clone()));
+        let proxied = runtime_proxy_args(&cli.command, &raw_args).unwrap();
+        assert_eq!(
+            proxied,
+            vec![
+                "run",
+                "--provider",
+                "codex",
+                "--",
+                "say \"hi\"\nthen $(exit)"
+            ]
+        );
+        let reparsed = Cli::parse_from(std::iter::once("lux".to_string()).chain(proxied));
+        assert!(matches!(
+            reparsed.command,
+            Commands::Run { prompt: Some(ref prompt), input_file: None, .. }
+                if prompt == "say \"hi\"\nthen $(exit)"
+        ));
+
+        fs::write(&input, "\n").unwrap();
+        let err = resolve_run_prompt(None, Some(&input_arg)).unwrap_err();
+        assert!(err.to_string().contains("empty prompt"));
+    }
+
+    #[test]
+    fn run_env_passthrough_is_resolved_locally_and_before_runtime_proxy() {
+        env::set_var("PASSTHROUGH_TEST_PROXY", "http://proxy:3128");
+        env::remove_var("PASSTHROUGH_TEST_UNSET");
+        let env = parse_env_entries(&["PASSTHROUGH_TEST_PROXY".to_string(), "FOO=bar".to_string()])
+            .unwrap();
+        assert_eq!(
+            env,
+            vec![
+                (
+                    "PASSTHROUGH_TEST_PROXY".to_string(),
+                    "http://proxy:3128".to_string()
+                ),
+                ("FOO".to_string(), "bar".to_string()),
+            ]
+        );
+        let err = parse_env_entries(&["PASSTHROUGH_TEST_UNSET".to_string()]).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("--env PASSTHROUGH_TEST_UNSET passes PASSTHROUGH_TEST_UNSET through from the current environment, but it is not set"));
+
+        let raw_args: Vec<String> = [
+            "run",
+            "--provider",
+            "codex",
+            "--env",
+            "PASSTHROUGH_TEST_PROXY",
+            "--env=FOO=bar",
+            "--",
+            "--env",
+        ]
+        .iter()
+        .map(|value| value.to_string())
+        .collect();
+        let cli = Cli::parse_from(std::iter::once("lux".to_string()).chain(raw_args.clone()));
+        let proxied = runtime_proxy_args(&cli.command, &raw_args).unwrap();
+        assert_eq!(
+            proxied,
+            vec![
+                "run",
+                "--provider",
+                "codex",
+                "--env",
+                "PASSTHROUGH_TEST_PROXY=http://proxy:3128",
+                "--env=FOO=bar",
+                "--",
+                "--env",
+            ]
+        );
+
+        let raw_args: Vec<String> = [
+            "run",
+            "--provider",
+            "codex",
+            "--env=PASSTHROUGH_TEST_UNSET",
+            "hi",
+        ]
+        .iter()
+        .map(|value| value.to_string())
+        .collect();
+        let cli = Cli::parse_from(std::iter::once("lux".to_string()).chain(raw_args.clone()));
+        assert!(runtime_proxy_args(&cli.command, &raw_args).is_err());
+        env::remove_var("PASSTHROUGH_TEST_PROXY");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runtime_execute_runs_in_the_callers_working_directory() {
+        let dir = tempdir().unwrap();
+        let ctx = make_context(dir.path());
+        let shared: Arc<(Mutex<RuntimeSharedState>, Condvar)> =
+            Arc::new((Mutex::new(RuntimeSharedState::default()), Condvar::new()));
+        let events_path = dir.path().join("events.jsonl");
+        let cli = MockRuntimeCliRunner::default();
+        let execute = |cwd: &Path| {
+            let (mut client, server) = UnixStream::pair().unwrap();
+            let body = serde_json::to_vec(&json!({
+                "argv": ["run", "--output-file", "out.json", "hi"],
+                "cwd": cwd,
+            }))
+            .unwrap();
+            client
+                .write_all(
+                    format!(
+                        "POST /v1/execute HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+                        body.len()
+                    )
+                    .as_bytes(),
+                )
+                .unwrap();
+            client.write_all(&body).unwrap();
+            runtime_handle_connection(
+                server,
+                ctx.clone(),
+                &cli,
+                shared.clone(),
+                events_path.clone(),
+                None,
+            )
+            .unwrap();
+            let mut response = String::new();
+            client.read_to_string(&mut response).unwrap();
+            response
+        };
+
+        let response = execute(dir.path());
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert_eq!(
+            *cli.cwds.lock().unwrap(),
+            vec![Some(dir.path().to_path_buf())]
+        );
+
+        let response = execute(&dir.path().join("missing"));
+        assert!(response.starts_with("HTTP/1.1 400"), "{response}");
+        assert_eq!(cli.calls().len(), 1);
+    }
+
+    #[test]
+    fn ui_up_build_appends_build_flag() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let runner = MockDockerRunner::default();
+
+        handle_ui(
+            &ctx,
+            UiCommand::Up {
+                wait: false,
+                timeout_sec: None,
+                pull: None,
+                pull_timeout_sec: None,
+                build: true,
+                no_cache: false,
+            },
+            &runner,
+        )
+        .unwrap();
+
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 1);
+        let args = &calls[0].args;
+        assert!(args.iter().any(|x| x == "--build"));
+        assert_eq!(args.last().map(String::as_str), Some("ui"));
+    }
+
+    #[test]
+    fn ui_up_pull_timeout_splits_pull_and_reports_timeout() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let up = |pull_timeout_sec| UiCommand::Up {
+            wait: false,
+            timeout_sec: None,
+            pull: Some("always".to_string()),
+            pull_timeout_sec,
+            build: false,
+            no_cache: false,
+        };
+
+        let runner = MockDockerRunner::default();
+        handle_ui(&ctx, up(Some(30)), &runner).unwrap();
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 2);
+        let pull_idx = calls[0].args.iter().position(|x| x == "pull").unwrap();
+        assert_eq!(calls[0].args[pull_idx + 1..], ["--policy", "always", "ui"]);
+        let up_args = &calls[1].args;
+        let policy_idx = up_args.iter().position(|x| x == "--pull").unwrap();
+        assert_eq!(up_args[policy_idx + 1], "never");
+
+        // Without the flag, a single `compose up` keeps the requested policy.
+        let runner = MockDockerRunner::default();
+        handle_ui(&ctx, up(None), &runner).unwrap();
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 1);
+        let policy_idx = calls[0].args.iter().position(|x| x == "--pull").unwrap();
+        assert_eq!(calls[0].args[policy_idx + 1], "always");
+
+        struct TimingOutDockerRunner;
+        impl DockerRunner for TimingOutDockerRunner {
+            fn run(
+                &self,
+                args: &[String],
+                _cwd: &Path,
+                _env_overrides: &BTreeMap<String, String>,
+                _capture_output: bool,
+            ) -> Result<CommandOutput, io::Error> {
+                panic!("compose up should not run after a pull timeout: {args:?}");
+            }
+
+            fn run_with_timeout(
+                &self,
+                _args: &[String],
+                _cwd: &Path,
+                _env_overrides: &BTreeMap<String, String>,
+                _capture_output: bool,
+                _timeout: Duration,
+            ) -> Result<Option<CommandOutput>, io::Error> {
+                Ok(None)
+            }
+        }
+        let err = handle_ui(&ctx, up(Some(1)), &TimingOutDockerRunner).unwrap_err();
+        match err {
+            LuxError::ProcessDetailed { details, .. } => {
+                assert_eq!(details.error_code, "docker_pull_timeout");
+                assert!(details.command.unwrap().contains(" pull "));
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn harness_readiness_poll_reports_not_ready_then_succeeds() {
+        let mut cfg: Config = serde_yaml::from_str("version: 2").unwrap();
+        cfg.harness.api_token = "tok".to_string();
+
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        cfg.harness.api_port = closed.local_addr().unwrap().port();
+        drop(closed);
+        let err = wait_for_harness_ready(&cfg, Duration::from_secs(0))
+            .expect_err("closed port should not be ready");
+        assert!(err.to_string().contains(&format!(
+            "http://127.0.0.1:{}/healthz",
+            cfg.harness.api_port
+        )));
+        let details = extract_process_error_details(&err).unwrap();
+        assert_eq!(details.error_code, "harness_not_ready");
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        cfg.harness.api_port = listener.local_addr().unwrap().port();
+        cfg.harness.health_path = "/ready".to_string();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 1024];
+            let n = stream.read(&mut buf).unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+            assert!(request.starts_with("get /ready "));
+            assert!(request.contains("x-harness-token: tok"));
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\n{\"ok\":true}")
+                .unwrap();
+        });
+        wait_for_harness_ready(&cfg, Duration::from_secs(5)).unwrap();
+        server.join().unwrap();
+
+        cfg.harness.health_path = "ready".to_string();
+        let yaml = serde_yaml::to_string(&cfg).unwrap();
+        let err = read_config_from_str(&yaml, Path::new("config.yaml"))
+            .expect_err("relative health path should fail");
+        assert!(err
+            .to_string()
+            .contains("harness.health_path must start with '/'"));
+    }
+
+    #[test]
+    fn compose_ps_rows_normalize_across_compose_versions() {
+        let modern = r#"[{"Name":"lux-harness-1","Service":"harness","State":"running","Health":"healthy","Image":"ghcr.io/scottmaran/lux-harness:v1","Publishers":[{"URL":"127.0.0.1","TargetPort":8081,"PublishedPort":8081,"Protocol":"tcp"},{"URL":"","TargetPort":22,"PublishedPort":0,"Protocol":"tcp"}]},{"Name":"lux-agent-1","Service":"agent","State":"exited","Health":"","Image":"ghcr.io/scottmaran/lux-agent:v1","Publishers":null}]"#;
+        let services = parse_compose_service_statuses(modern);
+        assert_eq!(
+            services[0],
+            ServiceStatus {
+                name: "harness".to_string(),
+                state: "running".to_string(),
+                health: Some("healthy".to_string()),
+                image: Some("ghcr.io/scottmaran/lux-harness:v1".to_string()),
+                ports: vec!["127.0.0.1:8081->8081/tcp".to_string(), "22/tcp".to_string()],
+                orphaned_project: None,
+            }
+        );
+        assert_eq!(services[1].state, "exited");
+        assert_eq!(services[1].health, None);
+        assert!(services[1].ports.is_empty());
+
+        let legacy = r#"{"Names":"lux-collector-1","Status":"Up 3 minutes (health: starting)","Image":"collector","Ports":"0.0.0.0:9000->9000/tcp, 22/tcp"}"#;
+        let services = parse_compose_service_statuses(legacy);
+        assert_eq!(services.len(), 1);
+        assert_eq!(services[0].name, "lux-collector-1");
+        assert_eq!(services[0].state, "running");
+        assert_eq!(services[0].health.as_deref(), Some("starting"));
+        assert_eq!(services[0].ports, vec!["0.0.0.0:9000->9000/tcp", "22/tcp"]);
+
+        let plain = service_statuses_json(&services, None).unwrap();
+        assert_eq!(plain[0]["name"], "lux-collector-1");
+        let with_raw = service_statuses_json(&services, Some(legacy)).unwrap();
+        assert_eq!(with_raw["normalized"], plain);
+        assert_eq!(with_raw["raw"][0]["Names"], "lux-collector-1");
+        assert_eq!(
+            with_raw["raw"][0]["Status"],
+            "Up 3 minutes (health: starting)"
+        );
+
+        let table = render_service_status_table(&services);
+        let mut lines = table.lines();
+        assert!(lines.next().unwrap().starts_with("NAME             STATE"));
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("lux-collector-1  running"));
+    }
+
+    #[test]
+    fn idle_timeout_override_prefers_active_run_and_rejects_zero() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let runner = MockDockerRunner::default();
+        let err = handle_up(
+            &ctx,
+            UpOptions {
+                provider: Some("codex".to_string()),
+                idle_timeout_min: Some(0),
+                ..Default::default()
+            },
+            &runner,
+        )
+        .expect_err("zero idle timeout should fail");
+        assert!(err
+            .to_string()
+            .contains("--idle-timeout-min must be greater than 0"));
+        assert!(runner.calls().is_empty());
+
+        let cfg: Config = serde_yaml::from_str("version: 2").unwrap();
+        let legacy: ActiveRunState = serde_json::from_str(
+            r#"{"run_id":"lux__2026_02_12_12_00_00","started_at":"2026-02-12T12:00:00Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            effective_idle_timeout_min(&cfg, &legacy),
+            (cfg.collector.idle_timeout_min, "config")
+        );
+        let overridden = ActiveRunState {
+            idle_timeout_min: Some(15),
+            ..legacy
+        };
+        assert_eq!(effective_idle_timeout_min(&cfg, &overridden), (15, "run"));
+        let stored = serde_json::to_value(&overridden).unwrap();
+        assert_eq!(stored["idle_timeout_min"], 15);
+    }
+
+    #[test]
+    fn up_timeout_requires_wait() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let runner = MockDockerRunner::default();
+
+        let err = handle_up(
+            &ctx,
+            UpOptions {
+                collector_only: true,
+                timeout_sec: Some(10),
+                ..Default::default()
+            },
+            &runner,
+        )
+        .expect_err("timeout without wait should fail");
+        assert!(err.to_string().contains("--timeout-sec requires --wait"));
+    }
+
+    #[test]
+    fn up_fails_when_stack_already_running() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let runner = MockDockerRunner::default();
+        runner.push_output(CommandOutput {
+            status_code: 0,
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        });
+        runner.push_output(CommandOutput {
+            status_code: 0,
+            stdout: b"collector\n".to_vec(),
+            stderr: Vec::new(),
+        });
+
+        let err = handle_up(
+            &ctx,
+            UpOptions {
+                collector_only: true,
+                ..Default::default()
+            },
+            &runner,
+        )
+        .expect_err("already-running stack should fail");
+        assert!(err.to_string().contains("collector is already running"));
+        assert_eq!(runner.calls().len(), 2);
+    }
+
+    #[test]
+    fn down_collector_only_builds_expected_args() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let runner = MockDockerRunner::default();
+
+        handle_down(&ctx, None, true, false, &runner).unwrap();
+
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 1);
+        let args = &calls[0].args;
+        assert!(args.iter().any(|x| x == "stop"));
+        assert!(args.iter().any(|x| x == "collector"));
+    }
+
+    #[test]
+    fn down_all_stops_running_services_and_clears_active_state() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.yaml");
+        write_minimal_config(&config_path);
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let cfg = read_config(&config_path).unwrap();
+        let policy = resolve_config_policy_paths(&cfg).unwrap();
+        let state_root = policy.state_root.clone();
+        write_active_run_state(
+            &state_root,
+            "lux__test_run",
+            &policy.workspace_root,
+            &[],
+            "lux",
+        )
+        .unwrap();
+        write_active_provider_state(&state_root, "codex", &AuthMode::ApiKey, "lux__test_run")
+            .unwrap();
+
+        let runner = MockDockerRunner::default();
+        runner.push_output(CommandOutput {
+            status_code: 0,
+            stdout: br#"[{"Service":"collector","State":"running"},{"Service":"ui","State":"running"},{"Service":"agent","State":"exited"},{"Service":"harness","State":"running"}]"#.to_vec(),
+            stderr: Vec::new(),
+        });
+
+        handle_down(&ctx, None, false, true, &runner).unwrap();
+
+        // compose ps, compose stop, then the label sweep.
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 3);
+        assert!(calls[0].args.iter().any(|x| x == "ps"));
+        assert!(calls[0].args.iter().any(|x| x.ends_with("compose.ui.yml")));
+        let stop_idx = calls[1].args.iter().position(|x| x == "stop").unwrap();
+        assert_eq!(
+            calls[1].args[stop_idx + 1..].to_vec(),
+            vec!["collector", "harness", "ui"]
+        );
+        assert!(!active_run_state_path(&state_root).exists());
+        assert!(!active_provider_state_path(&state_root).exists());
+
+        // Nothing running: no stop call, still succeeds.
+        let runner = MockDockerRunner::default();
+        runner.push_output(CommandOutput {
+            status_code: 0,
+            stdout: b"[]\n".to_vec(),
+            stderr: Vec::new(),
+        });
+        handle_down(&ctx, None, false, true, &runner).unwrap();
+        assert_eq!(runner.calls().len(), 2);
+    }
+
+    #[test]
+    fn managed_container_labels_find_orphans_and_leftovers() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.yaml");
+        write_minimal_config(&config_path);
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        // A comma inside a label value must not bleed into the other labels.
+        let labelled = concat!(
+            r#"{"ID":"aaa111","Names":"lux-renamed-1","Image":"lux-agent","State":"running","Labels":"com.docker.compose.project=lux,com.lux.managed=true,com.lux.run_id=lux__r1,note=a,com.lux.run_id=bogus"}"#,
+            "\t\"lux\"\t\"lux__r1\"\n",
+            r#"{"ID":"bbb222","Names":"lux2-collector-1","Image":"lux-collector","State":"running","Labels":"com.docker.compose.project=lux2,com.lux.managed=true,com.lux.run_id=lux__r2"}"#,
+            "\t\"lux2\"\t\"lux__r2\"\n",
+            r#"{"ID":"ccc333","Names":"stray-1","Image":"lux-agent","State":"exited","Labels":"com.lux.managed=true"}"#,
+            "\t\"\"\t\"\"\n"
+        );
+        let containers = parse_managed_containers(labelled);
+        assert_eq!(containers.len(), 3);
+        assert_eq!(containers[0].project.as_deref(), Some("lux"));
+        assert_eq!(containers[0].run_id.as_deref(), Some("lux__r1"));
+        assert_eq!(containers[1].run_id.as_deref(), Some("lux__r2"));
+        assert_eq!(containers[2].project, None);
+        assert_eq!(
+            managed_container_format(),
+            "{{json .}}\t{{json (.Label \"com.docker.compose.project\")}}\t{{json (.Label \"com.lux.run_id\")}}"
+        );
+
+        // down --all stops the renamed container of this project, never the other project's.
+        let runner = MockDockerRunner::default();
+        runner.push_output(CommandOutput {
+            status_code: 0,
+            stdout: b"[]\n".to_vec(),
+            stderr: Vec::new(),
+        });
+        runner.push_output(CommandOutput {
+            status_code: 0,
+            stdout: labelled.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        });
+        handle_down(&ctx, None, false, true, &runner).unwrap();
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 3);
+        assert!(calls[1]
+            .args
+            .contains(&"label=com.lux.managed=true".to_string()));
+        assert!(calls[1]
+            .args
+            .contains(&"label=com.docker.compose.project=lux".to_string()));
+        assert!(calls[1].args.contains(&"status=running".to_string()));
+        assert_eq!(calls[2].args, vec!["stop", "aaa111"]);
+    }
+
+    #[test]
+    fn compose_file_override_replaces_default_compose_selection() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        let override_file = dir.path().join("custom.compose.yml");
+        fs::write(&override_file, "services: {}\n").unwrap();
+        let mut ctx = make_context(dir.path());
+        ctx.compose_file_overrides = vec![override_file.clone()];
+        let runner = MockDockerRunner::default();
+        runner.push_output(CommandOutput {
+            status_code: 0,
+            stdout: b"[]\n".to_vec(),
+            stderr: Vec::new(),
+        });
+
+        handle_status(&ctx, None, true, false, &runner).unwrap();
+
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 2);
+        let args = &calls[0].args;
+        assert!(args
+            .iter()
+            .any(|x| x == &override_file.to_string_lossy().to_string()));
+    }
+
+    #[test]
+    fn normalize_version_tag_adds_prefix() {
+        assert_eq!(normalize_version_tag("0.1.0"), "v0.1.0");
+        assert_eq!(normalize_version_tag("v0.1.0"), "v0.1.0");
+    }
+
+    #[test]
+    fn select_previous_version_uses_installed_order() {
+        let installed = vec![
+            "0.1.0".to_string(),
+            "0.2.0".to_string(),
+            "0.3.0".to_string(),
+        ];
+        assert_eq!(
+            select_previous_version("v0.3.0", &installed),
+            Some("v0.2.0".to_string())
+        );
+        assert_eq!(select_previous_version("v0.1.0", &installed), None);
+    }
+
+    #[test]
+    fn parse_checksum_reads_first_token() {
+        let hash = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+        let content = format!("{hash}  lux_0.1.0_linux_amd64.tar.gz");
+        assert_eq!(parse_checksum(&content), Some(hash.to_string()));
+    }
+
+    #[test]
+    fn bundle_sha256_uses_native_fallback_and_size_mtime_cache() {
+        let dir = tempdir().unwrap();
+        let bundle = dir.path().join("lux_bundle.tar.gz");
+        fs::write(&bundle, b"abc").unwrap();
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert_eq!(sha256_file_native(&bundle).unwrap(), abc);
+
+        assert_eq!(cached_sha256_file(&bundle).unwrap(), abc);
+        let cache_path = dir.path().join("lux_bundle.tar.gz.computed-sha256");
+        let mut cache: ComputedSha256Cache =
+            serde_json::from_str(&fs::read_to_string(&cache_path).unwrap()).unwrap();
+        assert_eq!(cache.sha256, abc);
+
+        // A valid fingerprint is trusted without re-hashing.
+        cache.sha256 = "cached".to_string();
+        fs::write(&cache_path, serde_json::to_string(&cache).unwrap()).unwrap();
+        assert_eq!(cached_sha256_file(&bundle).unwrap(), "cached");
+
+        // A size change invalidates the entry.
+        fs::write(&bundle, b"abcd").unwrap();
+        assert_eq!(
+            cached_sha256_file(&bundle).unwrap(),
+            sha256_file_native(&bundle).unwrap()
+        );
+    }
+
+    #[test]
+    fn download_file_resumes_interrupted_transfer_with_range() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/bundle.tar.gz", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for attempt in 0..2 {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 2048];
+                let n = stream.read(&mut buf).unwrap();
+                requests.push(String::from_utf8_lossy(&buf[..n]).to_lowercase());
+                if attempt == 0 {
+                    // Advertise ranges, then drop the connection after 4 of 10 bytes.
+                    stream
+                        .write_all(
+                            b"HTTP/1.1 200 OK\r\nAccept-Ranges: bytes\r\nContent-Length: 10\r\n\r\n0123",
+                        )
+                        .unwrap();
+                } else {
+                    stream
+                        .write_all(
+                            b"HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 4-9/10\r\nContent-Length: 6\r\n\r\n456789",
+                        )
+                        .unwrap();
+                }
+            }
+            requests
+        });
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("bundle.tar.gz");
+        download_file(&url, &path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"0123456789");
+        assert!(!dir.path().join("bundle.tar.gz.part").exists());
+
+        let requests = server.join().unwrap();
+        assert!(!requests[0].contains("range:"));
+        assert!(requests[1].contains("range: bytes=4-"));
+    }
+
+    #[test]
+    fn download_file_restarts_when_resume_range_is_not_satisfiable() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/bundle.tar.gz", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for attempt in 0..3 {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 2048];
+                let n = stream.read(&mut buf).unwrap();
+                requests.push(String::from_utf8_lossy(&buf[..n]).to_lowercase());
+                let response: &[u8] = match attempt {
+                    0 => b"HTTP/1.1 200 OK\r\nAccept-Ranges: bytes\r\nContent-Length: 10\r\n\r\n0123",
+                    1 => b"HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */3\r\nContent-Length: 0\r\n\r\n",
+                    _ => b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nabc",
+                };
+                stream.write_all(response).unwrap();
+            }
+            requests
+        });
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("bundle.tar.gz");
+        download_file(&url, &path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"abc");
+
+        let requests = server.join().unwrap();
+        assert!(requests[1].contains("range: bytes=4-"));
+        assert!(!requests[2].contains("range:"));
+    }
+
+    #[test]
+    fn download_file_backs_off_between_retries() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/bundle.tar.gz", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let mut accepted_at = Vec::new();
+            for attempt in 0..3 {
+                let (mut stream, _) = listener.accept().unwrap();
+                accepted_at.push(Instant::now());
+                let mut buf = [0u8; 2048];
+                let _ = stream.read(&mut buf).unwrap();
+                let response: &[u8] = if attempt < 2 {
+                    b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n"
+                } else {
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nabc"
+                };
+                stream.write_all(response).unwrap();
+            }
+            accepted_at
+        });
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("bundle.tar.gz");
+        download_file(&url, &path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"abc");
+
+        let accepted_at = server.join().unwrap();
+        let first_wait = accepted_at[1] - accepted_at[0];
+        let second_wait = accepted_at[2] - accepted_at[1];
+        assert!(first_wait >= Duration::from_millis(DOWNLOAD_RETRY_BACKOFF_MS));
+        assert!(second_wait >= Duration::from_millis(2 * DOWNLOAD_RETRY_BACKOFF_MS));
+    }
+
+    #[test]
+    fn parse_checksum_supports_openssl_output() {
+        let hash = "abcdefabcdefabcdefabcdefabcdefabcdefabcdefabcdefabcdefabcdefabcd";
+        let content = format!("SHA2-256(file.tar.gz)= {hash}");
+        assert_eq!(parse_checksum(&content), Some(hash.to_string()));
+    }
+
+    #[test]
+    fn map_host_start_dir_to_container_maps_root_and_nested_paths() {
+        let workspace = PathBuf::from("/tmp/workspace");
+        let mapped_root = map_host_start_dir_to_container(&workspace, &workspace).unwrap();
+        assert_eq!(mapped_root, "/work");
 
-    fn make_context(dir: &Path) -> Context {
-        let config_path = dir.join("config.yaml");
-        let env_file = dir.join("compose.env");
-        Context {
-            config_path,
-            env_file,
-            bundle_dir: dir.to_path_buf(),
-            compose_file_overrides: Vec::new(),
-            json: true,
-        }
+        let nested = workspace.join("src").join("project");
+        let mapped_nested = map_host_start_dir_to_container(&nested, &workspace).unwrap();
+        assert_eq!(mapped_nested, "/work/src/project");
     }
 
     #[test]
-    fn config_unknown_field_errors() {
-        let yaml = r#"
-version: 2
-unknown: true
-paths:
-  log_root: ~/lux-logs
-  workspace_root: ~/lux-workspace
-"#;
-        let result: Result<Config, _> = serde_yaml::from_str(yaml);
-        assert!(result.is_err());
+    fn shim_map_exec_args_rewrites_workspace_paths_and_rejects_outside() {
+        let dir = tempdir().unwrap();
+        let workspace = fs::canonicalize(dir.path()).unwrap().join("workspace");
+        fs::create_dir_all(workspace.join("src")).unwrap();
+        let args = vec![
+            "--flag".to_string(),
+            workspace.join("src").to_string_lossy().to_string(),
+            workspace.join("src/new.txt").to_string_lossy().to_string(),
+        ];
+        let mapped = shim_map_exec_args(&args, &workspace).unwrap();
+        assert_eq!(mapped, vec!["--flag", "/work/src", "/work/src/new.txt"]);
+
+        let outside = vec![dir.path().join("other").to_string_lossy().to_string()];
+        let err = shim_map_exec_args(&outside, &workspace).unwrap_err();
+        assert!(err.to_string().contains("outside the workspace"));
     }
 
     #[test]
-    fn setup_post_actions_plan_respects_apply_and_interactive_flags() {
-        let choices = SetupPostSetupChoices::default();
-        assert!(plan_setup_post_actions(true, false, &choices).is_empty());
-        assert!(plan_setup_post_actions(false, true, &choices).is_empty());
-        assert!(plan_setup_post_actions(false, false, &choices).is_empty());
+    fn append_harness_tui_run_args_places_env_before_service_name() {
+        let mut args = Vec::new();
+        append_harness_tui_run_args(&mut args, "/work/project", &[]);
+        assert_eq!(
+            args,
+            vec![
+                "run".to_string(),
+                "--rm".to_string(),
+                "-e".to_string(),
+                "HARNESS_MODE=tui".to_string(),
+                "-e".to_string(),
+                "HARNESS_AGENT_WORKDIR=/work/project".to_string(),
+                "harness".to_string(),
+            ]
+        );
     }
 
     #[test]
-    fn setup_post_actions_plan_orders_shim_then_startup_actions() {
-        let mut choices = SetupPostSetupChoices::default();
-        choices.shim_choice = SetupShimChoice::EnableSelected;
-        choices.shim_providers = vec!["codex".to_string()];
-        choices.auto_start_services = true;
+    fn attach_execs_shell_in_agent_and_requires_active_run() {
+        let mut args = Vec::new();
+        append_agent_attach_args(&mut args, "/work/project");
+        assert_eq!(
+            args,
+            vec![
+                "exec",
+                "-w",
+                "/work/project",
+                "agent",
+                "sh",
+                "-c",
+                AGENT_ATTACH_SHELL
+            ]
+        );
 
-        let actions = plan_setup_post_actions(true, true, &choices);
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let runner = MockDockerRunner::default();
+        let err = handle_attach(&ctx, "codex".to_string(), &runner)
+            .expect_err("attach without an active run should fail");
+        assert!(err.to_string().contains("no active run found"));
+        assert!(err.to_string().contains("lux up --provider codex"));
+        assert!(runner.calls().is_empty());
+    }
+
+    #[test]
+    fn tui_env_is_validated_and_forwarded_to_harness() {
+        let env = parse_env_entries(&[
+            "FOO=bar".to_string(),
+            "EMPTY=".to_string(),
+            "FOO=baz=qux".to_string(),
+        ])
+        .unwrap();
         assert_eq!(
-            actions,
+            env,
             vec![
-                SetupPostAction::ShimEnable {
-                    providers: vec!["codex".to_string()],
-                },
-                SetupPostAction::StartupPreflightProviderInactive,
-                SetupPostAction::CollectorRefresh,
-                SetupPostAction::UiUp,
+                ("EMPTY".to_string(), String::new()),
+                ("FOO".to_string(), "baz=qux".to_string()),
             ]
         );
+
+        let mut args = Vec::new();
+        append_harness_tui_run_args(&mut args, "/work", &env);
+        let harness_idx = args.iter().position(|x| x == "harness").unwrap();
+        assert_eq!(harness_idx, args.len() - 1);
+        assert!(args.contains(&"FOO=baz=qux".to_string()));
+        assert_eq!(args[harness_idx - 1], "HARNESS_TUI_ENV_KEYS=EMPTY,FOO");
+
+        for bad in [
+            "NOEQUALS",
+            "=value",
+            "1BAD=x",
+            "LUX_RUN_ID=x",
+            "harness_mode=x",
+        ] {
+            assert!(parse_env_entries(&[bad.to_string()]).is_err(), "{bad}");
+        }
     }
 
     #[test]
-    fn setup_post_actions_plan_skips_empty_selected_provider_list() {
-        let mut choices = SetupPostSetupChoices::default();
-        choices.shim_choice = SetupShimChoice::EnableSelected;
-        choices.shim_providers = Vec::new();
-        choices.auto_start_services = false;
+    fn api_endpoint_parsing_and_network_egress_probe() {
+        assert_eq!(
+            parse_api_endpoint("api.openai.com").unwrap(),
+            ("api.openai.com".to_string(), 443)
+        );
+        assert_eq!(
+            parse_api_endpoint("http://proxy.local:8080/v1").unwrap(),
+            ("proxy.local".to_string(), 8080)
+        );
+        assert_eq!(
+            parse_api_endpoint("[::1]:9443").unwrap(),
+            ("::1".to_string(), 9443)
+        );
+        assert!(parse_api_endpoint("https://").is_err());
+        assert!(parse_api_endpoint("host:0").is_err());
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let open_port = listener.local_addr().unwrap().port();
+        let closed_port = {
+            let probe = TcpListener::bind("127.0.0.1:0").unwrap();
+            probe.local_addr().unwrap().port()
+        };
+        let ctx = make_context(tempdir().unwrap().path());
+        let mut cfg = Config::default();
+        for provider in cfg.providers.values_mut() {
+            provider.commands.api_endpoint = None;
+        }
+        let none = doctor_check_network_egress(&ctx, &cfg).unwrap();
+        assert!(none.ok);
+        assert_eq!(none.details["endpoints"], json!([]));
+
+        let mut names = cfg.providers.keys().cloned();
+        let (up, down) = (names.next().unwrap(), names.next().unwrap());
+        cfg.providers.get_mut(&up).unwrap().commands.api_endpoint =
+            Some(format!("127.0.0.1:{open_port}"));
+        cfg.providers.get_mut(&down).unwrap().commands.api_endpoint =
+            Some(format!("127.0.0.1:{closed_port}"));
+        let check = doctor_check_network_egress(&ctx, &cfg).unwrap();
+        assert!(!check.ok);
+        assert!(check.strict_fail);
+        assert!(check.message.contains(&down));
+        let endpoints = check.details["endpoints"].as_array().unwrap();
+        let by_provider = |name: &str| {
+            endpoints
+                .iter()
+                .find(|row| row["provider"] == name)
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(by_provider(&up)["reachable"], true);
+        assert!(by_provider(&up)["latency_ms"].is_u64());
+        assert_eq!(by_provider(&down)["reachable"], false);
+        assert!(by_provider(&down)["error"].is_string());
+    }
 
-        let actions = plan_setup_post_actions(true, true, &choices);
-        assert!(actions.is_empty());
+    #[test]
+    fn run_env_file_is_parsed_and_cli_entries_win() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("job.env");
+        fs::write(
+            &path,
+            "# job settings\n\nexport FOO=from-file\nQUOTED=\"a \\\"b\\\" # kept\"\nSINGLE='x $y'\nPLAIN=value # note\n",
+        )
+        .unwrap();
+
+        let env =
+            resolve_run_env(&["FOO=from-cli".to_string()], Some(path.to_str().unwrap())).unwrap();
+        assert_eq!(env.len(), 4);
+        assert_eq!(env["FOO"], "from-cli");
+        assert_eq!(env["QUOTED"], "a \"b\" # kept");
+        assert_eq!(env["SINGLE"], "x $y");
+        assert_eq!(env["PLAIN"], "value");
+
+        fs::write(&path, "OK=1\nHARNESS_TOKEN=x\n").unwrap();
+        let err = resolve_run_env(&[], Some(path.to_str().unwrap())).unwrap_err();
+        assert!(err.to_string().contains("reserved prefix"), "{err}");
+        fs::write(&path, "OK=1\nnot a pair\n").unwrap();
+        let err = resolve_run_env(&[], Some(path.to_str().unwrap())).unwrap_err();
+        assert!(err.to_string().contains(":2: expected KEY=VALUE"), "{err}");
     }
 
+    #[cfg(unix)]
     #[test]
-    fn setup_post_actions_stop_immediately_when_shim_enable_fails() {
-        let runner =
-            MockSetupPostActionRunner::default().with_failure(SetupRunnerFailurePoint::ShimEnable);
-        let actions = vec![
-            SetupPostAction::ShimEnable {
-                providers: vec!["codex".to_string()],
-            },
-            SetupPostAction::UiUp,
-        ];
+    fn bundle_dir_from_symlinked_exe_prefers_real_binary_parent() {
+        let dir = tempdir().unwrap();
+        let bundle_dir = dir.path().join("bundle");
+        fs::create_dir_all(&bundle_dir).unwrap();
+        fs::write(bundle_dir.join("compose.yml"), "services: {}\n").unwrap();
 
-        let err = execute_setup_post_actions(&runner, &actions)
-            .expect_err("shim failure should abort subsequent actions");
-        let outcomes = err.1;
-        assert_eq!(outcomes.len(), 1);
-        assert_eq!(outcomes[0].action, "shim_enable");
-        assert_eq!(outcomes[0].status, "failed");
-        assert_eq!(runner.calls(), vec!["shim_enable".to_string()]);
+        let real_exe = bundle_dir.join("lux");
+        fs::write(&real_exe, "").unwrap();
+
+        let bin_dir = dir.path().join("bin");
+        fs::create_dir_all(&bin_dir).unwrap();
+        let symlink_exe = bin_dir.join("lux");
+        std::os::unix::fs::symlink(&real_exe, &symlink_exe).unwrap();
+
+        let resolved = bundle_dir_from_exe_path(&symlink_exe).expect("bundle dir should resolve");
+        assert_eq!(resolved, bundle_dir);
     }
 
     #[test]
-    fn setup_post_actions_preflight_blocks_when_provider_plane_active() {
-        let runner = MockSetupPostActionRunner::default().with_provider_active("codex");
-        let actions = vec![
-            SetupPostAction::StartupPreflightProviderInactive,
-            SetupPostAction::CollectorRefresh,
-            SetupPostAction::UiUp,
-        ];
+    fn resolve_effective_workspace_root_enforces_home_policy_for_override() {
+        let dir = tempdir().unwrap();
+        let home = required_home_dir().unwrap();
+        let mut cfg = Config::default();
+        let trusted_root = dir.path().join("trusted");
+        cfg.paths.trusted_root = trusted_root.to_string_lossy().to_string();
+        cfg.paths.log_root = trusted_root.join("logs").to_string_lossy().to_string();
+        cfg.shims.bin_dir = trusted_root.join("bin").to_string_lossy().to_string();
+        cfg.paths.workspace_root = home.join("workspace").to_string_lossy().to_string();
 
-        let err = execute_setup_post_actions(&runner, &actions)
-            .expect_err("active provider plane should block collector refresh");
-        let outcomes = err.1;
-        assert_eq!(outcomes.len(), 1);
-        assert_eq!(outcomes[0].action, "startup_preflight_provider_inactive");
-        assert_eq!(outcomes[0].status, "failed");
-        assert_eq!(runner.calls(), vec!["provider_plane_active".to_string()]);
+        let override_workspace = home.join("workspace-alt");
+        let resolved = resolve_effective_workspace_root(
+            &cfg,
+            Some(override_workspace.to_str().unwrap()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(resolved, override_workspace);
+
+        let outside_workspace = dir.path().join("outside-home-workspace");
+        let err =
+            resolve_effective_workspace_root(&cfg, Some(outside_workspace.to_str().unwrap()), None)
+                .expect_err("outside-home workspace should fail");
+        assert!(err.to_string().contains("--workspace must be under $HOME"));
     }
 
     #[test]
-    fn setup_post_actions_collector_refresh_restarts_when_already_running() {
-        let runner = MockSetupPostActionRunner::default().with_collector_running(true);
-        let actions = vec![SetupPostAction::CollectorRefresh];
+    fn provider_workspace_root_overrides_global_and_is_policy_checked() {
+        let dir = tempdir().unwrap();
+        let home = required_home_dir().unwrap();
+        let mut cfg = Config::default();
+        let trusted_root = dir.path().join("trusted");
+        cfg.paths.trusted_root = trusted_root.to_string_lossy().to_string();
+        cfg.paths.log_root = trusted_root.join("logs").to_string_lossy().to_string();
+        cfg.shims.bin_dir = trusted_root.join("bin").to_string_lossy().to_string();
+        cfg.paths.workspace_root = home.join("workspace").to_string_lossy().to_string();
+        let provider = cfg.providers.keys().next().unwrap().clone();
+        cfg.providers.get_mut(&provider).unwrap().workspace_root =
+            Some(home.join("provider-project").to_string_lossy().to_string());
 
-        let outcomes = execute_setup_post_actions(&runner, &actions)
-            .expect("collector refresh should succeed");
-        assert_eq!(outcomes.len(), 1);
-        assert_eq!(outcomes[0].action, "collector_refresh");
-        assert_eq!(outcomes[0].status, "ok");
         assert_eq!(
-            runner.calls(),
-            vec![
-                "collector_running".to_string(),
-                "collector_stop".to_string(),
-                "collector_start".to_string(),
-            ]
+            resolve_effective_workspace_root(&cfg, None, Some(&provider)).unwrap(),
+            home.join("provider-project")
+        );
+        assert_eq!(
+            resolve_effective_workspace_root(&cfg, None, None).unwrap(),
+            home.join("workspace")
         );
+        let cli = home.join("cli-project");
+        assert_eq!(
+            resolve_effective_workspace_root(&cfg, Some(cli.to_str().unwrap()), Some(&provider))
+                .unwrap(),
+            cli
+        );
+
+        cfg.providers.get_mut(&provider).unwrap().workspace_root =
+            Some(dir.path().join("outside").to_string_lossy().to_string());
+        let err = resolve_config_policy_paths(&cfg).expect_err("outside-home must fail");
+        assert!(err.to_string().contains(&format!(
+            "providers.{provider}.workspace_root must be under $HOME"
+        )));
     }
 
     #[test]
-    fn setup_post_actions_ui_up_reports_local_url_and_port() {
-        let runner = MockSetupPostActionRunner::default();
-        let actions = vec![SetupPostAction::UiUp];
+    fn resolve_host_start_dir_requires_start_dir_inside_workspace() {
+        let dir = tempdir().unwrap();
+        let home = required_home_dir().unwrap();
+        let mut cfg = Config::default();
+        let trusted_root = dir.path().join("trusted");
+        cfg.paths.trusted_root = trusted_root.to_string_lossy().to_string();
+        cfg.paths.log_root = trusted_root.join("logs").to_string_lossy().to_string();
+        cfg.shims.bin_dir = trusted_root.join("bin").to_string_lossy().to_string();
+        cfg.paths.workspace_root = home.join("workspace").to_string_lossy().to_string();
 
-        let outcomes = execute_setup_post_actions(&runner, &actions).expect("ui up should succeed");
-        assert_eq!(outcomes.len(), 1);
-        assert_eq!(outcomes[0].action, "ui_up");
-        assert_eq!(outcomes[0].status, "ok");
-        assert!(outcomes[0].detail.contains("http://127.0.0.1:8090"));
-        assert!(outcomes[0].detail.contains("port 8090"));
+        let workspace_root = resolve_effective_workspace_root(&cfg, None, None).unwrap();
+        let inside = workspace_root.join("src");
+        let resolved =
+            resolve_host_start_dir(&cfg, &workspace_root, Some(inside.to_str().unwrap())).unwrap();
+        assert_eq!(resolved, inside);
+
+        let outside = dir.path().join("outside");
+        let err = resolve_host_start_dir(&cfg, &workspace_root, Some(outside.to_str().unwrap()))
+            .expect_err("outside workspace should fail");
+        assert!(err
+            .to_string()
+            .contains("--start-dir must be inside workspace"));
     }
 
     #[test]
-    fn config_defaults_apply() {
-        let yaml = r#"version: 2"#;
-        let cfg: Config = serde_yaml::from_str(yaml).expect("config");
-        assert_eq!(cfg.version, 2);
-        let home = required_home_dir().expect("home");
-        let expected = default_paths_for_os(env::consts::OS, &home).expect("default paths");
-        assert_eq!(cfg.paths.log_root, expected.log_root);
-        assert_eq!(cfg.paths.workspace_root, expected.workspace_root);
-        assert!(cfg.collector.auto_start);
-        assert_eq!(cfg.collector.idle_timeout_min, 10_080);
-        assert_eq!(cfg.collector.rotate_every_min, 1_440);
-        assert_eq!(cfg.runtime_control_plane.socket_path, "");
+    fn run_timeout_error_includes_partial_stdout() {
+        let dir = tempdir().unwrap();
+        let log_root = dir.path().join("logs");
+        let stdout_path = run_job_stdout_path(&log_root, "lux__run", "job_1");
+        fs::create_dir_all(stdout_path.parent().unwrap()).unwrap();
+        fs::write(&stdout_path, "partial line\n").unwrap();
+
+        let err = run_timeout_error("lux__run", "job_1", &stdout_path, Some(30));
+        assert!(err.to_string().contains("timed out after 30s"));
+        let details = extract_process_error_details(&err).expect("details");
+        assert_eq!(details.error_code, "run_timeout");
+        let partial = details.partial_outcome.expect("partial outcome");
+        assert_eq!(partial["job_id"], "job_1");
+        assert_eq!(partial["stdout"], "partial line\n");
+        assert!(stdout_path.ends_with("harness/jobs/job_1/stdout.log"));
     }
 
-    #[cfg(unix)]
     #[test]
-    fn runtime_socket_path_falls_back_when_default_is_too_long() {
-        let mut cfg: Config = serde_yaml::from_str("version: 2").expect("config");
-        let deep_trusted_root = PathBuf::from(format!("/tmp/{}", "a".repeat(180)));
-        cfg.paths.trusted_root = deep_trusted_root.to_string_lossy().to_string();
-        let preferred = deep_trusted_root.join("runtime").join("control_plane.sock");
-        assert!(unix_socket_path_too_long(&preferred));
-        let effective = effective_runtime_socket_path(&cfg);
-        assert!(!unix_socket_path_too_long(&effective));
-        assert_ne!(effective, preferred);
+    fn run_wait_timeout_error_points_at_the_still_running_job() {
+        let dir = tempdir().unwrap();
+        let stdout_path = run_job_stdout_path(&dir.path().join("logs"), "lux__run", "job_1");
+        fs::create_dir_all(stdout_path.parent().unwrap()).unwrap();
+        fs::write(&stdout_path, "partial line\n").unwrap();
+
+        let err = run_wait_timeout_error("lux__run", "job_1", &stdout_path, 3600);
+        assert!(err
+            .to_string()
+            .contains("after 3600s; the job is still running"));
+        let details = extract_process_error_details(&err).expect("details");
+        assert_eq!(details.error_code, "run_wait_timeout");
+        assert!(details.hint.unwrap().contains("lux jobs get job_1"));
+        let partial = details.partial_outcome.expect("partial outcome");
+        assert_eq!(partial["job_id"], "job_1");
+        assert_eq!(partial["stdout"], "partial line\n");
     }
 
-    #[cfg(unix)]
     #[test]
-    fn config_validate_rejects_overlong_runtime_socket_path() {
-        let mut cfg = Config::default();
-        cfg.runtime_control_plane.socket_path = format!("/tmp/{}", "b".repeat(180));
-        let yaml = serde_yaml::to_string(&cfg).expect("serialize config");
-        let err = read_config_from_str(&yaml).expect_err("long socket path should fail");
-        assert!(err
-            .to_string()
-            .contains("runtime_control_plane.socket_path is too long"));
+    fn runtime_pid_check_ignores_unrelated_processes() {
+        let argv = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert!(cmdline_is_lux_runtime_serve(&argv(&[
+            "/usr/local/bin/lux",
+            "--config",
+            "/etc/lux/config.yaml",
+            "runtime",
+            "serve",
+        ])));
+        assert!(!cmdline_is_lux_runtime_serve(&argv(&[
+            "/usr/local/bin/lux",
+            "runtime",
+            "status",
+        ])));
+        assert!(!cmdline_is_lux_runtime_serve(&argv(&[
+            "/usr/bin/python3",
+            "runtime",
+            "serve",
+        ])));
+        assert!(!cmdline_is_lux_runtime_serve(&[]));
+
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        assert!(process_is_alive(child.id()));
+        assert!(!pid_is_lux_runtime(child.id()));
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(wait_for_process_exit(child.id(), Duration::from_secs(1)));
     }
 
     #[test]
-    fn expand_tilde_works() {
-        let expanded = expand_path("~/lux-logs");
-        assert!(!expanded.starts_with("~/"));
+    fn constant_time_eq_compares_whole_tokens() {
+        assert!(constant_time_eq(b"runtime-token", b"runtime-token"));
+        assert!(!constant_time_eq(b"runtime-token", b"runtime-tokeN"));
+        assert!(!constant_time_eq(b"runtime-token", b"runtime"));
+        assert!(!constant_time_eq(b"", b"x"));
     }
 
     #[test]
-    fn display_path_with_home_rewrites_home_prefix() {
-        let home = PathBuf::from("/tmp/lux-home");
-        assert_eq!(display_path_with_home(&home, Some(&home)), "$HOME");
-        assert_eq!(
-            display_path_with_home(&home.join("workspace"), Some(&home)),
-            "$HOME/workspace"
-        );
+    fn run_failed_error_reports_exit_code_and_result() {
+        let job = json!({"status": "failed", "exit_code": 3});
+        let err = run_failed_error("job_1", &job, json!({"job_id": "job_1", "status_code": 3}));
+        assert!(err
+            .to_string()
+            .contains("run job job_1 failed (exit code 3)"));
+        let details = extract_process_error_details(&err).expect("details");
+        assert_eq!(details.error_code, "run_failed");
         assert_eq!(
-            display_path_with_home(Path::new("/var/lib/lux/logs"), Some(&home)),
-            "/var/lib/lux/logs"
+            details.partial_outcome.expect("partial outcome")["status_code"],
+            3
         );
-        assert_eq!(
-            display_path_with_home(&home.join("workspace"), None),
-            "/tmp/lux-home/workspace"
+    }
+
+    #[test]
+    fn classify_docker_command_failure_detects_compose_unavailable() {
+        let (code, hint) = classify_docker_command_failure(
+            "unknown flag: --env-file\n\nUsage:  docker [OPTIONS] COMMAND [ARG...]",
         );
+        assert_eq!(code, "docker_compose_unavailable");
+        assert!(hint.unwrap_or_default().contains("DOCKER_CONFIG"));
     }
 
     #[test]
-    fn env_file_written() {
+    fn execute_docker_nonzero_exit_surfaces_structured_details() {
         let dir = tempdir().unwrap();
-        let env_path = dir.path().join("compose.env");
-        let cfg: Config = serde_yaml::from_str("version: 2").unwrap();
-        let envs = config_to_env(&cfg);
-        write_env_file(&env_path, &envs).unwrap();
-        let content = fs::read_to_string(&env_path).unwrap();
-        assert!(content.contains("LUX_VERSION="));
-        assert!(content.contains("LUX_LOG_ROOT="));
-        assert!(content.contains("LUX_RUNTIME_DIR="));
-        assert!(content.contains("LUX_RUNTIME_GID="));
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let runner = MockDockerRunner::default();
+        runner.push_output(CommandOutput {
+            status_code: 125,
+            stdout: Vec::new(),
+            stderr: b"unknown flag: --env-file".to_vec(),
+        });
+        let args = vec![
+            "compose".to_string(),
+            "--env-file".to_string(),
+            "/tmp/compose.env".to_string(),
+            "ps".to_string(),
+        ];
+        let err = execute_docker(&ctx, &runner, &args, &BTreeMap::new(), true, false)
+            .expect_err("docker command should fail");
+
+        match err {
+            LuxError::ProcessDetailed { message, details } => {
+                assert!(message
+                    .contains("while running `docker compose --env-file /tmp/compose.env ps`"));
+                assert_eq!(details.error_code, "docker_compose_unavailable");
+                assert!(details.hint.unwrap_or_default().contains("DOCKER_CONFIG"));
+                assert_eq!(
+                    details.command.unwrap_or_default(),
+                    "docker compose --env-file /tmp/compose.env ps"
+                );
+                assert!(details
+                    .raw_stderr
+                    .unwrap_or_default()
+                    .contains("unknown flag: --env-file"));
+            }
+            other => panic!("unexpected error variant: {other:?}"),
+        }
     }
 
     #[test]
-    fn yaml_patch_preserves_comments_and_spacing() {
-        let input = r#"# top comment
-version: 2
+    fn execute_docker_spawn_not_found_sets_docker_not_found_code() {
+        struct NotFoundRunner;
+        impl DockerRunner for NotFoundRunner {
+            fn run(
+                &self,
+                _args: &[String],
+                _cwd: &Path,
+                _env_overrides: &BTreeMap<String, String>,
+                _capture_output: bool,
+            ) -> Result<CommandOutput, io::Error> {
+                Err(io::Error::new(io::ErrorKind::NotFound, "docker not found"))
+            }
+        }
 
-paths:   # paths comment
-  log_root : ~/lux-logs   # inline
-  workspace_root: "~/lux-workspace"   # keep quotes
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let runner = NotFoundRunner;
+        let args = vec!["compose".to_string(), "ps".to_string()];
+        let err = execute_docker(&ctx, &runner, &args, &BTreeMap::new(), true, false)
+            .expect_err("missing docker should fail");
+        match err {
+            LuxError::ProcessDetailed { message, details } => {
+                assert!(message.contains("failed to run command `docker compose ps`"));
+                assert_eq!(details.error_code, "docker_not_found");
+                assert!(details.hint.unwrap_or_default().contains("Install Docker"));
+                assert_eq!(details.command.unwrap_or_default(), "docker compose ps");
+            }
+            other => panic!("unexpected error variant: {other:?}"),
+        }
+    }
 
-providers:
-  codex:
-    auth_mode: api_key  # keep
-    mount_host_state_in_api_mode: false
-    commands:
-      tui: "codex"
-      run_template: "codex exec {prompt}"
-    auth:
-      api_key:
-        secrets_file: ~/.config/lux/secrets/codex.env
-        env_key: OPENAI_API_KEY
-      host_state:
-        paths:
-          - ~/.codex/auth.json
-    ownership:
-      root_comm:
-        - codex
-"#;
+    #[test]
+    fn shim_path_block_insert_replace_remove_is_idempotent() {
+        let initial = "# shell profile\n";
+        let block = render_shim_path_block(Path::new("/tmp/lux/bin"));
 
-        let mut edits = SetupYamlEdits::default();
-        edits.log_root = Some("/tmp/new-logs".to_string());
+        let (enabled, enabled_present, enabled_changed) =
+            apply_managed_path_block(initial, &block).expect("enable should work");
+        assert!(enabled_present);
+        assert!(enabled_changed);
+        assert_eq!(enabled.matches(SHIM_PATH_BEGIN_MARKER).count(), 1);
 
-        let (patched, changed) = patch_setup_config_yaml(input, &edits).unwrap();
-        assert!(changed);
-        assert!(patched.ends_with('\n'));
+        let (enabled_again, enabled_again_present, enabled_again_changed) =
+            apply_managed_path_block(&enabled, &block).expect("enable re-run should work");
+        assert!(enabled_again_present);
+        assert!(!enabled_again_changed);
+        assert_eq!(enabled_again.matches(SHIM_PATH_BEGIN_MARKER).count(), 1);
 
-        // Comments must remain byte-for-byte.
-        assert!(patched.contains("# top comment"));
-        assert!(patched.contains("paths:   # paths comment"));
-        assert!(patched.contains("  log_root : /tmp/new-logs   # inline"));
-        assert!(patched.contains("  workspace_root: \"~/lux-workspace\"   # keep quotes"));
+        let (disabled, disabled_present, disabled_changed) =
+            remove_managed_path_block(&enabled_again).expect("disable should work");
+        assert!(!disabled_present);
+        assert!(disabled_changed);
+        assert!(!disabled.contains(SHIM_PATH_BEGIN_MARKER));
 
-        // Unchanged provider line should remain unchanged.
-        assert!(patched.contains("    auth_mode: api_key  # keep"));
+        let (disabled_again, disabled_again_present, disabled_again_changed) =
+            remove_managed_path_block(&disabled).expect("disable re-run should work");
+        assert!(!disabled_again_present);
+        assert!(!disabled_again_changed);
+        assert_eq!(disabled, disabled_again);
+    }
+
+    #[test]
+    fn shim_path_persistence_state_computation() {
+        assert_eq!(shim_path_persistence_state(&[]), "no_startup_files");
+
+        let absent = vec![
+            ShimPathFileStatus {
+                path: PathBuf::from("/tmp/.zprofile"),
+                existed: true,
+                managed_block_present: false,
+                changed: false,
+                error: None,
+            },
+            ShimPathFileStatus {
+                path: PathBuf::from("/tmp/.bashrc"),
+                existed: true,
+                managed_block_present: false,
+                changed: false,
+                error: None,
+            },
+        ];
+        assert_eq!(shim_path_persistence_state(&absent), "absent");
+
+        let partial = vec![
+            ShimPathFileStatus {
+                path: PathBuf::from("/tmp/.zprofile"),
+                existed: true,
+                managed_block_present: true,
+                changed: false,
+                error: None,
+            },
+            ShimPathFileStatus {
+                path: PathBuf::from("/tmp/.bashrc"),
+                existed: true,
+                managed_block_present: false,
+                changed: false,
+                error: None,
+            },
+        ];
+        assert_eq!(shim_path_persistence_state(&partial), "partial");
+
+        let configured = vec![
+            ShimPathFileStatus {
+                path: PathBuf::from("/tmp/.zprofile"),
+                existed: true,
+                managed_block_present: true,
+                changed: false,
+                error: None,
+            },
+            ShimPathFileStatus {
+                path: PathBuf::from("/tmp/.bashrc"),
+                existed: true,
+                managed_block_present: true,
+                changed: false,
+                error: None,
+            },
+        ];
+        assert_eq!(shim_path_persistence_state(&configured), "configured");
+    }
+
+    #[test]
+    fn shim_status_summary_state_computation() {
+        let disabled_rows = vec![
+            json!({"provider":"codex","installed":false,"path_precedence_ok":false}),
+            json!({"provider":"claude","installed":false,"path_precedence_ok":false}),
+        ];
+        assert_eq!(shim_status_summary_state(&disabled_rows), "disabled");
+
+        let enabled_rows = vec![
+            json!({"provider":"codex","installed":true,"path_precedence_ok":true}),
+            json!({"provider":"claude","installed":true,"path_precedence_ok":true}),
+        ];
+        assert_eq!(shim_status_summary_state(&enabled_rows), "enabled");
+
+        let degraded_rows = vec![
+            json!({"provider":"codex","installed":true,"path_precedence_ok":false}),
+            json!({"provider":"claude","installed":true,"path_precedence_ok":true}),
+        ];
+        assert_eq!(shim_status_summary_state(&degraded_rows), "degraded");
+    }
+
+    #[test]
+    fn doctor_check_ids_are_unique_and_resolvable() {
+        let mut seen = std::collections::HashSet::new();
+        for spec in DOCTOR_CHECKS {
+            assert!(
+                seen.insert(spec.id),
+                "duplicate doctor check id {}",
+                spec.id
+            );
+            assert_eq!(find_doctor_check(spec.id).expect("known id").id, spec.id);
+        }
+        assert!(matches!(
+            find_doctor_check("missing"),
+            Err(LuxError::Config(_))
+        ));
     }
 
+    #[cfg(unix)]
     #[test]
-    fn up_wait_timeout_builds_expected_compose_args() {
+    fn doctor_secrets_file_permissions_flags_readable_files_and_dirs() {
+        use std::os::unix::fs::PermissionsExt;
         let dir = tempdir().unwrap();
-        write_minimal_config(&dir.path().join("config.yaml"));
-        write_default_compose_files(dir.path());
+        let secrets_dir = dir.path().join("secrets");
+        let secrets_file = secrets_dir.join("codex.env");
+        write_provider_secrets_file(&secrets_file, "OPENAI_API_KEY", "sk-test", false).unwrap();
+        let mut cfg = Config::default();
+        cfg.providers.retain(|name, _| name == "codex");
+        let provider = cfg.providers.get_mut("codex").unwrap();
+        provider.auth_mode = AuthMode::ApiKey;
+        provider.auth.api_key.secrets_file = secrets_file.to_string_lossy().to_string();
         let ctx = make_context(dir.path());
-        let runner = MockDockerRunner::default();
 
-        handle_up(&ctx, None, true, None, None, true, Some(45), &runner).unwrap();
+        let check = doctor_check_secrets_file_permissions(&ctx, &cfg).unwrap();
+        assert!(check.ok, "{check:?}");
+        assert_eq!(check.details["entries"][0]["mode"], "0600");
+        assert_eq!(check.details["entries"][1]["mode"], "0700");
+
+        fs::set_permissions(&secrets_file, fs::Permissions::from_mode(0o644)).unwrap();
+        fs::set_permissions(&secrets_dir, fs::Permissions::from_mode(0o755)).unwrap();
+        let check = doctor_check_secrets_file_permissions(&ctx, &cfg).unwrap();
+        assert!(!check.ok);
+        assert_eq!(check.details["entries"][0]["mode"], "0644");
+        assert_eq!(check.details["entries"][1]["group_or_other_readable"], true);
+        assert!(check
+            .remediation
+            .contains(&format!("chmod 600 '{}'", secrets_file.display())));
+        assert!(check
+            .remediation
+            .contains(&format!("chmod 700 '{}'", secrets_dir.display())));
+
+        // A provider that has not stored a key yet has nothing to check.
+        fs::remove_file(&secrets_file).unwrap();
+        let check = doctor_check_secrets_file_permissions(&ctx, &cfg).unwrap();
+        assert!(check.ok);
+        assert_eq!(check.details["entries"].as_array().unwrap().len(), 0);
+    }
 
-        let calls = runner.calls();
-        assert_eq!(calls.len(), 3);
+    #[cfg(unix)]
+    #[test]
+    fn doctor_runtime_socket_permissions_flags_long_paths_and_dir_drift() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempdir().unwrap();
+        let runtime_dir = dir.path().join("rt");
+        fs::create_dir_all(&runtime_dir).unwrap();
+        fs::set_permissions(&runtime_dir, fs::Permissions::from_mode(0o770)).unwrap();
+        let mut cfg = Config::default();
+        cfg.runtime_control_plane.socket_path = runtime_dir
+            .join("control_plane.sock")
+            .to_string_lossy()
+            .to_string();
+        cfg.runtime_control_plane.socket_gid = Some(current_primary_gid());
+        let ctx = make_context(dir.path());
 
-        let ps_args = &calls[0].args;
-        assert!(ps_args.iter().any(|x| x == "ps"));
-        assert!(calls[0].env_overrides.contains_key("LUX_WORKSPACE_ROOT"));
-        assert!(!calls[0].env_overrides.contains_key("LUX_RUN_ID"));
+        let check = doctor_check_runtime_socket_permissions(&ctx, &cfg).unwrap();
+        assert!(check.ok, "{check:?}");
+        assert_eq!(check.details["runtime_dir_mode"], "0770");
+        assert_eq!(check.details["user_in_group"], true);
+
+        fs::set_permissions(&runtime_dir, fs::Permissions::from_mode(0o755)).unwrap();
+        let check = doctor_check_runtime_socket_permissions(&ctx, &cfg).unwrap();
+        assert!(!check.ok);
+        assert_eq!(check.severity, "warn");
+        assert!(!check.strict_fail);
+        assert!(check
+            .remediation
+            .contains(&format!("chmod 770 '{}'", runtime_dir.display())));
+
+        let long_dir = dir.path().join("x".repeat(UNIX_SOCKET_PATH_LIMIT_BYTES));
+        cfg.runtime_control_plane.socket_path = long_dir
+            .join("control_plane.sock")
+            .to_string_lossy()
+            .to_string();
+        let check = doctor_check_runtime_socket_permissions(&ctx, &cfg).unwrap();
+        assert!(!check.ok);
+        assert_eq!(check.severity, "error");
+        assert_eq!(check.details["path_too_long"], true);
+        assert_eq!(check.details["runtime_dir_exists"], false);
+        assert!(check
+            .remediation
+            .contains("runtime_control_plane.socket_path"));
+    }
 
-        let args = &calls[2].args;
-        assert!(calls[2].capture_output);
-        assert!(args.iter().any(|x| x == "up"));
-        assert!(args.iter().any(|x| x == "--wait"));
-        let idx = args.iter().position(|x| x == "--wait-timeout").unwrap();
-        assert_eq!(args[idx + 1], "45");
-        assert!(calls[2].env_overrides.contains_key("LUX_RUN_ID"));
-        assert!(calls[2].env_overrides.contains_key("LUX_WORKSPACE_ROOT"));
+    #[test]
+    fn trusted_root_volatile_filesystem_detection_uses_longest_mount() {
+        let mounts = parse_proc_mounts(concat!(
+            "/dev/vda / ext4 rw,relatime 0 0\n",
+            "tmpfs /tmp tmpfs rw,nosuid 0 0\n",
+            "tmpfs /run/overlay-upper tmpfs rw 0 0\n",
+            "overlay /srv overlay rw,lowerdir=/l,upperdir=/run/overlay-upper/u,workdir=/run/overlay-upper/w 0 0\n",
+            "/dev/vdb /tmp/disk\\040backed ext4 rw 0 0\n",
+            "ramfs /mnt/ram ramfs rw 0 0\n",
+        ));
+        assert_eq!(mounts[4].mount_point, PathBuf::from("/tmp/disk backed"));
+        let volatile = |path: &str| volatile_filesystem_for(&mounts, Path::new(path));
+        assert_eq!(volatile("/tmp/lux/trusted").as_deref(), Some("tmpfs"));
+        assert_eq!(volatile("/mnt/ram/lux").as_deref(), Some("ramfs"));
+        assert_eq!(volatile("/srv/lux").as_deref(), Some("overlay-on-tmpfs"));
+        assert_eq!(volatile("/tmp/disk backed/lux"), None);
+        assert_eq!(volatile("/home/user/.lux"), None);
+        assert_eq!(volatile("/tmpfoo"), None);
+
+        let bsd = parse_bsd_mount_output(concat!(
+            "/dev/disk3s1s1 on / (apfs, sealed, local, read-only, journaled)\n",
+            "/dev/disk4 on /Volumes/Ram Disk (tmpfs, local, nodev)\n",
+        ));
+        assert_eq!(
+            volatile_filesystem_for(&bsd, Path::new("/Volumes/Ram Disk/lux")).as_deref(),
+            Some("tmpfs")
+        );
+        assert_eq!(
+            volatile_filesystem_for(&bsd, Path::new("/Users/me/.lux")),
+            None
+        );
     }
 
     #[test]
-    fn up_timeout_requires_wait() {
-        let dir = tempdir().unwrap();
-        write_minimal_config(&dir.path().join("config.yaml"));
-        write_default_compose_files(dir.path());
-        let ctx = make_context(dir.path());
-        let runner = MockDockerRunner::default();
+    fn runtime_event_filter_parses_repeatable_query_params() {
+        let query =
+            parse_query_map("event_type=stack.up&event_type=stack.down,warning&severity=error");
+        assert_eq!(query["event_type"].len(), 2);
+        let filter = RuntimeEventFilter::from_query(&query);
+        let event = |event_type: &str, severity: &str| RuntimeEvent {
+            id: 1,
+            ts: String::new(),
+            event_type: event_type.to_string(),
+            severity: severity.to_string(),
+            payload: json!({}),
+        };
+        assert!(filter.matches(&event("stack.down", "error")));
+        assert!(filter.matches(&event("warning", "error")));
+        assert!(!filter.matches(&event("stack.up", "info")));
+        assert!(!filter.matches(&event("other", "error")));
+        assert!(RuntimeEventFilter::from_query(&BTreeMap::new()).matches(&event("other", "info")));
+    }
 
-        let err = handle_up(&ctx, None, true, None, None, false, Some(10), &runner)
-            .expect_err("timeout without wait should fail");
-        assert!(err.to_string().contains("--timeout-sec requires --wait"));
+    #[cfg(unix)]
+    #[test]
+    fn runtime_connection_slots_are_bounded_and_released_on_drop() {
+        let shared: Arc<(Mutex<RuntimeSharedState>, Condvar)> =
+            Arc::new((Mutex::new(RuntimeSharedState::default()), Condvar::new()));
+        let first = runtime_acquire_connection_slot(&shared, 2).unwrap();
+        let second = runtime_acquire_connection_slot(&shared, 2).unwrap();
+        assert!(first.is_some() && second.is_some());
+        assert!(runtime_acquire_connection_slot(&shared, 2)
+            .unwrap()
+            .is_none());
+        drop(first);
+        assert_eq!(shared.0.lock().unwrap().active_connections, 1);
+        assert!(runtime_acquire_connection_slot(&shared, 2)
+            .unwrap()
+            .is_some());
+        assert_eq!(shared.0.lock().unwrap().active_connections, 1);
     }
 
     #[test]
-    fn up_fails_when_stack_already_running() {
-        let dir = tempdir().unwrap();
-        write_minimal_config(&dir.path().join("config.yaml"));
-        write_default_compose_files(dir.path());
-        let ctx = make_context(dir.path());
-        let runner = MockDockerRunner::default();
-        runner.push_output(CommandOutput {
-            status_code: 0,
-            stdout: Vec::new(),
-            stderr: Vec::new(),
+    fn runtime_rotation_cutover_does_not_overlap_a_slow_cutover() {
+        let tmp = tempdir().unwrap();
+        let ctx = make_context(tmp.path());
+        let events_path = tmp.path().join("events.jsonl");
+        let shared: Arc<(Mutex<RuntimeSharedState>, Condvar)> =
+            Arc::new((Mutex::new(RuntimeSharedState::default()), Condvar::new()));
+        let active = ActiveRunState {
+            run_id: "lux__test_run".to_string(),
+            started_at: Utc::now().to_rfc3339(),
+            workspace_root: None,
+            idle_timeout_min: None,
+            mounts: Vec::new(),
+            project_name: None,
+        };
+        // Blocks inside `down` until released, simulating a slow docker stop.
+        struct SlowRuntimeCliRunner {
+            inner: MockRuntimeCliRunner,
+            started_tx: Mutex<std::sync::mpsc::Sender<()>>,
+            release_rx: Mutex<std::sync::mpsc::Receiver<()>>,
+        }
+        impl RuntimeCliRunner for SlowRuntimeCliRunner {
+            fn run(
+                &self,
+                ctx: &Context,
+                argv: &[String],
+                cwd: Option<&Path>,
+            ) -> Result<CommandOutput, LuxError> {
+                if argv[0] == "down" {
+                    self.started_tx.lock().unwrap().send(()).unwrap();
+                    self.release_rx.lock().unwrap().recv().unwrap();
+                }
+                self.inner.run(ctx, argv, cwd)
+            }
+        }
+        let (started_tx, started_rx) = std::sync::mpsc::channel::<()>();
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let slow_cli = Arc::new(SlowRuntimeCliRunner {
+            inner: MockRuntimeCliRunner::default(),
+            started_tx: Mutex::new(started_tx),
+            release_rx: Mutex::new(release_rx),
         });
-        runner.push_output(CommandOutput {
+
+        let slow = {
+            let ctx = ctx.clone();
+            let shared = Arc::clone(&shared);
+            let events_path = events_path.clone();
+            let active = active.clone();
+            let slow_cli = Arc::clone(&slow_cli);
+            thread::spawn(move || {
+                runtime_rotation_cutover(&ctx, &shared, &events_path, &active, &*slow_cli).unwrap()
+            })
+        };
+
+        started_rx.recv().unwrap();
+        assert!(shared.0.lock().unwrap().rotation_in_progress);
+        let overlapping = MockRuntimeCliRunner::default();
+        assert!(
+            !runtime_rotation_cutover(&ctx, &shared, &events_path, &active, &overlapping).unwrap()
+        );
+        assert!(overlapping.calls().is_empty());
+        release_tx.send(()).unwrap();
+        assert!(slow.join().unwrap());
+
+        let calls = slow_cli.inner.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0], vec!["down", "--collector-only"]);
+        assert_eq!(calls[1][0], "up");
+        let state = shared.0.lock().unwrap();
+        assert!(!state.rotation_in_progress);
+        let event_types: Vec<&str> = state
+            .events
+            .iter()
+            .map(|event| event.event_type.as_str())
+            .collect();
+        assert_eq!(
+            event_types,
+            vec!["run.stopped", "collector.rotation.skipped", "run.started"]
+        );
+    }
+
+    fn scheduler_tick_fixture(
+        dir: &Path,
+        started_at: DateTime<Utc>,
+        idle_timeout_min: Option<u64>,
+    ) -> Context {
+        write_minimal_config(&dir.join("config.yaml"));
+        write_default_compose_files(dir);
+        let cfg = read_config(&dir.join("config.yaml")).unwrap();
+        let policy = resolve_config_policy_paths(&cfg).unwrap();
+        write_active_run_state(
+            &policy.state_root,
+            "lux__test_run",
+            &policy.workspace_root,
+            &[],
+            "lux",
+        )
+        .unwrap();
+        let mut state = load_active_run_state(&policy.state_root).unwrap().unwrap();
+        state.started_at = started_at.to_rfc3339();
+        state.idle_timeout_min = idle_timeout_min;
+        store_active_run_state(&policy.state_root, &state).unwrap();
+        make_context(dir)
+    }
+
+    fn running_services_output(services: &[&str]) -> CommandOutput {
+        CommandOutput {
             status_code: 0,
-            stdout: b"collector\n".to_vec(),
+            stdout: services.join("\n").into_bytes(),
             stderr: Vec::new(),
-        });
-
-        let err = handle_up(&ctx, None, true, None, None, false, None, &runner)
-            .expect_err("already-running stack should fail");
-        assert!(err.to_string().contains("collector is already running"));
-        assert_eq!(runner.calls().len(), 2);
+        }
     }
 
     #[test]
-    fn down_collector_only_builds_expected_args() {
+    fn runtime_scheduler_tick_idle_timeout_stops_collector() {
         let dir = tempdir().unwrap();
-        write_minimal_config(&dir.path().join("config.yaml"));
-        write_default_compose_files(dir.path());
-        let ctx = make_context(dir.path());
+        let ctx = scheduler_tick_fixture(
+            dir.path(),
+            Utc::now() - chrono::Duration::minutes(10),
+            Some(5),
+        );
+        let events_path = dir.path().join("events.jsonl");
+        let shared: Arc<(Mutex<RuntimeSharedState>, Condvar)> =
+            Arc::new((Mutex::new(RuntimeSharedState::default()), Condvar::new()));
         let runner = MockDockerRunner::default();
+        runner.push_output(running_services_output(&[]));
+        runner.push_output(running_services_output(&["collector"]));
+        let cli = MockRuntimeCliRunner::default();
 
-        handle_down(&ctx, None, true, &runner).unwrap();
+        runtime_scheduler_tick(&ctx, &runner, &cli, &shared, &events_path).unwrap();
 
-        let calls = runner.calls();
-        assert_eq!(calls.len(), 1);
-        let args = &calls[0].args;
-        assert!(args.iter().any(|x| x == "stop"));
-        assert!(args.iter().any(|x| x == "collector"));
+        assert_eq!(cli.calls(), vec![vec!["down", "--collector-only"]]);
+        let state = shared.0.lock().unwrap();
+        assert_eq!(state.events.len(), 1);
+        assert_eq!(state.events[0].event_type, "run.stopped");
+        assert_eq!(state.events[0].payload["reason"], "idle_timeout");
     }
 
     #[test]
-    fn compose_file_override_replaces_default_compose_selection() {
+    fn runtime_scheduler_tick_rotation_stops_then_starts_collector() {
         let dir = tempdir().unwrap();
-        write_minimal_config(&dir.path().join("config.yaml"));
-        let override_file = dir.path().join("custom.compose.yml");
-        fs::write(&override_file, "services: {}\n").unwrap();
-        let mut ctx = make_context(dir.path());
-        ctx.compose_file_overrides = vec![override_file.clone()];
+        let ctx =
+            scheduler_tick_fixture(dir.path(), Utc::now() - chrono::Duration::days(2), Some(45));
+        let events_path = dir.path().join("events.jsonl");
+        let shared: Arc<(Mutex<RuntimeSharedState>, Condvar)> = Arc::new((
+            Mutex::new(RuntimeSharedState {
+                last_provider_activity_at: Some(Utc::now().to_rfc3339()),
+                ..RuntimeSharedState::default()
+            }),
+            Condvar::new(),
+        ));
+
+        // Provider active: rotation is deferred and nothing is run.
         let runner = MockDockerRunner::default();
-        runner.push_output(CommandOutput {
-            status_code: 0,
-            stdout: b"[]\n".to_vec(),
-            stderr: Vec::new(),
+        runner.push_output(running_services_output(&["agent", "harness"]));
+        runner.push_output(running_services_output(&["collector"]));
+        let cli = MockRuntimeCliRunner::default();
+        runtime_scheduler_tick(&ctx, &runner, &cli, &shared, &events_path).unwrap();
+        assert!(cli.calls().is_empty());
+        assert!(shared.0.lock().unwrap().rotation_pending);
+
+        // Provider gone: the cutover runs the stop/start pair and clears the pending flag.
+        let runner = MockDockerRunner::default();
+        runner.push_output(running_services_output(&[]));
+        runner.push_output(running_services_output(&["collector"]));
+        runtime_scheduler_tick(&ctx, &runner, &cli, &shared, &events_path).unwrap();
+        assert_eq!(
+            cli.calls(),
+            vec![
+                vec!["down", "--collector-only"],
+                vec![
+                    "up",
+                    "--collector-only",
+                    "--wait",
+                    "--idle-timeout-min",
+                    "45"
+                ]
+            ]
+        );
+        let state = shared.0.lock().unwrap();
+        assert!(!state.rotation_pending);
+        assert_eq!(
+            state.events.back().unwrap().payload["reason"],
+            "rotation_cutover_complete"
+        );
+    }
+
+    #[test]
+    fn runtime_scheduler_tick_reports_failed_rotation_start() {
+        let dir = tempdir().unwrap();
+        let ctx = scheduler_tick_fixture(dir.path(), Utc::now() - chrono::Duration::days(2), None);
+        let events_path = dir.path().join("events.jsonl");
+        let shared: Arc<(Mutex<RuntimeSharedState>, Condvar)> =
+            Arc::new((Mutex::new(RuntimeSharedState::default()), Condvar::new()));
+        let runner = MockDockerRunner::default();
+        runner.push_output(running_services_output(&[]));
+        runner.push_output(running_services_output(&["collector"]));
+        let cli = MockRuntimeCliRunner::default();
+        cli.push_output(running_services_output(&[]));
+        cli.push_output(CommandOutput {
+            status_code: 1,
+            stdout: Vec::new(),
+            stderr: b"collector failed to start".to_vec(),
         });
 
-        handle_status(&ctx, None, true, &runner).unwrap();
+        runtime_scheduler_tick(&ctx, &runner, &cli, &shared, &events_path).unwrap();
 
-        let calls = runner.calls();
-        assert_eq!(calls.len(), 1);
-        let args = &calls[0].args;
-        assert!(args
+        assert_eq!(cli.calls().len(), 2);
+        let state = shared.0.lock().unwrap();
+        assert!(!state.rotation_in_progress);
+        assert!(state
+            .events
             .iter()
-            .any(|x| x == &override_file.to_string_lossy().to_string()));
+            .any(|event| event.payload["reason"] == "rotation_cutover_failed"));
+        assert_eq!(state.warnings.len(), 1);
     }
 
     #[test]
-    fn normalize_version_tag_adds_prefix() {
-        assert_eq!(normalize_version_tag("0.1.0"), "v0.1.0");
-        assert_eq!(normalize_version_tag("v0.1.0"), "v0.1.0");
+    fn runtime_events_file_rotates_by_size_and_bounds_generations() {
+        let tmp = tempdir().unwrap();
+        let events_path = tmp.path().join("events.jsonl");
+        let shared: Arc<(Mutex<RuntimeSharedState>, Condvar)> = Arc::new((
+            Mutex::new(RuntimeSharedState {
+                events_max_bytes: 200,
+                ..RuntimeSharedState::default()
+            }),
+            Condvar::new(),
+        ));
+        for idx in 0..40 {
+            runtime_emit_event(
+                &shared,
+                &events_path,
+                "test.event",
+                "info",
+                json!({"idx": idx}),
+            )
+            .unwrap();
+        }
+        let current = fs::read_to_string(&events_path).unwrap();
+        assert!(!current.is_empty() && current.len() <= 200);
+        let last: serde_json::Value =
+            serde_json::from_str(current.lines().last().unwrap()).unwrap();
+        assert_eq!(last["payload"]["idx"], 39);
+        for generation in 1..=RUNTIME_EVENTS_KEEP_GENERATIONS {
+            let rotated = runtime_events_generation_path(&events_path, generation);
+            let text = fs::read_to_string(&rotated).unwrap();
+            assert!(text.len() <= 200);
+            assert!(text
+                .lines()
+                .all(|line| serde_json::from_str::<serde_json::Value>(line).is_ok()));
+        }
+        assert!(
+            !runtime_events_generation_path(&events_path, RUNTIME_EVENTS_KEEP_GENERATIONS + 1)
+                .exists()
+        );
+        assert_eq!(shared.0.lock().unwrap().events.len(), 40);
     }
 
+    #[cfg(unix)]
     #[test]
-    fn select_previous_version_uses_installed_order() {
-        let installed = vec![
-            "0.1.0".to_string(),
-            "0.2.0".to_string(),
-            "0.3.0".to_string(),
-        ];
-        assert_eq!(
-            select_previous_version("v0.3.0", &installed),
-            Some("v0.2.0".to_string())
+    fn runtime_sse_reader_drains_backlog_and_stops_at_keepalive() {
+        let stream = concat!(
+            "id: 1\nevent: runtime.started\n",
+            "data: {\"id\":1,\"ts\":\"t1\",\"event_type\":\"runtime.started\",\"severity\":\"info\",\"payload\":{}}\n\n",
+            "id: 2\nevent: command.finished\n",
+            "data: {\"id\":2,\"ts\":\"t2\",\"event_type\":\"command.finished\",\"severity\":\"warn\",\"payload\":{\"code\":1}}\n\n",
+            ": keepalive\n\n",
+            "id: 3\nevent: runtime.stopped\n",
+            "data: {\"id\":3,\"ts\":\"t3\",\"event_type\":\"runtime.stopped\",\"severity\":\"info\",\"payload\":{}}\n\n",
         );
-        assert_eq!(select_previous_version("v0.1.0", &installed), None);
-    }
 
-    #[test]
-    fn parse_checksum_reads_first_token() {
-        let hash = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
-        let content = format!("{hash}  lux_0.1.0_linux_amd64.tar.gz");
-        assert_eq!(parse_checksum(&content), Some(hash.to_string()));
-    }
+        let mut drained = Vec::new();
+        runtime_read_sse_events(&mut io::Cursor::new(stream), false, |event| {
+            drained.push(event);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            drained.iter().map(|event| event.id).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(drained[1].payload["code"], 1);
 
-    #[test]
-    fn parse_checksum_supports_openssl_output() {
-        let hash = "abcdefabcdefabcdefabcdefabcdefabcdefabcdefabcdefabcdefabcdefabcd";
-        let content = format!("SHA2-256(file.tar.gz)= {hash}");
-        assert_eq!(parse_checksum(&content), Some(hash.to_string()));
+        let mut followed = Vec::new();
+        runtime_read_sse_events(&mut io::Cursor::new(stream), true, |event| {
+            followed.push(event.event_type);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            followed,
+            vec!["runtime.started", "command.finished", "runtime.stopped"]
+        );
     }
 
     #[test]
-    fn map_host_start_dir_to_container_maps_root_and_nested_paths() {
-        let workspace = PathBuf::from("/tmp/workspace");
-        let mapped_root = map_host_start_dir_to_container(&workspace, &workspace).unwrap();
-        assert_eq!(mapped_root, "/work");
+    fn verify_evidence_manifest_reports_modified_removed_and_added_files() {
+        let dir = tempdir().unwrap();
+        let collector = dir.path().join("collector");
+        fs::create_dir_all(collector.join("raw")).unwrap();
+        fs::write(collector.join("raw").join("ebpf.jsonl"), "{}\n").unwrap();
+        fs::write(collector.join("raw").join("audit.log"), "type=SYSCALL\n").unwrap();
+        fs::write(collector.join("gone.jsonl"), "x\n").unwrap();
+        let manifest = ["raw/audit.log", "raw/ebpf.jsonl", "gone.jsonl"]
+            .iter()
+            .map(|path| {
+                let hash = sha256_file_native(&collector.join(path)).unwrap();
+                format!("{hash}  {path}\n")
+            })
+            .collect::<String>();
+        fs::write(collector.join(EVIDENCE_MANIFEST_FILE), manifest).unwrap();
 
-        let nested = workspace.join("src").join("project");
-        let mapped_nested = map_host_start_dir_to_container(&nested, &workspace).unwrap();
-        assert_eq!(mapped_nested, "/work/src/project");
-    }
+        let files = verify_evidence_manifest(&collector).unwrap();
+        assert!(files.iter().all(|file| file.status == "ok"));
 
-    #[test]
-    fn append_harness_tui_run_args_places_env_before_service_name() {
-        let mut args = Vec::new();
-        append_harness_tui_run_args(&mut args, "/work/project");
+        fs::write(
+            collector.join("raw").join("ebpf.jsonl"),
+            "{\"tampered\":true}\n",
+        )
+        .unwrap();
+        fs::remove_file(collector.join("gone.jsonl")).unwrap();
+        fs::write(collector.join("extra.txt"), "new\n").unwrap();
+        let statuses = verify_evidence_manifest(&collector)
+            .unwrap()
+            .into_iter()
+            .map(|file| (file.path, file.status))
+            .collect::<Vec<_>>();
         assert_eq!(
-            args,
+            statuses,
             vec![
-                "run".to_string(),
-                "--rm".to_string(),
-                "-e".to_string(),
-                "HARNESS_MODE=tui".to_string(),
-                "-e".to_string(),
-                "HARNESS_AGENT_WORKDIR=/work/project".to_string(),
-                "harness".to_string(),
+                ("extra.txt".to_string(), "added".to_string()),
+                ("gone.jsonl".to_string(), "removed".to_string()),
+                ("raw/audit.log".to_string(), "ok".to_string()),
+                ("raw/ebpf.jsonl".to_string(), "modified".to_string()),
             ]
         );
     }
 
-    #[cfg(unix)]
     #[test]
-    fn bundle_dir_from_symlinked_exe_prefers_real_binary_parent() {
-        let dir = tempdir().unwrap();
-        let bundle_dir = dir.path().join("bundle");
-        fs::create_dir_all(&bundle_dir).unwrap();
-        fs::write(bundle_dir.join("compose.yml"), "services: {}\n").unwrap();
-
-        let real_exe = bundle_dir.join("lux");
-        fs::write(&real_exe, "").unwrap();
-
-        let bin_dir = dir.path().join("bin");
-        fs::create_dir_all(&bin_dir).unwrap();
-        let symlink_exe = bin_dir.join("lux");
-        std::os::unix::fs::symlink(&real_exe, &symlink_exe).unwrap();
-
-        let resolved = bundle_dir_from_exe_path(&symlink_exe).expect("bundle dir should resolve");
-        assert_eq!(resolved, bundle_dir);
+    fn parse_evidence_manifest_rejects_paths_outside_collector_dir() {
+        let hash = "a".repeat(64);
+        assert!(parse_evidence_manifest(&format!("{hash}  ../state/x\n")).is_err());
+        assert!(parse_evidence_manifest(&format!("{hash}  /etc/passwd\n")).is_err());
+        assert!(parse_evidence_manifest("nothex  raw/ebpf.jsonl\n").is_err());
+        let parsed = parse_evidence_manifest(&format!("{hash} *raw/ebpf.jsonl\n")).unwrap();
+        assert_eq!(parsed.get("raw/ebpf.jsonl"), Some(&hash));
+    }
+
+    /// Serves a canned response; once drained it either reports EOF or, like a peer that
+    /// keeps the connection open, fails the read.
+    struct ScriptedRuntimeStream {
+        incoming: io::Cursor<Vec<u8>>,
+        closes: bool,
+    }
+
+    impl Read for ScriptedRuntimeStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let read = self.incoming.read(buf)?;
+            if read == 0 && !buf.is_empty() && !self.closes {
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "read past the advertised response",
+                ));
+            }
+            Ok(read)
+        }
     }
 
-    #[test]
-    fn resolve_effective_workspace_root_enforces_home_policy_for_override() {
-        let dir = tempdir().unwrap();
-        let home = required_home_dir().unwrap();
-        let mut cfg = Config::default();
-        let trusted_root = dir.path().join("trusted");
-        cfg.paths.trusted_root = trusted_root.to_string_lossy().to_string();
-        cfg.paths.log_root = trusted_root.join("logs").to_string_lossy().to_string();
-        cfg.shims.bin_dir = trusted_root.join("bin").to_string_lossy().to_string();
-        cfg.paths.workspace_root = home.join("workspace").to_string_lossy().to_string();
-
-        let override_workspace = home.join("workspace-alt");
-        let resolved =
-            resolve_effective_workspace_root(&cfg, Some(override_workspace.to_str().unwrap()))
-                .unwrap();
-        assert_eq!(resolved, override_workspace);
+    impl Write for ScriptedRuntimeStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
 
-        let outside_workspace = dir.path().join("outside-home-workspace");
-        let err = resolve_effective_workspace_root(&cfg, Some(outside_workspace.to_str().unwrap()))
-            .expect_err("outside-home workspace should fail");
-        assert!(err.to_string().contains("--workspace must be under $HOME"));
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
     }
 
+    #[cfg(unix)]
     #[test]
-    fn resolve_host_start_dir_requires_start_dir_inside_workspace() {
-        let dir = tempdir().unwrap();
-        let home = required_home_dir().unwrap();
-        let mut cfg = Config::default();
-        let trusted_root = dir.path().join("trusted");
-        cfg.paths.trusted_root = trusted_root.to_string_lossy().to_string();
-        cfg.paths.log_root = trusted_root.join("logs").to_string_lossy().to_string();
-        cfg.shims.bin_dir = trusted_root.join("bin").to_string_lossy().to_string();
-        cfg.paths.workspace_root = home.join("workspace").to_string_lossy().to_string();
+    fn runtime_http_exchange_reads_exactly_content_length() {
+        let exchange = |response: &[u8], closes: bool| {
+            let stream = ScriptedRuntimeStream {
+                incoming: io::Cursor::new(response.to_vec()),
+                closes,
+            };
+            runtime_http_exchange(stream, "GET", "/v1/healthz", &[], None)
+        };
 
-        let workspace_root = resolve_effective_workspace_root(&cfg, None).unwrap();
-        let inside = workspace_root.join("src");
-        let resolved =
-            resolve_host_start_dir(&cfg, &workspace_root, Some(inside.to_str().unwrap())).unwrap();
-        assert_eq!(resolved, inside);
+        let response = exchange(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\n{\"ok\":true}NEXT",
+            false,
+        )
+        .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"{\"ok\":true}");
+
+        let large = vec![b'x'; 20_000];
+        let mut raw =
+            format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n", large.len()).into_bytes();
+        raw.extend_from_slice(&large);
+        assert_eq!(exchange(&raw, false).unwrap().body, large);
+
+        let response = exchange(
+            b"HTTP/1.1 404 Not Found\r\nConnection: close\r\n\r\nmissing",
+            true,
+        )
+        .unwrap();
+        assert_eq!(response.status, 404);
+        assert_eq!(response.body, b"missing");
 
-        let outside = dir.path().join("outside");
-        let err = resolve_host_start_dir(&cfg, &workspace_root, Some(outside.to_str().unwrap()))
-            .expect_err("outside workspace should fail");
-        assert!(err
-            .to_string()
-            .contains("--start-dir must be inside workspace"));
+        let err = exchange(b"HTTP/1.1 200 OK\r\n\r\nbody", true).unwrap_err();
+        assert!(err.to_string().contains("neither Content-Length"));
+        let err =
+            exchange(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort", true).unwrap_err();
+        assert!(err.to_string().contains("truncated"));
     }
 
     #[test]
-    fn classify_docker_command_failure_detects_compose_unavailable() {
-        let (code, hint) = classify_docker_command_failure(
-            "unknown flag: --env-file\n\nUsage:  docker [OPTIONS] COMMAND [ARG...]",
+    fn runtime_proxy_pins_the_callers_json_layout() {
+        let mut ctx = make_context(Path::new("/tmp"));
+        let args = |items: &[&str]| items.iter().map(|item| item.to_string()).collect();
+        assert_eq!(
+            runtime_proxy_pin_json_format(&ctx, args(&["--json", "status"])),
+            args(&["--json-pretty", "--json", "status"])
+        );
+        ctx.json_pretty = false;
+        assert_eq!(
+            runtime_proxy_pin_json_format(&ctx, args(&["run", "--", "--json-pretty"])),
+            args(&["--json-compact", "run", "--", "--json-pretty"])
+        );
+        assert_eq!(
+            runtime_proxy_pin_json_format(&ctx, args(&["--json-pretty", "status"])),
+            args(&["--json-pretty", "status"])
         );
-        assert_eq!(code, "docker_compose_unavailable");
-        assert!(hint.unwrap_or_default().contains("DOCKER_CONFIG"));
     }
 
     #[test]
-    fn execute_docker_nonzero_exit_surfaces_structured_details() {
-        let dir = tempdir().unwrap();
-        write_minimal_config(&dir.path().join("config.yaml"));
-        write_default_compose_files(dir.path());
-        let ctx = make_context(dir.path());
-        let runner = MockDockerRunner::default();
-        runner.push_output(CommandOutput {
-            status_code: 125,
-            stdout: Vec::new(),
-            stderr: b"unknown flag: --env-file".to_vec(),
-        });
-        let args = vec![
-            "compose".to_string(),
-            "--env-file".to_string(),
-            "/tmp/compose.env".to_string(),
-            "ps".to_string(),
-        ];
-        let err = execute_docker(&ctx, &runner, &args, &BTreeMap::new(), true, false)
-            .expect_err("docker command should fail");
-
-        match err {
-            LuxError::ProcessDetailed { message, details } => {
-                assert!(message
-                    .contains("while running `docker compose --env-file /tmp/compose.env ps`"));
-                assert_eq!(details.error_code, "docker_compose_unavailable");
-                assert!(details.hint.unwrap_or_default().contains("DOCKER_CONFIG"));
-                assert_eq!(
-                    details.command.unwrap_or_default(),
-                    "docker compose --env-file /tmp/compose.env ps"
-                );
-                assert!(details
-                    .raw_stderr
-                    .unwrap_or_default()
-                    .contains("unknown flag: --env-file"));
-            }
-            other => panic!("unexpected error variant: {other:?}"),
+    fn runtime_ready_backoff_doubles_up_to_the_cap() {
+        let mut delay = RUNTIME_READY_INITIAL_BACKOFF;
+        let mut delays = Vec::new();
+        for _ in 0..7 {
+            delays.push(delay.as_millis());
+            delay = runtime_ready_next_backoff(delay);
         }
+        assert_eq!(delays, vec![50, 100, 200, 400, 800, 1000, 1000]);
     }
 
     #[test]
-    fn execute_docker_spawn_not_found_sets_docker_not_found_code() {
-        struct NotFoundRunner;
-        impl DockerRunner for NotFoundRunner {
+    fn shim_exec_timeout_kills_run_and_removes_named_container() {
+        let tmp = tempdir().unwrap();
+        let ctx = make_context(tmp.path());
+
+        struct TimingOutRunner(MockDockerRunner);
+        impl DockerRunner for TimingOutRunner {
             fn run(
+                &self,
+                args: &[String],
+                cwd: &Path,
+                env_overrides: &BTreeMap<String, String>,
+                capture_output: bool,
+            ) -> Result<CommandOutput, io::Error> {
+                self.0.run(args, cwd, env_overrides, capture_output)
+            }
+
+            fn run_with_timeout(
                 &self,
                 _args: &[String],
                 _cwd: &Path,
                 _env_overrides: &BTreeMap<String, String>,
-                _capture_output: bool,
-            ) -> Result<CommandOutput, io::Error> {
-                Err(io::Error::new(io::ErrorKind::NotFound, "docker not found"))
+                capture_output: bool,
+                timeout: Duration,
+            ) -> Result<Option<CommandOutput>, io::Error> {
+                assert!(!capture_output, "shim exec must keep the terminal attached");
+                assert_eq!(timeout, Duration::from_secs(5));
+                Ok(None)
             }
         }
 
+        let runner = TimingOutRunner(MockDockerRunner::default());
+        let args: Vec<String> = ["compose", "run", "--name", "lux-shim-codex-1", "harness"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let err = execute_shim_exec_with_timeout(
+            &ctx,
+            &runner,
+            &args,
+            &BTreeMap::new(),
+            "lux-shim-codex-1",
+            5,
+            json!({"action": "shim_exec"}),
+        )
+        .unwrap_err();
+        let details = extract_process_error_details(&err).unwrap();
+        assert_eq!(details.error_code, "shim_exec_timeout");
+        let partial = details.partial_outcome.as_ref().unwrap();
+        assert_eq!(partial["cleaned_up"], true);
+        let calls = runner.0.calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].args, vec!["rm", "-f", "lux-shim-codex-1"]);
+    }
+
+    #[test]
+    fn up_rejects_host_state_provider_without_any_host_state_path() {
         let dir = tempdir().unwrap();
-        write_minimal_config(&dir.path().join("config.yaml"));
+        let config_path = dir.path().join("config.yaml");
+        write_minimal_config(&config_path);
         write_default_compose_files(dir.path());
+        let missing_a = dir.path().join("missing-a");
+        let missing_b = dir.path().join("missing-b");
+        let mut cfg = read_config(&config_path).unwrap();
+        let provider = cfg.providers.get_mut("codex").unwrap();
+        provider.auth_mode = AuthMode::HostState;
+        provider.auth.host_state.paths = vec![
+            missing_a.to_string_lossy().to_string(),
+            missing_b.to_string_lossy().to_string(),
+        ];
+        provider.auth.host_state.keychain_items.clear();
+        fs::write(&config_path, serde_yaml::to_string(&cfg).unwrap()).unwrap();
         let ctx = make_context(dir.path());
-        let runner = NotFoundRunner;
-        let args = vec!["compose".to_string(), "ps".to_string()];
-        let err = execute_docker(&ctx, &runner, &args, &BTreeMap::new(), true, false)
-            .expect_err("missing docker should fail");
-        match err {
-            LuxError::ProcessDetailed { message, details } => {
-                assert!(message.contains("failed to run command `docker compose ps`"));
-                assert_eq!(details.error_code, "docker_not_found");
-                assert!(details.hint.unwrap_or_default().contains("Install Docker"));
-                assert_eq!(details.command.unwrap_or_default(), "docker compose ps");
-            }
-            other => panic!("unexpected error variant: {other:?}"),
-        }
+        let runner = MockDockerRunner::default();
+
+        let err = handle_up(
+            &ctx,
+            UpOptions {
+                provider: Some("codex".to_string()),
+                ..Default::default()
+            },
+            &runner,
+        )
+        .expect_err("host_state provider without host-state paths should fail");
+        assert!(matches!(err, LuxError::Config(_)));
+        let message = err.to_string();
+        assert!(message.contains(&format!("missing: {}", missing_a.display())));
+        assert!(message.contains(&format!("missing: {}", missing_b.display())));
+        assert!(message.contains("login flow"));
+        assert!(runner.calls().is_empty());
+
+        // One present path is enough; the rest only warn at compose generation.
+        fs::create_dir_all(&missing_b).unwrap();
+        let cfg = read_config(&config_path).unwrap();
+        ensure_host_state_available("codex", &cfg.providers["codex"]).unwrap();
     }
 
     #[test]
-    fn shim_path_block_insert_replace_remove_is_idempotent() {
-        let initial = "# shell profile\n";
-        let block = render_shim_path_block(Path::new("/tmp/lux/bin"));
-
-        let (enabled, enabled_present, enabled_changed) =
-            apply_managed_path_block(initial, &block).expect("enable should work");
-        assert!(enabled_present);
-        assert!(enabled_changed);
-        assert_eq!(enabled.matches(SHIM_PATH_BEGIN_MARKER).count(), 1);
+    fn run_prune_plan_keeps_newest_runs_or_recent_days() {
+        let run_ids: Vec<String> = [
+            "lux__2026_01_01_00_00_00",
+            "lux__2026_01_05_00_00_00",
+            "lux__2026_01_09_00_00_00",
+            "lux__adhoc",
+        ]
+        .iter()
+        .map(|run_id| run_id.to_string())
+        .collect();
+        let now = run_id_started_at("lux__2026_01_10_00_00_00").unwrap();
 
-        let (enabled_again, enabled_again_present, enabled_again_changed) =
-            apply_managed_path_block(&enabled, &block).expect("enable re-run should work");
-        assert!(enabled_again_present);
-        assert!(!enabled_again_changed);
-        assert_eq!(enabled_again.matches(SHIM_PATH_BEGIN_MARKER).count(), 1);
+        assert_eq!(
+            plan_run_prune(&run_ids, Some(2), None, now),
+            vec!["lux__2026_01_01_00_00_00", "lux__2026_01_05_00_00_00"]
+        );
+        assert!(plan_run_prune(&run_ids, Some(10), None, now).is_empty());
+        // Undated names are never selected by age.
+        assert_eq!(
+            plan_run_prune(&run_ids, None, Some(3), now),
+            vec!["lux__2026_01_01_00_00_00", "lux__2026_01_05_00_00_00"]
+        );
+    }
 
-        let (disabled, disabled_present, disabled_changed) =
-            remove_managed_path_block(&enabled_again).expect("disable should work");
-        assert!(!disabled_present);
-        assert!(disabled_changed);
-        assert!(!disabled.contains(SHIM_PATH_BEGIN_MARKER));
+    #[test]
+    fn new_run_ids_get_a_suffix_instead_of_reusing_an_existing_run_directory() {
+        let dir = tempdir().unwrap();
+        let log_root = dir.path().join("logs");
+        let base = "lux__2026_02_12_12_00_00";
+        assert_eq!(next_free_run_id(&log_root, base), base);
+        fs::create_dir_all(log_root.join(base)).unwrap();
+        fs::create_dir_all(log_root.join(format!("{base}_2"))).unwrap();
+        assert_eq!(next_free_run_id(&log_root, base), format!("{base}_3"));
+        assert_eq!(claim_run_id(&log_root, base).unwrap(), format!("{base}_3"));
+        assert!(log_root.join(format!("{base}_3")).is_dir());
+        assert_eq!(claim_run_id(&log_root, base).unwrap(), format!("{base}_4"));
+        assert_eq!(
+            run_id_started_at(&format!("{base}_4")),
+            run_id_started_at(base)
+        );
+        assert!(run_id_started_at("lux__2026_02_12_12_00_00_x").is_none());
 
-        let (disabled_again, disabled_again_present, disabled_again_changed) =
-            remove_managed_path_block(&disabled).expect("disable re-run should work");
-        assert!(!disabled_again_present);
-        assert!(!disabled_again_changed);
-        assert_eq!(disabled, disabled_again);
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let cfg = read_config(&ctx.config_path).unwrap();
+        let policy = resolve_config_policy_paths(&cfg).unwrap();
+        let mut run_ids = Vec::new();
+        for _ in 0..2 {
+            let runner = MockDockerRunner::default();
+            handle_up(
+                &ctx,
+                UpOptions {
+                    collector_only: true,
+                    ..Default::default()
+                },
+                &runner,
+            )
+            .unwrap();
+            let active = load_active_run_state(&policy.state_root).unwrap().unwrap();
+            let up_call = runner
+                .calls()
+                .into_iter()
+                .find(|call| call.args.iter().any(|arg| arg == "up"))
+                .unwrap();
+            assert_eq!(up_call.env_overrides["LUX_RUN_ID"], active.run_id);
+            run_ids.push(active.run_id);
+        }
+        assert_ne!(run_ids[0], run_ids[1]);
+        for run_id in &run_ids {
+            assert!(run_root(&policy.log_root, run_id).is_dir());
+        }
     }
 
     #[test]
-    fn shim_path_persistence_state_computation() {
-        assert_eq!(shim_path_persistence_state(&[]), "no_startup_files");
-
-        let absent = vec![
-            ShimPathFileStatus {
-                path: PathBuf::from("/tmp/.zprofile"),
-                existed: true,
-                managed_block_present: false,
-                changed: false,
-                error: None,
-            },
-            ShimPathFileStatus {
-                path: PathBuf::from("/tmp/.bashrc"),
-                existed: true,
-                managed_block_present: false,
-                changed: false,
-                error: None,
-            },
-        ];
-        assert_eq!(shim_path_persistence_state(&absent), "absent");
+    fn lifecycle_refuses_an_active_run_from_another_compose_project() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let cfg = read_config(&ctx.config_path).unwrap();
+        let policy = resolve_config_policy_paths(&cfg).unwrap();
+        write_active_run_state(
+            &policy.state_root,
+            "lux__other_run",
+            &policy.workspace_root,
+            &[],
+            "other",
+        )
+        .unwrap();
+        let collector_only = || UpOptions {
+            collector_only: true,
+            ..Default::default()
+        };
 
-        let partial = vec![
-            ShimPathFileStatus {
-                path: PathBuf::from("/tmp/.zprofile"),
-                existed: true,
-                managed_block_present: true,
-                changed: false,
-                error: None,
-            },
-            ShimPathFileStatus {
-                path: PathBuf::from("/tmp/.bashrc"),
-                existed: true,
-                managed_block_present: false,
-                changed: false,
-                error: None,
-            },
-        ];
-        assert_eq!(shim_path_persistence_state(&partial), "partial");
+        // The other project's collector is still running: refuse to start beside it.
+        let runner = MockDockerRunner::default();
+        runner.push_output(CommandOutput {
+            status_code: 0,
+            stdout: b"collector\n".to_vec(),
+            stderr: Vec::new(),
+        });
+        let err = handle_up(&ctx, collector_only(), &runner)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("compose project 'other', not 'lux'"), "{err}");
+        let probe = &runner.calls()[0].args;
+        let project_idx = probe.iter().position(|arg| arg == "-p").unwrap();
+        assert_eq!(probe[project_idx + 1], "other");
+        assert_eq!(runner.calls().len(), 1);
+
+        let err = handle_down(
+            &ctx,
+            Some("codex".to_string()),
+            false,
+            false,
+            &MockDockerRunner::default(),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("compose project 'other'"), "{err}");
 
-        let configured = vec![
-            ShimPathFileStatus {
-                path: PathBuf::from("/tmp/.zprofile"),
-                existed: true,
-                managed_block_present: true,
-                changed: false,
-                error: None,
-            },
-            ShimPathFileStatus {
-                path: PathBuf::from("/tmp/.bashrc"),
-                existed: true,
-                managed_block_present: true,
-                changed: false,
-                error: None,
-            },
-        ];
-        assert_eq!(shim_path_persistence_state(&configured), "configured");
+        // Once it is stopped, its leftover state is replaced by this project's run.
+        let runner = MockDockerRunner::default();
+        handle_up(&ctx, collector_only(), &runner).unwrap();
+        let active = load_active_run_state(&policy.state_root).unwrap().unwrap();
+        assert_ne!(active.run_id, "lux__other_run");
+        assert_eq!(active.project_name.as_deref(), Some("lux"));
+        handle_down(&ctx, None, true, false, &MockDockerRunner::default()).unwrap();
     }
 
     #[test]
-    fn shim_status_summary_state_computation() {
-        let disabled_rows = vec![
-            json!({"provider":"codex","installed":false,"path_precedence_ok":false}),
-            json!({"provider":"claude","installed":false,"path_precedence_ok":false}),
-        ];
-        assert_eq!(shim_status_summary_state(&disabled_rows), "disabled");
+    fn up_foreground_attaches_without_detach_and_keeps_run_state() {
+        let dir = tempdir().unwrap();
+        write_minimal_config(&dir.path().join("config.yaml"));
+        write_default_compose_files(dir.path());
+        let ctx = make_context(dir.path());
+        let runner = MockDockerRunner::default();
 
-        let enabled_rows = vec![
-            json!({"provider":"codex","installed":true,"path_precedence_ok":true}),
-            json!({"provider":"claude","installed":true,"path_precedence_ok":true}),
-        ];
-        assert_eq!(shim_status_summary_state(&enabled_rows), "enabled");
+        handle_up(
+            &ctx,
+            UpOptions {
+                collector_only: true,
+                foreground: true,
+                ..Default::default()
+            },
+            &runner,
+        )
+        .unwrap();
 
-        let degraded_rows = vec![
-            json!({"provider":"codex","installed":true,"path_precedence_ok":false}),
-            json!({"provider":"claude","installed":true,"path_precedence_ok":true}),
-        ];
-        assert_eq!(shim_status_summary_state(&degraded_rows), "degraded");
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 3);
+        assert!(!calls[2].capture_output);
+        assert!(calls[2].args.iter().any(|x| x == "up"));
+        assert!(!calls[2].args.iter().any(|x| x == "-d"));
+        let cfg = read_config(&ctx.config_path).unwrap();
+        let state_root = resolve_config_policy_paths(&cfg).unwrap().state_root;
+        assert!(load_active_run_state(&state_root).unwrap().is_some());
+
+        // A provider plane whose foreground compose fails leaves no provider recorded.
+        let policy = resolve_config_policy_paths(&cfg).unwrap();
+        let host_state = dir.path().join("codex-home");
+        fs::create_dir_all(&host_state).unwrap();
+        let mut cfg = cfg;
+        cfg.collector.auto_start = false;
+        let provider = cfg.providers.get_mut("codex").unwrap();
+        provider.auth_mode = AuthMode::HostState;
+        provider.auth.host_state.paths = vec![host_state.to_string_lossy().to_string()];
+        fs::write(&ctx.config_path, serde_yaml::to_string(&cfg).unwrap()).unwrap();
+        let runner = MockDockerRunner::default();
+        runner.push_output(CommandOutput {
+            status_code: 0,
+            stdout: b"collector\n".to_vec(),
+            stderr: Vec::new(),
+        });
+        runner.push_output(CommandOutput {
+            status_code: 0,
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        });
+        runner.push_output(CommandOutput {
+            status_code: 1,
+            stdout: Vec::new(),
+            stderr: b"agent failed to start".to_vec(),
+        });
+        handle_up(
+            &ctx,
+            UpOptions {
+                provider: Some("codex".to_string()),
+                foreground: true,
+                ..Default::default()
+            },
+            &runner,
+        )
+        .expect_err("failed foreground compose should surface");
+        assert!(runner
+            .calls()
+            .last()
+            .unwrap()
+            .args
+            .iter()
+            .any(|x| x == "up"));
+        assert!(load_active_provider_state(&policy.state_root)
+            .unwrap()
+            .is_none());
+        assert!(load_active_run_state(&policy.state_root).unwrap().is_some());
+
+        let cli = Cli::parse_from(["lux", "up", "--provider", "codex", "--foreground"]);
+        assert!(!should_route_through_runtime(&cli.command));
+        assert!(
+            Cli::try_parse_from(["lux", "up", "--collector-only", "--foreground", "--wait"])
+                .is_err()
+        );
     }
 }
@@ -112,6 +112,90 @@ fn config_init_creates_and_preserves_existing() {
     assert_eq!(content, "sentinel: true\n");
 }
 
+#[test]
+fn config_dir_flag_sits_between_lux_config_and_lux_config_dir() {
+    let dir = tempdir().unwrap();
+    let env_dir = dir.path().join("env-dir");
+    let flag_dir = dir.path().join("flag-dir");
+
+    bin()
+        .env_remove("LUX_CONFIG")
+        .env("LUX_CONFIG_DIR", &env_dir)
+        .arg("--config-dir")
+        .arg(&flag_dir)
+        .arg("config")
+        .arg("init")
+        .assert()
+        .success();
+    assert!(flag_dir.join("config.yaml").exists());
+    assert!(!env_dir.join("config.yaml").exists());
+
+    let explicit = dir.path().join("explicit.yaml");
+    bin()
+        .env("LUX_CONFIG", &explicit)
+        .env("LUX_CONFIG_DIR", &env_dir)
+        .arg("--config-dir")
+        .arg(&flag_dir)
+        .arg("config")
+        .arg("init")
+        .assert()
+        .success();
+    assert!(explicit.exists());
+    assert!(!env_dir.join("config.yaml").exists());
+}
+
+#[test]
+fn config_init_force_backs_up_and_rewrites_defaults() {
+    let dir = tempdir().unwrap();
+    let config_dir = dir.path().join("config");
+    fs::create_dir_all(&config_dir).unwrap();
+    let config_path = config_dir.join("config.yaml");
+    fs::write(&config_path, "version: [unparseable\n").unwrap();
+
+    let printed = bin()
+        .env("LUX_CONFIG_DIR", &config_dir)
+        .arg("config")
+        .arg("init")
+        .arg("--print")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let printed = String::from_utf8(printed).unwrap();
+    assert!(printed.contains("version:"));
+    assert_eq!(
+        fs::read_to_string(&config_path).unwrap(),
+        "version: [unparseable\n"
+    );
+
+    let output = bin()
+        .env("LUX_CONFIG_DIR", &config_dir)
+        .arg("--json")
+        .arg("config")
+        .arg("init")
+        .arg("--force")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    assert!(value["result"]["created"].as_bool().unwrap());
+    let backup_path = PathBuf::from(value["result"]["backup_path"].as_str().unwrap());
+    assert!(backup_path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .starts_with("config.yaml.bak."));
+    assert_eq!(
+        fs::read_to_string(&backup_path).unwrap(),
+        "version: [unparseable\n"
+    );
+    assert_eq!(fs::read_to_string(&config_path).unwrap(), printed);
+}
+
 #[test]
 fn config_validate_rejects_unknown_fields() {
     let dir = tempdir().unwrap();
@@ -179,6 +263,75 @@ fn config_validate_rejects_workspace_outside_home() {
     assert!(error.contains("paths.workspace_root must be under $HOME"));
 }
 
+#[test]
+fn config_validate_strict_reports_all_reachability_findings() {
+    let dir = tempdir().unwrap();
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
+    let config_path = dir.path().join("config.yaml");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
+    let secrets_file = trusted_root.join("secrets").join("codex.env");
+    let host_state = home.join(".codex").join("auth.json");
+    let mut yaml = fs::read_to_string(&config_path).unwrap();
+    yaml.push_str(&format!(
+        "providers:\n  codex:\n    auth_mode: api_key\n    mount_host_state_in_api_mode: true\n    auth:\n      api_key:\n        secrets_file: {}\n        env_key: OPENAI_API_KEY\n      host_state:\n        paths:\n          - {}\n    ownership:\n      root_comm:\n        - codex\n",
+        secrets_file.display(),
+        host_state.display()
+    ));
+    fs::write(&config_path, yaml).unwrap();
+
+    bin()
+        .env("HOME", &home)
+        .arg("--config")
+        .arg(&config_path)
+        .arg("config")
+        .arg("validate")
+        .assert()
+        .success();
+
+    let output = bin()
+        .env("HOME", &home)
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("config")
+        .arg("validate")
+        .arg("--strict")
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+    let value = parse_json(&output);
+    assert_eq!(value["ok"], false);
+    assert_eq!(value["result"]["valid"], false);
+    let findings = value["result"]["findings"].as_array().unwrap();
+    let find = |check: &str| {
+        findings
+            .iter()
+            .find(|finding| finding["check"] == check)
+            .unwrap_or_else(|| panic!("missing finding {check}"))
+    };
+    assert_eq!(find("trusted_root_writable")["ok"], true);
+    assert_eq!(find("log_root_writable")["ok"], true);
+    assert_eq!(find("secrets_file")["ok"], false);
+    assert_eq!(find("host_state_path")["ok"], false);
+    assert_eq!(find("host_state_path")["severity"], "warn");
+
+    fs::create_dir_all(secrets_file.parent().unwrap()).unwrap();
+    fs::write(&secrets_file, "OPENAI_API_KEY=test\n").unwrap();
+    bin()
+        .env("HOME", &home)
+        .arg("--config")
+        .arg(&config_path)
+        .arg("config")
+        .arg("validate")
+        .arg("--strict")
+        .assert()
+        .success()
+        .stdout(contains("[fail] host_state_path (warn)"))
+        .stdout(contains("[ok] secrets_file (error)"));
+}
+
 #[test]
 fn config_validate_rejects_log_root_inside_home() {
     let dir = tempdir().unwrap();
@@ -236,6 +389,118 @@ fn run_rejects_removed_cwd_flag() {
     assert!(stderr.contains("--cwd"));
 }
 
+#[test]
+fn secrets_rotate_reads_the_value_from_stdin_only() {
+    let dir = tempdir().unwrap();
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
+    let config_path = dir.path().join("config.yaml");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
+    let secrets_file = trusted_root.join("secrets").join("codex.env");
+    let mut yaml = fs::read_to_string(&config_path).unwrap();
+    yaml.push_str(&format!(
+        "providers:\n  codex:\n    auth_mode: api_key\n    auth:\n      api_key:\n        secrets_file: {}\n        env_key: OPENAI_API_KEY\n      host_state:\n        paths:\n          - {}\n    ownership:\n      root_comm:\n        - codex\n",
+        secrets_file.display(),
+        home.join(".codex").join("auth.json").display()
+    ));
+    fs::write(&config_path, yaml).unwrap();
+
+    bin()
+        .env("HOME", &home)
+        .arg("--config")
+        .arg(&config_path)
+        .args([
+            "secrets",
+            "rotate",
+            "--provider",
+            "codex",
+            "--value",
+            "sk-inline",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("possible values: -"));
+    assert!(!secrets_file.exists());
+
+    bin()
+        .env("HOME", &home)
+        .arg("--config")
+        .arg(&config_path)
+        .args(["secrets", "rotate", "--provider", "codex", "--value", "-"])
+        .arg("--no-restart")
+        .write_stdin("sk-from-stdin\n")
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&secrets_file).unwrap(),
+        "OPENAI_API_KEY='sk-from-stdin'\n"
+    );
+}
+
+#[test]
+fn config_diff_reports_changed_keys_against_defaults() {
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    fs::create_dir_all(&home).unwrap();
+    let config_path = dir.path().join("config.yaml");
+    bin()
+        .env("HOME", &home)
+        .arg("--config")
+        .arg(&config_path)
+        .arg("config")
+        .arg("init")
+        .assert()
+        .success();
+    let content = fs::read_to_string(&config_path).unwrap();
+    fs::write(
+        &config_path,
+        content.replace("idle_timeout_min: 10080", "idle_timeout_min: 60"),
+    )
+    .unwrap();
+
+    let output = bin()
+        .env("HOME", &home)
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("config")
+        .arg("diff")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let value = parse_json(&output);
+    let changed = value["result"]["changed"].as_object().unwrap();
+    assert_eq!(changed.len(), 1, "{changed:?}");
+    assert_eq!(changed["collector.idle_timeout_min"]["default"], 10080);
+    assert_eq!(changed["collector.idle_timeout_min"]["current"], 60);
+}
+
+#[test]
+fn run_requires_exactly_one_of_prompt_or_input_file() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("prompt.txt");
+    fs::write(&input, "hello\n").unwrap();
+
+    bin()
+        .arg("run")
+        .arg("--provider")
+        .arg("codex")
+        .arg("--input-file")
+        .arg(&input)
+        .arg("hello")
+        .assert()
+        .failure()
+        .stderr(contains("--input-file"));
+    bin()
+        .arg("run")
+        .arg("--provider")
+        .arg("codex")
+        .assert()
+        .failure()
+        .stderr(contains("--input-file"));
+}
+
 #[test]
 fn config_apply_writes_env_and_dirs() {
     let dir = tempdir().unwrap();
@@ -263,6 +528,119 @@ fn config_apply_writes_env_and_dirs() {
     assert!(work_root.exists());
 }
 
+#[test]
+fn config_apply_diff_previews_env_changes_without_writing() {
+    let dir = tempdir().unwrap();
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
+    let config_path = dir.path().join("config.yaml");
+    let env_file = dir.path().join("compose.env");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
+
+    bin()
+        .arg("--config")
+        .arg(&config_path)
+        .env("HOME", &home)
+        .env("LUX_ENV_FILE", &env_file)
+        .arg("config")
+        .arg("apply")
+        .assert()
+        .success();
+    let mut env_content = fs::read_to_string(&env_file).unwrap();
+    env_content.push_str("LUX_STALE=1\n");
+    fs::write(&env_file, &env_content).unwrap();
+
+    let new_log_root = trusted_root.join("other-logs");
+    write_config_with_paths(&config_path, &trusted_root, &new_log_root, &work_root);
+    let output = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .env("HOME", &home)
+        .env("LUX_ENV_FILE", &env_file)
+        .arg("config")
+        .arg("apply")
+        .arg("--diff")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let result = &parse_json(&output)["result"];
+    assert_eq!(result["dry_run"], true);
+    assert_eq!(result["removed"]["LUX_STALE"], "1");
+    assert!(result["added"].as_object().unwrap().is_empty());
+    let changed = result["changed"].as_array().unwrap();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0]["key"], "LUX_LOG_ROOT");
+    assert_eq!(changed[0]["new"], new_log_root.to_string_lossy().as_ref());
+    let create_dirs = result["create_dirs"].as_array().unwrap();
+    assert_eq!(create_dirs.len(), 1);
+    assert_eq!(create_dirs[0]["field"], "paths.log_root");
+
+    assert_eq!(fs::read_to_string(&env_file).unwrap(), env_content);
+    assert!(!new_log_root.exists());
+}
+
+#[test]
+fn config_apply_diff_redacts_secret_env_values() {
+    let dir = tempdir().unwrap();
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
+    let config_path = dir.path().join("config.yaml");
+    let env_file = dir.path().join("compose.env");
+    let write_config = |token: &str| {
+        write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
+        let mut content = fs::read_to_string(&config_path).unwrap();
+        content.push_str(&format!("harness:\n  api_token: {token}\n"));
+        fs::write(&config_path, content).unwrap();
+    };
+    write_config("old-secret-token");
+    bin()
+        .arg("--config")
+        .arg(&config_path)
+        .env("HOME", &home)
+        .env("LUX_ENV_FILE", &env_file)
+        .arg("config")
+        .arg("apply")
+        .assert()
+        .success();
+
+    write_config("new-secret-token");
+    let diff = |json: bool| {
+        let mut cmd = bin();
+        if json {
+            cmd.arg("--json");
+        }
+        let output = cmd
+            .arg("--config")
+            .arg(&config_path)
+            .env("HOME", &home)
+            .env("LUX_ENV_FILE", &env_file)
+            .arg("config")
+            .arg("apply")
+            .arg("--diff")
+            .assert()
+            .success()
+            .get_output()
+            .clone();
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let all = format!("{stdout}{}", String::from_utf8_lossy(&output.stderr));
+        assert!(!all.contains("old-secret-token"), "{all}");
+        assert!(!all.contains("new-secret-token"), "{all}");
+        stdout
+    };
+
+    let json_output = diff(true);
+    let result = &parse_json(json_output.as_bytes())["result"];
+    let changed = result["changed"].as_array().unwrap();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0]["key"], "HARNESS_API_TOKEN");
+    assert_eq!(changed[0]["redacted"], true);
+
+    let text_output = diff(false);
+    assert!(text_output.contains("~ HARNESS_API_TOKEN: (changed)"));
+}
+
 #[test]
 fn config_apply_invalid_config_is_actionable() {
     let dir = tempdir().unwrap();
@@ -339,46 +717,258 @@ fn doctor_strict_fails_when_checks_fail() {
 }
 
 #[test]
-fn status_fails_when_docker_missing() {
+fn doctor_check_runs_single_check_in_json() {
     let dir = tempdir().unwrap();
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
     let config_path = dir.path().join("config.yaml");
-    write_valid_config(&config_path);
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
 
-    bin()
+    let output = bin()
+        .env("HOME", &home)
         .env("PATH", "")
+        .arg("--json")
         .arg("--config")
         .arg(&config_path)
-        .arg("--compose-file")
-        .arg("../compose.yml")
-        .arg("--compose-file")
-        .arg("../tests/integration/compose.test.override.yml")
-        .arg("status")
-        .arg("--collector-only")
+        .arg("doctor")
+        .arg("--check")
+        .arg("docker_runtime")
         .assert()
-        .failure();
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    assert!(!value["ok"].as_bool().unwrap());
+    assert_eq!(value["result"]["id"], "docker_runtime");
+    assert_eq!(value["result"]["ok"], false);
 }
 
 #[test]
-fn status_json_includes_structured_docker_error_details() {
+fn doctor_check_rejects_unknown_id() {
     let dir = tempdir().unwrap();
     let config_path = dir.path().join("config.yaml");
     write_valid_config(&config_path);
 
-    let output = bin()
-        .env("PATH", "")
-        .arg("--json")
+    bin()
         .arg("--config")
         .arg(&config_path)
-        .arg("--compose-file")
-        .arg("../compose.yml")
-        .arg("--compose-file")
-        .arg("../tests/integration/compose.test.override.yml")
-        .arg("status")
-        .arg("--collector-only")
+        .arg("doctor")
+        .arg("--check")
+        .arg("not_a_check")
         .assert()
         .failure()
-        .get_output()
-        .stdout
+        .stderr(contains("unknown doctor check"));
+}
+
+#[test]
+fn doctor_list_prints_check_ids_without_config() {
+    let dir = tempdir().unwrap();
+    let output = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(dir.path().join("missing.yaml"))
+        .arg("doctor")
+        .arg("--list")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    let checks = value["result"]["checks"].as_array().expect("checks");
+    assert!(checks.iter().any(|row| row["id"] == "docker_runtime"));
+    assert!(checks
+        .iter()
+        .all(|row| !row["description"].as_str().unwrap_or_default().is_empty()));
+}
+
+#[test]
+fn json_layout_defaults_to_compact_off_a_terminal_and_honors_overrides() {
+    let dir = tempdir().unwrap();
+    let missing = dir.path().join("missing.yaml");
+    let run = |flags: &[&str], args: &[&str]| {
+        let output = bin()
+            .arg("--json")
+            .args(flags)
+            .arg("--config")
+            .arg(&missing)
+            .args(args)
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let compact = run(&[], &["doctor", "--list"]);
+    assert_eq!(compact.trim_end().lines().count(), 1);
+    assert_eq!(parse_json(compact.as_bytes())["ok"], true);
+    assert_eq!(
+        run(&["--json-compact"], &["doctor", "--list"])
+            .trim_end()
+            .lines()
+            .count(),
+        1
+    );
+
+    let pretty = run(&["--json-pretty"], &["doctor", "--list"]);
+    assert!(pretty.trim_end().lines().count() > 1);
+    assert_eq!(
+        parse_json(pretty.as_bytes()),
+        parse_json(compact.as_bytes())
+    );
+
+    // Error results use the same layout.
+    let error = run(&["--json-pretty"], &["doctor", "--check", "missing"]);
+    assert!(error.trim_end().lines().count() > 1);
+    assert_eq!(parse_json(error.as_bytes())["ok"], false);
+
+    bin()
+        .arg("--json-pretty")
+        .arg("--json-compact")
+        .arg("doctor")
+        .arg("--list")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn status_fails_when_docker_missing() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.yaml");
+    write_valid_config(&config_path);
+
+    bin()
+        .env("PATH", "")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--compose-file")
+        .arg("../compose.yml")
+        .arg("--compose-file")
+        .arg("../tests/integration/compose.test.override.yml")
+        .arg("status")
+        .arg("--collector-only")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn up_dry_run_prints_plan_without_docker() {
+    let dir = tempdir().unwrap();
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
+    let config_path = dir.path().join("config.yaml");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
+
+    let output = bin()
+        .env("HOME", &home)
+        .env("PATH", "")
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--compose-file")
+        .arg("../compose.yml")
+        .arg("up")
+        .arg("--collector-only")
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    assert!(value["ok"].as_bool().unwrap());
+    let plan = &value["result"];
+    assert_eq!(plan["dry_run"], true);
+    assert_eq!(plan["run_id_source"], "new");
+    assert_eq!(plan["services"], serde_json::json!(["collector"]));
+    assert!(plan["command"]
+        .as_str()
+        .unwrap()
+        .starts_with("docker compose --env-file"));
+    assert!(!trusted_root.join("state").join(".active_run.json").exists());
+}
+
+#[test]
+fn up_dry_run_uses_project_name_override() {
+    let dir = tempdir().unwrap();
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
+    let config_path = dir.path().join("config.yaml");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
+
+    let output = bin()
+        .env("HOME", &home)
+        .env("PATH", "")
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--compose-file")
+        .arg("../compose.yml")
+        .arg("--project-name")
+        .arg("lux-checkout_2")
+        .arg("up")
+        .arg("--collector-only")
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let plan = &parse_json(&output)["result"];
+    assert_eq!(plan["project_name"], "lux-checkout_2");
+    assert!(plan["command"]
+        .as_str()
+        .unwrap()
+        .contains(" -p lux-checkout_2 "));
+
+    let output = bin()
+        .env("HOME", &home)
+        .env("PATH", "")
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--compose-file")
+        .arg("../compose.yml")
+        .arg("--project-name")
+        .arg("Lux.Dev")
+        .arg("up")
+        .arg("--collector-only")
+        .arg("--dry-run")
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+    let value = parse_json(&output);
+    assert!(!value["ok"].as_bool().unwrap());
+    assert!(value["error"]
+        .as_str()
+        .unwrap()
+        .contains("invalid --project-name"));
+}
+
+#[test]
+fn status_json_includes_structured_docker_error_details() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.yaml");
+    write_valid_config(&config_path);
+
+    let output = bin()
+        .env("PATH", "")
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--compose-file")
+        .arg("../compose.yml")
+        .arg("--compose-file")
+        .arg("../tests/integration/compose.test.override.yml")
+        .arg("status")
+        .arg("--collector-only")
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
         .clone();
 
     let value = parse_json(&output);
@@ -485,24 +1075,44 @@ fn logs_tail_latest_resolves_most_recent_run_directory() {
 }
 
 #[test]
-fn jobs_list_with_run_id_uses_run_scoped_jobs_directory() {
+fn collector_stats_reads_published_counters() {
     let dir = tempdir().unwrap();
     let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
     let config_path = dir.path().join("config.yaml");
     write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
 
     let run_id = "lux__2026_02_12_12_00_00";
-    let jobs_dir = log_root.join(run_id).join("harness").join("jobs");
-    fs::create_dir_all(jobs_dir.join("job_1")).unwrap();
-    fs::create_dir_all(jobs_dir.join("job_2")).unwrap();
+    let stats_path = log_root
+        .join(run_id)
+        .join("collector")
+        .join("raw")
+        .join("ebpf.stats.json");
+    fs::create_dir_all(stats_path.parent().unwrap()).unwrap();
+    bin()
+        .env("HOME", &home)
+        .arg("--config")
+        .arg(&config_path)
+        .arg("collector")
+        .arg("stats")
+        .arg("--run-id")
+        .arg(run_id)
+        .assert()
+        .failure()
+        .stderr(contains("collector stats not found"));
+
+    fs::write(
+        &stats_path,
+        r#"{"schema_version":"ebpf.stats.v1","updated_at":"2026-02-12T12:00:05Z","drops":3,"events_emitted":{"net_connect":2,"net_send":5},"events_emitted_total":7,"events_written":7,"ring_buffer_bytes":16777216}"#,
+    )
+    .unwrap();
 
     let output = bin()
         .env("HOME", &home)
         .arg("--json")
         .arg("--config")
         .arg(&config_path)
-        .arg("jobs")
-        .arg("list")
+        .arg("collector")
+        .arg("stats")
         .arg("--run-id")
         .arg(run_id)
         .assert()
@@ -510,324 +1120,354 @@ fn jobs_list_with_run_id_uses_run_scoped_jobs_directory() {
         .get_output()
         .stdout
         .clone();
-    let value = parse_json(&output);
-    assert_eq!(value["result"]["run_id"], run_id);
-    let jobs = value["result"]["jobs"].as_array().expect("jobs array");
-    let rendered: Vec<String> = jobs
-        .iter()
-        .filter_map(|v| v.as_str().map(ToString::to_string))
-        .collect();
-    assert!(rendered.contains(&"job_1".to_string()));
-    assert!(rendered.contains(&"job_2".to_string()));
+    let json = parse_json(&output);
+    assert_eq!(json["result"]["run_id"], run_id);
+    assert_eq!(json["result"]["drops"], 3);
+    assert_eq!(json["result"]["events_emitted"]["net_send"], 5);
+
+    bin()
+        .env("HOME", &home)
+        .arg("--config")
+        .arg(&config_path)
+        .arg("collector")
+        .arg("stats")
+        .arg("--run-id")
+        .arg(run_id)
+        .assert()
+        .success()
+        .stdout(contains("drops: 3"))
+        .stdout(contains("  net_connect: 2"));
 }
 
 #[test]
-fn paths_reports_resolved_values() {
+fn logs_tail_since_until_filters_timeline_records() {
     let dir = tempdir().unwrap();
     let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
-    let canonical_log_root = fs::canonicalize(&log_root).unwrap();
-    let canonical_work_root = fs::canonicalize(&work_root).unwrap();
     let config_path = dir.path().join("config.yaml");
-    let env_file = dir.path().join("compose.env");
-    let install_dir = home.join(".lux");
-    let bin_dir = home.join(".local").join("bin");
-    fs::write(&env_file, "LUX_VERSION=v0.1.0\n").unwrap();
     write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
 
+    let run_id = "lux__2026_02_12_12_00_00";
+    let timeline = log_root
+        .join(run_id)
+        .join("collector")
+        .join("filtered")
+        .join("filtered_timeline.jsonl");
+    fs::create_dir_all(timeline.parent().unwrap()).unwrap();
+    fs::write(
+        &timeline,
+        concat!(
+            "{\"ts\":\"2026-02-12T12:00:00Z\",\"n\":1}\n",
+            "{\"ts\":\"2026-02-12T12:05:00Z\",\"n\":2}\n",
+            "{\"n\":3}\n",
+            "{\"ts\":\"2026-02-12T12:10:00Z\",\"n\":4}\n",
+        ),
+    )
+    .unwrap();
+
     let output = bin()
-        .arg("--json")
+        .env("HOME", &home)
         .arg("--config")
         .arg(&config_path)
-        .env("HOME", home)
-        .env("LUX_ENV_FILE", &env_file)
-        .arg("paths")
+        .arg("logs")
+        .arg("tail")
+        .arg("--run-id")
+        .arg(run_id)
+        .arg("--since")
+        .arg("2026-02-12T12:01:00Z")
+        .arg("--until")
+        .arg("2026-02-12T12:10:00Z")
         .assert()
         .success()
         .get_output()
         .stdout
         .clone();
+    let text = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("\"n\":2"));
+    assert!(lines[1].contains("\"n\":4"));
 
-    let value = parse_json(&output);
-    assert!(value["ok"].as_bool().unwrap());
-    assert_eq!(
-        value["result"]["log_root"].as_str().unwrap(),
-        canonical_log_root.to_string_lossy()
-    );
-    assert_eq!(
-        value["result"]["workspace_root"].as_str().unwrap(),
-        canonical_work_root.to_string_lossy()
-    );
-    assert_eq!(
-        value["result"]["install_dir"].as_str().unwrap(),
-        install_dir.to_string_lossy()
-    );
-    assert_eq!(
-        value["result"]["bin_dir"].as_str().unwrap(),
-        bin_dir.to_string_lossy()
-    );
-}
+    let output = bin()
+        .env("HOME", &home)
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("logs")
+        .arg("tail")
+        .arg("--run-id")
+        .arg(run_id)
+        .arg("--lines")
+        .arg("1")
+        .arg("--format")
+        .arg("pretty")
+        .assert()
+        .success()
+        .stderr(contains("--format overrides --json"))
+        .get_output()
+        .stdout
+        .clone();
+    let text = String::from_utf8(output).unwrap();
+    assert!(text.lines().count() > 1, "{text}");
+    assert_eq!(parse_json(text.as_bytes())["n"], 4);
 
-#[test]
-fn info_text_includes_core_concepts_and_quickstart_tracks() {
     let output = bin()
-        .arg("info")
+        .env("HOME", &home)
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("logs")
+        .arg("stats")
+        .arg("--run-id")
+        .arg(run_id)
+        .arg("--since")
+        .arg("2026-02-12T12:05:00Z")
         .assert()
         .success()
         .get_output()
         .stdout
         .clone();
-    let rendered = String::from_utf8_lossy(&output);
-    assert!(rendered.contains("What Lux Is:"));
-    assert!(rendered.contains("Quickstart:"));
-    assert!(rendered.contains("Core Concepts:"));
-    assert!(rendered.contains("Logs:"));
-    assert!(rendered.contains("runtime control plane"));
-    assert!(rendered.contains("collector-only: Starts only the collector container"));
-    assert!(rendered.contains("provider plane"));
-    assert!(rendered.contains("lux up --collector-only --wait"));
-    assert!(rendered.contains("lux ui up --wait"));
-    assert!(rendered.contains("lux up --provider <provider> --wait"));
-    assert!(rendered.contains("lux tui --provider <provider>"));
-    assert!(rendered.contains("http://localhost:8090"));
+    let value = parse_json(&output);
+    assert_eq!(value["result"]["timeline_events"], 2);
 }
 
 #[test]
-fn info_with_json_flag_outputs_info_text() {
+fn logs_grep_matches_timeline_and_raw_logs() {
+    let dir = tempdir().unwrap();
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
+    let config_path = dir.path().join("config.yaml");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
+
+    let run_id = "lux__2026_02_12_12_00_00";
+    let collector = log_root.join(run_id).join("collector");
+    fs::create_dir_all(collector.join("filtered")).unwrap();
+    fs::create_dir_all(collector.join("raw")).unwrap();
+    fs::write(
+        collector.join("filtered").join("filtered_timeline.jsonl"),
+        concat!(
+            "{\"n\":1,\"dst\":\"api.Example.com\"}\n",
+            "{\"n\":2,\"dst\":\"10.0.0.1\"}\n",
+            "{\"n\":3,\"comm\":\"curl\"}\n",
+        ),
+    )
+    .unwrap();
+    fs::write(
+        collector.join("raw").join("audit.log"),
+        "type=EXECVE msg=audit(1:1): a0=\"curl\" a1=\"example.com\"\n",
+    )
+    .unwrap();
+
     let output = bin()
-        .arg("--json")
-        .arg("info")
+        .env("HOME", &home)
+        .arg("--config")
+        .arg(&config_path)
+        .arg("logs")
+        .arg("grep")
+        .arg("example.com")
+        .arg("--run-id")
+        .arg(run_id)
+        .arg("-i")
         .assert()
         .success()
         .get_output()
         .stdout
         .clone();
-    let rendered = String::from_utf8_lossy(&output);
-    assert!(rendered.contains("What Lux Is:"));
-    assert!(rendered.contains("Quickstart:"));
-    assert!(rendered.contains("Core Concepts:"));
-    assert!(rendered.contains("Logs:"));
-}
+    let text = String::from_utf8(output).unwrap();
+    assert_eq!(text.lines().count(), 1, "{text}");
+    assert!(text.contains("\"n\":1"));
 
-#[test]
-fn help_lists_info_without_rewriting_existing_descriptions() {
     let output = bin()
-        .arg("--help")
+        .env("HOME", &home)
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("logs")
+        .arg("grep")
+        .arg(r"^\{.n.:[23],")
+        .arg("--regex")
+        .arg("--latest")
         .assert()
         .success()
         .get_output()
         .stdout
         .clone();
-    let rendered = String::from_utf8_lossy(&output);
-    assert!(rendered.contains(" info "));
-    assert!(rendered.contains("Start collector-only or provider plane services"));
-    assert!(rendered.contains("Manage runtime control-plane lifecycle"));
-}
-
-#[test]
-fn setup_defaults_creates_secrets_from_env_when_missing() {
-    let dir = tempdir().unwrap();
-    let home = dir.path().join("home");
-    let config_dir = dir.path().join("config");
-    let trusted_root = dir.path().join("trusted");
-    fs::create_dir_all(&home).unwrap();
-    fs::create_dir_all(&config_dir).unwrap();
-    let _config_path = write_default_template_config(&config_dir, &trusted_root);
-
-    let secrets_path = trusted_root.join("secrets").join("codex.env");
-    assert!(!secrets_path.exists());
+    let value = parse_json(&output);
+    let matches = value["result"]["matches"].as_array().unwrap();
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0]["record"]["n"], 2);
+    assert_eq!(matches[1]["line"], 3);
 
     let output = bin()
         .env("HOME", &home)
-        .env("LUX_CONFIG_DIR", &config_dir)
-        .env("OPENAI_API_KEY", "test-key-123")
         .arg("--json")
-        .arg("setup")
-        .arg("--defaults")
-        .arg("--yes")
-        .arg("--no-apply")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("logs")
+        .arg("grep")
+        .arg("curl")
+        .arg("--run-id")
+        .arg(run_id)
+        .arg("--raw")
         .assert()
         .success()
         .get_output()
         .stdout
         .clone();
-
     let value = parse_json(&output);
-    assert!(value["ok"].as_bool().unwrap());
-    assert!(secrets_path.exists());
-
-    let content = fs::read_to_string(&secrets_path).unwrap();
-    assert!(content.contains("OPENAI_API_KEY="));
-    assert!(content.contains("test-key-123"));
-
-    let config_path = config_dir.join("config.yaml");
-    assert!(config_path.exists());
+    let matches = value["result"]["matches"].as_array().unwrap();
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0]["file"], "timeline");
+    assert_eq!(matches[1]["file"], "audit");
+    assert!(matches[1]["record"]
+        .as_str()
+        .unwrap()
+        .starts_with("type=EXECVE"));
 
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mode = fs::metadata(&secrets_path).unwrap().permissions().mode() & 0o777;
-        assert_eq!(mode, 0o600);
-    }
+    bin()
+        .env("HOME", &home)
+        .arg("--config")
+        .arg(&config_path)
+        .arg("logs")
+        .arg("grep")
+        .arg("(")
+        .arg("--regex")
+        .arg("--latest")
+        .assert()
+        .failure()
+        .stderr(contains("invalid --regex pattern"));
 }
 
 #[test]
-fn setup_defaults_errors_when_api_key_missing_and_env_missing() {
+fn jobs_list_with_run_id_uses_run_scoped_jobs_directory() {
     let dir = tempdir().unwrap();
-    let home = dir.path().join("home");
-    let config_dir = dir.path().join("config");
-    let trusted_root = dir.path().join("trusted");
-    fs::create_dir_all(&home).unwrap();
-    fs::create_dir_all(&config_dir).unwrap();
-    let _config_path = write_default_template_config(&config_dir, &trusted_root);
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
+    let config_path = dir.path().join("config.yaml");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
+
+    let run_id = "lux__2026_02_12_12_00_00";
+    let jobs_dir = log_root.join(run_id).join("harness").join("jobs");
+    fs::create_dir_all(jobs_dir.join("job_1")).unwrap();
+    fs::create_dir_all(jobs_dir.join("job_2")).unwrap();
 
     let output = bin()
         .env("HOME", &home)
-        .env("LUX_CONFIG_DIR", &config_dir)
         .arg("--json")
-        .arg("setup")
-        .arg("--defaults")
-        .arg("--no-apply")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("jobs")
+        .arg("list")
+        .arg("--run-id")
+        .arg(run_id)
         .assert()
-        .failure()
+        .success()
         .get_output()
         .stdout
         .clone();
-
     let value = parse_json(&output);
-    assert!(!value["ok"].as_bool().unwrap());
-    let error = value["error"].as_str().unwrap_or_default();
-    assert!(error.contains("OPENAI_API_KEY"));
-    assert!(error.contains("secrets"));
+    assert_eq!(value["result"]["run_id"], run_id);
+    let jobs = value["result"]["jobs"].as_array().expect("jobs array");
+    let rendered: Vec<String> = jobs
+        .iter()
+        .filter_map(|v| v.as_str().map(ToString::to_string))
+        .collect();
+    assert!(rendered.contains(&"job_1".to_string()));
+    assert!(rendered.contains(&"job_2".to_string()));
 }
 
 #[test]
-fn setup_dry_run_writes_nothing() {
+fn jobs_logs_prints_captured_output_for_a_job() {
     let dir = tempdir().unwrap();
-    let home = dir.path().join("home");
-    let config_dir = dir.path().join("config");
-    fs::create_dir_all(&home).unwrap();
-    fs::create_dir_all(&config_dir).unwrap();
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
+    let config_path = dir.path().join("config.yaml");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
 
-    let config_path = config_dir.join("config.yaml");
-    let secrets_path = home
-        .join(".config")
-        .join("lux")
-        .join("secrets")
-        .join("codex.env");
+    let run_id = "lux__2026_02_12_12_00_00";
+    let job_dir = log_root
+        .join(run_id)
+        .join("harness")
+        .join("jobs")
+        .join("job_1");
+    fs::create_dir_all(&job_dir).unwrap();
+    fs::write(job_dir.join("stdout.log"), "hello from job\n").unwrap();
+    fs::write(job_dir.join("stderr.log"), "warning: noisy\n").unwrap();
+    fs::write(job_dir.join("status.json"), r#"{"status": "complete"}"#).unwrap();
 
     let output = bin()
         .env("HOME", &home)
-        .env("LUX_CONFIG_DIR", &config_dir)
-        .env("OPENAI_API_KEY", "dry-run-key")
         .arg("--json")
-        .arg("setup")
-        .arg("--defaults")
-        .arg("--dry-run")
+        .arg("--config")
+        .arg(&config_path)
+        .args(["jobs", "logs", "job_1", "--run-id", run_id, "--follow"])
         .assert()
         .success()
         .get_output()
         .stdout
         .clone();
-
     let value = parse_json(&output);
-    assert!(value["ok"].as_bool().unwrap());
-    assert!(value["result"]["dry_run"].as_bool().unwrap());
-
-    assert!(!config_path.exists());
-    assert!(!secrets_path.exists());
-}
-
-#[test]
-fn setup_defaults_does_not_autostart_services() {
-    let dir = tempdir().unwrap();
-    let home = dir.path().join("home");
-    let config_dir = dir.path().join("config");
-    let trusted_root = dir.path().join("trusted");
-    fs::create_dir_all(&home).unwrap();
-    fs::create_dir_all(&config_dir).unwrap();
-    let _config_path = write_default_template_config(&config_dir, &trusted_root);
+    assert_eq!(value["result"]["id"], "job_1");
+    assert_eq!(value["result"]["status"], "complete");
+    assert_eq!(value["result"]["stdout"], "hello from job\n");
+    assert_eq!(value["result"]["stderr"], "warning: noisy\n");
+    assert!(value["result"]["stdout_path"]
+        .as_str()
+        .unwrap()
+        .ends_with("harness/jobs/job_1/stdout.log"));
 
-    // `--defaults` should not attempt collector/UI startup; this must succeed even without docker in PATH.
-    let output = bin()
+    let text = bin()
         .env("HOME", &home)
-        .env("PATH", "")
-        .env("LUX_CONFIG_DIR", &config_dir)
-        .env("OPENAI_API_KEY", "defaults-no-autostart-key")
-        .arg("--json")
-        .arg("setup")
-        .arg("--defaults")
-        .arg("--yes")
+        .arg("--config")
+        .arg(&config_path)
+        .args(["jobs", "logs", "job_1", "--run-id", run_id])
         .assert()
         .success()
         .get_output()
-        .stdout
         .clone();
+    assert_eq!(String::from_utf8_lossy(&text.stdout), "hello from job\n");
+    assert_eq!(String::from_utf8_lossy(&text.stderr), "warning: noisy\n");
 
-    let value = parse_json(&output);
-    assert!(value["ok"].as_bool().unwrap());
-    assert!(value["result"]["apply"].as_bool().unwrap());
-}
-
-#[test]
-fn uninstall_requires_yes_without_dry_run() {
-    let dir = tempdir().unwrap();
-    let config_path = dir.path().join("config.yaml");
-    write_valid_config(&config_path);
-
-    let output = bin()
+    let missing = bin()
+        .env("HOME", &home)
         .arg("--json")
         .arg("--config")
         .arg(&config_path)
-        .arg("uninstall")
-        .arg("--force")
+        .args(["jobs", "logs", "job_404", "--run-id", run_id])
         .assert()
         .failure()
         .get_output()
         .stdout
         .clone();
-
-    let value = parse_json(&output);
-    let error = value["error"].as_str().unwrap_or_default();
-    assert!(error.contains("--yes"));
+    let value = parse_json(&missing);
+    assert!(value["error"]
+        .as_str()
+        .unwrap_or_default()
+        .contains("job not found: job_404"));
 }
 
 #[cfg(unix)]
 #[test]
-fn uninstall_dry_run_preserves_files() {
+fn version_reports_build_bundle_and_installed_version() {
     let dir = tempdir().unwrap();
-    let home = dir.path().join("home");
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
     let config_path = dir.path().join("config.yaml");
-    let env_file = dir.path().join("compose.env");
-    let trusted_root = dir.path().join("trusted");
-    let log_root = trusted_root.join("logs");
-    let work_root = home.join("work");
-    let install_dir = home.join(".lux");
-    let versions_dir = install_dir.join("versions").join("0.1.0");
-    let current_link = install_dir.join("current");
-    let bin_dir = home.join(".local").join("bin");
-    let bin_link = bin_dir.join("lux");
-    fs::create_dir_all(&home).unwrap();
-    fs::create_dir_all(&versions_dir).unwrap();
-    fs::create_dir_all(&bin_dir).unwrap();
-    fs::create_dir_all(&log_root).unwrap();
-    fs::create_dir_all(&work_root).unwrap();
-    fs::write(versions_dir.join("lux"), "binary").unwrap();
-    symlink(&versions_dir, &current_link).unwrap();
-    symlink(current_link.join("lux"), &bin_link).unwrap();
-    fs::write(&env_file, "LUX_VERSION=v0.1.0\n").unwrap();
     write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
+    let versions_dir = home.join(".lux").join("versions");
+    fs::create_dir_all(versions_dir.join("0.9.0")).unwrap();
+    std::os::unix::fs::symlink(
+        versions_dir.join("0.9.0"),
+        home.join(".lux").join("current"),
+    )
+    .unwrap();
+    let bundle_dir = dir.path().join("bundle");
+    fs::create_dir_all(&bundle_dir).unwrap();
+    fs::write(bundle_dir.join("compose.yml"), "services: {}\n").unwrap();
 
     let output = bin()
         .arg("--json")
         .arg("--config")
         .arg(&config_path)
+        .arg("--bundle-dir")
+        .arg(&bundle_dir)
         .env("HOME", &home)
-        .env("LUX_ENV_FILE", &env_file)
-        .arg("uninstall")
-        .arg("--dry-run")
-        .arg("--remove-config")
-        .arg("--all-versions")
-        .arg("--force")
+        .arg("version")
         .assert()
         .success()
         .get_output()
@@ -835,39 +1475,275 @@ fn uninstall_dry_run_preserves_files() {
         .clone();
 
     let value = parse_json(&output);
-    assert!(value["result"]["dry_run"].as_bool().unwrap());
-    assert!(bin_link.exists());
-    assert!(current_link.exists());
-    assert!(install_dir.join("versions").exists());
-    assert!(config_path.exists());
-    assert!(env_file.exists());
-    assert!(log_root.exists());
-    assert!(work_root.exists());
+    let result = &value["result"];
+    assert_eq!(result["version"], env!("CARGO_PKG_VERSION"));
+    assert!(!result["git_commit"].as_str().unwrap().is_empty());
+    assert!(!result["target"].as_str().unwrap().is_empty());
+    assert_eq!(result["compose_found"], true);
+    assert_eq!(result["installed_version"], "v0.9.0");
+}
+
+#[test]
+fn stdin_config_serves_read_only_commands_and_rejects_writes() {
+    let dir = tempdir().unwrap();
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
+    let staged = dir.path().join("staged.yaml");
+    write_config_with_paths(&staged, &trusted_root, &log_root, &work_root);
+    let yaml = fs::read_to_string(&staged).unwrap();
+
+    let output = bin()
+        .args(["--json", "--config", "-", "config", "validate"])
+        .env("HOME", &home)
+        .write_stdin(yaml.clone())
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let value = parse_json(&output);
+    assert_eq!(value["result"]["valid"], true);
+
+    let output = bin()
+        .args(["--json", "--config", "-", "paths"])
+        .env("HOME", &home)
+        .write_stdin(yaml.clone())
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let value = parse_json(&output);
+    assert_eq!(value["result"]["config_exists"], true);
+    assert_eq!(
+        value["result"]["log_root"].as_str().unwrap(),
+        fs::canonicalize(&log_root).unwrap().to_string_lossy()
+    );
+
+    let output = bin()
+        .args(["--json", "--config", "-", "config", "apply"])
+        .env("HOME", &home)
+        .write_stdin(yaml)
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+    let value = parse_json(&output);
+    assert!(value["error"].as_str().unwrap().contains("read-only"));
+    assert!(!trusted_root.join("state").join("compose.env").exists());
 }
 
 #[cfg(unix)]
 #[test]
-fn uninstall_exec_removes_requested_targets() {
+fn stdin_config_status_passes_env_without_writing_env_file() {
+    use std::os::unix::fs::PermissionsExt;
+
     let dir = tempdir().unwrap();
-    let home = dir.path().join("home");
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
+    let staged = dir.path().join("staged.yaml");
+    write_config_with_paths(&staged, &trusted_root, &log_root, &work_root);
+    let yaml = fs::read_to_string(&staged).unwrap();
+    let bundle_dir = dir.path().join("bundle");
+    fs::create_dir_all(&bundle_dir).unwrap();
+    fs::write(bundle_dir.join("compose.yml"), "services: {}\n").unwrap();
+    let fake_bin = dir.path().join("fake-bin");
+    fs::create_dir_all(&fake_bin).unwrap();
+    let docker_log = dir.path().join("docker.log");
+    let docker = fake_bin.join("docker");
+    fs::write(
+        &docker,
+        format!(
+            "#!/bin/sh\necho \"$* LUX_LOG_ROOT=$LUX_LOG_ROOT\" >> {}\n",
+            docker_log.display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&docker, fs::Permissions::from_mode(0o755)).unwrap();
+
+    bin()
+        .args(["--json", "--config", "-", "--bundle-dir"])
+        .arg(&bundle_dir)
+        .args(["status", "--collector-only"])
+        .env("HOME", &home)
+        .env_remove("LUX_LOG_ROOT")
+        .env("PATH", format!("{}:/usr/bin:/bin", fake_bin.display()))
+        .write_stdin(yaml)
+        .assert()
+        .success();
+
+    assert!(!trusted_root.join("state").join("compose.env").exists());
+    let calls = fs::read_to_string(&docker_log).unwrap();
+    let compose_call = calls
+        .lines()
+        .find(|line| line.starts_with("compose"))
+        .expect("status should call docker compose");
+    assert!(!compose_call.contains("--env-file"), "{compose_call}");
+    let log_root = fs::canonicalize(&log_root).unwrap();
+    assert!(
+        compose_call.ends_with(&format!("LUX_LOG_ROOT={}", log_root.display())),
+        "{compose_call}"
+    );
+}
+
+#[test]
+fn runs_prune_deletes_oldest_runs_and_guards_active_run() {
+    let dir = tempdir().unwrap();
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
+    let config_path = dir.path().join("config.yaml");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
+    let runs = [
+        "lux__2026_01_01_00_00_00",
+        "lux__2026_01_02_00_00_00",
+        "lux__2026_01_03_00_00_00",
+    ];
+    for run_id in runs {
+        fs::create_dir_all(log_root.join(run_id).join("collector")).unwrap();
+        fs::write(
+            log_root.join(run_id).join("collector").join("a.log"),
+            "12345",
+        )
+        .unwrap();
+    }
+    let state_dir = trusted_root.join("state");
+    fs::create_dir_all(&state_dir).unwrap();
+    let set_active = |run_id: &str| {
+        fs::write(
+            state_dir.join(".active_run.json"),
+            format!(r#"{{"run_id":"{run_id}","started_at":"2026-01-01T00:00:00Z"}}"#),
+        )
+        .unwrap();
+    };
+    let prune = |extra: &[&str]| {
+        let mut cmd = bin();
+        cmd.arg("--json")
+            .arg("--config")
+            .arg(&config_path)
+            .env("HOME", &home)
+            .args(["runs", "prune", "--max-runs", "2"])
+            .args(extra);
+        cmd
+    };
+
+    set_active(runs[0]);
+    let output = prune(&[]).assert().failure().get_output().stdout.clone();
+    let value = parse_json(&output);
+    assert_eq!(
+        value["error_details"]["error_code"],
+        "runs_prune_active_run"
+    );
+    assert!(log_root.join(runs[0]).exists());
+
+    set_active(runs[2]);
+    let output = prune(&["--dry-run"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let value = parse_json(&output);
+    assert_eq!(value["result"]["pruned"][0]["run_id"], runs[0]);
+    assert_eq!(value["result"]["freed_bytes"], 5);
+    assert!(log_root.join(runs[0]).exists());
+
+    let output = prune(&[]).assert().success().get_output().stdout.clone();
+    let value = parse_json(&output);
+    assert_eq!(value["result"]["kept"][0], runs[1]);
+    assert_eq!(value["result"]["kept"][1], runs[2]);
+    assert!(!log_root.join(runs[0]).exists());
+    assert!(log_root.join(runs[1]).exists());
+}
+
+#[test]
+fn runs_show_reports_historical_run_from_filesystem() {
+    let dir = tempdir().unwrap();
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
+    let config_path = dir.path().join("config.yaml");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
+    let old_run = "lux__2026_01_01_00_00_00";
+    let active_run = "lux__2026_01_02_00_00_00";
+    let old_root = log_root.join(old_run);
+    fs::create_dir_all(old_root.join("collector").join("raw")).unwrap();
+    fs::write(
+        old_root.join("collector").join("raw").join("audit.log"),
+        "audit",
+    )
+    .unwrap();
+    fs::create_dir_all(old_root.join("harness").join("sessions").join("s1")).unwrap();
+    for (job_id, status) in [("j1", "running"), ("j2", "complete")] {
+        let job_dir = old_root.join("harness").join("jobs").join(job_id);
+        fs::create_dir_all(&job_dir).unwrap();
+        fs::write(
+            job_dir.join("status.json"),
+            format!(r#"{{"status":"{status}"}}"#),
+        )
+        .unwrap();
+    }
+    fs::create_dir_all(log_root.join(active_run)).unwrap();
+    let state_dir = trusted_root.join("state");
+    fs::create_dir_all(&state_dir).unwrap();
+    fs::write(
+        state_dir.join(".active_run.json"),
+        format!(
+            r#"{{"run_id":"{active_run}","started_at":"2026-01-02T00:00:05Z","workspace_root":"/w"}}"#
+        ),
+    )
+    .unwrap();
+    let show = |extra: &[&str]| {
+        let output = bin()
+            .arg("--json")
+            .arg("--config")
+            .arg(&config_path)
+            .env("HOME", &home)
+            .args(["runs", "show"])
+            .args(extra)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        parse_json(&output)["result"].clone()
+    };
+
+    let result = show(&["--run-id", old_run]);
+    assert_eq!(result["run_id"], old_run);
+    assert_eq!(result["active"], false);
+    assert_eq!(result["started_at"], "2026-01-01T00:00:00+00:00");
+    assert!(result["workspace_root"].is_null());
+    assert_eq!(result["sessions"]["count"], 1);
+    assert_eq!(result["jobs"]["count"], 2);
+    assert_eq!(result["jobs"]["running"], 1);
+    assert_eq!(result["jobs"]["finished"], 1);
+    assert_eq!(result["pipeline"][0]["name"], "raw.audit");
+    assert_eq!(result["pipeline"][0]["present"], true);
+    assert_eq!(result["pipeline"][0]["size_bytes"], 5);
+    assert_eq!(result["pipeline"][1]["present"], false);
+
+    let result = show(&[]);
+    assert_eq!(result["run_id"], active_run);
+    assert_eq!(result["active"], true);
+    assert_eq!(result["started_at"], "2026-01-02T00:00:05Z");
+    assert_eq!(result["workspace_root"], "/w");
+    assert_eq!(result["jobs"]["count"], 0);
+
+    bin()
+        .arg("--config")
+        .arg(&config_path)
+        .env("HOME", &home)
+        .args(["runs", "show", "--run-id", "lux__missing"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn paths_reports_resolved_values() {
+    let dir = tempdir().unwrap();
+    let (home, trusted_root, log_root, work_root) = make_policy_paths(dir.path());
+    let canonical_log_root = fs::canonicalize(&log_root).unwrap();
+    let canonical_work_root = fs::canonicalize(&work_root).unwrap();
     let config_path = dir.path().join("config.yaml");
     let env_file = dir.path().join("compose.env");
-    let trusted_root = dir.path().join("trusted");
-    let log_root = trusted_root.join("logs");
-    let work_root = home.join("work");
     let install_dir = home.join(".lux");
-    let versions_dir = install_dir.join("versions").join("0.1.0");
-    let current_link = install_dir.join("current");
     let bin_dir = home.join(".local").join("bin");
-    let bin_link = bin_dir.join("lux");
-    fs::create_dir_all(&home).unwrap();
-    fs::create_dir_all(&versions_dir).unwrap();
-    fs::create_dir_all(&bin_dir).unwrap();
-    fs::create_dir_all(&log_root).unwrap();
-    fs::create_dir_all(&work_root).unwrap();
-    fs::write(versions_dir.join("lux"), "binary").unwrap();
-    symlink(&versions_dir, &current_link).unwrap();
-    symlink(current_link.join("lux"), &bin_link).unwrap();
     fs::write(&env_file, "LUX_VERSION=v0.1.0\n").unwrap();
     write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
 
@@ -875,188 +1751,1243 @@ fn uninstall_exec_removes_requested_targets() {
         .arg("--json")
         .arg("--config")
         .arg(&config_path)
-        .env("HOME", &home)
+        .env("HOME", home)
         .env("LUX_ENV_FILE", &env_file)
-        .arg("uninstall")
+        .arg("paths")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    assert!(value["ok"].as_bool().unwrap());
+    assert_eq!(
+        value["result"]["log_root"].as_str().unwrap(),
+        canonical_log_root.to_string_lossy()
+    );
+    assert_eq!(
+        value["result"]["workspace_root"].as_str().unwrap(),
+        canonical_work_root.to_string_lossy()
+    );
+    assert_eq!(
+        value["result"]["install_dir"].as_str().unwrap(),
+        install_dir.to_string_lossy()
+    );
+    assert_eq!(
+        value["result"]["bin_dir"].as_str().unwrap(),
+        bin_dir.to_string_lossy()
+    );
+    assert!(value["result"]["runtime_socket_gid"].is_u64());
+    assert_eq!(value["result"]["exists"]["log_root"], true);
+    assert_eq!(value["result"]["exists"]["workspace_root"], true);
+    assert_eq!(value["result"]["exists"]["install_dir"], false);
+}
+
+#[test]
+fn info_text_includes_core_concepts_and_quickstart_tracks() {
+    let output = bin()
+        .arg("info")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let rendered = String::from_utf8_lossy(&output);
+    assert!(rendered.contains("What Lux Is:"));
+    assert!(rendered.contains("Quickstart:"));
+    assert!(rendered.contains("Core Concepts:"));
+    assert!(rendered.contains("Logs:"));
+    assert!(rendered.contains("runtime control plane"));
+    assert!(rendered.contains("collector-only: Starts only the collector container"));
+    assert!(rendered.contains("provider plane"));
+    assert!(rendered.contains("lux up --collector-only --wait"));
+    assert!(rendered.contains("lux ui up --wait"));
+    assert!(rendered.contains("lux up --provider <provider> --wait"));
+    assert!(rendered.contains("lux tui --provider <provider>"));
+    assert!(rendered.contains("http://localhost:8090"));
+}
+
+#[test]
+fn info_with_json_flag_outputs_info_text() {
+    let output = bin()
+        .arg("--json")
+        .arg("info")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let rendered = String::from_utf8_lossy(&output);
+    assert!(rendered.contains("What Lux Is:"));
+    assert!(rendered.contains("Quickstart:"));
+    assert!(rendered.contains("Core Concepts:"));
+    assert!(rendered.contains("Logs:"));
+}
+
+#[test]
+fn help_lists_info_without_rewriting_existing_descriptions() {
+    let output = bin()
+        .arg("--help")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let rendered = String::from_utf8_lossy(&output);
+    assert!(rendered.contains(" info "));
+    assert!(rendered.contains("Start collector-only or provider plane services"));
+    assert!(rendered.contains("Manage runtime control-plane lifecycle"));
+}
+
+#[test]
+fn setup_defaults_creates_secrets_from_env_when_missing() {
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    let config_dir = dir.path().join("config");
+    let trusted_root = dir.path().join("trusted");
+    fs::create_dir_all(&home).unwrap();
+    fs::create_dir_all(&config_dir).unwrap();
+    let _config_path = write_default_template_config(&config_dir, &trusted_root);
+
+    let secrets_path = trusted_root.join("secrets").join("codex.env");
+    assert!(!secrets_path.exists());
+
+    let output = bin()
+        .env("HOME", &home)
+        .env("LUX_CONFIG_DIR", &config_dir)
+        .env("OPENAI_API_KEY", "test-key-123")
+        .arg("--json")
+        .arg("setup")
+        .arg("--defaults")
         .arg("--yes")
-        .arg("--remove-config")
-        .arg("--all-versions")
-        .arg("--force")
+        .arg("--no-apply")
         .assert()
         .success()
         .get_output()
         .stdout
         .clone();
 
-    let value = parse_json(&output);
-    assert!(!value["result"]["dry_run"].as_bool().unwrap());
-    assert!(!bin_link.exists());
-    assert!(!current_link.exists());
-    assert!(!install_dir.join("versions").exists());
-    assert!(!config_path.exists());
-    assert!(!env_file.exists());
-    assert!(log_root.exists());
-    assert!(work_root.exists());
+    let value = parse_json(&output);
+    assert!(value["ok"].as_bool().unwrap());
+    assert!(secrets_path.exists());
+
+    let content = fs::read_to_string(&secrets_path).unwrap();
+    assert!(content.contains("OPENAI_API_KEY="));
+    assert!(content.contains("test-key-123"));
+
+    let config_path = config_dir.join("config.yaml");
+    assert!(config_path.exists());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&secrets_path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+    }
+}
+
+#[test]
+fn setup_defaults_errors_when_api_key_missing_and_env_missing() {
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    let config_dir = dir.path().join("config");
+    let trusted_root = dir.path().join("trusted");
+    fs::create_dir_all(&home).unwrap();
+    fs::create_dir_all(&config_dir).unwrap();
+    let _config_path = write_default_template_config(&config_dir, &trusted_root);
+
+    let output = bin()
+        .env("HOME", &home)
+        .env("LUX_CONFIG_DIR", &config_dir)
+        .arg("--json")
+        .arg("setup")
+        .arg("--defaults")
+        .arg("--no-apply")
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    assert!(!value["ok"].as_bool().unwrap());
+    let error = value["error"].as_str().unwrap_or_default();
+    assert!(error.contains("OPENAI_API_KEY"));
+    assert!(error.contains("secrets"));
+}
+
+#[test]
+fn setup_defaults_imports_secrets_from_env_file() {
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    let config_dir = dir.path().join("config");
+    let trusted_root = dir.path().join("trusted");
+    fs::create_dir_all(&home).unwrap();
+    fs::create_dir_all(&config_dir).unwrap();
+    let _config_path = write_default_template_config(&config_dir, &trusted_root);
+
+    let env_file = dir.path().join("ci.env");
+    fs::write(
+        &env_file,
+        "# CI secrets\nexport OPENAI_API_KEY=\"file-key-456\"\nOTHER=ignored\n",
+    )
+    .unwrap();
+    let secrets_path = trusted_root.join("secrets").join("codex.env");
+
+    let output = bin()
+        .env("HOME", &home)
+        .env("LUX_CONFIG_DIR", &config_dir)
+        .env_remove("OPENAI_API_KEY")
+        .arg("--json")
+        .arg("setup")
+        .arg("--defaults")
+        .arg("--dry-run")
+        .arg("--import-env")
+        .arg(&env_file)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let rendered = String::from_utf8_lossy(&output).to_string();
+    assert!(!rendered.contains("file-key-456"));
+    let value = parse_json(&output);
+    let wrote = value["result"]["wrote_secrets"].as_array().unwrap();
+    assert_eq!(wrote.len(), 1);
+    assert_eq!(wrote[0]["env_key"], "OPENAI_API_KEY");
+    assert_eq!(wrote[0]["action"], "create_from_env_file");
+    assert!(!secrets_path.exists());
+
+    bin()
+        .env("HOME", &home)
+        .env("LUX_CONFIG_DIR", &config_dir)
+        .env("OPENAI_API_KEY", "process-key-789")
+        .arg("--json")
+        .arg("setup")
+        .arg("--defaults")
+        .arg("--no-apply")
+        .arg("--import-env")
+        .arg(&env_file)
+        .assert()
+        .success();
+    let content = fs::read_to_string(&secrets_path).unwrap();
+    assert!(content.contains("process-key-789"));
+    assert!(!content.contains("file-key-456"));
+}
+
+#[test]
+fn setup_dry_run_writes_nothing() {
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    let config_dir = dir.path().join("config");
+    fs::create_dir_all(&home).unwrap();
+    fs::create_dir_all(&config_dir).unwrap();
+
+    let config_path = config_dir.join("config.yaml");
+    let secrets_path = home
+        .join(".config")
+        .join("lux")
+        .join("secrets")
+        .join("codex.env");
+
+    let output = bin()
+        .env("HOME", &home)
+        .env("LUX_CONFIG_DIR", &config_dir)
+        .env("OPENAI_API_KEY", "dry-run-key")
+        .arg("--json")
+        .arg("setup")
+        .arg("--defaults")
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    assert!(value["ok"].as_bool().unwrap());
+    assert!(value["result"]["dry_run"].as_bool().unwrap());
+
+    assert!(!config_path.exists());
+    assert!(!secrets_path.exists());
+}
+
+#[test]
+fn setup_defaults_does_not_autostart_services() {
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    let config_dir = dir.path().join("config");
+    let trusted_root = dir.path().join("trusted");
+    fs::create_dir_all(&home).unwrap();
+    fs::create_dir_all(&config_dir).unwrap();
+    let _config_path = write_default_template_config(&config_dir, &trusted_root);
+
+    // `--defaults` should not attempt collector/UI startup; this must succeed even without docker in PATH.
+    let output = bin()
+        .env("HOME", &home)
+        .env("PATH", "")
+        .env("LUX_CONFIG_DIR", &config_dir)
+        .env("OPENAI_API_KEY", "defaults-no-autostart-key")
+        .arg("--json")
+        .arg("setup")
+        .arg("--defaults")
+        .arg("--yes")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    assert!(value["ok"].as_bool().unwrap());
+    assert!(value["result"]["apply"].as_bool().unwrap());
+}
+
+#[test]
+fn uninstall_requires_yes_without_dry_run() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.yaml");
+    write_valid_config(&config_path);
+
+    let output = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("uninstall")
+        .arg("--force")
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    let error = value["error"].as_str().unwrap_or_default();
+    assert!(error.contains("--yes"));
+}
+
+#[cfg(unix)]
+#[test]
+fn uninstall_dry_run_preserves_files() {
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    let config_path = dir.path().join("config.yaml");
+    let env_file = dir.path().join("compose.env");
+    let trusted_root = dir.path().join("trusted");
+    let log_root = trusted_root.join("logs");
+    let work_root = home.join("work");
+    let install_dir = home.join(".lux");
+    let versions_dir = install_dir.join("versions").join("0.1.0");
+    let current_link = install_dir.join("current");
+    let bin_dir = home.join(".local").join("bin");
+    let bin_link = bin_dir.join("lux");
+    fs::create_dir_all(&home).unwrap();
+    fs::create_dir_all(&versions_dir).unwrap();
+    fs::create_dir_all(&bin_dir).unwrap();
+    fs::create_dir_all(&log_root).unwrap();
+    fs::create_dir_all(&work_root).unwrap();
+    fs::write(versions_dir.join("lux"), "binary").unwrap();
+    symlink(&versions_dir, &current_link).unwrap();
+    symlink(current_link.join("lux"), &bin_link).unwrap();
+    fs::write(&env_file, "LUX_VERSION=v0.1.0\n").unwrap();
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
+
+    let output = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .env("HOME", &home)
+        .env("LUX_ENV_FILE", &env_file)
+        .arg("uninstall")
+        .arg("--dry-run")
+        .arg("--remove-config")
+        .arg("--all-versions")
+        .arg("--force")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    assert!(value["result"]["dry_run"].as_bool().unwrap());
+    assert!(bin_link.exists());
+    assert!(current_link.exists());
+    assert!(install_dir.join("versions").exists());
+    assert!(config_path.exists());
+    assert!(env_file.exists());
+    assert!(log_root.exists());
+    assert!(work_root.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn uninstall_exec_removes_requested_targets() {
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    let config_path = dir.path().join("config.yaml");
+    let env_file = dir.path().join("compose.env");
+    let trusted_root = dir.path().join("trusted");
+    let log_root = trusted_root.join("logs");
+    let work_root = home.join("work");
+    let install_dir = home.join(".lux");
+    let versions_dir = install_dir.join("versions").join("0.1.0");
+    let current_link = install_dir.join("current");
+    let bin_dir = home.join(".local").join("bin");
+    let bin_link = bin_dir.join("lux");
+    fs::create_dir_all(&home).unwrap();
+    fs::create_dir_all(&versions_dir).unwrap();
+    fs::create_dir_all(&bin_dir).unwrap();
+    fs::create_dir_all(&log_root).unwrap();
+    fs::create_dir_all(trusted_root.join("secrets")).unwrap();
+    fs::create_dir_all(&work_root).unwrap();
+    fs::write(versions_dir.join("lux"), "binary").unwrap();
+    symlink(&versions_dir, &current_link).unwrap();
+    symlink(current_link.join("lux"), &bin_link).unwrap();
+    fs::write(&env_file, "LUX_VERSION=v0.1.0\n").unwrap();
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
+
+    let output = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .env("HOME", &home)
+        .env("LUX_ENV_FILE", &env_file)
+        .arg("uninstall")
+        .arg("--yes")
+        .arg("--remove-config")
+        .arg("--all-versions")
+        .arg("--force")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    assert!(!value["result"]["dry_run"].as_bool().unwrap());
+    assert!(!bin_link.exists());
+    assert!(!current_link.exists());
+    assert!(!install_dir.join("versions").exists());
+    assert!(!config_path.exists());
+    assert!(!env_file.exists());
+    assert!(log_root.exists());
+    assert!(work_root.exists());
+    assert!(trusted_root.join("secrets").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn uninstall_dry_run_manifest_marks_kept_trusted_root_data() {
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    let config_path = dir.path().join("config.yaml");
+    let env_file = dir.path().join("compose.env");
+    let trusted_root = dir.path().join("trusted");
+    let log_root = trusted_root.join("logs");
+    let secrets_root = trusted_root.join("secrets");
+    let work_root = home.join("work");
+    fs::create_dir_all(&log_root).unwrap();
+    fs::create_dir_all(&secrets_root).unwrap();
+    fs::create_dir_all(&work_root).unwrap();
+    fs::write(log_root.join("audit.log"), "0123456789").unwrap();
+    fs::write(secrets_root.join("codex.env"), "OPENAI_API_KEY=sk\n").unwrap();
+    fs::write(&env_file, "LUX_VERSION=v0.1.0\n").unwrap();
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &work_root);
+
+    let output = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .env("HOME", &home)
+        .env("LUX_ENV_FILE", &env_file)
+        .arg("uninstall")
+        .arg("--dry-run")
+        .arg("--remove-config")
+        .arg("--keep-logs=false")
+        .arg("--keep-secrets")
+        .arg("--force")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    let manifest = value["result"]["manifest"].as_array().unwrap();
+    let entry = |kind: &str| {
+        manifest
+            .iter()
+            .find(|entry| entry["kind"] == kind)
+            .unwrap_or_else(|| panic!("missing manifest entry {kind}"))
+            .clone()
+    };
+    let logs = entry("logs");
+    assert_eq!(logs["scope"], "log_root");
+    assert_eq!(logs["action"], "remove");
+    assert_eq!(logs["size_bytes"], 10);
+    let secrets = entry("secrets");
+    assert_eq!(secrets["scope"], "trusted_root");
+    assert_eq!(secrets["action"], "keep");
+    assert_eq!(entry("config")["scope"], "config");
+    assert_eq!(entry("config")["action"], "remove");
+    assert_eq!(entry("bin_link")["scope"], "install");
+    assert!(log_root.join("audit.log").exists());
+    assert!(secrets_root.join("codex.env").exists());
+}
+
+#[test]
+fn update_apply_requires_yes_without_dry_run() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.yaml");
+    write_valid_config(&config_path);
+
+    let output = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("update")
+        .arg("apply")
+        .arg("--to")
+        .arg("v0.1.0")
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    let error = value["error"].as_str().unwrap_or_default();
+    assert!(error.contains("requires --yes"));
+}
+
+#[test]
+fn update_apply_dry_run_reports_target() {
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    fs::create_dir_all(&home).unwrap();
+    let config_path = dir.path().join("config.yaml");
+    let trusted_root = dir.path().join("trusted");
+    let log_root = trusted_root.join("logs");
+    let workspace_root = home.join("workspace");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &workspace_root);
+
+    let output = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .env("HOME", &home)
+        .arg("update")
+        .arg("apply")
+        .arg("--to")
+        .arg("0.9.9")
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    assert!(value["ok"].as_bool().unwrap());
+    assert_eq!(value["result"]["target_version"], "v0.9.9");
+    assert_eq!(value["result"]["dry_run"], true);
+}
+
+#[cfg(unix)]
+#[test]
+fn update_apply_verify_only_stages_without_switching_and_apply_reuses_it() {
+    use sha2::Digest;
+
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    fs::create_dir_all(&home).unwrap();
+    let config_path = dir.path().join("config.yaml");
+    let install_dir = home.join(".lux");
+    let versions_dir = install_dir.join("versions");
+    let trusted_root = dir.path().join("trusted");
+    let log_root = trusted_root.join("logs");
+    let workspace_root = home.join("workspace");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &workspace_root);
+    // Nothing listens here, so any download attempt fails fast.
+    let unreachable = "http://127.0.0.1:9/releases";
+
+    let output = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .env("HOME", &home)
+        .env("LUX_RELEASE_BASE_URL", unreachable)
+        .args(["update", "apply", "--to", "0.9.9", "--verify-only"])
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+    let value = parse_json(&output);
+    assert!(value["error"]
+        .as_str()
+        .unwrap_or_default()
+        .contains("download failed"));
+
+    // A marker without the extracted binary's hash (or one that no longer matches) is not
+    // trusted, so staging falls back to downloading again.
+    let staged = versions_dir.join("0.9.9");
+    fs::create_dir_all(&staged).unwrap();
+    fs::write(staged.join("lux"), "v0.9.9").unwrap();
+    let lux_sha: String = sha2::Sha256::digest(b"v0.9.9")
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    for marker in [
+        format!("{lux_sha}\n"),
+        format!("bundle {lux_sha}\nlux {}\n", "0".repeat(64)),
+    ] {
+        fs::write(staged.join(".lux-verified"), marker).unwrap();
+        let output = bin()
+            .arg("--json")
+            .arg("--config")
+            .arg(&config_path)
+            .env("HOME", &home)
+            .env("LUX_RELEASE_BASE_URL", unreachable)
+            .args(["update", "apply", "--to", "0.9.9", "--verify-only"])
+            .assert()
+            .failure()
+            .get_output()
+            .stdout
+            .clone();
+        let value = parse_json(&output);
+        assert!(value["error"]
+            .as_str()
+            .unwrap_or_default()
+            .contains("download failed"));
+    }
+    fs::write(
+        staged.join(".lux-verified"),
+        format!("bundle {}\nlux {lux_sha}\n", "1".repeat(64)),
+    )
+    .unwrap();
+
+    let output = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .env("HOME", &home)
+        .env("LUX_RELEASE_BASE_URL", unreachable)
+        .args(["update", "apply", "--to", "0.9.9", "--verify-only"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let value = parse_json(&output);
+    assert_eq!(value["result"]["verified"], true);
+    assert_eq!(value["result"]["reused_staged"], true);
+    assert_eq!(value["result"]["target_version"], "v0.9.9");
+    assert_eq!(
+        value["result"]["staged_dir"].as_str().unwrap(),
+        staged.to_string_lossy()
+    );
+    assert!(fs::symlink_metadata(install_dir.join("current")).is_err());
+
+    let output = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .env("HOME", &home)
+        .env("LUX_RELEASE_BASE_URL", unreachable)
+        .args(["update", "apply", "--to", "0.9.9", "--yes"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let value = parse_json(&output);
+    assert_eq!(value["result"]["updated"], true);
+    assert_eq!(value["result"]["reused_staged"], true);
+    assert_eq!(fs::read_link(install_dir.join("current")).unwrap(), staged);
+}
+
+#[cfg(unix)]
+#[test]
+fn update_rollback_dry_run_previous_selects_prior_version() {
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    fs::create_dir_all(&home).unwrap();
+    let config_path = dir.path().join("config.yaml");
+    let install_dir = home.join(".lux");
+    let versions_dir = install_dir.join("versions");
+    let v1 = versions_dir.join("0.1.0");
+    let v2 = versions_dir.join("0.2.0");
+    fs::create_dir_all(&v1).unwrap();
+    fs::create_dir_all(&v2).unwrap();
+    fs::write(v1.join("lux"), "v1").unwrap();
+    fs::write(v2.join("lux"), "v2").unwrap();
+    symlink(&v2, install_dir.join("current")).unwrap();
+    let trusted_root = dir.path().join("trusted");
+    let log_root = trusted_root.join("logs");
+    let workspace_root = home.join("workspace");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &workspace_root);
+
+    let output = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .env("HOME", &home)
+        .arg("update")
+        .arg("rollback")
+        .arg("--dry-run")
+        .arg("--previous")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    assert!(value["ok"].as_bool().unwrap());
+    assert_eq!(value["result"]["target_version"], "v0.1.0");
+}
+
+#[cfg(unix)]
+#[test]
+fn update_list_offline_marks_current_installed_version() {
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    fs::create_dir_all(&home).unwrap();
+    let config_path = dir.path().join("config.yaml");
+    let versions_dir = home.join(".lux").join("versions");
+    for version in ["0.10.0", "0.2.0", "0.9.1"] {
+        fs::create_dir_all(versions_dir.join(version)).unwrap();
+    }
+    symlink(
+        versions_dir.join("0.9.1"),
+        home.join(".lux").join("current"),
+    )
+    .unwrap();
+    let trusted_root = dir.path().join("trusted");
+    let log_root = trusted_root.join("logs");
+    let workspace_root = home.join("workspace");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &workspace_root);
+
+    let output = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .env("HOME", &home)
+        .arg("update")
+        .arg("list")
+        .arg("--offline")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let value = parse_json(&output);
+    let result = &value["result"];
+    assert_eq!(result["current"], "v0.9.1");
+    assert!(result["latest_remote"].is_null());
+    let installed = result["installed"].as_array().unwrap();
+    let versions: Vec<&str> = installed
+        .iter()
+        .map(|item| item["version"].as_str().unwrap())
+        .collect();
+    assert_eq!(versions, vec!["v0.2.0", "v0.9.1", "v0.10.0"]);
+    let current: Vec<bool> = installed
+        .iter()
+        .map(|item| item["current"].as_bool().unwrap())
+        .collect();
+    assert_eq!(current, vec![false, true, false]);
+}
+
+#[test]
+fn ui_url_returns_default_local_url() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.yaml");
+    write_valid_config(&config_path);
+
+    let output = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("ui")
+        .arg("url")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let value = parse_json(&output);
+    assert_eq!(value["result"]["url"], "http://127.0.0.1:8090");
+}
+
+#[test]
+fn up_rejects_removed_ui_flag() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.yaml");
+    write_valid_config(&config_path);
+
+    bin()
+        .arg("--config")
+        .arg(&config_path)
+        .arg("up")
+        .arg("--collector-only")
+        .arg("--ui")
+        .assert()
+        .failure()
+        .stderr(contains("unexpected argument '--ui'"));
+}
+
+#[cfg(unix)]
+#[test]
+fn runtime_up_status_down_cycle() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.yaml");
+    write_valid_config(&config_path);
+
+    let up = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("runtime")
+        .arg("up")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let up_value = parse_json(&up);
+    assert!(up_value["result"]["running"].as_bool().unwrap_or(false));
+    assert!(up_value["result"].get("stack").is_none());
+
+    let wait = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("runtime")
+        .arg("up")
+        .arg("--wait")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let wait_value = parse_json(&wait);
+    assert!(wait_value["result"]["already_running"]
+        .as_bool()
+        .unwrap_or(false));
+    assert!(wait_value["result"]["stack"]["collector_running"].is_boolean());
+
+    let status = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("runtime")
+        .arg("status")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let status_value = parse_json(&status);
+    assert!(status_value["result"]["running"].as_bool().unwrap_or(false));
+
+    let down = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("runtime")
+        .arg("down")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let down_value = parse_json(&down);
+    assert!(!down_value["result"]["running"].as_bool().unwrap_or(true));
+    assert_eq!(down_value["result"]["shutdown"], "graceful");
+}
+
+#[cfg(unix)]
+#[test]
+fn runtime_down_sends_sigterm_when_daemon_outlives_timeout() {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixListener;
+    use std::os::unix::process::ExitStatusExt;
+
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.yaml");
+    write_valid_config(&config_path);
+    let runtime_dir = dir.path().join("rt");
+    fs::create_dir_all(&runtime_dir).unwrap();
+    let socket_path = runtime_dir.join("control_plane.sock");
+    let mut config = fs::read_to_string(&config_path).unwrap();
+    config.push_str(&format!(
+        "runtime_control_plane:\n  socket_path: {}\n",
+        socket_path.display()
+    ));
+    fs::write(&config_path, config).unwrap();
+
+    // A daemon that acknowledges every request, including /v1/runtime/down, but never exits.
+    let listener = UnixListener::bind(&socket_path).unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { break };
+            // Drain headers and body so the client never writes into a closed socket.
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while let Ok(n) = stream.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_lowercase();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let body_len = text
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length:"))
+                        .and_then(|value| value.trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + body_len {
+                        break;
+                    }
+                }
+            }
+            let _ = stream.write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+            );
+        }
+    });
+    // `down` only signals a pid whose cmdline is `lux ... runtime serve`.
+    let fake_lux = dir.path().join("lux");
+    std::os::unix::fs::symlink("/bin/sh", &fake_lux).unwrap();
+    let mut stuck = std::process::Command::new(&fake_lux)
+        .args(["-c", "sleep 30; exit 0", "runtime", "serve"])
+        .spawn()
+        .unwrap();
+    fs::write(
+        runtime_dir.join("control_plane.pid"),
+        stuck.id().to_string(),
+    )
+    .unwrap();
+
+    let down = bin()
+        .arg("--json")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("runtime")
+        .arg("down")
+        .arg("--timeout-sec")
+        .arg("0")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let down_value = parse_json(&down);
+    assert_eq!(down_value["result"]["shutdown"], "forced");
+    assert_eq!(down_value["result"]["signaled_pid"], stuck.id());
+    assert_eq!(stuck.wait().unwrap().signal(), Some(15));
+    assert!(!socket_path.exists());
+    assert!(!runtime_dir.join("control_plane.pid").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn runtime_log_file_records_structured_lifecycle_lines() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.yaml");
+    write_valid_config(&config_path);
+    let log_file = dir.path().join("runtime.log");
+    let mut config = fs::read_to_string(&config_path).unwrap();
+    config.push_str(&format!(
+        "runtime_control_plane:\n  log_file: {}\n",
+        log_file.display()
+    ));
+    fs::write(&config_path, config).unwrap();
+
+    for action in ["up", "down"] {
+        bin()
+            .arg("--config")
+            .arg(&config_path)
+            .arg("runtime")
+            .arg(action)
+            .assert()
+            .success();
+    }
+
+    let lines = fs::read_to_string(&log_file).unwrap();
+    let records: Vec<serde_json::Value> = lines
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("log line is JSON"))
+        .collect();
+    let messages: Vec<&str> = records
+        .iter()
+        .map(|record| record["message"].as_str().unwrap())
+        .collect();
+    assert_eq!(messages.first(), Some(&"runtime starting"));
+    assert!(messages.contains(&"runtime listening"));
+    assert_eq!(messages.last(), Some(&"runtime stopped"));
+    assert!(records
+        .iter()
+        .all(|record| record["ts"].is_string() && record["level"].is_string()));
 }
 
 #[test]
-fn update_apply_requires_yes_without_dry_run() {
+fn runtime_logs_tails_configured_log_file() {
     let dir = tempdir().unwrap();
     let config_path = dir.path().join("config.yaml");
     write_valid_config(&config_path);
 
-    let output = bin()
-        .arg("--json")
+    bin()
         .arg("--config")
         .arg(&config_path)
-        .arg("update")
-        .arg("apply")
-        .arg("--to")
-        .arg("v0.1.0")
+        .arg("runtime")
+        .arg("logs")
         .assert()
         .failure()
+        .stderr(contains("runtime_control_plane.log_file is not set"));
+
+    let log_file = dir.path().join("runtime.log");
+    let mut config = fs::read_to_string(&config_path).unwrap();
+    config.push_str(&format!(
+        "runtime_control_plane:\n  log_file: {}\n",
+        log_file.display()
+    ));
+    fs::write(&config_path, config).unwrap();
+    fs::write(
+        &log_file,
+        concat!(
+            "{\"ts\":\"2026-02-12T12:00:00Z\",\"level\":\"info\",\"message\":\"runtime starting\"}\n",
+            "{\"ts\":\"2026-02-12T12:00:01Z\",\"level\":\"info\",\"message\":\"runtime listening\"}\n",
+            "thread 'main' panicked\n",
+        ),
+    )
+    .unwrap();
+
+    let output = bin()
+        .arg("--config")
+        .arg(&config_path)
+        .arg("runtime")
+        .arg("logs")
+        .arg("--lines")
+        .arg("2")
+        .assert()
+        .success()
         .get_output()
         .stdout
         .clone();
-
-    let value = parse_json(&output);
-    let error = value["error"].as_str().unwrap_or_default();
-    assert!(error.contains("requires --yes"));
-}
-
-#[test]
-fn update_apply_dry_run_reports_target() {
-    let dir = tempdir().unwrap();
-    let home = dir.path().join("home");
-    fs::create_dir_all(&home).unwrap();
-    let config_path = dir.path().join("config.yaml");
-    let trusted_root = dir.path().join("trusted");
-    let log_root = trusted_root.join("logs");
-    let workspace_root = home.join("workspace");
-    write_config_with_paths(&config_path, &trusted_root, &log_root, &workspace_root);
+    let text = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 2, "{text}");
+    assert!(lines[0].contains("runtime listening"));
+    assert_eq!(lines[1], "thread 'main' panicked");
 
     let output = bin()
         .arg("--json")
         .arg("--config")
         .arg(&config_path)
-        .env("HOME", &home)
-        .arg("update")
-        .arg("apply")
-        .arg("--to")
-        .arg("0.9.9")
-        .arg("--dry-run")
+        .arg("runtime")
+        .arg("logs")
         .assert()
         .success()
         .get_output()
         .stdout
         .clone();
-
-    let value = parse_json(&output);
-    assert!(value["ok"].as_bool().unwrap());
-    assert_eq!(value["result"]["target_version"], "v0.9.9");
-    assert_eq!(value["result"]["dry_run"], true);
+    let records: Vec<serde_json::Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("one JSON object per line"))
+        .collect();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0]["message"], "runtime starting");
+    assert_eq!(records[2]["raw"], "thread 'main' panicked");
 }
 
 #[cfg(unix)]
 #[test]
-fn update_rollback_dry_run_previous_selects_prior_version() {
+fn runtime_events_drains_buffered_events_with_filters() {
     let dir = tempdir().unwrap();
-    let home = dir.path().join("home");
-    fs::create_dir_all(&home).unwrap();
     let config_path = dir.path().join("config.yaml");
-    let install_dir = home.join(".lux");
-    let versions_dir = install_dir.join("versions");
-    let v1 = versions_dir.join("0.1.0");
-    let v2 = versions_dir.join("0.2.0");
-    fs::create_dir_all(&v1).unwrap();
-    fs::create_dir_all(&v2).unwrap();
-    fs::write(v1.join("lux"), "v1").unwrap();
-    fs::write(v2.join("lux"), "v2").unwrap();
-    symlink(&v2, install_dir.join("current")).unwrap();
-    let trusted_root = dir.path().join("trusted");
-    let log_root = trusted_root.join("logs");
-    let workspace_root = home.join("workspace");
-    write_config_with_paths(&config_path, &trusted_root, &log_root, &workspace_root);
+    write_valid_config(&config_path);
 
-    let output = bin()
-        .arg("--json")
+    bin()
         .arg("--config")
         .arg(&config_path)
-        .env("HOME", &home)
-        .arg("update")
-        .arg("rollback")
-        .arg("--dry-run")
-        .arg("--previous")
+        .arg("runtime")
+        .arg("up")
+        .assert()
+        .success();
+
+    let events = bin()
+        .arg("--config")
+        .arg(&config_path)
+        .arg("runtime")
+        .arg("events")
         .assert()
         .success()
         .get_output()
         .stdout
         .clone();
+    let events = String::from_utf8(events).unwrap();
+    let first = events.lines().next().expect("runtime.started event");
+    assert!(first.starts_with("1 "), "{events}");
+    assert!(first.contains(" runtime.started "), "{events}");
 
-    let value = parse_json(&output);
-    assert!(value["ok"].as_bool().unwrap());
-    assert_eq!(value["result"]["target_version"], "v0.1.0");
+    let skipped = bin()
+        .arg("--config")
+        .arg(&config_path)
+        .arg("runtime")
+        .arg("events")
+        .arg("--since-id")
+        .arg("1")
+        .arg("--event-type")
+        .arg("runtime.started")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert!(skipped.is_empty(), "{}", String::from_utf8_lossy(&skipped));
+
+    bin()
+        .arg("--config")
+        .arg(&config_path)
+        .arg("runtime")
+        .arg("down")
+        .assert()
+        .success();
 }
 
+#[cfg(unix)]
 #[test]
-fn ui_url_returns_default_local_url() {
+fn runtime_serve_cleans_up_on_sigterm() {
     let dir = tempdir().unwrap();
     let config_path = dir.path().join("config.yaml");
     write_valid_config(&config_path);
 
-    let output = bin()
+    bin()
+        .arg("--config")
+        .arg(&config_path)
+        .arg("runtime")
+        .arg("up")
+        .assert()
+        .success();
+    let status = bin()
         .arg("--json")
         .arg("--config")
         .arg(&config_path)
-        .arg("ui")
-        .arg("url")
+        .arg("runtime")
+        .arg("status")
         .assert()
         .success()
         .get_output()
         .stdout
         .clone();
-    let value = parse_json(&output);
-    assert_eq!(value["result"]["url"], "http://127.0.0.1:8090");
+    let status_value = parse_json(&status);
+    let pid = status_value["result"]["pid"].as_u64().expect("runtime pid");
+    let socket_path = PathBuf::from(status_value["result"]["socket_path"].as_str().unwrap());
+    let pid_path = PathBuf::from(status_value["result"]["pid_path"].as_str().unwrap());
+
+    let killed = std::process::Command::new("kill")
+        .arg("-TERM")
+        .arg(pid.to_string())
+        .status()
+        .unwrap();
+    assert!(killed.success());
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while (socket_path.exists() || pid_path.exists()) && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert!(!socket_path.exists());
+    assert!(!pid_path.exists());
+
+    let events = fs::read_to_string(socket_path.parent().unwrap().join("events.jsonl")).unwrap();
+    let stopped: Value = serde_json::from_str(events.lines().last().unwrap()).unwrap();
+    assert_eq!(stopped["event_type"], "runtime.stopped");
+    assert_eq!(stopped["payload"]["reason"], "signal");
+    assert_eq!(stopped["payload"]["signal"], "SIGTERM");
 }
 
+#[cfg(unix)]
 #[test]
-fn up_rejects_removed_ui_flag() {
+fn runtime_tcp_listener_requires_bearer_token() {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+
     let dir = tempdir().unwrap();
     let config_path = dir.path().join("config.yaml");
     write_valid_config(&config_path);
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let addr = format!("127.0.0.1:{port}");
+    let mut content = fs::read_to_string(&config_path).unwrap();
+    content.push_str(&format!(
+        "harness:\n  api_token: harness-token\nruntime_control_plane:\n  tcp_listen: \"{addr}\"\n  tcp_token: tcp-test-token\n"
+    ));
+    fs::write(&config_path, content).unwrap();
 
     bin()
         .arg("--config")
         .arg(&config_path)
+        .arg("runtime")
         .arg("up")
-        .arg("--collector-only")
-        .arg("--ui")
         .assert()
-        .failure()
-        .stderr(contains("unexpected argument '--ui'"));
+        .success();
+
+    let request = |auth: Option<&str>| {
+        let mut stream = TcpStream::connect(&addr).unwrap();
+        let mut head = "GET /v1/healthz HTTP/1.1\r\nHost: localhost\r\n".to_string();
+        if let Some(token) = auth {
+            head.push_str(&format!("Authorization: Bearer {token}\r\n"));
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+    assert!(request(None).starts_with("HTTP/1.1 401"));
+    assert!(request(Some("wrong")).starts_with("HTTP/1.1 401"));
+    assert!(request(Some("harness-token")).starts_with("HTTP/1.1 401"));
+    let ok = request(Some("tcp-test-token"));
+    assert!(ok.starts_with("HTTP/1.1 200"), "{ok}");
+
+    bin()
+        .env("LUX_RUNTIME_TCP", &addr)
+        .arg("--config")
+        .arg(&config_path)
+        .arg("runtime")
+        .arg("down")
+        .assert()
+        .success();
 }
 
 #[cfg(unix)]
 #[test]
-fn runtime_up_status_down_cycle() {
+fn runtime_rejects_connections_over_limit_with_503() {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
     let dir = tempdir().unwrap();
     let config_path = dir.path().join("config.yaml");
     write_valid_config(&config_path);
+    let mut content = fs::read_to_string(&config_path).unwrap();
+    content.push_str("runtime_control_plane:\n  max_connections: 1\n");
+    fs::write(&config_path, content).unwrap();
 
-    let up = bin()
-        .arg("--json")
+    bin()
         .arg("--config")
         .arg(&config_path)
         .arg("runtime")
         .arg("up")
         .assert()
-        .success()
-        .get_output()
-        .stdout
-        .clone();
-    let up_value = parse_json(&up);
-    assert!(up_value["result"]["running"].as_bool().unwrap_or(false));
-
+        .success();
     let status = bin()
         .arg("--json")
         .arg("--config")
@@ -1069,21 +3000,148 @@ fn runtime_up_status_down_cycle() {
         .stdout
         .clone();
     let status_value = parse_json(&status);
-    assert!(status_value["result"]["running"].as_bool().unwrap_or(false));
+    let pid = status_value["result"]["pid"].as_u64().expect("runtime pid");
+    let socket_path = PathBuf::from(status_value["result"]["socket_path"].as_str().unwrap());
 
-    let down = bin()
+    let mut held = UnixStream::connect(&socket_path).unwrap();
+    held.write_all(b"GET /v1/events HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let mut head = [0u8; 12];
+    held.read_exact(&mut head).unwrap();
+    assert_eq!(&head, b"HTTP/1.1 200");
+
+    let mut overflow = UnixStream::connect(&socket_path).unwrap();
+    overflow
+        .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    let _ = overflow.write_all(b"GET /v1/healthz HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    let mut response = String::new();
+    overflow.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 503"), "{response}");
+    assert!(response.contains("Retry-After: 1"), "{response}");
+    drop(held);
+
+    // The held SSE stream only notices the disconnect on its next keepalive, so stop the daemon
+    // by signal rather than competing for the single slot with `runtime down`.
+    let killed = std::process::Command::new("kill")
+        .arg("-TERM")
+        .arg(pid.to_string())
+        .status()
+        .unwrap();
+    assert!(killed.success());
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while socket_path.exists() && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert!(!socket_path.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn doctor_flags_partial_shim_path_persistence() {
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    fs::create_dir_all(&home).unwrap();
+    fs::write(home.join(".zprofile"), "# existing zprofile\n").unwrap();
+    fs::write(home.join(".bashrc"), "# existing bashrc\n").unwrap();
+    let config_path = dir.path().join("config.yaml");
+    let trusted_root = dir.path().join("trusted");
+    let log_root = trusted_root.join("logs");
+    let workspace_root = home.join("workspace");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &workspace_root);
+
+    let doctor_check = || {
+        let output = bin()
+            .env("HOME", &home)
+            .arg("--json")
+            .arg("--config")
+            .arg(&config_path)
+            .arg("doctor")
+            .arg("--check")
+            .arg("shim_path_persistence")
+            .output()
+            .unwrap();
+        parse_json(&output.stdout)
+    };
+
+    let before = doctor_check();
+    assert_eq!(before["result"]["ok"], true);
+    assert!(before["result"]["details"]["installed_providers"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+
+    bin()
+        .env("HOME", &home)
+        .arg("--config")
+        .arg(&config_path)
+        .arg("shim")
+        .arg("enable")
+        .assert()
+        .success();
+    let configured = doctor_check();
+    assert_eq!(configured["ok"], true);
+    assert_eq!(configured["result"]["details"]["state"], "configured");
+
+    fs::write(home.join(".bashrc"), "# existing bashrc\n").unwrap();
+    let partial = doctor_check();
+    assert_eq!(partial["ok"], false);
+    assert_eq!(partial["result"]["severity"], "warn");
+    assert_eq!(partial["result"]["details"]["state"], "partial");
+    let files = partial["result"]["details"]["files"].as_array().unwrap();
+    let bashrc = files
+        .iter()
+        .find(|row| row["path"] == "~/.bashrc")
+        .expect("bashrc row");
+    assert_eq!(bashrc["managed_block_present"], false);
+    assert!(partial["result"]["remediation"]
+        .as_str()
+        .unwrap()
+        .contains("lux shim enable"));
+}
+
+#[cfg(unix)]
+#[test]
+fn quiet_flag_suppresses_shim_session_guidance() {
+    let dir = tempdir().unwrap();
+    let home = dir.path().join("home");
+    fs::create_dir_all(&home).unwrap();
+    fs::write(home.join(".bashrc"), "# existing bashrc\n").unwrap();
+    let config_path = dir.path().join("config.yaml");
+    let trusted_root = dir.path().join("trusted");
+    let log_root = trusted_root.join("logs");
+    let workspace_root = home.join("workspace");
+    write_config_with_paths(&config_path, &trusted_root, &log_root, &workspace_root);
+
+    let shim = |quiet: bool, action: &str| {
+        let mut cmd = bin();
+        cmd.env("HOME", &home).arg("--config").arg(&config_path);
+        if quiet {
+            cmd.arg("--quiet");
+        }
+        let output = cmd.arg("shim").arg(action).output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stderr).to_string()
+    };
+
+    assert!(shim(false, "enable").contains("Current session PATH update:"));
+    assert!(!shim(true, "enable").contains("Current session PATH update:"));
+    assert!(!shim(true, "disable").contains("Current session PATH update:"));
+
+    let output = bin()
+        .env("HOME", &home)
+        .arg("--quiet")
         .arg("--json")
         .arg("--config")
         .arg(&config_path)
-        .arg("runtime")
-        .arg("down")
+        .arg("shim")
+        .arg("enable")
         .assert()
         .success()
         .get_output()
         .stdout
         .clone();
-    let down_value = parse_json(&down);
-    assert!(!down_value["result"]["running"].as_bool().unwrap_or(true));
+    assert_eq!(parse_json(&output)["result"]["action"], "shim_enable");
 }
 
 #[cfg(unix)]
@@ -1394,37 +3452,3 @@ fn shim_enable_fails_when_no_providers_configured() {
     let error = value["error"].as_str().unwrap_or_default();
     assert!(error.contains("config.providers must contain at least one provider"));
 }
-
-#[test]
-fn shim_exec_rejects_absolute_paths() {
-    let dir = tempdir().unwrap();
-    let home = dir.path().join("home");
-    let workspace = home.join("workspace");
-    let trusted_root = dir.path().join("trusted");
-    let logs = trusted_root.join("logs");
-    fs::create_dir_all(&workspace).unwrap();
-    fs::create_dir_all(&logs).unwrap();
-    fs::create_dir_all(&home).unwrap();
-    let config_path = dir.path().join("config.yaml");
-    write_config_with_paths(&config_path, &trusted_root, &logs, &workspace);
-
-    let output = bin()
-        .env("HOME", &home)
-        .current_dir(&workspace)
-        .arg("--json")
-        .arg("--config")
-        .arg(&config_path)
-        .arg("shim")
-        .arg("exec")
-        .arg("codex")
-        .arg("--")
-        .arg("/tmp/host-path")
-        .assert()
-        .failure()
-        .get_output()
-        .stdout
-        .clone();
-    let value = parse_json(&output);
-    let error = value["error"].as_str().unwrap_or_default();
-    assert!(error.contains("absolute host path"));
-}
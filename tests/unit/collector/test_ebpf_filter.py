@@ -77,11 +77,18 @@ def make_dns_event(ts: str, pid: int, ppid: int, comm: str, event_type: str) ->
     }
 
 
-def make_unix_event(ts: str, pid: int, ppid: int, comm: str, path: str) -> dict:
+def make_unix_event(
+    ts: str,
+    pid: int,
+    ppid: int,
+    comm: str,
+    path: str,
+    event_type: str = "unix_connect",
+) -> dict:
     return {
         "schema_version": "ebpf.v1",
         "ts": ts,
-        "event_type": "unix_connect",
+        "event_type": event_type,
         "pid": pid,
         "ppid": ppid,
         "uid": 1001,
@@ -163,9 +170,13 @@ class TestEbpfFilter(unittest.TestCase):
                 "event_types": [
                     "net_connect",
                     "net_send",
+                    "net_bind",
+                    "net_listen",
                     "dns_query",
                     "dns_response",
                     "unix_connect",
+                    "unix_send",
+                    "unix_recv",
                 ]
             },
             "exclude": {"comm": [], "unix_paths": [], "net_dst_ports": [], "net_dst_ips": []},
@@ -289,6 +300,34 @@ class TestEbpfFilter(unittest.TestCase):
         self.assertIn("cmd", events[0])
         self.assertEqual(events[0]["cmd"], "curl example.com")
 
+    def test_run_tag_passed_through_when_present(self) -> None:
+        base = datetime(2026, 1, 22, 0, 0, 0, tzinfo=timezone.utc)
+        ts_sec = f"{int(base.timestamp())}.123"
+        ebpf_ts = "2026-01-22T00:00:00.123456789Z"
+
+        audit_lines = [
+            make_syscall(ts_sec, 1, 100, 1, 1001, 1001, "codex", "/usr/bin/codex", "exec"),
+            make_execve(ts_sec, 1, ["codex"]),
+        ]
+        tagged = make_net_event(ebpf_ts, 100, 1, "codex", "93.184.216.34", 443)
+        tagged["run_tag"] = "0x9f3c2a1b4d5e6f70"
+        untagged = make_net_event(ebpf_ts, 100, 1, "codex", "93.184.216.35", 443)
+        sessions = [
+            {
+                "session_id": "session_test_0002",
+                "started_at": base.isoformat(),
+                "ended_at": (base + timedelta(seconds=5)).isoformat(),
+                "mode": "tui",
+                "root_pid": 100,
+                "root_sid": 100,
+            }
+        ]
+
+        events = self.run_filter(audit_lines, [tagged, untagged], self.base_config(), sessions=sessions)
+        self.assertEqual(len(events), 2)
+        self.assertEqual(events[0]["run_tag"], "0x9f3c2a1b4d5e6f70")
+        self.assertNotIn("run_tag", events[1])
+
     def test_exclude_comm(self) -> None:
         base = datetime(2026, 1, 22, 0, 0, 1, tzinfo=timezone.utc)
         ts_sec = f"{int(base.timestamp())}.456"
@@ -349,6 +388,81 @@ class TestEbpfFilter(unittest.TestCase):
         event_types = {event["event_type"] for event in events}
         self.assertEqual(event_types, {"dns_query", "dns_response"})
 
+    def test_unix_send_recv_kept_and_path_excluded(self) -> None:
+        base = datetime(2026, 1, 22, 0, 0, 4, tzinfo=timezone.utc)
+        ts_sec = f"{int(base.timestamp())}.000"
+        send_ts = "2026-01-22T00:00:04.000000000Z"
+        recv_ts = "2026-01-22T00:00:04.100000000Z"
+
+        audit_lines = [
+            make_syscall(ts_sec, 1, 500, 1, 1001, 1001, "codex", "/usr/bin/codex", "exec"),
+            make_execve(ts_sec, 1, ["codex"]),
+            make_syscall(ts_sec, 2, 501, 500, 1001, 1001, "bash", "/usr/bin/bash", "exec"),
+            make_execve(ts_sec, 2, ["bash", "-lc", "true"]),
+        ]
+
+        send = make_unix_event(send_ts, 501, 500, "bash", "/tmp/tool.sock", "unix_send")
+        send["unix"].update({"bytes": 2, "payload_len": 2, "payload_truncated": False, "payload_hex": "6869"})
+        recv = make_unix_event(recv_ts, 501, 500, "bash", "/tmp/tool.sock", "unix_recv")
+        excluded = make_unix_event(recv_ts, 501, 500, "bash", "/var/run/nscd/socket", "unix_send")
+
+        config = self.base_config()
+        config["exclude"]["unix_paths"] = ["/var/run/nscd/socket"]
+        events = self.run_filter(audit_lines, [send, recv, excluded], config)
+        self.assertEqual([event["event_type"] for event in events], ["unix_send", "unix_recv"])
+        self.assertEqual(events[0]["unix"]["payload_hex"], "6869")
+
+    def test_net_bind_listen_kept_with_local_endpoint(self) -> None:
+        base = datetime(2026, 1, 22, 0, 0, 4, tzinfo=timezone.utc)
+        ts_sec = f"{int(base.timestamp())}.000"
+        bind_ts = "2026-01-22T00:00:04.000000000Z"
+        listen_ts = "2026-01-22T00:00:04.100000000Z"
+
+        audit_lines = [
+            make_syscall(ts_sec, 1, 500, 1, 1001, 1001, "codex", "/usr/bin/codex", "exec"),
+            make_execve(ts_sec, 1, ["codex"]),
+        ]
+
+        bind = make_net_event(bind_ts, 500, 1, "codex", "", 0)
+        bind["event_type"] = "net_bind"
+        bind["net"] = {
+            "protocol": "tcp",
+            "family": "ipv4",
+            "src_ip": "0.0.0.0",
+            "src_port": 8080,
+            "wildcard": True,
+            "flowinfo": 0,
+            "scope_id": 0,
+        }
+        listen = dict(bind, ts=listen_ts, event_type="net_listen")
+
+        events = self.run_filter(audit_lines, [bind, listen], self.base_config())
+        self.assertEqual([event["event_type"] for event in events], ["net_bind", "net_listen"])
+        self.assertTrue(events[0]["net"]["wildcard"])
+        self.assertEqual(events[1]["net"]["src_port"], 8080)
+
+    def test_net_recv_kept_and_subject_to_dst_excludes(self) -> None:
+        base = datetime(2026, 1, 22, 0, 0, 4, tzinfo=timezone.utc)
+        ts_sec = f"{int(base.timestamp())}.000"
+
+        audit_lines = [
+            make_syscall(ts_sec, 1, 500, 1, 1001, 1001, "codex", "/usr/bin/codex", "exec"),
+            make_execve(ts_sec, 1, ["codex"]),
+        ]
+
+        recv = make_net_event("2026-01-22T00:00:04.000000000Z", 500, 1, "codex", "93.184.216.34", 443)
+        recv["event_type"] = "net_recv"
+        recv["net"]["bytes"] = 512
+        excluded = make_net_event("2026-01-22T00:00:04.100000000Z", 500, 1, "codex", "10.0.0.9", 9000)
+        excluded["event_type"] = "net_recv"
+
+        config = self.base_config()
+        config["include"]["event_types"].append("net_recv")
+        config["exclude"]["net_dst_ports"] = [9000]
+        events = self.run_filter(audit_lines, [recv, excluded], config)
+        self.assertEqual([event["event_type"] for event in events], ["net_recv"])
+        self.assertEqual(events[0]["net"]["bytes"], 512)
+
     def test_session_job_precedence(self) -> None:
         base = datetime(2026, 1, 22, 0, 0, 4, tzinfo=timezone.utc)
         ts_sec = f"{int(base.timestamp())}.500"
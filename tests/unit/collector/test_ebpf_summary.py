@@ -230,6 +230,83 @@ class TestEbpfSummary(unittest.TestCase):
         self.assertEqual(rows[0]["event_type"], "unix_connect")
         self.assertEqual(rows[0]["schema_version"], "ebpf.summary.v1")
 
+    def test_dns_rows_pass_through_unless_disabled(self) -> None:
+        events = [
+            make_event(
+                "dns_query",
+                "2026-01-22T00:00:01.000Z",
+                dns={
+                    "query_name": "example.com",
+                    "query_type": "A",
+                    "questions": [{"name": "example.com", "type": "A"}],
+                    "truncated": False,
+                },
+            ),
+            make_event(
+                "dns_response",
+                "2026-01-22T00:00:01.100Z",
+                dns={
+                    "query_name": "example.com",
+                    "answers": ["1.2.3.4"],
+                    "records": [{"name": "example.com", "type": "A", "data": "1.2.3.4"}],
+                    "truncated": True,
+                },
+            ),
+        ]
+
+        self.assertEqual(
+            self.run_summary(events, {"include_dns_rows": False, "include_dns_answers": False}), []
+        )
+
+        rows = self.run_summary(events, {"include_dns_answers": False})
+        self.assertEqual([row["event_type"] for row in rows], ["dns_query", "dns_response"])
+        self.assertTrue(all(row["schema_version"] == "ebpf.summary.v1" for row in rows))
+        self.assertEqual(rows[0]["dns"]["questions"][0]["name"], "example.com")
+        self.assertTrue(rows[1]["dns"]["truncated"])
+
+    def test_dns_answer_rows_link_names_to_addresses(self) -> None:
+        events = [
+            make_event(
+                "dns_response",
+                "2026-01-22T00:00:01.000Z",
+                dns={
+                    "query_name": "api.example.com",
+                    "query_type": "A",
+                    "answers": ["1.2.3.4", "5.6.7.8"],
+                    "records": [
+                        {"name": "api.example.com", "type": "CNAME", "ttl": 60, "data": "edge.example.net"},
+                        {"name": "edge.example.net", "type": "A", "ttl": 30, "data": "1.2.3.4"},
+                        {"name": "edge.example.net", "type": "A", "ttl": 30, "data": "5.6.7.8"},
+                        {"name": "example.net", "type": "NS", "ttl": 900, "data": "ns1.example.net"},
+                    ],
+                    "truncated": False,
+                    "capture_truncated": True,
+                },
+            ),
+            make_event(
+                "dns_response",
+                "2026-01-22T00:00:02.000Z",
+                dns={"query_name": "nothing.example.com", "answers": [], "records": []},
+            ),
+        ]
+
+        self.assertEqual(
+            self.run_summary(events, {"include_dns_rows": False, "include_dns_answers": False}), []
+        )
+
+        rows = self.run_summary(events, {"include_dns_rows": False})
+        self.assertEqual([row["event_type"] for row in rows], ["dns_answer"])
+        row = rows[0]
+        self.assertEqual(row["schema_version"], "ebpf.summary.v1")
+        self.assertEqual(row["query_name"], "api.example.com")
+        self.assertEqual(row["addresses"], ["1.2.3.4", "5.6.7.8"])
+        self.assertEqual(
+            [(a["type"], a["ttl"], a["data"]) for a in row["answers"]],
+            [("CNAME", 60, "edge.example.net"), ("A", 30, "1.2.3.4"), ("A", 30, "5.6.7.8")],
+        )
+        self.assertFalse(row["truncated"])
+        self.assertTrue(row["capture_truncated"])
+
 
 if __name__ == "__main__":
     unittest.main()
@@ -129,6 +129,12 @@ def test_compose_base_runtime_contract() -> None:
         "COLLECTOR_SESSIONS_DIR",
         "COLLECTOR_JOBS_DIR",
         "COLLECTOR_ROOT_COMM",
+        "COLLECTOR_DNS_CAPTURE_BYTES",
+        "COLLECTOR_SAMPLING_RATE",
+        "COLLECTOR_PID_RATE_MAX_EVENTS",
+        "COLLECTOR_PID_RATE_WINDOW_MS",
+        "COLLECTOR_RINGBUF_BYTES",
+        "COLLECTOR_SLOW_CONNECT_MS",
     }.issubset(
         _env_keys(collector)
     )
@@ -107,3 +107,13 @@ def test_build_remote_command_omits_marker_prefix_without_pid_path() -> None:
     assert "ROOT_SID=" not in cmd
     assert "cd /work" in cmd
     assert "hello world" in cmd
+
+
+def test_tui_session_env_exports_only_listed_valid_keys(monkeypatch) -> None:
+    """`lux tui --env` keys should be re-exported; unlisted or invalid names are dropped."""
+    harness = _load_harness_module()
+    monkeypatch.setenv("HARNESS_TUI_ENV_KEYS", "FOO,BAD-KEY,MISSING")
+    monkeypatch.setenv("FOO", "bar baz")
+    monkeypatch.setenv("BAD-KEY", "x")
+    monkeypatch.setenv("OTHER", "y")
+    assert harness.tui_session_env() == {"FOO": "bar baz"}
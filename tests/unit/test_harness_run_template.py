@@ -0,0 +1,86 @@
+from __future__ import annotations
+
+import importlib.util
+import shlex
+from pathlib import Path
+
+import pytest
+
+
+pytestmark = pytest.mark.unit
+
+
+ROOT_DIR = Path(__file__).resolve().parents[2]
+HARNESS_PATH = ROOT_DIR / "harness" / "harness.py"
+
+
+def _load_harness_module():
+    spec = importlib.util.spec_from_file_location("harness_module_for_template_tests", HARNESS_PATH)
+    if spec is None or spec.loader is None:
+        raise AssertionError(f"Failed to load harness module from {HARNESS_PATH}")
+    module = importlib.util.module_from_spec(spec)
+    spec.loader.exec_module(module)
+    return module
+
+
+def test_render_run_command_keeps_prompt_quoting() -> None:
+    """`{prompt}` expands exactly as the previous single-placeholder replace did."""
+    harness = _load_harness_module()
+    prompt = "it's a \"quoted\" prompt; rm -rf /"
+    cmd = harness.render_run_command("claude -p {prompt}", prompt, "/work", {})
+    assert cmd == f"claude -p {shlex.quote(prompt)}"
+    assert shlex.split(cmd) == ["claude", "-p", prompt]
+
+
+def test_render_run_command_expands_context_placeholders() -> None:
+    """start_dir, workspace, run_id and env lookups are each quoted as one argument."""
+    harness = _load_harness_module()
+    harness.RUN_ID = "lux__2026_02_12_12_00_00"
+    cmd = harness.render_run_command(
+        "agent --cwd {start_dir} --root {workspace} --run {run_id} --model {env:MODEL} {prompt}",
+        "hi",
+        "/work/my project",
+        {"MODEL": "o'neil \"x\""},
+    )
+    assert shlex.split(cmd) == [
+        "agent",
+        "--cwd",
+        "/work/my project",
+        "--root",
+        harness.DEFAULT_CWD,
+        "--run",
+        "lux__2026_02_12_12_00_00",
+        "--model",
+        "o'neil \"x\"",
+        "hi",
+    ]
+
+
+def test_render_run_command_does_not_reexpand_substituted_values() -> None:
+    """Placeholders inside a prompt are data, not template syntax."""
+    harness = _load_harness_module()
+    cmd = harness.render_run_command("echo {prompt}", "{start_dir} {bogus}", "/work", {})
+    assert shlex.split(cmd) == ["echo", "{start_dir} {bogus}"]
+
+
+def test_render_run_command_leaves_shell_braces_alone() -> None:
+    harness = _load_harness_module()
+    template = "bash -lc 'echo ${HOME} {a,b} {}' {prompt}"
+    cmd = harness.render_run_command(template, "x", "/work", {})
+    assert cmd == "bash -lc 'echo ${HOME} {a,b} {}' x"
+
+
+def test_render_run_command_rejects_unknown_placeholder_and_missing_env() -> None:
+    harness = _load_harness_module()
+    with pytest.raises(ValueError, match="unknown placeholder"):
+        harness.render_run_command("run {workdir} {prompt}", "x", "/work", {})
+    with pytest.raises(ValueError, match="env MODEL is not set"):
+        harness.render_run_command("run --model {env:MODEL}", "x", "/work", {})
+
+
+def test_handle_run_rejects_unrenderable_template() -> None:
+    harness = _load_harness_module()
+    harness.RUN_CMD_TEMPLATE = "codex exec {model} {prompt}"
+    response, status = harness.handle_run({"prompt": "hello"})
+    assert status == 400
+    assert "unknown placeholder {model}" in response["error"]